@@ -36,16 +36,24 @@ pub mod rad_node;
 pub mod rad_patch;
 #[path = "commands/path.rs"]
 pub mod rad_path;
+#[path = "commands/person.rs"]
+pub mod rad_person;
 #[path = "commands/remote.rs"]
 pub mod rad_remote;
 #[path = "commands/review.rs"]
 pub mod rad_review;
 #[path = "commands/rm.rs"]
 pub mod rad_rm;
+#[path = "commands/search.rs"]
+pub mod rad_search;
 #[path = "commands/self.rs"]
 pub mod rad_self;
+#[path = "commands/stats.rs"]
+pub mod rad_stats;
 #[path = "commands/sync.rs"]
 pub mod rad_sync;
+#[path = "commands/token.rs"]
+pub mod rad_token;
 #[path = "commands/track.rs"]
 pub mod rad_track;
 #[path = "commands/unassign.rs"]
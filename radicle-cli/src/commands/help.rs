@@ -28,10 +28,14 @@ const COMMANDS: &[Help] = &[
     rad_node::HELP,
     rad_patch::HELP,
     rad_path::HELP,
+    rad_person::HELP,
     rad_review::HELP,
     rad_rm::HELP,
+    rad_search::HELP,
     rad_self::HELP,
+    rad_stats::HELP,
     rad_label::HELP,
+    rad_token::HELP,
     rad_track::HELP,
     rad_unassign::HELP,
     rad_unlabel::HELP,
@@ -341,7 +341,7 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
             )?;
         }
         Operation::List { assigned, state } => {
-            list(&issues, &assigned, &state, &profile)?;
+            list(&issues, &repo, &assigned, &state, &profile)?;
         }
         Operation::Delete { id } => {
             let id = id.resolve(&repo.backend)?;
@@ -364,6 +364,7 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
 
 fn list<R: WriteRepository + cob::Store>(
     issues: &Issues<R>,
+    repo: &R,
     assigned: &Option<Assigned>,
     state: &Option<State>,
     profile: &profile::Profile,
@@ -439,7 +440,7 @@ fn list<R: WriteRepository + cob::Store>(
 
         let author = issue.author().id;
         let alias = aliases.alias(&author);
-        let display = Author::new(&author, alias, profile);
+        let display = Author::resolve(&author, alias, profile, repo);
 
         table.push([
             match issue.state() {
@@ -33,6 +33,13 @@ Usage
     rad node routing [--rid <rid>] [--nid <nid>] [--json] [<option>...]
     rad node tracking [--repos | --nodes] [<option>...]
     rad node events [--timeout <secs>] [-n <count>] [<option>...]
+    rad node metrics [--json] [<option>...]
+    rad node disconnect <nid> [<option>...]
+    rad node addresses [--json] [<option>...]
+    rad node inventory <nid> [--json] [<option>...]
+    rad node config [<option>...]
+    rad node limits [--routing-max-size <n>] [--routing-max-age <secs>]
+                    [--gossip-max-age <secs>] [--fetch-concurrency <n>] [--json] [<option>...]
 
     For `<node-option>` see `radicle-node --help`.
 
@@ -51,6 +58,28 @@ Tracking options
     --repos              Show the tracked repositories table
     --nodes              Show the tracked nodes table
 
+Addresses options
+
+    --json               Output the address book as json
+
+Inventory options
+
+    --json               Output the repositories seeded by the node as json
+
+Limits options
+
+    --routing-max-size <n>      Number of routing table entries before pruning
+    --routing-max-age <secs>    How long to keep routing table entries
+    --gossip-max-age <secs>     How long to keep gossip announcements
+    --fetch-concurrency <n>     Maximum number of concurrent fetches per peer
+    --json                      Output the limits as json
+
+    Limits changed at runtime are reset when the node restarts.
+
+Metrics options
+
+    --json               Output metrics as json, instead of the Prometheus text format
+
 Events options
 
     --timeout <secs>     How long to wait to receive an event before giving up
@@ -67,14 +96,29 @@ pub struct Options {
 }
 
 pub enum Operation {
+    Addresses {
+        json: bool,
+    },
+    Config,
     Connect {
         addr: PeerAddr<NodeId, Address>,
         timeout: time::Duration,
     },
+    Disconnect {
+        nid: NodeId,
+    },
     Events {
         timeout: time::Duration,
         count: usize,
     },
+    Inventory {
+        nid: NodeId,
+        json: bool,
+    },
+    Limits {
+        update: control::LimitsUpdate,
+        json: bool,
+    },
     Routing {
         json: bool,
         rid: Option<Id>,
@@ -87,6 +131,9 @@ pub enum Operation {
     Logs {
         lines: usize,
     },
+    Metrics {
+        json: bool,
+    },
     Status,
     Stop,
     Tracking {
@@ -103,10 +150,16 @@ pub enum TrackingMode {
 
 #[derive(Default, PartialEq, Eq)]
 pub enum OperationName {
+    Addresses,
+    Config,
     Connect,
+    Disconnect,
     Events,
+    Inventory,
+    Limits,
     Routing,
     Logs,
+    Metrics,
     Start,
     #[default]
     Status,
@@ -130,6 +183,7 @@ impl Args for Options {
         let mut lines: usize = 10;
         let mut count: usize = usize::MAX;
         let mut timeout = time::Duration::MAX;
+        let mut limits = control::LimitsUpdate::default();
 
         while let Some(arg) = parser.next()? {
             match arg {
@@ -137,9 +191,15 @@ impl Args for Options {
                     return Err(Error::Help.into());
                 }
                 Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
+                    "addresses" => op = Some(OperationName::Addresses),
+                    "config" => op = Some(OperationName::Config),
                     "connect" => op = Some(OperationName::Connect),
+                    "disconnect" => op = Some(OperationName::Disconnect),
                     "events" => op = Some(OperationName::Events),
+                    "inventory" => op = Some(OperationName::Inventory),
+                    "limits" => op = Some(OperationName::Limits),
                     "logs" => op = Some(OperationName::Logs),
+                    "metrics" => op = Some(OperationName::Metrics),
                     "routing" => op = Some(OperationName::Routing),
                     "start" => op = Some(OperationName::Start),
                     "status" => op = Some(OperationName::Status),
@@ -151,6 +211,30 @@ impl Args for Options {
                 Value(val) if matches!(op, Some(OperationName::Connect)) => {
                     addr = Some(val.parse()?);
                 }
+                Value(val)
+                    if matches!(
+                        op,
+                        Some(OperationName::Disconnect | OperationName::Inventory)
+                    ) =>
+                {
+                    nid = Some(term::args::nid(&val)?);
+                }
+                Long("routing-max-size") if matches!(op, Some(OperationName::Limits)) => {
+                    let val = parser.value()?;
+                    limits.routing_max_size = Some(term::args::number(&val)?);
+                }
+                Long("routing-max-age") if matches!(op, Some(OperationName::Limits)) => {
+                    let val = parser.value()?;
+                    limits.routing_max_age = Some(term::args::seconds(&val)?);
+                }
+                Long("gossip-max-age") if matches!(op, Some(OperationName::Limits)) => {
+                    let val = parser.value()?;
+                    limits.gossip_max_age = Some(term::args::seconds(&val)?);
+                }
+                Long("fetch-concurrency") if matches!(op, Some(OperationName::Limits)) => {
+                    let val = parser.value()?;
+                    limits.fetch_concurrency = Some(term::args::number(&val)?);
+                }
                 Long("rid") if matches!(op, Some(OperationName::Routing)) => {
                     let val = parser.value()?;
                     rid = term::args::rid(&val).ok();
@@ -159,7 +243,20 @@ impl Args for Options {
                     let val = parser.value()?;
                     nid = term::args::nid(&val).ok();
                 }
-                Long("json") if matches!(op, Some(OperationName::Routing)) => json = true,
+                Long("json")
+                    if matches!(
+                        op,
+                        Some(
+                            OperationName::Routing
+                                | OperationName::Metrics
+                                | OperationName::Addresses
+                                | OperationName::Inventory
+                                | OperationName::Limits
+                        )
+                    ) =>
+                {
+                    json = true
+                }
                 Long("timeout")
                     if op == Some(OperationName::Events) || op == Some(OperationName::Connect) =>
                 {
@@ -190,6 +287,19 @@ impl Args for Options {
         }
 
         let op = match op.unwrap_or_default() {
+            OperationName::Addresses => Operation::Addresses { json },
+            OperationName::Config => Operation::Config,
+            OperationName::Disconnect => Operation::Disconnect {
+                nid: nid.ok_or_else(|| anyhow!("a Node ID must be provided"))?,
+            },
+            OperationName::Inventory => Operation::Inventory {
+                nid: nid.ok_or_else(|| anyhow!("a Node ID must be provided"))?,
+                json,
+            },
+            OperationName::Limits => Operation::Limits {
+                update: limits,
+                json,
+            },
             OperationName::Connect => Operation::Connect {
                 addr: addr.ok_or_else(|| {
                     anyhow!("an address of the form `<nid>@<host>:<port>` must be provided")
@@ -199,6 +309,7 @@ impl Args for Options {
             OperationName::Events => Operation::Events { timeout, count },
             OperationName::Routing => Operation::Routing { rid, nid, json },
             OperationName::Logs => Operation::Logs { lines },
+            OperationName::Metrics => Operation::Metrics { json },
             OperationName::Start => Operation::Start {
                 foreground,
                 options,
@@ -218,6 +329,11 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     let mut node = Node::new(profile.socket());
 
     match options.op {
+        Operation::Addresses { json } => control::addresses(&node, json)?,
+        Operation::Config => control::config(&node)?,
+        Operation::Disconnect { nid } => control::disconnect(&mut node, nid)?,
+        Operation::Inventory { nid, json } => control::inventory(&node, nid, json)?,
+        Operation::Limits { update, json } => control::limits(&mut node, update, json)?,
         Operation::Connect { addr, timeout } => {
             control::connect(&mut node, addr.id, addr.addr, timeout)?
         }
@@ -230,6 +346,7 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
             routing::run(&store, rid, nid, json)?;
         }
         Operation::Logs { lines } => control::logs(lines, Some(time::Duration::MAX), &profile)?,
+        Operation::Metrics { json } => control::metrics(&node, json)?,
         Operation::Start {
             foreground,
             options,
@@ -4,9 +4,10 @@ use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
 use std::{process, thread, time};
 
 use anyhow::Context as _;
-use localtime::LocalTime;
+use localtime::{LocalDuration, LocalTime};
 
 use radicle::node;
+use radicle::node::address::Store as _;
 use radicle::node::{Address, ConnectResult, Handle as _, NodeId};
 use radicle::Node;
 use radicle::{profile, Profile};
@@ -165,6 +166,20 @@ pub fn status(node: &Node, profile: &Profile) -> anyhow::Result<()> {
         table.print();
     }
 
+    reachability(node)?;
+
+    if let Some(table) = replication(node)? {
+        term::blank();
+        table.print();
+    }
+
+    // Nb. the address book may not exist yet, or may predate penalties. In both cases, there
+    // is nothing to show.
+    if let Ok(Some(table)) = penalties(profile) {
+        term::blank();
+        table.print();
+    }
+
     if profile.home.node().join("node.log").exists() {
         term::blank();
         // If we're running the node via `systemd` for example, there won't be a log file
@@ -174,6 +189,176 @@ pub fn status(node: &Node, profile: &Profile) -> anyhow::Result<()> {
     Ok(())
 }
 
+pub fn reachability(node: &Node) -> Result<(), node::Error> {
+    let reachability = node.reachability()?;
+
+    // Nb. Until a peer dials us back, there is nothing worth showing.
+    let Some(reachable) = reachability.reachable else {
+        return Ok(());
+    };
+    term::blank();
+
+    if reachable {
+        term::info!("Node is {}.", term::format::positive("publicly reachable"));
+    } else {
+        term::info!(
+            "Node is {}.",
+            term::format::negative("not publicly reachable")
+        );
+    }
+    for addr in &reachability.observed {
+        term::info!(
+            "Observed external address: {}",
+            term::format::tertiary(addr)
+        );
+    }
+    if let Some(addr) = &reachability.mapped {
+        term::info!("Mapped external address: {}", term::format::tertiary(addr));
+    }
+    Ok(())
+}
+
+pub fn metrics(node: &Node, json: bool) -> anyhow::Result<()> {
+    let metrics = node.metrics()?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&metrics)?);
+    } else {
+        print!("{}", metrics.encode());
+    }
+    Ok(())
+}
+
+pub fn disconnect(node: &mut Node, nid: NodeId) -> anyhow::Result<()> {
+    node.disconnect(nid)?;
+    term::success!("Disconnected from {}", term::format::node(&nid));
+
+    Ok(())
+}
+
+pub fn addresses(node: &Node, json: bool) -> anyhow::Result<()> {
+    let nodes = node.addresses()?;
+
+    if json {
+        for node in nodes {
+            println!("{}", serde_json::to_string(&node)?);
+        }
+        return Ok(());
+    }
+    let mut table = term::Table::new(term::table::TableOptions::bordered());
+    let now = LocalTime::now();
+
+    table.push([
+        term::format::bold("Peer").into(),
+        term::format::bold("Address").into(),
+        term::format::bold("Source").into(),
+        term::format::bold("Last success").into(),
+    ]);
+    table.divider();
+
+    for node in nodes {
+        let connected = node.is_connected();
+
+        for addr in node.addrs {
+            let nid = if connected {
+                term::format::positive(term::format::node(&node.nid)).into()
+            } else {
+                term::format::tertiary(term::format::node(&node.nid)).into()
+            };
+            let success = match addr.last_success {
+                Some(t) => term::format::dim(format!("{} ago", now - t)).into(),
+                None => term::Label::blank(),
+            };
+            table.push([
+                nid,
+                addr.addr.to_string().into(),
+                term::format::dim(addr.source.to_string()).into(),
+                success,
+            ]);
+        }
+    }
+    table.print();
+
+    Ok(())
+}
+
+pub fn inventory(node: &Node, nid: NodeId, json: bool) -> anyhow::Result<()> {
+    let entries = node.routing(nid)?.into_iter().map(|rid| (rid, nid));
+
+    if json {
+        super::routing::print_json(entries);
+    } else {
+        super::routing::print_table(entries);
+    }
+    Ok(())
+}
+
+pub fn config(node: &Node) -> anyhow::Result<()> {
+    let config = node.config()?;
+    println!("{}", serde_json::to_string_pretty(&config)?);
+
+    Ok(())
+}
+
+/// Changes to the node limits.
+#[derive(Debug, Default)]
+pub struct LimitsUpdate {
+    pub routing_max_size: Option<usize>,
+    pub routing_max_age: Option<time::Duration>,
+    pub gossip_max_age: Option<time::Duration>,
+    pub fetch_concurrency: Option<usize>,
+}
+
+impl LimitsUpdate {
+    /// Whether there are any changes.
+    pub fn is_empty(&self) -> bool {
+        self.routing_max_size.is_none()
+            && self.routing_max_age.is_none()
+            && self.gossip_max_age.is_none()
+            && self.fetch_concurrency.is_none()
+    }
+}
+
+pub fn limits(node: &mut Node, update: LimitsUpdate, json: bool) -> anyhow::Result<()> {
+    let mut limits = node.config()?.limits;
+
+    if !update.is_empty() {
+        if let Some(size) = update.routing_max_size {
+            limits.routing_max_size = size;
+        }
+        if let Some(age) = update.routing_max_age {
+            limits.routing_max_age = LocalDuration::from_secs(age.as_secs());
+        }
+        if let Some(age) = update.gossip_max_age {
+            limits.gossip_max_age = LocalDuration::from_secs(age.as_secs());
+        }
+        if let Some(n) = update.fetch_concurrency {
+            limits.fetch_concurrency = n;
+        }
+        node.update_limits(limits.clone())?;
+    }
+
+    if json {
+        println!("{}", serde_json::to_string(&limits)?);
+        return Ok(());
+    }
+    let mut table = term::Table::<2, term::Label>::new(term::table::TableOptions::bordered());
+    for (key, val) in [
+        ("routingMaxSize", limits.routing_max_size.to_string()),
+        ("routingMaxAge", limits.routing_max_age.to_string()),
+        ("gossipMaxAge", limits.gossip_max_age.to_string()),
+        ("fetchConcurrency", limits.fetch_concurrency.to_string()),
+    ] {
+        table.push([term::format::bold(key).into(), val.into()]);
+    }
+    table.print();
+
+    if !update.is_empty() {
+        term::info!("Limits will be reset when the node restarts.");
+    }
+    Ok(())
+}
+
 pub fn sessions(node: &Node) -> Result<Option<term::Table<4, term::Label>>, node::Error> {
     let sessions = node.sessions()?;
     if sessions.is_empty() {
@@ -218,3 +403,64 @@ pub fn sessions(node: &Node) -> Result<Option<term::Table<4, term::Label>>, node
     }
     Ok(Some(table))
 }
+
+/// Repositories replicated on fewer seeds than the node's configured replication factor.
+pub fn replication(node: &Node) -> Result<Option<term::Table<3, term::Label>>, node::Error> {
+    let replication = node.replication()?;
+    if replication.is_empty() {
+        return Ok(None);
+    }
+    let mut table = term::Table::new(term::table::TableOptions::bordered());
+
+    table.push([
+        term::format::bold("Under-replicated").into(),
+        term::format::bold("Seeds").into(),
+        term::format::bold("Target").into(),
+    ]);
+    table.divider();
+
+    for r in replication {
+        table.push([
+            term::format::tertiary(r.rid).into(),
+            term::format::negative(r.replicas.to_string()).into(),
+            r.target.to_string().into(),
+        ]);
+    }
+    Ok(Some(table))
+}
+
+pub fn penalties(
+    profile: &Profile,
+) -> Result<Option<term::Table<3, term::Label>>, node::address::Error> {
+    let now = LocalTime::now();
+    let reputations = profile
+        .addresses()?
+        .reputations(now.as_millis())?
+        .collect::<Vec<_>>();
+    if reputations.is_empty() {
+        return Ok(None);
+    }
+    let mut table = term::Table::new(term::table::TableOptions::bordered());
+
+    table.push([
+        term::format::bold("Peer").into(),
+        term::format::bold("Penalty").into(),
+        term::format::bold("Banned").into(),
+    ]);
+    table.divider();
+
+    for (nid, reputation) in reputations {
+        let banned = match reputation.banned_until {
+            Some(until) if until > now => {
+                term::format::negative(format!("for {}", until - now)).into()
+            }
+            _ => term::Label::blank(),
+        };
+        table.push([
+            term::format::tertiary(nid).into(),
+            reputation.score.to_string().into(),
+            banned,
+        ]);
+    }
+    Ok(Some(table))
+}
@@ -25,7 +25,7 @@ pub fn run<S: node::routing::Store>(
     Ok(())
 }
 
-fn print_table(entries: impl IntoIterator<Item = (Id, NodeId)>) {
+pub fn print_table(entries: impl IntoIterator<Item = (Id, NodeId)>) {
     let mut t = term::Table::new(term::table::TableOptions::bordered());
     t.push([
         term::format::default(String::from("RID")),
@@ -42,7 +42,7 @@ fn print_table(entries: impl IntoIterator<Item = (Id, NodeId)>) {
     t.print();
 }
 
-fn print_json(entries: impl IntoIterator<Item = (Id, NodeId)>) {
+pub fn print_json(entries: impl IntoIterator<Item = (Id, NodeId)>) {
     for (rid, nid) in entries {
         println!("{}", serde_json::json!({ "rid": rid, "nid": nid }));
     }
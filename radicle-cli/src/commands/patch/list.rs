@@ -103,7 +103,7 @@ pub fn row(
     let (from, to) = patch.range(repository)?;
     let stats = common::diff_stats(repository.raw(), &from, &to)?;
     let author = patch.author().id;
-    let display = Author::new(&author, alias, profile);
+    let display = Author::resolve(&author, alias, profile, repository);
 
     Ok([
         match state {
@@ -139,7 +139,12 @@ pub fn timeline(
         term::format::default("opened by").into(),
     ])
     .space()
-    .extend(Author::new(patch.author().id(), alias, profile));
+    .extend(Author::resolve(
+        patch.author().id(),
+        alias,
+        profile,
+        repository,
+    ));
 
     let mut timeline = vec![(patch.timestamp(), open)];
 
@@ -172,11 +177,17 @@ pub fn timeline(
                 term::format::default("by").into(),
             ])
             .space()
-            .extend(Author::new(&peer.id, alias, profile));
+            .extend(Author::resolve(&peer.id, alias, profile, repository));
 
             timeline.push((merge.timestamp, line));
         }
-        for (reviewer, review) in revision.reviews() {
+        // Only show the reviews that count, ie. one per person.
+        let verdicts = revision.verdicts(repository)?;
+
+        for (reviewer, review) in revision
+            .reviews()
+            .filter(|(reviewer, _)| verdicts.contains_key(reviewer))
+        {
             let verdict = review.verdict();
             let verdict_symbol = match verdict {
                 Some(Verdict::Accept) => term::format::positive("✓"),
@@ -196,7 +207,7 @@ pub fn timeline(
                 term::format::default("by").into(),
             ])
             .space()
-            .extend(Author::new(&peer.id, alias, profile));
+            .extend(Author::resolve(&peer.id, alias, profile, repository));
 
             timeline.push((review.timestamp(), line));
         }
@@ -0,0 +1,187 @@
+use std::ffi::OsString;
+use std::path::Path;
+
+use anyhow::{anyhow, Context as _};
+use nonempty::NonEmpty;
+
+use radicle::crypto::{PublicKey, Signature};
+use radicle::identity::person::{self, Person};
+use radicle::identity::{Doc, Id};
+use radicle::prelude::{Did, Verified};
+use radicle::storage::{SignRepository as _, WriteRepository as _, WriteStorage as _};
+
+use crate::terminal as term;
+use crate::terminal::args;
+use crate::terminal::args::{Args, Error, Help};
+
+pub const HELP: Help = Help {
+    name: "person",
+    description: "Link several devices to one person",
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad person sign --name <name> [--device <did>...] [<option>...]
+    rad person publish --name <name> [--device <did>...] [--signature <did>:<sig>...] [--to <rid>] [<option>...]
+
+    A person identity groups the device keys of a single person. It is
+    published in a repository by one of the devices, and each device listed
+    must sign it to be a member.
+
+    The `sign` command prints this device's signature of the person identity,
+    to be passed to `publish` on the publishing device with `--signature`.
+    The same name and devices must be given to both commands. This device
+    is always included.
+
+    The `publish` command signs the person identity with this device's key,
+    publishes it along with the given signatures, and signs the refs of
+    the repository.
+
+Options
+
+    --name <name>               The person's name
+    --device <did>              Another device of the person
+    --signature <did>:<sig>     The signature of another device
+    --to <rid>                  The repository to publish to (default: cwd)
+    --help                      Print help
+"#,
+};
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub enum OperationName {
+    #[default]
+    Sign,
+    Publish,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Operation {
+    Sign,
+    Publish {
+        id: Option<Id>,
+        signatures: Vec<(PublicKey, Signature)>,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Options {
+    pub op: Operation,
+    pub name: String,
+    pub devices: Vec<Did>,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut op: Option<OperationName> = None;
+        let mut id: Option<Id> = None;
+        let mut name: Option<String> = None;
+        let mut devices = Vec::new();
+        let mut signatures = Vec::new();
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") | Short('h') => {
+                    return Err(Error::Help.into());
+                }
+                Long("name") => {
+                    name = Some(args::string(&parser.value()?));
+                }
+                Long("device") => {
+                    devices.push(args::did(&parser.value()?)?);
+                }
+                Long("signature") => {
+                    let val = args::string(&parser.value()?);
+                    let (did, sig) = val.rsplit_once(':').ok_or_else(|| {
+                        anyhow!("invalid signature '{val}', expected <did>:<sig>")
+                    })?;
+                    let did = Did::decode(did)?;
+                    let sig = sig
+                        .parse::<Signature>()
+                        .map_err(|e| anyhow!("invalid signature '{sig}': {e}"))?;
+
+                    signatures.push((*did, sig));
+                }
+                Long("to") => {
+                    id = Some(args::rid(&parser.value()?)?);
+                }
+                Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
+                    "s" | "sign" => op = Some(OperationName::Sign),
+                    "p" | "publish" => op = Some(OperationName::Publish),
+
+                    unknown => anyhow::bail!("unknown operation '{}'", unknown),
+                },
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        let op = match op.unwrap_or_default() {
+            OperationName::Sign => Operation::Sign,
+            OperationName::Publish => Operation::Publish { id, signatures },
+        };
+        let name = name.ok_or_else(|| anyhow!("a name must be provided with `--name`"))?;
+
+        Ok((Options { op, name, devices }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let signer = term::signer(&profile)?;
+    let doc = document(options.name, *profile.id(), options.devices)?;
+
+    match options.op {
+        Operation::Sign => {
+            let (_, sig) = doc.sign(&signer)?;
+            term::print(format!("{}:{sig}", Did::from(profile.id())));
+        }
+        Operation::Publish { id, signatures } => {
+            let id = match id {
+                Some(id) => id,
+                None => {
+                    let (_, id) = radicle::rad::repo(Path::new("."))
+                        .context("Current directory is not a radicle project")?;
+                    id
+                }
+            };
+            let repo = profile.storage.repository_mut(id)?;
+            let (oid, sig) = doc.sign(&signer)?;
+            let mut sigs = vec![(profile.id(), sig)];
+
+            for (key, sig) in signatures.iter() {
+                if !doc.is_delegate(key) {
+                    anyhow::bail!("'{}' is not a device of this person", Did::from(key));
+                }
+                if key.verify(oid.as_bytes(), sig).is_err() {
+                    anyhow::bail!("invalid signature for '{}'", Did::from(key));
+                }
+                sigs.push((key, *sig));
+            }
+            person::publish(&doc, profile.id(), "Publish person", &sigs, repo.raw())?;
+            repo.sign_refs(&signer)?;
+
+            term::success!(
+                "Published person identity with {} of {} device(s) signed",
+                sigs.len(),
+                doc.delegates.len()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Build the person identity document. Devices are sorted so that every device signs the
+/// same document given the same arguments.
+fn document(name: String, me: PublicKey, devices: Vec<Did>) -> anyhow::Result<Doc<Verified>> {
+    let mut devices = devices;
+    devices.push(Did::from(me));
+    devices.sort();
+    devices.dedup();
+
+    let devices = NonEmpty::from_vec(devices).expect("the device list is not empty");
+    let doc = Doc::new_person(Person::new(name)?, devices).verified()?;
+
+    Ok(doc)
+}
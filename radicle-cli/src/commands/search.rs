@@ -0,0 +1,109 @@
+use std::ffi::OsString;
+
+use radicle::node::{Handle as _, Node};
+use radicle::search::{Kind, DEFAULT_LIMIT};
+
+use crate::terminal as term;
+use crate::terminal::args::{Args, Error, Help};
+
+use term::Element;
+
+pub const HELP: Help = Help {
+    name: "search",
+    description: "Search projects, issues and patches",
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad search <query> [<option>...]
+
+    Searches the names and descriptions of local projects, as well as the
+    titles, descriptions and comments of their issues and patches. Results
+    contain all the words of the query. The last word may be incomplete.
+
+    The search index is updated by the node as repositories change. If the
+    node isn't running, it is updated before searching.
+
+Options
+
+    --limit <n>     Maximum number of results (default: 20)
+    --help          Print help
+"#,
+};
+
+pub struct Options {
+    query: String,
+    limit: usize,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut words = Vec::new();
+        let mut limit = DEFAULT_LIMIT;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") | Short('h') => {
+                    return Err(Error::Help.into());
+                }
+                Long("limit") => {
+                    let value = parser.value()?;
+                    limit = term::args::number(&value)?;
+                }
+                Value(val) => words.push(val.to_string_lossy().into_owned()),
+                _ => return Err(anyhow::anyhow!(arg.unexpected())),
+            }
+        }
+        if words.is_empty() {
+            anyhow::bail!("a search query must be specified");
+        }
+
+        Ok((
+            Options {
+                query: words.join(" "),
+                limit,
+            },
+            vec![],
+        ))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let mut index = profile.search()?;
+
+    // The node keeps the index up to date while it's running.
+    if !Node::new(profile.socket()).is_running() {
+        index.sync(&profile.storage)?;
+    }
+
+    let results = index.search(&options.query, options.limit)?;
+    if results.is_empty() {
+        term::info!("No results found for {}", term::format::dim(&options.query));
+        return Ok(());
+    }
+    let mut table = term::Table::default();
+
+    for result in results {
+        let id = match result.id {
+            Some(id) => term::format::secondary(term::format::cob(&id)),
+            None => term::format::tertiary(result.rid.urn()),
+        };
+        let kind = match result.kind {
+            Kind::Project => term::format::positive(result.kind.to_string()),
+            Kind::Issue | Kind::Patch => term::format::yellow(result.kind.to_string()),
+        };
+        table.push([
+            kind,
+            id,
+            term::format::bold(result.title),
+            term::format::italic(result.snippet),
+        ]);
+    }
+    table.print();
+
+    Ok(())
+}
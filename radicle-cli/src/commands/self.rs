@@ -1,6 +1,14 @@
 use std::ffi::OsString;
+use std::fs;
+use std::ops::Not as _;
+
+use localtime::LocalTime;
 
 use radicle::crypto::ssh;
+use radicle::crypto::KeyPair;
+use radicle::node::succession::Succession;
+use radicle::node::{self, Handle as _};
+use radicle::profile::env::RAD_PASSPHRASE;
 use radicle::Profile;
 
 use crate::terminal as term;
@@ -15,6 +23,14 @@ pub const HELP: Help = Help {
 Usage
 
     rad self [<option>...]
+    rad self rotate [--no-confirm]
+
+    The `rotate` command replaces your node key with a new one, and records
+    a succession signed by the old key. The succession is announced to the
+    network the next time your node starts, so that other nodes can carry
+    over their tracking policies and aliases to your new key. Your node must
+    be stopped while the key is rotated. The old key is kept in your keys
+    folder.
 
 Options
 
@@ -25,6 +41,7 @@ Options
     --config             Show the location of your configuration file
     --ssh-key            Show your public key in OpenSSH format
     --ssh-fingerprint    Show your public key fingerprint in OpenSSH format
+    --no-confirm         Don't ask for confirmation when rotating your key
     --help               Show help
 "#,
 };
@@ -44,6 +61,8 @@ enum Show {
 #[derive(Debug)]
 pub struct Options {
     show: Show,
+    rotate: bool,
+    confirm: bool,
 }
 
 impl Args for Options {
@@ -52,6 +71,8 @@ impl Args for Options {
 
         let mut parser = lexopt::Parser::from_args(args);
         let mut show: Option<Show> = None;
+        let mut rotate = false;
+        let mut confirm = true;
 
         while let Some(arg) = parser.next()? {
             match arg {
@@ -76,6 +97,12 @@ impl Args for Options {
                 Long("ssh-fingerprint") if show.is_none() => {
                     show = Some(Show::SshFingerprint);
                 }
+                Value(val) if val == "rotate" && show.is_none() => {
+                    rotate = true;
+                }
+                Long("no-confirm") => {
+                    confirm = false;
+                }
                 Long("help") | Short('h') => {
                     return Err(Error::Help.into());
                 }
@@ -86,6 +113,8 @@ impl Args for Options {
         Ok((
             Options {
                 show: show.unwrap_or(Show::All),
+                rotate,
+                confirm,
             },
             vec![],
         ))
@@ -95,6 +124,10 @@ impl Args for Options {
 pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     let profile = ctx.profile()?;
 
+    if options.rotate {
+        return rotate(&profile, options.confirm);
+    }
+
     match options.show {
         Show::Alias => {
             term::print(profile.config.alias());
@@ -201,3 +234,50 @@ fn all(profile: &Profile) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+fn rotate(profile: &Profile, confirm: bool) -> anyhow::Result<()> {
+    let node = radicle::Node::new(profile.socket());
+    if node.is_running() {
+        anyhow::bail!(
+            "your node must be stopped to rotate its key; run {} first",
+            term::format::command("rad node stop")
+        );
+    }
+    let old = *profile.id();
+
+    if confirm
+        && !term::confirm(format!(
+            "Rotate your key {}? Repositories you are a delegate of will need your new key added",
+            term::format::tertiary(old)
+        ))
+    {
+        return Ok(());
+    }
+    // Sign the succession with the old key, before it's replaced, and co-sign it with
+    // the new key.
+    let signer = profile.signer()?;
+    let passphrase =
+        term::passphrase_confirm("Enter a passphrase for your new key:", RAD_PASSPHRASE)?;
+    let passphrase = passphrase.trim().is_empty().not().then_some(passphrase);
+    let keypair = KeyPair::generate();
+    let successor = ssh::keystore::MemorySigner::from(keypair.clone());
+    let succession = Succession::new(LocalTime::now().as_millis(), &signer, &successor);
+
+    profile.keystore.rotate(keypair, "radicle", passphrase)?;
+
+    fs::create_dir_all(profile.home.node())?;
+    succession.write(&profile.home.node().join(node::SUCCESSION_FILE))?;
+
+    term::success!(
+        "Your key {} was succeeded by {}",
+        term::format::tertiary(succession.old),
+        term::format::highlight(succession.new)
+    );
+    term::blank();
+    term::info!(
+        "Run {} to add your new key to ssh-agent, and start your node to announce the succession.",
+        term::format::command("rad auth")
+    );
+
+    Ok(())
+}
@@ -0,0 +1,184 @@
+use std::ffi::OsString;
+
+use chrono::prelude::*;
+
+use radicle::identity::Id;
+use radicle::stats;
+use radicle::storage::ReadStorage;
+
+use crate::terminal as term;
+use crate::terminal::args::{Args, Error, Help};
+
+use term::Element;
+
+/// Number of commit activity periods shown.
+const ACTIVITY_PERIODS: usize = 8;
+/// Number of contributors shown.
+const CONTRIBUTORS: usize = 10;
+
+pub const HELP: Help = Help {
+    name: "stats",
+    description: "Show node and repository statistics",
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad stats [<rid>] [<option>...]
+
+    Without a repository, shows the number of repositories, remotes and
+    contributors across the local node. With a repository, shows its
+    contributors, commit activity and issue and patch statistics.
+
+    Contributors are identified by the keys that authored issues, patches
+    or signed commits on the default branch.
+
+Options
+
+    --help          Print help
+"#,
+};
+
+pub struct Options {
+    rid: Option<Id>,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut rid = None;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") | Short('h') => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if rid.is_none() => {
+                    rid = Some(term::args::rid(&val)?);
+                }
+                _ => return Err(anyhow::anyhow!(arg.unexpected())),
+            }
+        }
+
+        Ok((Options { rid }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let storage = &profile.storage;
+
+    let Some(rid) = options.rid else {
+        let stats = stats::node(storage)?;
+        let mut table = term::Table::default();
+
+        table.push(row("Projects", stats.projects.to_string()));
+        table.push(row("Remotes", stats.remotes.to_string()));
+        table.push(row("Contributors", stats.contributors.to_string()));
+        table.print();
+
+        return Ok(());
+    };
+    let repo = storage.repository(rid)?;
+    let stats = stats::repo(&repo)?;
+    let mut table = term::Table::default();
+
+    table.push(row("Remotes", stats.remotes.to_string()));
+    table.push(row("Contributors", stats.contributors.len().to_string()));
+    table.push(row("Commits", stats.commits.total.to_string()));
+    table.push(row(
+        "Issues",
+        format!(
+            "{} open, {} closed ({} closed)",
+            stats.issues.open,
+            stats.issues.closed,
+            percent(stats.issues.close_rate)
+        ),
+    ));
+    table.push(row(
+        "Patches",
+        format!(
+            "{} open, {} draft, {} archived, {} merged ({} merged)",
+            stats.patches.open,
+            stats.patches.draft,
+            stats.patches.archived,
+            stats.patches.merged,
+            percent(stats.patches.merge_rate)
+        ),
+    ));
+    if let Some(secs) = stats.patches.average_time_to_merge {
+        table.push(row(
+            "Time to merge",
+            format!("{} on average", duration(secs)),
+        ));
+    }
+    table.print();
+
+    if !stats.contributors.is_empty() {
+        term::blank();
+        term::print(term::format::bold("Top contributors"));
+
+        let mut table = term::Table::default();
+        for contributor in stats.contributors.iter().take(CONTRIBUTORS) {
+            table.push([
+                term::format::tertiary(contributor.id.to_string()),
+                term::format::default(format!("{} commit(s)", contributor.commits)),
+                term::format::default(format!("{} patch(es)", contributor.patches)),
+                term::format::default(format!("{} issue(s)", contributor.issues)),
+            ]);
+        }
+        table.print();
+    }
+
+    if !stats.commits.activity.is_empty() {
+        term::blank();
+        term::print(term::format::bold("Recent commit activity"));
+
+        let activity = &stats.commits.activity;
+        let recent = &activity[activity.len().saturating_sub(ACTIVITY_PERIODS)..];
+        let max = recent.iter().map(|a| a.commits).max().unwrap_or(1);
+        let mut table = term::Table::default();
+
+        for activity in recent {
+            let week = NaiveDateTime::from_timestamp_opt(activity.time as i64, 0)
+                .map(|t| t.format("%Y-%m-%d").to_string())
+                .unwrap_or_default();
+            let bar = "▇".repeat((activity.commits * 20 + max - 1) / max);
+
+            table.push([
+                term::format::dim(format!("Week of {week}")),
+                term::format::positive(bar),
+                term::format::default(activity.commits.to_string()),
+            ]);
+        }
+        table.print();
+    }
+
+    Ok(())
+}
+
+fn row(label: &str, value: String) -> [term::Paint<String>; 2] {
+    [
+        term::format::dim(label.to_owned()),
+        term::format::default(value),
+    ]
+}
+
+fn percent(rate: f64) -> String {
+    format!("{:.0}%", rate * 100.)
+}
+
+fn duration(secs: u64) -> String {
+    const MINUTE: u64 = 60;
+    const HOUR: u64 = 60 * MINUTE;
+    const DAY: u64 = 24 * HOUR;
+
+    if secs >= DAY {
+        format!("{:.1} days", secs as f64 / DAY as f64)
+    } else if secs >= HOUR {
+        format!("{:.1} hours", secs as f64 / HOUR as f64)
+    } else {
+        format!("{} minutes", secs / MINUTE)
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::BTreeSet;
+use std::ffi::OsString;
+
+use anyhow::anyhow;
+
+use radicle::identity::Id;
+use radicle::tokens::Scope;
+
+use crate::terminal as term;
+use crate::terminal::args::{Args, Error, Help};
+
+use term::Element;
+
+pub const HELP: Help = Help {
+    name: "token",
+    description: "Manage API tokens",
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad token create <name> --scope <scope>... [--repo <rid>] [<option>...]
+    rad token revoke <id> [<option>...]
+    rad token list [<option>...]
+
+    API tokens let non-interactive clients, eg. CI bots, use the HTTP API
+    of `radicle-httpd` without signing in. Tokens don't expire, so make
+    sure to revoke the ones you no longer need.
+
+    The token secret is only shown once, when the token is created.
+
+Scopes
+
+    read                List webhooks and their deliveries
+    issue-write         Open, comment on and update issues
+    patch-write         Open, comment on, review and update patches
+
+Create options
+
+    --scope <scope>     Grant a scope to the token (may be specified multiple times)
+    --repo <rid>        Restrict the token to a single repository
+
+Options
+
+    --help              Print help
+"#,
+};
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub enum OperationName {
+    Create,
+    Revoke,
+    #[default]
+    List,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum Operation {
+    Create {
+        name: String,
+        scopes: BTreeSet<Scope>,
+        rid: Option<Id>,
+    },
+    Revoke {
+        id: String,
+    },
+    List,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct Options {
+    pub op: Operation,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut op: Option<OperationName> = None;
+        let mut value: Option<String> = None;
+        let mut scopes = BTreeSet::new();
+        let mut rid: Option<Id> = None;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") | Short('h') => {
+                    return Err(Error::Help.into());
+                }
+                Long("scope") if op == Some(OperationName::Create) => {
+                    let val = parser.value()?;
+                    scopes.insert(val.to_string_lossy().parse::<Scope>()?);
+                }
+                Long("repo") if op == Some(OperationName::Create) => {
+                    let val = parser.value()?;
+                    rid = Some(term::args::rid(&val)?);
+                }
+                Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
+                    "c" | "create" => op = Some(OperationName::Create),
+                    "r" | "revoke" => op = Some(OperationName::Revoke),
+                    "l" | "list" => op = Some(OperationName::List),
+
+                    unknown => anyhow::bail!("unknown operation '{}'", unknown),
+                },
+                Value(val) if value.is_none() && op != Some(OperationName::List) => {
+                    value = Some(val.to_string_lossy().into_owned());
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        let op = match op.unwrap_or_default() {
+            OperationName::List => Operation::List,
+            OperationName::Create => {
+                if scopes.is_empty() {
+                    anyhow::bail!("at least one scope must be specified with `--scope`");
+                }
+                Operation::Create {
+                    name: value.ok_or_else(|| anyhow!("a token name must be provided"))?,
+                    scopes,
+                    rid,
+                }
+            }
+            OperationName::Revoke => Operation::Revoke {
+                id: value.ok_or_else(|| anyhow!("a token id must be provided"))?,
+            },
+        };
+
+        Ok((Options { op }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let mut tokens = profile.tokens()?;
+
+    match options.op {
+        Operation::Create { name, scopes, rid } => {
+            let (token, secret) = tokens.create(&name, scopes, rid)?;
+
+            term::success!(
+                "Created token {} ({})",
+                term::format::highlight(&token.id),
+                term::format::dim(&token.name)
+            );
+            term::blob(secret);
+            term::tip!("Make sure to copy the token now, it won't be shown again.");
+        }
+        Operation::Revoke { id } => {
+            if !tokens.revoke(&id)? {
+                anyhow::bail!("token '{id}' not found");
+            }
+            term::success!("Revoked token {}", term::format::highlight(&id));
+        }
+        Operation::List => {
+            let tokens = tokens.tokens()?;
+            if tokens.is_empty() {
+                term::info!("No tokens found");
+                return Ok(());
+            }
+            let mut table = term::Table::default();
+
+            for token in tokens {
+                let scopes = token
+                    .scopes
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let repo = match token.rid {
+                    Some(rid) => term::format::tertiary(rid.urn()),
+                    None => term::format::dim(String::from("all repositories")),
+                };
+                table.push([
+                    term::format::highlight(token.id),
+                    term::format::bold(token.name),
+                    term::format::yellow(scopes),
+                    repo,
+                    term::format::timestamp(&token.created_at),
+                ]);
+            }
+            table.print();
+        }
+    }
+
+    Ok(())
+}
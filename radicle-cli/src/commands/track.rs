@@ -19,6 +19,7 @@ pub const HELP: Help = Help {
 Usage
 
     rad track <nid> [--alias <name>] [<option>...]
+    rad track <nid> --repo <rid> [<option>...]
     rad track <rid> [--[no-]fetch] [--scope <scope>] [<option>...]
 
     The `track` command takes either an NID or an RID. Based on the argument, it will
@@ -29,9 +30,14 @@ Usage
     On the other hand, with `trusted`, only the repository delegates will be tracked,
     plus any remote that is explicitly tracked via `rad track <nid>`.
 
+    When tracking a node with `--repo`, the node is tracked as a remote of the given
+    repository only, regardless of the repository's scope. This also lifts any block
+    on that remote set via `rad untrack <nid> --repo <rid> --block`.
+
 Options
 
     --alias <name>         Associate an alias to a tracked node
+    --repo <rid>           Only track the node as a remote of the given repository
     --[no-]fetch           Fetch refs after tracking
     --scope <scope>        Node (remote) tracking scope for a repository
     --verbose, -v          Verbose output
@@ -42,6 +48,7 @@ Options
 #[derive(Debug)]
 pub enum Operation {
     TrackNode { nid: NodeId, alias: Option<Alias> },
+    TrackRemote { rid: Id, nid: NodeId },
     TrackRepo { rid: Id, scope: Scope },
 }
 
@@ -84,6 +91,12 @@ impl Args for Options {
 
                     *alias = Some(name.to_owned());
                 }
+                (Long("repo"), Some(Operation::TrackNode { nid, alias: None })) => {
+                    let val = parser.value()?;
+                    let rid = term::args::rid(&val)?;
+
+                    op = Some(Operation::TrackRemote { rid, nid: *nid });
+                }
                 (Long("scope"), Some(Operation::TrackRepo { scope, .. })) => {
                     let val = parser.value()?;
 
@@ -120,6 +133,9 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
         Operation::TrackNode { nid, alias } => {
             track_node(nid, alias, &mut node)?;
         }
+        Operation::TrackRemote { rid, nid } => {
+            track_remote(rid, nid, &mut node)?;
+        }
         Operation::TrackRepo { rid, scope } => {
             track_repo(rid, scope, &mut node)?;
 
@@ -148,6 +164,19 @@ pub fn track_repo(rid: Id, scope: Scope, node: &mut Node) -> anyhow::Result<()>
     Ok(())
 }
 
+pub fn track_remote(rid: Id, nid: NodeId, node: &mut Node) -> anyhow::Result<()> {
+    let tracked = node.track_remote(rid, nid)?;
+    let outcome = if tracked { "updated" } else { "exists" };
+
+    term::success!(
+        "Tracking policy {outcome} for {} in {}",
+        term::format::tertiary(nid),
+        term::format::tertiary(rid),
+    );
+
+    Ok(())
+}
+
 pub fn track_node(nid: NodeId, alias: Option<Alias>, node: &mut Node) -> anyhow::Result<()> {
     let tracked = node.track_node(nid, alias.clone())?;
     let outcome = if tracked { "updated" } else { "exists" };
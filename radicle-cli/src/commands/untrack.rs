@@ -16,13 +16,21 @@ pub const HELP: Help = Help {
 Usage
 
     rad untrack <nid> [<option>...]
+    rad untrack <nid> --repo <rid> [--block] [<option>...]
     rad untrack <rid> [<option>...]
 
     The `untrack` command takes either an NID or an RID. Based on the argument, it will
     either update the tracking policy of a node (NID), or a repository (RID).
 
+    When untracking a node with `--repo`, only the policy of the node as a remote of the
+    given repository is removed. With `--block`, the remote is instead blocked for that
+    repository, even if the repository is tracked with the `all` scope, or the node is
+    tracked.
+
 Options
 
+    --repo <rid>           Only untrack the node as a remote of the given repository
+    --block                Block the remote for the given repository
     --verbose, -v          Verbose output
     --help                 Print help
 "#,
@@ -31,6 +39,7 @@ Options
 #[derive(Debug)]
 pub enum Operation {
     UntrackNode { nid: NodeId },
+    UntrackRemote { rid: Id, nid: NodeId, block: bool },
     UntrackRepo { rid: Id },
 }
 
@@ -59,6 +68,17 @@ impl Args for Options {
                         op = Some(Operation::UntrackNode { nid });
                     }
                 }
+                (Long("repo"), Some(Operation::UntrackNode { nid })) => {
+                    let val = parser.value()?;
+                    let rid = term::args::rid(&val)?;
+
+                    op = Some(Operation::UntrackRemote {
+                        rid,
+                        nid: *nid,
+                        block: false,
+                    });
+                }
+                (Long("block"), Some(Operation::UntrackRemote { block, .. })) => *block = true,
                 (Long("verbose") | Short('v'), _) => verbose = true,
                 (Long("help") | Short('h'), _) => {
                     return Err(Error::Help.into());
@@ -85,6 +105,7 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
 
     match options.op {
         Operation::UntrackNode { nid } => untrack_node(nid, &mut node),
+        Operation::UntrackRemote { rid, nid, block } => untrack_remote(rid, nid, block, &mut node),
         Operation::UntrackRepo { rid } => untrack_repo(rid, &mut node),
     }?;
 
@@ -102,6 +123,26 @@ pub fn untrack_repo(rid: Id, node: &mut Node) -> anyhow::Result<()> {
     Ok(())
 }
 
+pub fn untrack_remote(rid: Id, nid: NodeId, block: bool, node: &mut Node) -> anyhow::Result<()> {
+    if block {
+        let blocked = node.block_remote(rid, nid)?;
+        let outcome = if blocked { "updated" } else { "exists" };
+
+        term::success!(
+            "Blocking policy {outcome} for {} in {}",
+            term::format::tertiary(nid),
+            term::format::tertiary(rid),
+        );
+    } else if node.untrack_remote(rid, nid)? {
+        term::success!(
+            "Tracking policy for {} in {} removed",
+            term::format::tertiary(nid),
+            term::format::tertiary(rid),
+        );
+    }
+    Ok(())
+}
+
 pub fn untrack_node(nid: NodeId, node: &mut Node) -> anyhow::Result<()> {
     let untracked = node.untrack_node(nid)?;
     if untracked {
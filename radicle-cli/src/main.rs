@@ -241,6 +241,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "person" => {
+            term::run_command_args::<rad_person::Options, _>(
+                rad_person::HELP,
+                "Person",
+                rad_person::run,
+                args.to_vec(),
+            );
+        }
         "review" => {
             term::run_command_args::<rad_review::Options, _>(
                 rad_review::HELP,
@@ -257,6 +265,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "search" => {
+            term::run_command_args::<rad_search::Options, _>(
+                rad_search::HELP,
+                "Search",
+                rad_search::run,
+                args.to_vec(),
+            );
+        }
         "self" => {
             term::run_command_args::<rad_self::Options, _>(
                 rad_self::HELP,
@@ -265,6 +281,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "stats" => {
+            term::run_command_args::<rad_stats::Options, _>(
+                rad_stats::HELP,
+                "Stats",
+                rad_stats::run,
+                args.to_vec(),
+            );
+        }
         "sync" => {
             term::run_command_args::<rad_sync::Options, _>(
                 rad_sync::HELP,
@@ -273,6 +297,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "token" => {
+            term::run_command_args::<rad_token::Options, _>(
+                rad_token::HELP,
+                "Token",
+                rad_token::run,
+                args.to_vec(),
+            );
+        }
         "label" => {
             term::run_command_args::<rad_label::Options, _>(
                 rad_label::HELP,
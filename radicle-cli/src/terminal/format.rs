@@ -4,9 +4,11 @@ pub use radicle_term::format::*;
 pub use radicle_term::{style, Paint};
 
 use radicle::cob::{ObjectId, Timestamp};
+use radicle::identity::person::Persona;
 use radicle::node::{Alias, AliasStore, NodeId};
 use radicle::prelude::Did;
 use radicle::profile::Profile;
+use radicle::storage::ReadRepository;
 use radicle_term::element::Line;
 
 use crate::terminal as term;
@@ -143,15 +145,17 @@ impl<'a> fmt::Display for Identity<'a> {
 pub enum Author<'a> {
     Author {
         nid: &'a NodeId,
-        alias: Option<Alias>,
+        alias: Option<String>,
     },
     Me {
-        alias: Option<Alias>,
+        alias: Option<String>,
     },
 }
 
 impl<'a> Author<'a> {
     pub fn new(nid: &'a NodeId, alias: Option<Alias>, me: &Profile) -> Author<'a> {
+        let alias = alias.map(|a| a.to_string());
+
         if nid == me.id() {
             Self::Me { alias }
         } else {
@@ -159,6 +163,29 @@ impl<'a> Author<'a> {
         }
     }
 
+    /// Like [`Author::new`], but resolves the key to its person identity in the given
+    /// repository: the person's name is shown instead of the node alias, and all of the
+    /// user's devices are shown as "you".
+    pub fn resolve<R: ReadRepository>(
+        nid: &'a NodeId,
+        alias: Option<Alias>,
+        me: &Profile,
+        repo: &R,
+    ) -> Author<'a> {
+        match Persona::load(nid, repo) {
+            Ok(Some(persona)) => {
+                let alias = Some(persona.person.name().to_owned());
+
+                if persona.is_device(me.id()) {
+                    Self::Me { alias }
+                } else {
+                    Self::Author { nid, alias }
+                }
+            }
+            _ => Self::new(nid, alias, me),
+        }
+    }
+
     /// Author: `<alias>` || ``
     /// Me    : `<alias> (you)` || `(you)`
     pub fn alias(&self) -> Line {
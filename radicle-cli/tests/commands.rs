@@ -214,6 +214,31 @@ fn rad_delegate() {
     test("examples/rad-delegate.md", working.path(), Some(home), []).unwrap();
 }
 
+#[test]
+fn rad_person() {
+    let mut environment = Environment::new();
+    let profile = environment.profile("alice");
+    let working = tempfile::tempdir().unwrap();
+    let home = &profile.home;
+
+    // Setup a test repository.
+    fixtures::repository(working.path());
+
+    test("examples/rad-init.md", working.path(), Some(home), []).unwrap();
+
+    // The person's second device.
+    let device = environment.profile("bob");
+
+    test(
+        "examples/rad-person-sign.md",
+        working.path(),
+        Some(&device.home),
+        [],
+    )
+    .unwrap();
+    test("examples/rad-person.md", working.path(), Some(home), []).unwrap();
+}
+
 #[test]
 fn rad_id() {
     let mut environment = Environment::new();
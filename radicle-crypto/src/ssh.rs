@@ -23,6 +23,8 @@ pub enum ExtendedSignatureError {
     Crypto(#[from] crypto::Error),
     #[error("unsupported signature algorithm")]
     UnsupportedAlgorithm,
+    #[error("unexpected signature namespace '{0}'")]
+    Namespace(String),
 }
 
 /// Signature with public key, used for SSH signing.
@@ -72,6 +74,53 @@ impl ExtendedSignature {
     pub fn verify(&self, payload: &[u8]) -> bool {
         self.key.verify(payload, &self.sig).is_ok()
     }
+
+    /// Verify an OpenSSH PEM signature over a message, as created by `ssh-keygen -Y sign`.
+    /// This is how git signs commits with SSH keys, under the `git` namespace.
+    ///
+    /// Unlike [`ExtendedSignature::verify`], the signature is over the hash of the message,
+    /// wrapped as specified by the `SSHSIG` format. Returns the signature if it is valid.
+    pub fn verify_pem(
+        pem: impl AsRef<[u8]>,
+        namespace: &str,
+        msg: &[u8],
+    ) -> Result<Self, ExtendedSignatureError> {
+        let pem = pem.as_ref();
+        let sshsig = ssh_key::SshSig::from_pem(pem)?;
+
+        if sshsig.namespace() != namespace {
+            return Err(ExtendedSignatureError::Namespace(
+                sshsig.namespace().to_owned(),
+            ));
+        }
+        let signed = ssh_key::SshSig::signed_data(namespace, sshsig.hash_alg(), msg)?;
+        let signature = Self::from_pem(pem)?;
+        signature.key.verify(signed, &signature.sig)?;
+
+        Ok(signature)
+    }
+
+    /// Sign a message under the given namespace, the way `ssh-keygen -Y sign` does.
+    /// This is the inverse of [`ExtendedSignature::verify_pem`].
+    pub fn sign_pem<G: crypto::Signer>(
+        signer: &G,
+        namespace: &str,
+        msg: &[u8],
+    ) -> Result<String, ExtendedSignatureError> {
+        let hash = ssh_key::HashAlg::Sha256;
+        let signed = ssh_key::SshSig::signed_data(namespace, hash, msg)?;
+        let sig = signer.sign(&signed);
+        let key = *signer.public_key();
+
+        ssh_key::SshSig::new(
+            ssh_key::public::KeyData::from(ssh_key::public::Ed25519PublicKey(**key)),
+            String::from(namespace),
+            hash,
+            ssh_key::Signature::new(ssh_key::Algorithm::Ed25519, **sig)?,
+        )?
+        .to_pem(ssh_key::LineEnding::default())
+        .map_err(ExtendedSignatureError::from)
+    }
 }
 
 pub mod fmt {
@@ -281,6 +330,42 @@ mod test {
         assert_eq!(sk, output);
     }
 
+    #[test]
+    fn test_verify_pem() {
+        // Created with `ssh-keygen -Y sign -n git`.
+        let pem = "\
+-----BEGIN SSH SIGNATURE-----
+U1NIU0lHAAAAAQAAADMAAAALc3NoLWVkMjU1MTkAAAAgb3AYirZDDDJ/Y/GkpJsnGtrq4T
+xs4NGsjvJHWpjQnv4AAAADZ2l0AAAAAAAAAAZzaGE1MTIAAABTAAAAC3NzaC1lZDI1NTE5
+AAAAQKuDd+DTXVU+N/z6lyYE/Lx7FU+hm31Aoi6QdYkW6/isjzSALRkD8+BEUm5TjGvXxx
+PlyB5Z5v3fTLYWZK7JVA4=
+-----END SSH SIGNATURE-----
+";
+        let msg = b"tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\n\nmessage\n";
+        let sig = super::ExtendedSignature::verify_pem(pem, "git", msg).unwrap();
+
+        assert_eq!(
+            super::fmt::key(&sig.key),
+            "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIG9wGIq2Qwwyf2PxpKSbJxra6uE8bODRrI7yR1qY0J7+"
+        );
+        assert!(super::ExtendedSignature::verify_pem(pem, "file", msg).is_err());
+        assert!(super::ExtendedSignature::verify_pem(pem, "git", b"message\n").is_err());
+    }
+
+    #[test]
+    fn test_sign_pem() {
+        use crate::test::signer::MockSigner;
+        use crate::Signer as _;
+
+        let signer = MockSigner::from_seed([0xff; 32]);
+        let msg = b"tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\n\nmessage\n";
+        let pem = super::ExtendedSignature::sign_pem(&signer, "git", msg).unwrap();
+        let sig = super::ExtendedSignature::verify_pem(&pem, "git", msg).unwrap();
+
+        assert_eq!(&sig.key, signer.public_key());
+        assert!(super::ExtendedSignature::verify_pem(&pem, "file", msg).is_err());
+    }
+
     #[test]
     fn test_agent_encoding_remove() {
         use std::str::FromStr;
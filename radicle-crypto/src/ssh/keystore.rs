@@ -23,6 +23,8 @@ pub enum Error {
     InvalidKeyType,
     #[error("keystore already initialized")]
     AlreadyInitialized,
+    #[error("keystore not initialized")]
+    NotInitialized,
     #[error("keystore is encrypted; a passphrase is required")]
     PassphraseMissing,
 }
@@ -97,6 +99,36 @@ impl Keystore {
         Ok(keypair.pk.into())
     }
 
+    /// Replace the stored key pair with a new one. The old key pair is kept in the store,
+    /// under a name suffixed with its public key, eg. `radicle.z6Mk...` and
+    /// `radicle.z6Mk....pub`.
+    ///
+    /// Returns the old public key.
+    pub fn rotate(
+        &self,
+        keypair: KeyPair,
+        comment: &str,
+        passphrase: Option<Passphrase>,
+    ) -> Result<PublicKey, Error> {
+        let old = self.public_key()?.ok_or(Error::NotInitialized)?;
+        let secret = self.path.join("radicle");
+        let public = self.path.join("radicle.pub");
+        let archived = self.path.join(format!("radicle.{old}"));
+        let archived_pub = self.path.join(format!("radicle.{old}.pub"));
+
+        fs::rename(&secret, &archived)?;
+        fs::rename(&public, &archived_pub)?;
+
+        if let Err(e) = self.store(keypair, comment, passphrase) {
+            // Put the old key back in place, so that we're not left without a key.
+            fs::rename(&archived, &secret)?;
+            fs::rename(&archived_pub, &public)?;
+
+            return Err(e);
+        }
+        Ok(old)
+    }
+
     /// Load the public key from the store. Returns `None` if it wasn't found.
     pub fn public_key(&self) -> Result<Option<PublicKey>, Error> {
         let path = self.path.join("radicle.pub");
@@ -239,7 +271,12 @@ impl MemorySigner {
 
     /// Generate a new memory signer.
     pub fn gen() -> Self {
-        let keypair = KeyPair::generate();
+        Self::from(KeyPair::generate())
+    }
+}
+
+impl From<KeyPair> for MemorySigner {
+    fn from(keypair: KeyPair) -> Self {
         let sk = keypair.sk;
 
         Self {
@@ -301,6 +338,27 @@ mod tests {
         assert_eq!(PublicKey::from(secret.public_key()), public);
     }
 
+    #[test]
+    fn test_rotate() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = Keystore::new(&tmp.path());
+
+        assert!(matches!(
+            store.rotate(KeyPair::generate(), "test", None),
+            Err(Error::NotInitialized)
+        ));
+        let old = store.init("test", None).unwrap();
+        let new = KeyPair::generate();
+
+        assert_eq!(store.rotate(new.clone(), "test", None).unwrap(), old);
+        assert_eq!(
+            store.public_key().unwrap().unwrap(),
+            PublicKey::from(new.pk)
+        );
+        assert!(tmp.path().join(format!("radicle.{old}")).exists());
+        assert!(tmp.path().join(format!("radicle.{old}.pub")).exists());
+    }
+
     #[test]
     fn test_signer() {
         let tmp = tempfile::tempdir().unwrap();
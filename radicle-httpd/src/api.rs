@@ -1,6 +1,5 @@
 pub mod auth;
 
-use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
@@ -12,7 +11,7 @@ use axum::routing::get;
 use axum::Router;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use tokio::sync::RwLock;
+use tokio::sync::Mutex;
 use tower_http::cors::{self, CorsLayer};
 
 use radicle::cob::issue;
@@ -24,32 +23,37 @@ use radicle::Profile;
 
 mod error;
 mod json;
-mod v1;
+pub(crate) mod v1;
 
 use crate::cache::Cache;
 use crate::Options;
 
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
-/// Identifier for sessions
-type SessionId = String;
-
 #[derive(Clone)]
 pub struct Context {
     profile: Arc<Profile>,
-    sessions: Arc<RwLock<HashMap<SessionId, auth::Session>>>,
+    sessions: Arc<Mutex<auth::Sessions>>,
     cache: Option<Cache>,
+    /// Repository statistics, computed again only when repositories change.
+    stats: Arc<radicle::stats::Cache>,
+    /// Node events, shared by clients.
+    events: v1::events::Hub,
     address: SocketAddr,
 }
 
 impl Context {
-    pub fn new(profile: Arc<Profile>, options: &Options) -> Self {
-        Self {
+    pub fn new(profile: Arc<Profile>, options: &Options) -> Result<Self, auth::store::Error> {
+        let sessions = auth::Sessions::open(profile.home.node().join(auth::SESSIONS_DB_FILE))?;
+
+        Ok(Self {
             profile,
-            sessions: Default::default(),
+            sessions: Arc::new(Mutex::new(sessions)),
             cache: options.cache.map(Cache::new),
+            stats: Arc::default(),
+            events: v1::events::Hub::default(),
             address: options.listen,
-        }
+        })
     }
 
     pub fn project_info(&self, id: Id) -> Result<project::Info, error::Error> {
@@ -75,13 +79,16 @@ impl Context {
         })
     }
 
-    #[cfg(test)]
     pub fn profile(&self) -> &Arc<Profile> {
         &self.profile
     }
 
+    pub fn stats(&self) -> &Arc<radicle::stats::Cache> {
+        &self.stats
+    }
+
     #[cfg(test)]
-    pub fn sessions(&self) -> &Arc<RwLock<HashMap<SessionId, auth::Session>>> {
+    pub fn sessions(&self) -> &Arc<Mutex<auth::Sessions>> {
         &self.sessions
     }
 }
@@ -1,12 +1,18 @@
+pub mod store;
+
 use serde::{Deserialize, Serialize};
 use time::serde::timestamp;
 use time::{Duration, OffsetDateTime};
 
 use radicle::crypto::PublicKey;
+use radicle::identity::Id;
+use radicle::tokens::Scope;
 
 use crate::api::error::Error;
 use crate::api::Context;
 
+pub use store::{Sessions, SESSIONS_DB_FILE};
+
 pub const UNAUTHORIZED_SESSIONS_EXPIRATION: Duration = Duration::seconds(60);
 pub const AUTHORIZED_SESSIONS_EXPIRATION: Duration = Duration::weeks(1);
 
@@ -28,15 +34,35 @@ pub struct Session {
     pub expires_at: OffsetDateTime,
 }
 
-pub async fn validate(ctx: &Context, token: &str) -> Result<(), Error> {
-    let sessions_store = ctx.sessions.read().await;
+pub async fn validate(ctx: &Context, token: &str) -> Result<Session, Error> {
+    let sessions_store = ctx.sessions.lock().await;
     let session = sessions_store
-        .get(token)
+        .get(token)?
         .ok_or(Error::Auth("Unauthorized"))?;
 
     if session.status != AuthState::Authorized || session.expires_at <= OffsetDateTime::now_utc() {
         return Err(Error::Auth("Unauthorized"));
     }
 
+    Ok(session)
+}
+
+/// Check that a bearer token grants the given scope on a repository.
+///
+/// The token is either a session token, which grants every scope, or an API token, which
+/// only grants the scopes it was created with.
+pub async fn authorize(ctx: &Context, token: &str, scope: Scope, rid: Id) -> Result<(), Error> {
+    if validate(ctx, token).await.is_ok() {
+        return Ok(());
+    }
+    let Some(api_token) = ctx.profile.tokens()?.authenticate(token)? else {
+        return Err(Error::Auth("Unauthorized"));
+    };
+    if !api_token.allows(scope, &rid) {
+        return Err(Error::Forbidden(
+            "Token doesn't grant access to this resource",
+        ));
+    }
+
     Ok(())
 }
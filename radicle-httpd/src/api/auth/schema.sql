@@ -0,0 +1,18 @@
+--
+-- HTTP API sessions SQL schema.
+--
+create table if not exists "sessions" (
+  -- SHA-256 hash of the session identifier. The identifier is used as bearer
+  -- token, so it isn't stored.
+  "hash"         text      primary key not null,
+  -- Non-secret session handle, used to list and revoke sessions.
+  "handle"       text      unique not null,
+  -- Whether the session was authorized, ie. 'authorized' or 'unauthorized'.
+  "status"       text      not null,
+  -- Public key the session was issued for.
+  "public_key"   text      not null,
+  -- UNIX time at which the session was issued.
+  "issued_at"    integer   not null,
+  -- UNIX time at which the session expires.
+  "expires_at"   integer   not null
+) strict;
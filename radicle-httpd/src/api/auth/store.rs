@@ -0,0 +1,261 @@
+use std::path::Path;
+
+use sha2::{Digest as _, Sha256};
+use sqlite as sql;
+use thiserror::Error;
+use time::OffsetDateTime;
+
+use radicle::crypto::PublicKey;
+
+use super::{AuthState, Session};
+
+/// Sessions database file name.
+pub const SESSIONS_DB_FILE: &str = "sessions.db";
+
+/// How long to wait for the database lock to be released before failing.
+const DB_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(6);
+
+/// An error occuring with the session store.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// An Internal error.
+    #[error("internal error: {0}")]
+    Internal(#[from] sql::Error),
+    /// A stored timestamp is out of range.
+    #[error("invalid timestamp: {0}")]
+    Timestamp(#[from] time::error::ComponentRange),
+    /// A stored session status is invalid.
+    #[error("invalid session status '{0}'")]
+    Status(String),
+}
+
+/// Length of a session handle, in hexadecimal digits.
+const HANDLE_LENGTH: usize = 16;
+
+/// Persistent session store, so that sessions survive restarts.
+///
+/// Session identifiers are bearer tokens, so only their hash is stored. Sessions are listed
+/// and revoked by their handle, which is derived from that hash.
+pub struct Sessions {
+    db: sql::Connection,
+}
+
+impl Sessions {
+    const SCHEMA: &str = include_str!("schema.sql");
+
+    /// Open a session store at the given path. Creates a new empty store
+    /// if an existing store isn't found. Expired sessions are removed.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let mut db = sql::Connection::open(path)?;
+        db.set_busy_timeout(DB_TIMEOUT.as_millis() as usize)?;
+        db.execute(Self::SCHEMA)?;
+
+        let mut sessions = Self { db };
+        sessions.remove_expired()?;
+
+        Ok(sessions)
+    }
+
+    /// Create a new in-memory session store.
+    #[cfg(test)]
+    pub fn memory() -> Result<Self, Error> {
+        let db = sql::Connection::open(":memory:")?;
+        db.execute(Self::SCHEMA)?;
+
+        Ok(Self { db })
+    }
+
+    /// Get a session.
+    pub fn get(&self, id: &str) -> Result<Option<Session>, Error> {
+        let mut stmt = self.db.prepare(
+            "SELECT status, public_key, issued_at, expires_at FROM sessions WHERE hash = ?",
+        )?;
+        stmt.bind((1, hash(id).as_str()))?;
+
+        match stmt.into_iter().next() {
+            Some(row) => Ok(Some(session(&row?)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Insert or update a session.
+    pub fn insert(&mut self, id: &str, session: &Session) -> Result<(), Error> {
+        let mut stmt = self.db.prepare(
+            "INSERT INTO sessions (hash, handle, status, public_key, issued_at, expires_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT (hash) DO UPDATE
+             SET status = ?3, public_key = ?4, issued_at = ?5, expires_at = ?6",
+        )?;
+        stmt.bind((1, hash(id).as_str()))?;
+        stmt.bind((2, handle(id).as_str()))?;
+        stmt.bind((3, status(&session.status)))?;
+        stmt.bind((4, &session.public_key))?;
+        stmt.bind((5, session.issued_at.unix_timestamp()))?;
+        stmt.bind((6, session.expires_at.unix_timestamp()))?;
+        stmt.next()?;
+
+        Ok(())
+    }
+
+    /// Remove a session, given its handle. Returns whether the session existed.
+    pub fn remove(&mut self, handle: &str) -> Result<bool, Error> {
+        let mut stmt = self.db.prepare("DELETE FROM sessions WHERE handle = ?")?;
+        stmt.bind((1, handle))?;
+        stmt.next()?;
+
+        Ok(self.db.change_count() > 0)
+    }
+
+    /// Remove expired sessions. Returns the number of sessions removed.
+    pub fn remove_expired(&mut self) -> Result<usize, Error> {
+        let mut stmt = self
+            .db
+            .prepare("DELETE FROM sessions WHERE expires_at <= ?")?;
+        stmt.bind((1, OffsetDateTime::now_utc().unix_timestamp()))?;
+        stmt.next()?;
+
+        Ok(self.db.change_count())
+    }
+
+    /// Get the handles of all sessions that haven't expired, with the sessions, most recent
+    /// first.
+    pub fn list(&self) -> Result<Vec<(String, Session)>, Error> {
+        let mut stmt = self.db.prepare(
+            "SELECT handle, status, public_key, issued_at, expires_at FROM sessions
+             WHERE expires_at > ?
+             ORDER BY issued_at DESC",
+        )?;
+        stmt.bind((1, OffsetDateTime::now_utc().unix_timestamp()))?;
+
+        let mut sessions = Vec::new();
+        for row in stmt.into_iter() {
+            let row = row?;
+            let handle = row.read::<&str, _>("handle").to_owned();
+
+            sessions.push((handle, session(&row)?));
+        }
+        Ok(sessions)
+    }
+}
+
+/// Get the handle of a session, given its identifier.
+pub fn handle(id: &str) -> String {
+    let mut handle = hash(id);
+    handle.truncate(HANDLE_LENGTH);
+    handle
+}
+
+fn hash(id: &str) -> String {
+    Sha256::digest(id.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+fn status(status: &AuthState) -> &'static str {
+    match status {
+        AuthState::Authorized => "authorized",
+        AuthState::Unauthorized => "unauthorized",
+    }
+}
+
+fn session(row: &sql::Row) -> Result<Session, Error> {
+    let status = match row.read::<&str, _>("status") {
+        "authorized" => AuthState::Authorized,
+        "unauthorized" => AuthState::Unauthorized,
+        other => return Err(Error::Status(other.to_owned())),
+    };
+
+    Ok(Session {
+        status,
+        public_key: row.try_read::<PublicKey, _>("public_key")?,
+        issued_at: OffsetDateTime::from_unix_timestamp(row.read::<i64, _>("issued_at"))?,
+        expires_at: OffsetDateTime::from_unix_timestamp(row.read::<i64, _>("expires_at"))?,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::api::auth::AUTHORIZED_SESSIONS_EXPIRATION;
+    use crate::test::CONTRIBUTOR_NID;
+
+    #[test]
+    fn test_insert_get_remove() {
+        let mut sessions = Sessions::memory().unwrap();
+        let issued_at = OffsetDateTime::from_unix_timestamp(1671125284).unwrap();
+        let mut session = Session {
+            status: AuthState::Unauthorized,
+            public_key: PublicKey::from_str(CONTRIBUTOR_NID).unwrap(),
+            issued_at,
+            expires_at: OffsetDateTime::now_utc() + AUTHORIZED_SESSIONS_EXPIRATION,
+        };
+        let expires_at = session.expires_at.replace_nanosecond(0).unwrap();
+
+        sessions.insert("abc", &session).unwrap();
+        let stored = sessions.get("abc").unwrap().unwrap();
+        assert_eq!(stored.status, AuthState::Unauthorized);
+        assert_eq!(stored.public_key, session.public_key);
+        assert_eq!(stored.issued_at, issued_at);
+        assert_eq!(stored.expires_at, expires_at);
+
+        session.status = AuthState::Authorized;
+        sessions.insert("abc", &session).unwrap();
+        assert_eq!(
+            sessions.get("abc").unwrap().unwrap().status,
+            AuthState::Authorized
+        );
+        assert_eq!(sessions.list().unwrap().len(), 1);
+
+        // Only the hash of the identifier is stored.
+        let mut stmt = sessions
+            .db
+            .prepare("SELECT hash, handle FROM sessions")
+            .unwrap();
+        let row = stmt.iter().next().unwrap().unwrap();
+        assert_eq!(row.read::<&str, _>("hash"), hash("abc"));
+        assert_eq!(row.read::<&str, _>("handle"), handle("abc"));
+        assert_ne!(row.read::<&str, _>("hash"), "abc");
+        drop(stmt);
+
+        assert!(sessions.remove(&handle("abc")).unwrap());
+        assert!(!sessions.remove(&handle("abc")).unwrap());
+        assert!(sessions.get("abc").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_remove_expired() {
+        let mut sessions = Sessions::memory().unwrap();
+        let now = OffsetDateTime::now_utc();
+        let session = Session {
+            status: AuthState::Authorized,
+            public_key: PublicKey::from_str(CONTRIBUTOR_NID).unwrap(),
+            issued_at: now - AUTHORIZED_SESSIONS_EXPIRATION,
+            expires_at: now - time::Duration::seconds(1),
+        };
+        sessions.insert("expired", &session).unwrap();
+        sessions
+            .insert(
+                "active",
+                &Session {
+                    expires_at: now + AUTHORIZED_SESSIONS_EXPIRATION,
+                    ..session.clone()
+                },
+            )
+            .unwrap();
+
+        assert_eq!(
+            sessions
+                .list()
+                .unwrap()
+                .into_iter()
+                .map(|(handle, _)| handle)
+                .collect::<Vec<_>>(),
+            vec![handle("active")]
+        );
+        assert_eq!(sessions.remove_expired().unwrap(), 1);
+        assert!(sessions.get("expired").unwrap().is_none());
+    }
+}
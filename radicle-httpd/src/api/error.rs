@@ -14,6 +14,14 @@ pub enum Error {
     #[error("could not authenticate: {0}")]
     Auth(&'static str),
 
+    /// The request is authenticated, but not allowed.
+    #[error("forbidden: {0}")]
+    Forbidden(&'static str),
+
+    /// I/O error.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
     /// An error occurred with env variables.
     #[error(transparent)]
     Env(#[from] std::env::VarError),
@@ -78,9 +86,37 @@ pub enum Error {
     #[error(transparent)]
     RoutingStore(#[from] radicle::node::routing::Error),
 
+    /// Search index error.
+    #[error(transparent)]
+    Search(#[from] radicle::search::Error),
+
+    /// Session store error.
+    #[error(transparent)]
+    Sessions(#[from] crate::api::auth::store::Error),
+
+    /// Statistics error.
+    #[error(transparent)]
+    Stats(#[from] radicle::stats::Error),
+
+    /// Webhooks error.
+    #[error(transparent)]
+    Webhooks(#[from] crate::webhooks::Error),
+
+    /// API tokens error.
+    #[error(transparent)]
+    Tokens(#[from] radicle::tokens::Error),
+
+    /// Blocking task error.
+    #[error(transparent)]
+    Join(#[from] tokio::task::JoinError),
+
     /// Invalid update to issue or patch.
     #[error("{0}")]
     BadRequest(String),
+
+    /// A service the request depends on is unavailable.
+    #[error("{0}")]
+    Unavailable(&'static str),
 }
 
 impl IntoResponse for Error {
@@ -92,6 +128,7 @@ impl IntoResponse for Error {
                 (StatusCode::NOT_FOUND, None)
             }
             Error::Auth(msg) => (StatusCode::BAD_REQUEST, Some(msg.to_string())),
+            Error::Forbidden(msg) => (StatusCode::FORBIDDEN, Some(msg.to_string())),
             Error::Crypto(msg) => (StatusCode::BAD_REQUEST, Some(msg.to_string())),
             Error::Surf(radicle_surf::Error::Git(e)) if radicle::git::is_not_found_err(&e) => {
                 (StatusCode::NOT_FOUND, None)
@@ -107,6 +144,7 @@ impl IntoResponse for Error {
             Error::Storage(err) if err.is_not_found() => (StatusCode::NOT_FOUND, None),
             Error::StorageRef(err) if err.is_not_found() => (StatusCode::NOT_FOUND, None),
             Error::BadRequest(msg) => (StatusCode::BAD_REQUEST, Some(msg)),
+            Error::Unavailable(msg) => (StatusCode::SERVICE_UNAVAILABLE, Some(msg.to_owned())),
             other => {
                 tracing::error!("Error: {message}");
 
@@ -21,7 +21,7 @@ use radicle_surf::blob::Blob;
 use radicle_surf::tree::Tree;
 use radicle_surf::{Commit, Oid, Stats};
 
-use crate::api::auth::Session;
+use crate::api::auth::{self, Session};
 
 /// Returns JSON of a commit.
 pub(crate) fn commit(commit: &Commit) -> Value {
@@ -42,10 +42,17 @@ pub(crate) fn commit(commit: &Commit) -> Value {
     })
 }
 
-/// Returns JSON of a session.
+/// Returns JSON of a session, given its identifier.
 pub(crate) fn session(session_id: String, session: &Session) -> Value {
+    let mut json = session_info(&auth::store::handle(&session_id), session);
+    json["sessionId"] = session_id.into();
+    json
+}
+
+/// Returns JSON of a session, without its identifier, which is secret.
+pub(crate) fn session_info(handle: &str, session: &Session) -> Value {
     json!({
-      "sessionId": session_id,
+      "handle": handle,
       "status": session.status,
       "publicKey": session.public_key,
       "issuedAt": session.issued_at.unix_timestamp(),
@@ -141,6 +148,10 @@ pub(crate) fn patch(
                   .collect::<Vec<_>>(),
                 "timestamp": rev.timestamp().as_secs(),
                 "reviews": rev.reviews().map(|(nid, _review)| review(nid, aliases.alias(nid), _review)).collect::<Vec<_>>(),
+                "verdicts": rev.verdicts(repo).unwrap_or_else(|e| {
+                    tracing::error!("Error resolving verdicts of revision {id}: {e}");
+                    Default::default()
+                }),
             })
         }).collect::<Vec<_>>(),
     })
@@ -1,8 +1,11 @@
 mod delegates;
+pub(crate) mod events;
 mod node;
 mod projects;
+mod search;
 mod sessions;
 mod stats;
+mod webhooks;
 
 use axum::extract::State;
 use axum::response::{IntoResponse, Json};
@@ -22,7 +25,10 @@ pub fn router(ctx: Context) -> Router {
         .merge(node::router(ctx.clone()))
         .merge(sessions::router(ctx.clone()))
         .merge(delegates::router(ctx.clone()))
+        .merge(events::router(ctx.clone()))
         .merge(projects::router(ctx.clone()))
+        .merge(search::router(ctx.clone()))
+        .merge(webhooks::router(ctx.clone()))
         .merge(stats::router(ctx));
 
     Router::new().nest("/v1", routes)
@@ -51,10 +57,20 @@ async fn root_handler(State(ctx): State<Context>) -> impl IntoResponse {
                 "rel": "projects",
                 "type": "GET"
             },
+            {
+                "href": "/search",
+                "rel": "search",
+                "type": "GET"
+            },
             {
                 "href": "/stats",
                 "rel": "stats",
                 "type": "GET"
+            },
+            {
+                "href": "/events",
+                "rel": "events",
+                "type": "GET"
             }
         ]
     });
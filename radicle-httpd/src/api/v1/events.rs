@@ -0,0 +1,319 @@
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::{Arc, Once};
+use std::{io, thread, time};
+
+use axum::extract::{Query, State};
+use axum::response::sse::{self, KeepAlive, Sse};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use futures_util::stream;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, OwnedSemaphorePermit, Semaphore};
+
+use radicle::cob;
+use radicle::git;
+use radicle::identity::Id;
+use radicle::node::{Event, Handle as _, NodeId};
+use radicle::storage::RefUpdate;
+
+use crate::api::error::Error;
+use crate::api::Context;
+
+/// How long to wait for a node event, before checking whether the node is still running.
+const POLL_INTERVAL: time::Duration = time::Duration::from_secs(30);
+/// How long to wait before re-connecting to the node.
+const RECONNECT_INTERVAL: time::Duration = time::Duration::from_secs(5);
+/// Number of events buffered for clients. Clients that fall behind miss events.
+const BUFFER_SIZE: usize = 64;
+/// Maximum number of clients subscribed to events at the same time.
+const MAX_SUBSCRIBERS: usize = 64;
+
+/// Node events, shared by all clients. A single subscription to the node is made when the
+/// first client subscribes, and its events are broadcast to every client.
+#[derive(Clone)]
+pub struct Hub {
+    sender: broadcast::Sender<Event>,
+    subscribers: Arc<Semaphore>,
+    started: Arc<Once>,
+}
+
+impl Default for Hub {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::channel(BUFFER_SIZE).0,
+            subscribers: Arc::new(Semaphore::new(MAX_SUBSCRIBERS)),
+            started: Arc::new(Once::new()),
+        }
+    }
+}
+
+impl Hub {
+    /// Subscribe to node events. Returns `None` if there are too many subscribers. The
+    /// subscription lasts as long as the returned permit.
+    fn subscribe(
+        &self,
+        socket: PathBuf,
+    ) -> Option<(OwnedSemaphorePermit, broadcast::Receiver<Event>)> {
+        let permit = self.subscribers.clone().try_acquire_owned().ok()?;
+
+        self.started.call_once(|| {
+            let sender = self.sender.clone();
+            thread::spawn(move || relay(socket, sender));
+        });
+        Some((permit, self.sender.subscribe()))
+    }
+}
+
+/// Relay node events to subscribers. Never returns.
+///
+/// Reading from the control socket blocks, so it's done on its own thread. If the node isn't
+/// running, or stops, we keep trying to reconnect.
+fn relay(socket: PathBuf, sender: broadcast::Sender<Event>) {
+    loop {
+        match radicle::Node::new(&socket).subscribe(POLL_INTERVAL) {
+            Ok(events) => {
+                for event in events {
+                    match event {
+                        // Sending only fails when nobody is subscribed.
+                        Ok(event) => sender.send(event).ok(),
+                        Err(e) if e.kind() == io::ErrorKind::TimedOut => None,
+                        Err(e) => {
+                            tracing::debug!("failed to read node event: {e}");
+                            break;
+                        }
+                    };
+                }
+            }
+            Err(e) => tracing::debug!("failed to subscribe to node events: {e}"),
+        }
+        thread::sleep(RECONNECT_INTERVAL);
+    }
+}
+
+pub fn router(ctx: Context) -> Router {
+    Router::new()
+        .route("/events", get(events_handler))
+        .with_state(ctx)
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventsQuery {
+    pub rid: Option<Id>,
+}
+
+/// Change to a collaborative object, eg. an issue or a patch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "type", rename = "cobChanged")]
+pub struct CobChanged {
+    pub rid: Id,
+    pub remote: NodeId,
+    pub type_name: cob::TypeName,
+    pub id: String,
+    pub change: Change,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Change {
+    Created,
+    Updated,
+    Deleted,
+}
+
+/// An event relayed to clients.
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum Activity {
+    Node(Event),
+    Cob(CobChanged),
+}
+
+impl Activity {
+    /// Convert a node event to the activity it represents, optionally filtered by repository.
+    pub fn from_event(event: Event, filter: Option<Id>) -> Vec<Self> {
+        let rid = match &event {
+            Event::RefsFetched { rid, .. }
+            | Event::RefsSynced { rid, .. }
+            | Event::SeedDiscovered { rid, .. }
+            | Event::SeedDropped { rid, .. } => Some(*rid),
+            Event::PeerConnected { .. } | Event::PeerDisconnected { .. } => None,
+        };
+        if filter.is_some() && rid != filter {
+            return vec![];
+        }
+        let mut activity = Vec::new();
+
+        if let Event::RefsFetched {
+            remote,
+            rid,
+            updated,
+        } = &event
+        {
+            activity.extend(
+                updated
+                    .iter()
+                    .filter_map(|update| cob_changed(*rid, *remote, update))
+                    .map(Self::Cob),
+            );
+        }
+        activity.insert(0, Self::Node(event));
+        activity
+    }
+
+    /// Convert to a server-sent event, named after the event type.
+    fn to_sse(&self) -> Result<sse::Event, axum::Error> {
+        let data = serde_json::to_value(self).map_err(axum::Error::new)?;
+        let name = data["type"].as_str().unwrap_or("message");
+
+        Ok(sse::Event::default().event(name).data(data.to_string()))
+    }
+}
+
+/// Get the collaborative object change of a ref update, if any.
+fn cob_changed(rid: Id, remote: NodeId, update: &RefUpdate) -> Option<CobChanged> {
+    let (name, change) = match update {
+        RefUpdate::Created { name, .. } => (name, Change::Created),
+        RefUpdate::Updated { name, .. } => (name, Change::Updated),
+        RefUpdate::Deleted { name, .. } => (name, Change::Deleted),
+        RefUpdate::Skipped { .. } => return None,
+    };
+    let (namespace, _) = git::parse_ref::<NodeId>(name.as_str()).ok()?;
+    let (type_name, id) = cob::object::parse_refstr(name)?;
+
+    Some(CobChanged {
+        rid,
+        remote: namespace.unwrap_or(remote),
+        type_name,
+        id: id.to_string(),
+        change,
+    })
+}
+
+/// Subscribe to live node activity, optionally for a single repository.
+/// `GET /events?rid=<rid>`
+async fn events_handler(
+    State(ctx): State<Context>,
+    Query(qs): Query<EventsQuery>,
+) -> impl IntoResponse {
+    let node = radicle::Node::new(ctx.profile.socket());
+    if !node.is_running() {
+        return Err(Error::Unavailable("node is not running"));
+    }
+    let Some((permit, receiver)) = ctx.events.subscribe(ctx.profile.socket()) else {
+        return Err(Error::Unavailable("too many event subscribers"));
+    };
+    let rid = qs.rid;
+    let stream = stream::unfold(
+        (receiver, permit, VecDeque::new()),
+        move |(mut receiver, permit, mut pending)| async move {
+            loop {
+                if let Some(activity) = pending.pop_front() {
+                    return Some((Activity::to_sse(&activity), (receiver, permit, pending)));
+                }
+                match receiver.recv().await {
+                    Ok(event) => pending.extend(Activity::from_event(event, rid)),
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        tracing::debug!("event subscriber lagged behind by {n} event(s)");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        },
+    );
+
+    Ok::<_, Error>(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+#[cfg(test)]
+mod routes {
+    use std::str::FromStr;
+
+    use axum::http::StatusCode;
+    use radicle::git::raw::Oid;
+
+    use super::*;
+    use crate::test::{self, get, CONTRIBUTOR_NID, ISSUE_ID, RID};
+
+    #[tokio::test]
+    async fn test_events_without_node() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = super::router(test::seed(tmp.path()));
+        let response = get(&app, format!("/events?rid={RID}")).await;
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[test]
+    fn test_hub_subscribers() {
+        let tmp = tempfile::tempdir().unwrap();
+        let socket = tmp.path().join("node.sock");
+        let hub = Hub::default();
+        let mut subscriptions = (0..MAX_SUBSCRIBERS)
+            .map(|_| hub.subscribe(socket.clone()).unwrap())
+            .collect::<Vec<_>>();
+
+        assert!(hub.subscribe(socket.clone()).is_none());
+
+        // Events are broadcast to every subscriber.
+        let nid = NodeId::from_str(CONTRIBUTOR_NID).unwrap();
+        hub.sender.send(Event::PeerConnected { nid }).unwrap();
+        for (_, receiver) in subscriptions.iter_mut() {
+            assert!(matches!(
+                receiver.try_recv(),
+                Ok(Event::PeerConnected { nid: n }) if n == nid
+            ));
+        }
+
+        subscriptions.pop();
+        assert!(hub.subscribe(socket).is_some());
+    }
+
+    #[test]
+    fn test_activity_from_event() {
+        let rid = Id::from_str(RID).unwrap();
+        let remote = NodeId::from_str(CONTRIBUTOR_NID).unwrap();
+        let oid = Oid::from_str(ISSUE_ID).unwrap();
+        let event = Event::RefsFetched {
+            remote,
+            rid,
+            updated: vec![
+                RefUpdate::Created {
+                    name: git::RefString::try_from(format!(
+                        "refs/namespaces/{remote}/refs/cobs/xyz.radicle.issue/{ISSUE_ID}"
+                    ))
+                    .unwrap(),
+                    oid: oid.into(),
+                },
+                RefUpdate::Updated {
+                    name: git::RefString::try_from("refs/heads/master").unwrap(),
+                    old: oid.into(),
+                    new: oid.into(),
+                },
+            ],
+        };
+
+        let activity = Activity::from_event(event.clone(), Some(rid));
+        assert_eq!(activity.len(), 2);
+        assert!(matches!(
+            activity[0],
+            Activity::Node(Event::RefsFetched { .. })
+        ));
+        assert!(matches!(
+            &activity[1],
+            Activity::Cob(CobChanged { remote: r, type_name, id, change: Change::Created, .. })
+            if *r == remote && type_name.as_str() == "xyz.radicle.issue" && id == ISSUE_ID
+        ));
+
+        // Events of other repositories, and events without a repository, are filtered out.
+        let other = Id::from_str(test::CONTRIBUTOR_RID).unwrap();
+        assert!(Activity::from_event(event, Some(other)).is_empty());
+        assert!(Activity::from_event(Event::PeerConnected { nid: remote }, Some(rid)).is_empty());
+        assert_eq!(
+            Activity::from_event(Event::PeerConnected { nid: remote }, None).len(),
+            1
+        );
+    }
+}
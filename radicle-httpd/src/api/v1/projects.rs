@@ -1,4 +1,11 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead as _, BufReader, Read as _};
+use std::ops::Range;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{self, AtomicBool};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
 
 use axum::extract::State;
 use axum::handler::Handler;
@@ -19,6 +26,7 @@ use radicle::node::AliasStore;
 use radicle::node::NodeId;
 use radicle::storage::git::paths;
 use radicle::storage::{ReadRepository, ReadStorage, WriteRepository};
+use radicle::tokens::Scope;
 use radicle_surf::{Glob, Oid, Repository};
 
 use crate::api::error::Error;
@@ -27,6 +35,12 @@ use crate::api::{self, CobsQuery, Context, PaginationQuery};
 use crate::axum_extra::{Path, Query};
 
 const CACHE_1_HOUR: &str = "public, max-age=3600, must-revalidate";
+/// Maximum number of code search matches returned.
+const MAX_SEARCH_MATCHES: usize = 100;
+/// Number of lines of context returned around code search matches.
+const SEARCH_CONTEXT_LINES: usize = 2;
+/// How long a code search may take, before it is stopped.
+const SEARCH_TIMEOUT: Duration = Duration::from_secs(10);
 
 pub fn router(ctx: Context) -> Router {
     Router::new()
@@ -49,7 +63,9 @@ pub fn router(ctx: Context) -> Router {
         .route("/projects/:project/remotes", get(remotes_handler))
         .route("/projects/:project/remotes/:peer", get(remote_handler))
         .route("/projects/:project/blob/:sha/*path", get(blob_handler))
+        .route("/projects/:project/blame/:sha/*path", get(blame_handler))
         .route("/projects/:project/readme/:sha", get(readme_handler))
+        .route("/projects/:project/search", get(search_handler))
         .route(
             "/projects/:project/issues",
             post(issue_create_handler).get(issues_handler),
@@ -66,6 +82,10 @@ pub fn router(ctx: Context) -> Router {
             "/projects/:project/patches/:id",
             patch(patch_update_handler).get(patch_handler),
         )
+        .route(
+            "/projects/:project/patches/:id/reviews",
+            post(patch_review_handler),
+        )
         .with_state(ctx)
 }
 
@@ -412,6 +432,97 @@ async fn blob_handler(
     Ok::<_, Error>(Json(response))
 }
 
+/// Get the authorship of each line of a project source file.
+/// `GET /projects/:project/blame/:sha/*path`
+async fn blame_handler(
+    State(ctx): State<Context>,
+    Path((project, sha, path)): Path<(Id, Oid, String)>,
+) -> impl IntoResponse {
+    // A file's blame at a given commit never changes.
+    if let Some(ref cache) = ctx.cache {
+        let cache = &mut cache.blame.lock().await;
+        if let Some(response) = cache.get(&(project, sha, path.clone())) {
+            return Ok::<_, Error>(Json(response.clone()));
+        }
+    }
+    let response = tokio::task::spawn_blocking({
+        let profile = ctx.profile.clone();
+        let path = path.clone();
+        move || {
+            let repo = profile.storage.repository(project)?;
+            let aliases = profile.aliases();
+            blame(&repo, sha, &path, &aliases)
+        }
+    })
+    .await??;
+
+    if let Some(cache) = ctx.cache {
+        let cache = &mut cache.blame.lock().await;
+        cache.put((project, sha, path), response.clone());
+    }
+
+    Ok::<_, Error>(Json(response))
+}
+
+/// Blame a file at the given commit. Commits signed by known keys, ie. keys of the
+/// repository's remotes or of nodes we have an alias for, are mapped to their signer.
+fn blame(
+    repo: &radicle::storage::git::Repository,
+    sha: Oid,
+    path: &str,
+    aliases: &impl AliasStore,
+) -> Result<serde_json::Value, Error> {
+    let mut opts = radicle::git::raw::BlameOptions::new();
+    opts.newest_commit(*sha);
+
+    let blame = repo
+        .backend
+        .blame_file(std::path::Path::new(path), Some(&mut opts))?;
+    let remotes = repo.remote_ids()?.collect::<Result<Vec<_>, _>>()?;
+    let mut hunks = Vec::new();
+    let mut signers = serde_json::Map::new();
+
+    for hunk in blame.iter() {
+        let commit = Oid::from(hunk.final_commit_id());
+        let author = hunk.final_signature();
+        let start = hunk.final_start_line();
+
+        hunks.push(json!({
+            "start": start,
+            "end": start + hunk.lines_in_hunk() - 1,
+            "commit": commit,
+            "author": {
+                "name": author.name(),
+                "email": author.email(),
+            },
+            "time": author.when().seconds(),
+        }));
+
+        if signers.contains_key(&commit.to_string()) {
+            continue;
+        }
+        let Some(signer) = radicle::git::signer(&repo.backend, commit)? else {
+            continue;
+        };
+        let alias = aliases.alias(&signer);
+        if alias.is_none() && !remotes.contains(&signer) {
+            continue;
+        }
+        let signer = match alias {
+            Some(alias) => json!({ "id": Did::from(signer), "alias": alias }),
+            None => json!({ "id": Did::from(signer) }),
+        };
+        signers.insert(commit.to_string(), signer);
+    }
+
+    Ok(json!({
+        "path": path,
+        "sha": sha,
+        "hunks": hunks,
+        "signers": signers,
+    }))
+}
+
 /// Get project readme.
 /// `GET /projects/:project/readme/:sha`
 async fn readme_handler(
@@ -439,6 +550,189 @@ async fn readme_handler(
     Err(Error::NotFound)
 }
 
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeSearchQuery {
+    pub q: String,
+    pub sha: Option<Oid>,
+    #[serde(default)]
+    pub regex: bool,
+    #[serde(default)]
+    pub ignore_case: bool,
+    pub path: Option<String>,
+}
+
+/// Search the source tree of a project revision, by default the project head. Queries are
+/// matched literally, or as POSIX extended regular expressions if `regex` is set. The search
+/// can be restricted to paths matching a git pathspec, eg. `*.rs` or `src/`.
+/// `GET /projects/:project/search?q=<query>&sha=<sha>&regex=<bool>&ignoreCase=<bool>&path=<pathspec>`
+async fn search_handler(
+    State(ctx): State<Context>,
+    Path(project): Path<Id>,
+    Query(qs): Query<CodeSearchQuery>,
+) -> impl IntoResponse {
+    let storage = &ctx.profile.storage;
+    let sha = match qs.sha {
+        Some(sha) => sha,
+        None => storage.repository(project)?.head()?.1,
+    };
+    // Results only depend on the query and the commit searched, which is immutable.
+    let key = json!([qs.q, qs.regex, qs.ignore_case, qs.path]).to_string();
+
+    if let Some(ref cache) = ctx.cache {
+        let cache = &mut cache.search.lock().await;
+        if let Some(response) = cache.get(&(project, sha, key.clone())) {
+            return Ok::<_, Error>(Json(response.clone()));
+        }
+    }
+    let response = tokio::task::spawn_blocking({
+        let profile = ctx.profile.clone();
+        move || {
+            let repo = profile.storage.repository(project)?;
+            code_search(&repo, sha, &qs)
+        }
+    })
+    .await??;
+
+    if let Some(cache) = ctx.cache {
+        let cache = &mut cache.search.lock().await;
+        cache.put((project, sha, key), response.clone());
+    }
+
+    Ok::<_, Error>(Json(response))
+}
+
+/// Search the tree of a commit with `git grep`.
+///
+/// The output of `git grep` is read as it comes, and the search is stopped once enough matches
+/// were found, or if it takes too long. In both cases, the results are marked as truncated.
+fn code_search(
+    repo: &radicle::storage::git::Repository,
+    sha: Oid,
+    qs: &CodeSearchQuery,
+) -> Result<serde_json::Value, Error> {
+    if qs.q.is_empty() {
+        return Err(Error::BadRequest(
+            "search query must not be empty".to_owned(),
+        ));
+    }
+    let tree = repo.backend.find_commit(*sha)?.tree()?;
+    let mut grep = Command::new("git");
+
+    grep.current_dir(repo.path())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .args(["grep", "-z", "-n", "-I", "--no-color"])
+        .arg(if qs.regex { "-E" } else { "-F" });
+    if qs.ignore_case {
+        grep.arg("-i");
+    }
+    grep.arg("-e").arg(&qs.q).arg(sha.to_string()).arg("--");
+    if let Some(path) = qs.path.as_deref().filter(|p| !p.is_empty()) {
+        grep.arg(path);
+    }
+    let mut child = grep.spawn()?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let child = Arc::new(Mutex::new(child));
+    let timed_out = Arc::new(AtomicBool::new(false));
+    let (done, wait) = mpsc::channel::<()>();
+
+    // Kill the search if it takes too long. Dropping `done` stops the watchdog.
+    thread::spawn({
+        let child = child.clone();
+        let timed_out = timed_out.clone();
+
+        move || {
+            if let Err(mpsc::RecvTimeoutError::Timeout) = wait.recv_timeout(SEARCH_TIMEOUT) {
+                timed_out.store(true, atomic::Ordering::SeqCst);
+                child
+                    .lock()
+                    .expect("search: lock is not poisoned")
+                    .kill()
+                    .ok();
+            }
+        }
+    });
+
+    let prefix = format!("{sha}:");
+    let mut matches = Vec::new();
+    let mut truncated = false;
+    let mut file: Option<(String, Vec<String>)> = None;
+
+    // Each match is output as `<sha>:<path>\0<line>\0<content>`.
+    for record in BufReader::new(stdout).split(b'\n') {
+        let record = record?;
+        let mut fields = record.splitn(3, |b| *b == 0);
+        let (Some(path), Some(line), Some(_)) = (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        let Some(path) = String::from_utf8_lossy(path).strip_prefix(&prefix).map(String::from)
+        else {
+            continue;
+        };
+        let Ok(line) = String::from_utf8_lossy(line).parse::<usize>() else {
+            continue;
+        };
+        if matches.len() == MAX_SEARCH_MATCHES {
+            truncated = true;
+            break;
+        }
+        // Matches are grouped by file, so each file is only read once, for context.
+        if file.as_ref().map_or(true, |(p, _)| *p != path) {
+            let blob = tree
+                .get_path(std::path::Path::new(&path))?
+                .to_object(&repo.backend)?
+                .peel_to_blob()?;
+            let lines = String::from_utf8_lossy(blob.content())
+                .lines()
+                .map(String::from)
+                .collect();
+
+            file = Some((path.clone(), lines));
+        }
+        let Some((_, lines)) = &file else {
+            continue;
+        };
+        let i = line.saturating_sub(1).min(lines.len());
+        let before = &lines[i.saturating_sub(SEARCH_CONTEXT_LINES)..i];
+        let after =
+            &lines[(i + 1).min(lines.len())..(i + 1 + SEARCH_CONTEXT_LINES).min(lines.len())];
+
+        matches.push(json!({
+            "path": path,
+            "line": line,
+            "content": lines.get(i),
+            "before": before,
+            "after": after,
+        }));
+    }
+    drop(done);
+
+    let mut child = child.lock().expect("search: lock is not poisoned");
+    if truncated {
+        child.kill().ok();
+    }
+    let status = child.wait()?;
+
+    if timed_out.load(atomic::Ordering::SeqCst) {
+        truncated = true;
+    } else if !truncated && !status.success() && status.code() != Some(1) {
+        // Git exits with `1` when nothing matched.
+        let mut stderr = String::new();
+        if let Some(mut e) = child.stderr.take() {
+            e.read_to_string(&mut stderr)?;
+        }
+        return Err(Error::BadRequest(stderr.trim().to_owned()));
+    }
+
+    Ok(json!({
+        "sha": sha,
+        "matches": matches,
+        "truncated": truncated,
+    }))
+}
+
 /// Get project issues list.
 /// `GET /projects/:project/issues`
 async fn issues_handler(
@@ -493,7 +787,7 @@ async fn issue_create_handler(
     Path(project): Path<Id>,
     Json(issue): Json<IssueCreate>,
 ) -> impl IntoResponse {
-    api::auth::validate(&ctx, &token).await?;
+    api::auth::authorize(&ctx, &token, Scope::IssueWrite, project).await?;
     let storage = &ctx.profile.storage;
     let signer = ctx
         .profile
@@ -526,7 +820,7 @@ async fn issue_update_handler(
     Path((project, issue_id)): Path<(Id, Oid)>,
     Json(action): Json<issue::Action>,
 ) -> impl IntoResponse {
-    api::auth::validate(&ctx, &token).await?;
+    api::auth::authorize(&ctx, &token, Scope::IssueWrite, project).await?;
 
     let storage = &ctx.profile.storage;
     let signer = ctx.profile.signer().unwrap();
@@ -605,7 +899,7 @@ async fn patch_create_handler(
     Path(project): Path<Id>,
     Json(patch): Json<PatchCreate>,
 ) -> impl IntoResponse {
-    api::auth::validate(&ctx, &token).await?;
+    api::auth::authorize(&ctx, &token, Scope::PatchWrite, project).await?;
     let storage = &ctx.profile.storage;
     let signer = ctx
         .profile
@@ -640,7 +934,7 @@ async fn patch_update_handler(
     Path((project, patch_id)): Path<(Id, Oid)>,
     Json(action): Json<patch::Action>,
 ) -> impl IntoResponse {
-    api::auth::validate(&ctx, &token).await?;
+    api::auth::authorize(&ctx, &token, Scope::PatchWrite, project).await?;
     let storage = &ctx.profile.storage;
     let signer = ctx
         .profile
@@ -707,8 +1001,20 @@ async fn patch_update_handler(
             summary,
             verdict,
             labels,
+            comments,
         } => {
-            patch.review(revision, verdict, summary, labels, &signer)?;
+            review(
+                &repo,
+                &mut patch,
+                ReviewCreate {
+                    revision,
+                    summary,
+                    verdict,
+                    labels,
+                    comments,
+                },
+                &signer,
+            )?;
         }
         patch::Action::Merge { revision, commit } => {
             patch.merge(revision, commit, &signer)?;
@@ -747,6 +1053,127 @@ async fn patch_update_handler(
     Ok::<_, Error>(Json(json!({ "success": true })))
 }
 
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewCreate {
+    pub revision: patch::RevisionId,
+    pub summary: Option<String>,
+    pub verdict: Option<patch::Verdict>,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    #[serde(default)]
+    pub comments: Vec<patch::CodeComment>,
+}
+
+/// Review a patch revision, with comments on its code.
+/// `POST /projects/:project/patches/:id/reviews`
+async fn patch_review_handler(
+    State(ctx): State<Context>,
+    AuthBearer(token): AuthBearer,
+    Path((project, patch_id)): Path<(Id, Oid)>,
+    Json(draft): Json<ReviewCreate>,
+) -> impl IntoResponse {
+    api::auth::authorize(&ctx, &token, Scope::PatchWrite, project).await?;
+    let storage = &ctx.profile.storage;
+    let signer = ctx
+        .profile
+        .signer()
+        .map_err(|_| Error::Auth("Unauthorized"))?;
+    let repo = storage.repository(project)?;
+    let mut patches = patch::Patches::open(&repo)?;
+    let mut patch = patches.get_mut(&patch_id.into())?;
+    let review = review(&repo, &mut patch, draft, &signer)?;
+
+    Ok::<_, Error>((
+        StatusCode::CREATED,
+        Json(json!({ "success": true, "id": review.to_string() })),
+    ))
+}
+
+/// Submit a review and its comments as a single change, after checking that the comments are
+/// on code changed by the revision.
+fn review<G: radicle::crypto::Signer>(
+    repo: &radicle::storage::git::Repository,
+    patch: &mut patch::PatchMut<'_, '_, radicle::storage::git::Repository>,
+    draft: ReviewCreate,
+    signer: &G,
+) -> Result<radicle::cob::EntryId, Error> {
+    let revision = patch.revision(&draft.revision).ok_or(Error::NotFound)?;
+    let base = repo.backend.find_commit(**revision.base())?.tree()?;
+    let head = repo.backend.find_commit(*revision.head())?.tree()?;
+    let diff = repo
+        .backend
+        .diff_tree_to_tree(Some(&base), Some(&head), None)?;
+
+    // Line ranges of the hunks of each changed file, on the old and new side.
+    let mut hunks = HashMap::<std::path::PathBuf, (Vec<Range<usize>>, Vec<Range<usize>>)>::new();
+    for (i, delta) in diff.deltas().enumerate() {
+        let mut ranges = (Vec::new(), Vec::new());
+
+        if let Some(patch) = radicle::git::raw::Patch::from_diff(&diff, i)? {
+            for h in 0..patch.num_hunks() {
+                let (hunk, _) = patch.hunk(h)?;
+                let (old, new) = (hunk.old_start() as usize, hunk.new_start() as usize);
+
+                ranges.0.push(old..old + hunk.old_lines() as usize);
+                ranges.1.push(new..new + hunk.new_lines() as usize);
+            }
+        }
+        for path in [delta.old_file().path(), delta.new_file().path()]
+            .into_iter()
+            .flatten()
+        {
+            hunks.insert(path.to_path_buf(), ranges.clone());
+        }
+    }
+
+    for comment in &draft.comments {
+        let location = &comment.location;
+        let invalid = || {
+            Error::BadRequest(format!(
+                "invalid location for comment on '{}'",
+                location.path.display()
+            ))
+        };
+        if comment.body.is_empty() {
+            return Err(Error::BadRequest(
+                "comment body must not be empty".to_owned(),
+            ));
+        }
+        let (old, new) = hunks.get(&location.path).ok_or_else(invalid)?;
+
+        for (range, hunks) in [(&location.old, old), (&location.new, new)] {
+            let valid = match range {
+                None => true,
+                Some(patch::CodeRange::Lines { range }) => {
+                    !range.is_empty()
+                        && hunks
+                            .iter()
+                            .any(|h| h.start <= range.start && range.end <= h.end)
+                }
+                Some(patch::CodeRange::Chars { line, .. }) => {
+                    hunks.iter().any(|h| h.contains(line))
+                }
+            };
+            if !valid {
+                return Err(invalid());
+            }
+        }
+    }
+
+    let review = patch.transaction("Review", signer, |tx| {
+        tx.review_with_comments(
+            draft.revision,
+            draft.verdict,
+            draft.summary,
+            draft.labels,
+            draft.comments,
+        )
+    })?;
+
+    Ok(review)
+}
+
 /// Get project patches list.
 /// `GET /projects/:project/patches`
 async fn patches_handler(
@@ -1560,6 +1987,102 @@ mod routes {
         assert_eq!(response.status(), StatusCode::NOT_FOUND);
     }
 
+    #[tokio::test]
+    async fn test_projects_blame() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = super::router(seed(tmp.path()));
+        let response = get(&app, format!("/projects/{RID}/blame/{HEAD}/dir1/README")).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.json().await,
+            json!({
+                "path": "dir1/README",
+                "sha": HEAD,
+                "hunks": [
+                    {
+                        "start": 1,
+                        "end": 1,
+                        "commit": HEAD,
+                        "author": {
+                            "name": "Alice Liddell",
+                            "email": "alice@radicle.xyz",
+                        },
+                        "time": 1673003014,
+                    }
+                ],
+                "signers": {},
+            })
+        );
+
+        let response = get(
+            &app,
+            format!("/projects/{RID}/blame/{INITIAL_COMMIT}/README"),
+        )
+        .await;
+        assert_eq!(response.json().await["hunks"][0]["commit"], INITIAL_COMMIT);
+
+        let response = get(&app, format!("/projects/{RID}/blame/{HEAD}/unknown")).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_projects_blame_signers() {
+        use radicle::crypto::ssh::ExtendedSignature;
+        use radicle::git::raw as git2;
+        use radicle::storage::ReadStorage;
+        use radicle_crypto::test::signer::MockSigner;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let ctx = seed(tmp.path());
+        let signer = MockSigner::from_seed([0xff; 32]);
+        let repo = ctx
+            .profile
+            .storage
+            .repository(RID.parse().unwrap())
+            .unwrap();
+
+        // Commit a change to `dir1/README` on top of `HEAD`, signed with the delegate's key.
+        let commit = {
+            let parent = repo.backend.find_commit(HEAD.parse().unwrap()).unwrap();
+            let blob = repo
+                .backend
+                .blob(b"Hello World from dir1!\nSigned.\n")
+                .unwrap();
+            let tree = git2::build::TreeUpdateBuilder::new()
+                .upsert("dir1/README", blob, git2::FileMode::Blob)
+                .create_updated(&repo.backend, &parent.tree().unwrap())
+                .unwrap();
+            let tree = repo.backend.find_tree(tree).unwrap();
+            let sig = git2::Signature::new(
+                "Alice Liddell",
+                "alice@radicle.xyz",
+                &git2::Time::new(1673004014, 0),
+            )
+            .unwrap();
+            let buffer = repo
+                .backend
+                .commit_create_buffer(&sig, &sig, "Sign README\n", &tree, &[&parent])
+                .unwrap();
+            let buffer = buffer.as_str().unwrap();
+            let pem = ExtendedSignature::sign_pem(&signer, "git", buffer.as_bytes()).unwrap();
+
+            repo.backend.commit_signed(buffer, &pem, None).unwrap()
+        };
+        let app = super::router(ctx);
+        let response = get(&app, format!("/projects/{RID}/blame/{commit}/dir1/README")).await;
+        let body = response.json().await;
+
+        assert_eq!(body["hunks"][0]["commit"], HEAD);
+        assert_eq!(body["hunks"][1]["commit"], commit.to_string());
+        assert_eq!(
+            body["signers"],
+            json!({
+                commit.to_string(): { "id": DID },
+            })
+        );
+    }
+
     #[tokio::test]
     async fn test_projects_readme() {
         let tmp = tempfile::tempdir().unwrap();
@@ -1593,6 +2116,83 @@ mod routes {
         );
     }
 
+    #[tokio::test]
+    async fn test_projects_search() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = super::router(seed(tmp.path()));
+        let response = get(&app, format!("/projects/{RID}/search?q=Hello%20World")).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.json().await,
+            json!({
+                "sha": HEAD,
+                "matches": [
+                    {
+                        "path": "README",
+                        "line": 1,
+                        "content": "Hello World!",
+                        "before": [],
+                        "after": [],
+                    },
+                    {
+                        "path": "dir1/README",
+                        "line": 1,
+                        "content": "Hello World from dir1!",
+                        "before": [],
+                        "after": [],
+                    },
+                ],
+                "truncated": false,
+            })
+        );
+
+        // Searches are case-sensitive by default.
+        let response = get(&app, format!("/projects/{RID}/search?q=hello")).await;
+        assert_eq!(response.json().await["matches"], json!([]));
+
+        let response = get(
+            &app,
+            format!("/projects/{RID}/search?q=hello&ignoreCase=true&path=dir1/"),
+        )
+        .await;
+        let matches = response.json().await["matches"].clone();
+        assert_eq!(matches.as_array().unwrap().len(), 1);
+        assert_eq!(matches[0]["path"], "dir1/README");
+
+        let response = get(
+            &app,
+            format!("/projects/{RID}/search?q=%5EThank&regex=true"),
+        )
+        .await;
+        assert_eq!(response.json().await["matches"], json!([]));
+
+        let response = get(
+            &app,
+            format!("/projects/{RID}/search?q=%5EThank&regex=true&sha={PARENT}"),
+        )
+        .await;
+        assert_eq!(
+            response.json().await,
+            json!({
+                "sha": PARENT,
+                "matches": [
+                    {
+                        "path": "CONTRIBUTING",
+                        "line": 1,
+                        "content": "Thank you very much!",
+                        "before": [],
+                        "after": [],
+                    },
+                ],
+                "truncated": false,
+            })
+        );
+
+        let response = get(&app, format!("/projects/{RID}/search?q=(&regex=true")).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
     #[tokio::test]
     async fn test_projects_diff() {
         let tmp = tempfile::tempdir().unwrap();
@@ -1796,6 +2396,59 @@ mod routes {
         );
     }
 
+    #[tokio::test]
+    async fn test_projects_issues_create_with_token() {
+        use std::collections::BTreeSet;
+        use std::str::FromStr;
+
+        use radicle::identity::Id;
+        use radicle::tokens::Scope;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let ctx = contributor(tmp.path());
+        let app = super::router(ctx.to_owned());
+        let rid = Id::from_str(CONTRIBUTOR_RID).unwrap();
+        let other = Id::from_str(RID).unwrap();
+        let mut tokens = ctx.profile.tokens().unwrap();
+        let (_, patches) = tokens
+            .create("patches", BTreeSet::from([Scope::PatchWrite]), None)
+            .unwrap();
+        let (_, elsewhere) = tokens
+            .create(
+                "elsewhere",
+                BTreeSet::from([Scope::IssueWrite]),
+                Some(other),
+            )
+            .unwrap();
+        let (_, issues) = tokens
+            .create("issues", BTreeSet::from([Scope::IssueWrite]), Some(rid))
+            .unwrap();
+        let body = json!({
+            "title": "Issue #2",
+            "description": "Change 'hello world' to 'hello everyone'",
+            "labels": [],
+            "assignees": [],
+        })
+        .to_string();
+
+        for (token, status) in [
+            ("rad_unknown".to_owned(), StatusCode::BAD_REQUEST),
+            (patches, StatusCode::FORBIDDEN),
+            (elsewhere, StatusCode::FORBIDDEN),
+            (issues, StatusCode::CREATED),
+        ] {
+            let response = post(
+                &app,
+                format!("/projects/{CONTRIBUTOR_RID}/issues"),
+                Some(Body::from(body.clone())),
+                Some(token),
+            )
+            .await;
+
+            assert_eq!(response.status(), status);
+        }
+    }
+
     #[tokio::test]
     async fn test_projects_issues_comment() {
         let tmp = tempfile::tempdir().unwrap();
@@ -1996,6 +2649,7 @@ mod routes {
                     "discussions": [],
                     "timestamp": TIMESTAMP,
                     "reviews": [],
+                    "verdicts": {},
                   }
                 ],
               }
@@ -2038,6 +2692,7 @@ mod routes {
                     "discussions": [],
                     "timestamp": TIMESTAMP,
                     "reviews": [],
+                    "verdicts": {},
                   }
                 ],
               }
@@ -2119,6 +2774,7 @@ mod routes {
                     "discussions": [],
                     "timestamp": TIMESTAMP,
                     "reviews": [],
+                    "verdicts": {},
                   }
                 ],
               }
@@ -2184,6 +2840,7 @@ mod routes {
                   "discussions": [],
                   "timestamp": TIMESTAMP,
                   "reviews": [],
+                  "verdicts": {},
                 },
               ],
             })
@@ -2247,6 +2904,7 @@ mod routes {
                   "discussions": [],
                   "timestamp": TIMESTAMP,
                   "reviews": [],
+                  "verdicts": {},
                 },
                 {
                   "id": "b1f68feacb7040b089a77c1a0bff60a0411e6c1e",
@@ -2262,6 +2920,7 @@ mod routes {
                   "discussions": [],
                   "timestamp": TIMESTAMP,
                   "reviews": [],
+                  "verdicts": {},
                 }
               ],
             })
@@ -2325,6 +2984,7 @@ mod routes {
                   "discussions": [],
                   "timestamp": TIMESTAMP,
                   "reviews": [],
+                  "verdicts": {},
                 },
               ],
             })
@@ -2456,12 +3116,92 @@ mod routes {
                   ],
                   "timestamp": TIMESTAMP,
                   "reviews": [],
+                  "verdicts": {},
                 },
               ],
             })
         );
     }
 
+    #[tokio::test]
+    async fn test_projects_patches_review_with_comments() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ctx = contributor(tmp.path());
+        let app = super::router(ctx.to_owned());
+        create_session(ctx).await;
+
+        // Comments must be on lines changed by the revision.
+        for location in [
+            json!({ "path": "README", "new": { "type": "lines", "range": { "start": 5, "end": 8 } } }),
+            json!({ "path": "unknown", "new": { "type": "lines", "range": { "start": 1, "end": 2 } } }),
+        ] {
+            let body = serde_json::to_vec(&json!({
+              "revision": CONTRIBUTOR_PATCH_ID,
+              "verdict": "reject",
+              "comments": [{ "location": location, "body": "Out of range" }],
+            }))
+            .unwrap();
+            let response = post(
+                &app,
+                format!("/projects/{CONTRIBUTOR_RID}/patches/{CONTRIBUTOR_PATCH_ID}/reviews"),
+                Some(Body::from(body)),
+                Some(SESSION_ID.to_string()),
+            )
+            .await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+
+        let body = serde_json::to_vec(&json!({
+          "revision": CONTRIBUTOR_PATCH_ID,
+          "summary": "A review with comments",
+          "verdict": "accept",
+          "comments": [
+            {
+              "location": {
+                "path": "README",
+                "new": { "type": "lines", "range": { "start": 1, "end": 2 } },
+              },
+              "body": "Nice greeting",
+            },
+            {
+              "location": {
+                "path": "dir1/README",
+                "new": { "type": "chars", "line": 1, "range": { "start": 0, "end": 5 } },
+              },
+              "body": "Maybe 'Hi'?",
+            },
+          ],
+        }))
+        .unwrap();
+        let response = post(
+            &app,
+            format!("/projects/{CONTRIBUTOR_RID}/patches/{CONTRIBUTOR_PATCH_ID}/reviews"),
+            Some(Body::from(body)),
+            Some(SESSION_ID.to_string()),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let response = get(
+            &app,
+            format!("/projects/{CONTRIBUTOR_RID}/patches/{CONTRIBUTOR_PATCH_ID}"),
+        )
+        .await;
+        let revision = response.json().await["revisions"][0].clone();
+        let reviews = revision["reviews"].clone();
+        // Rejected drafts aren't committed, so there is only one review.
+        assert_eq!(reviews.as_array().unwrap().len(), 1);
+        assert_eq!(reviews[0]["verdict"], "accept");
+        assert_eq!(revision["verdicts"], json!({ CONTRIBUTOR_NID: "accept" }));
+        assert_eq!(reviews[0]["summary"], "A review with comments");
+        assert_eq!(reviews[0]["comments"][0][1]["body"], "Nice greeting");
+        assert_eq!(reviews[0]["comments"][1][1]["body"], "Maybe 'Hi'?");
+        assert_eq!(
+            reviews[0]["comments"][1][1]["location"]["path"],
+            "dir1/README"
+        );
+    }
+
     #[tokio::test]
     async fn test_projects_patches_reviews() {
         let tmp = tempfile::tempdir().unwrap();
@@ -2586,6 +3326,9 @@ mod routes {
                       "timestamp": TIMESTAMP,
                     },
                   ],
+                  "verdicts": {
+                    CONTRIBUTOR_NID: "accept",
+                  },
                 },
               ],
             })
@@ -2659,6 +3402,7 @@ mod routes {
                   "discussions": [],
                   "timestamp": TIMESTAMP,
                   "reviews": [],
+                  "verdicts": {},
                 },
               ],
             })
@@ -0,0 +1,97 @@
+use axum::extract::State;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+
+use radicle::search::DEFAULT_LIMIT;
+
+use crate::api::error::Error;
+use crate::api::Context;
+use crate::axum_extra::Query;
+
+/// Maximum number of results returned.
+const MAX_LIMIT: usize = 100;
+
+pub fn router(ctx: Context) -> Router {
+    Router::new()
+        .route("/search", get(search_handler))
+        .with_state(ctx)
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchQuery {
+    pub q: String,
+    pub limit: Option<usize>,
+}
+
+/// Search projects, issues and patches. The index is kept up to date by the node, if its
+/// `search` option is enabled, so results only include repositories the node has indexed.
+/// `GET /search?q=<query>&limit=<limit>`
+async fn search_handler(
+    State(ctx): State<Context>,
+    Query(qs): Query<SearchQuery>,
+) -> impl IntoResponse {
+    let SearchQuery { q, limit } = qs;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    let results = ctx.profile.search()?.search(&q, limit)?;
+
+    Ok::<_, Error>(Json(results))
+}
+
+#[cfg(test)]
+mod routes {
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    use crate::test::{self, get, ISSUE_ID, RID};
+
+    #[tokio::test]
+    async fn test_search() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ctx = test::seed(tmp.path());
+        let app = super::router(ctx.clone());
+
+        // Nothing was indexed yet, since indexing is up to the node.
+        let response = get(&app, "/search?q=hello%20every").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.json().await, json!([]));
+
+        let profile = ctx.profile();
+        profile.search().unwrap().sync(&profile.storage).unwrap();
+
+        let response = get(&app, "/search?q=hello%20every").await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.json().await,
+            json!([
+                {
+                    "rid": RID,
+                    "kind": "issue",
+                    "id": ISSUE_ID,
+                    "title": "Issue #1",
+                    "snippet": "Change 'hello world' to 'hello everyone'",
+                }
+            ])
+        );
+
+        let response = get(&app, "/search?q=rad%20repository").await;
+        assert_eq!(
+            response.json().await,
+            json!([
+                {
+                    "rid": RID,
+                    "kind": "project",
+                    "id": null,
+                    "title": "hello-world",
+                    "snippet": "Rad repository for tests",
+                }
+            ])
+        );
+
+        let response = get(&app, "/search?q=nothing").await;
+        assert_eq!(response.json().await, json!([]));
+    }
+}
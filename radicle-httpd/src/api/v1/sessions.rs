@@ -18,7 +18,10 @@ use crate::axum_extra::Path;
 
 pub fn router(ctx: Context) -> Router {
     Router::new()
-        .route("/sessions", post(session_create_handler))
+        .route(
+            "/sessions",
+            post(session_create_handler).get(sessions_handler),
+        )
         .route(
             "/sessions/:id",
             put(session_signin_handler)
@@ -50,8 +53,9 @@ async fn session_create_handler(State(ctx): State<Context>) -> impl IntoResponse
             .checked_add(auth::UNAUTHORIZED_SESSIONS_EXPIRATION)
             .unwrap(),
     };
-    let mut sessions = ctx.sessions.write().await;
-    sessions.insert(session_id.clone(), session.clone());
+    let mut sessions = ctx.sessions.lock().await;
+    sessions.remove_expired()?;
+    sessions.insert(&session_id, &session)?;
 
     Ok::<_, Error>((
         StatusCode::CREATED,
@@ -65,10 +69,26 @@ async fn session_handler(
     State(ctx): State<Context>,
     Path(session_id): Path<String>,
 ) -> impl IntoResponse {
-    let sessions = ctx.sessions.read().await;
-    let session = sessions.get(&session_id).ok_or(Error::NotFound)?;
+    let sessions = ctx.sessions.lock().await;
+    let session = sessions.get(&session_id)?.ok_or(Error::NotFound)?;
 
-    Ok::<_, Error>(Json(json::session(session_id, session)))
+    Ok::<_, Error>(Json(json::session(session_id, &session)))
+}
+
+/// List the sessions that haven't expired, by handle.
+/// `GET /sessions`
+async fn sessions_handler(
+    State(ctx): State<Context>,
+    AuthBearer(token): AuthBearer,
+) -> impl IntoResponse {
+    auth::validate(&ctx, &token).await?;
+    let sessions = ctx.sessions.lock().await.list()?;
+    let sessions = sessions
+        .into_iter()
+        .map(|(handle, session)| json::session_info(&handle, &session))
+        .collect::<Vec<_>>();
+
+    Ok::<_, Error>(Json(sessions))
 }
 
 /// Update session.
@@ -78,8 +98,8 @@ async fn session_signin_handler(
     Path(session_id): Path<String>,
     Json(request): Json<AuthChallenge>,
 ) -> impl IntoResponse {
-    let mut sessions = ctx.sessions.write().await;
-    let session = sessions.get_mut(&session_id).ok_or(Error::NotFound)?;
+    let mut sessions = ctx.sessions.lock().await;
+    let mut session = sessions.get(&session_id)?.ok_or(Error::NotFound)?;
     if session.status == AuthState::Unauthorized {
         if session.public_key != request.pk {
             return Err(Error::Auth("Invalid public key"));
@@ -96,6 +116,7 @@ async fn session_signin_handler(
         session.expires_at = OffsetDateTime::now_utc()
             .checked_add(auth::AUTHORIZED_SESSIONS_EXPIRATION)
             .unwrap();
+        sessions.insert(&session_id, &session)?;
 
         return Ok::<_, Error>(Json(json!({ "success": true })));
     }
@@ -104,17 +125,25 @@ async fn session_signin_handler(
 }
 
 /// Delete session.
+/// Sessions can delete themselves given their id, and authorized sessions can revoke other
+/// sessions given their handle.
 /// `DELETE /sessions/:id`
 async fn session_delete_handler(
     State(ctx): State<Context>,
     AuthBearer(token): AuthBearer,
-    Path(session_id): Path<String>,
+    Path(id): Path<String>,
 ) -> impl IntoResponse {
-    if token != session_id {
+    let handle = if token == id {
+        auth::store::handle(&id)
+    } else if auth::validate(&ctx, &token).await.is_ok() {
+        id
+    } else {
         return Err(Error::Auth("Not authorized to delete this session"));
+    };
+    let mut sessions = ctx.sessions.lock().await;
+    if !sessions.remove(&handle)? {
+        return Err(Error::NotFound);
     }
-    let mut sessions = ctx.sessions.write().await;
-    sessions.remove_entry(&token).ok_or(Error::NotFound)?;
 
     Ok::<_, Error>(Json(json!({ "success": true })))
 }
@@ -125,8 +154,8 @@ mod routes {
     use axum::http::StatusCode;
     use radicle_cli::commands::rad_web::{self, SessionInfo};
 
-    use crate::api::auth::{AuthState, Session};
-    use crate::test::{self, get, post, put};
+    use crate::api::auth::{store, AuthState, Session};
+    use crate::test::{self, delete, get, get_with_auth, post, put, SESSION_ID};
 
     #[tokio::test]
     async fn test_session() {
@@ -179,4 +208,71 @@ mod routes {
         assert_eq!(status, StatusCode::OK);
         assert_eq!(body.status, AuthState::Authorized);
     }
+
+    #[tokio::test]
+    async fn test_sessions_list_and_revoke() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ctx = test::seed(tmp.path());
+        let app = super::router(ctx.to_owned());
+
+        test::create_session(ctx.clone()).await;
+        let response = post(&app, "/sessions", None, None).await;
+        let json = response.json().await;
+        let pending: SessionInfo = serde_json::from_value(json).unwrap();
+
+        // Sessions are persisted, so they survive restarts.
+        let sessions = crate::api::auth::Sessions::open(
+            ctx.profile()
+                .home
+                .node()
+                .join(crate::api::auth::SESSIONS_DB_FILE),
+        )
+        .unwrap();
+        assert!(sessions.get(SESSION_ID).unwrap().is_some());
+
+        // Listing sessions requires an authorized session.
+        let response = get_with_auth(&app, "/sessions", Some(pending.session_id.clone())).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = get_with_auth(&app, "/sessions", Some(SESSION_ID.to_owned())).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Session ids are secret, so sessions are listed by handle.
+        let json = response.json().await;
+        let listed = json.as_array().unwrap();
+        let handle = store::handle(&pending.session_id);
+        assert_eq!(listed.len(), 2);
+        assert!(listed.iter().all(|s| s.get("sessionId").is_none()));
+        assert!(listed.iter().any(|s| s["handle"] == handle));
+
+        // Authorized sessions can revoke other sessions, by handle.
+        let response = delete(
+            &app,
+            format!("/sessions/{}", pending.session_id),
+            Some(SESSION_ID.to_owned()),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = delete(
+            &app,
+            format!("/sessions/{handle}"),
+            Some(SESSION_ID.to_owned()),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = get(&app, format!("/sessions/{}", pending.session_id)).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // Sessions can delete themselves.
+        let response = delete(
+            &app,
+            format!("/sessions/{SESSION_ID}"),
+            Some(SESSION_ID.to_owned()),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(sessions.get(SESSION_ID).unwrap().is_none());
+    }
 }
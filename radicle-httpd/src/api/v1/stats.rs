@@ -4,24 +4,50 @@ use axum::routing::get;
 use axum::{Json, Router};
 use serde_json::json;
 
+use radicle::identity::Id;
+use radicle::stats::RepoStats;
+use radicle::storage::ReadStorage;
+
 use crate::api::error::Error;
 use crate::api::Context;
+use crate::axum_extra::Path;
 
 pub fn router(ctx: Context) -> Router {
     Router::new()
         .route("/stats", get(stats_handler))
+        .route("/projects/:project/stats", get(project_stats_handler))
         .with_state(ctx)
 }
 
-/// Return the stats for the node.
+/// Return the stats for the node. Statistics are computed in the background, so this only
+/// includes the repositories whose statistics were computed so far.
 /// `GET /stats`
 async fn stats_handler(State(ctx): State<Context>) -> impl IntoResponse {
-    let storage = &ctx.profile.storage;
-    let projects = storage.repositories()?.len();
+    let stats = ctx.stats.node(&ctx.profile.storage)?;
+
+    Ok::<_, Error>(Json(json!({
+        "projects": { "count": stats.projects },
+        "users": { "count": stats.contributors },
+        "remotes": { "count": stats.remotes },
+    })))
+}
 
-    Ok::<_, Error>(Json(
-        json!({ "projects": { "count": projects }, "users": { "count": 0 } }),
-    ))
+/// Return the stats for a project: remotes, contributors, commit activity and issue and patch
+/// rates.
+/// `GET /projects/:project/stats`
+async fn project_stats_handler(
+    State(ctx): State<Context>,
+    Path(project): Path<Id>,
+) -> impl IntoResponse {
+    let stats = tokio::task::spawn_blocking(move || {
+        let repo = ctx.profile.storage.repository(project)?;
+        let stats: RepoStats = (*ctx.stats.repo(&repo)?).clone();
+
+        Ok::<_, Error>(stats)
+    })
+    .await??;
+
+    Ok::<_, Error>(Json(stats))
 }
 
 #[cfg(test)]
@@ -29,14 +55,16 @@ mod routes {
     use axum::http::StatusCode;
     use serde_json::json;
 
-    use crate::test::{self, get};
+    use crate::test::{self, get, DID, RID};
 
     #[tokio::test]
     async fn test_stats() {
         let tmp = tempfile::tempdir().unwrap();
-        let app = super::router(test::seed(tmp.path()));
-        let response = get(&app, "/stats").await;
+        let ctx = test::seed(tmp.path());
+        let app = super::router(ctx.clone());
 
+        // Nothing was computed yet.
+        let response = get(&app, "/stats").await;
         assert_eq!(response.status(), StatusCode::OK);
         assert_eq!(
             response.json().await,
@@ -46,6 +74,73 @@ mod routes {
                 },
                 "users": {
                     "count": 0
+                },
+                "remotes": {
+                    "count": 0
+                }
+            })
+        );
+
+        ctx.stats().update(&ctx.profile().storage).unwrap();
+
+        let response = get(&app, "/stats").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.json().await,
+            json!({
+                "projects": {
+                    "count": 1
+                },
+                "users": {
+                    "count": 1
+                },
+                "remotes": {
+                    "count": 1
+                }
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_project_stats() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = super::router(test::seed(tmp.path()));
+        let response = get(&app, format!("/projects/{RID}/stats")).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.json().await,
+            json!({
+                "remotes": 1,
+                "contributors": [
+                    {
+                        "id": DID,
+                        "commits": 0,
+                        "patches": 1,
+                        "issues": 1
+                    }
+                ],
+                "commits": {
+                    "total": 3,
+                    "activity": [
+                        {
+                            "time": 1672876800,
+                            "commits": 3
+                        }
+                    ]
+                },
+                "issues": {
+                    "open": 1,
+                    "closed": 0,
+                    "closeRate": 0.0
+                },
+                "patches": {
+                    "open": 1,
+                    "draft": 0,
+                    "archived": 0,
+                    "merged": 0,
+                    "mergeRate": 0.0,
+                    "averageTimeToMerge": null
                 }
             })
         );
@@ -0,0 +1,270 @@
+use std::collections::BTreeSet;
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::{delete, get};
+use axum::{Json, Router};
+use axum_auth::AuthBearer;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use radicle::identity::Id;
+use radicle::storage::ReadStorage;
+use radicle::tokens::Scope;
+
+use crate::api::error::Error;
+use crate::api::{self, Context};
+use crate::axum_extra::Path;
+use crate::webhooks::{self, EventKind};
+
+pub fn router(ctx: Context) -> Router {
+    Router::new()
+        .route(
+            "/projects/:project/webhooks",
+            get(webhooks_handler).post(webhook_create_handler),
+        )
+        .route(
+            "/projects/:project/webhooks/:id",
+            delete(webhook_delete_handler),
+        )
+        .route(
+            "/projects/:project/webhooks/:id/deliveries",
+            get(webhook_deliveries_handler),
+        )
+        .with_state(ctx)
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookCreate {
+    pub url: String,
+    /// Secret used to sign payloads. Generated if not provided.
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Events triggering the webhook. Defaults to all events.
+    #[serde(default)]
+    pub events: Option<BTreeSet<EventKind>>,
+}
+
+/// Open the webhook store, making sure the project exists.
+fn store(ctx: &Context, project: Id) -> Result<webhooks::Store, Error> {
+    ctx.profile.storage.repository(project)?;
+
+    let path = ctx.profile.home.node().join(webhooks::WEBHOOKS_DB_FILE);
+    let store = webhooks::Store::open(path)?;
+
+    Ok(store)
+}
+
+/// List the webhooks of a project.
+/// Requires a session, or an API token with the `read` scope.
+/// `GET /projects/:project/webhooks`
+async fn webhooks_handler(
+    State(ctx): State<Context>,
+    AuthBearer(token): AuthBearer,
+    Path(project): Path<Id>,
+) -> impl IntoResponse {
+    api::auth::authorize(&ctx, &token, Scope::Read, project).await?;
+    let webhooks = store(&ctx, project)?.webhooks(&project)?;
+
+    Ok::<_, Error>(Json(webhooks))
+}
+
+/// Create a webhook.
+/// `POST /projects/:project/webhooks`
+async fn webhook_create_handler(
+    State(ctx): State<Context>,
+    AuthBearer(token): AuthBearer,
+    Path(project): Path<Id>,
+    Json(webhook): Json<WebhookCreate>,
+) -> impl IntoResponse {
+    api::auth::validate(&ctx, &token).await?;
+
+    if !webhook.url.starts_with("https://") && !webhook.url.starts_with("http://") {
+        return Err(Error::BadRequest(String::from(
+            "webhook URL must start with 'https://' or 'http://'",
+        )));
+    }
+    let events = webhook.events.unwrap_or_else(EventKind::all);
+    if events.is_empty() {
+        return Err(Error::BadRequest(String::from(
+            "webhook must be triggered by at least one event",
+        )));
+    }
+    let secret = webhook.secret.unwrap_or_else(webhooks::secret);
+    let webhook = store(&ctx, project)?.create(project, &webhook.url, &secret, events)?;
+
+    Ok::<_, Error>((
+        StatusCode::CREATED,
+        Json(json!({ "success": true, "id": webhook.id, "secret": secret })),
+    ))
+}
+
+/// Delete a webhook.
+/// `DELETE /projects/:project/webhooks/:id`
+async fn webhook_delete_handler(
+    State(ctx): State<Context>,
+    AuthBearer(token): AuthBearer,
+    Path((project, id)): Path<(Id, String)>,
+) -> impl IntoResponse {
+    api::auth::validate(&ctx, &token).await?;
+    if !store(&ctx, project)?.remove(&project, &id)? {
+        return Err(Error::NotFound);
+    }
+
+    Ok::<_, Error>(Json(json!({ "success": true })))
+}
+
+/// Get the delivery log of a webhook, most recent first.
+/// Requires a session, or an API token with the `read` scope.
+/// `GET /projects/:project/webhooks/:id/deliveries`
+async fn webhook_deliveries_handler(
+    State(ctx): State<Context>,
+    AuthBearer(token): AuthBearer,
+    Path((project, id)): Path<(Id, String)>,
+) -> impl IntoResponse {
+    api::auth::authorize(&ctx, &token, Scope::Read, project).await?;
+    let store = store(&ctx, project)?;
+    let webhook = store.get(&project, &id)?.ok_or(Error::NotFound)?;
+    let deliveries = store.deliveries(&webhook.id)?;
+
+    Ok::<_, Error>(Json(deliveries))
+}
+
+#[cfg(test)]
+mod routes {
+    use std::collections::BTreeSet;
+
+    use axum::body::Body;
+    use axum::http::StatusCode;
+    use radicle::tokens::Scope;
+    use serde_json::json;
+
+    use crate::test::{self, delete, get_with_auth, post, CONTRIBUTOR_RID, RID, SESSION_ID};
+
+    #[tokio::test]
+    async fn test_projects_webhooks() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ctx = test::seed(tmp.path());
+        let app = super::router(ctx.to_owned());
+
+        test::create_session(ctx.clone()).await;
+
+        let response = post(
+            &app,
+            format!("/projects/{RID}/webhooks"),
+            Some(Body::from(
+                json!({ "url": "ftp://ci.example.com/hook" }).to_string(),
+            )),
+            Some(SESSION_ID.to_owned()),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = post(
+            &app,
+            format!("/projects/{RID}/webhooks"),
+            Some(Body::from(
+                json!({ "url": "https://ci.example.com/hook" }).to_string(),
+            )),
+            Some(SESSION_ID.to_owned()),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let response = post(
+            &app,
+            format!("/projects/{RID}/webhooks"),
+            Some(Body::from(
+                json!({ "url": "http://127.0.0.1:8000/hook", "events": ["patch"] }).to_string(),
+            )),
+            Some(SESSION_ID.to_owned()),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let json = response.json().await;
+        let id = json["id"].as_str().unwrap().to_owned();
+        assert_eq!(json["secret"].as_str().unwrap().len(), 64);
+
+        let response = get_with_auth(
+            &app,
+            format!("/projects/{RID}/webhooks"),
+            Some(SESSION_ID.to_owned()),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let json = response.json().await;
+        assert_eq!(json[1]["id"], id);
+        assert_eq!(json[1]["url"], "http://127.0.0.1:8000/hook");
+        assert_eq!(json[1]["events"], json!(["patch"]));
+        assert!(json[1].get("secret").is_none());
+
+        let response = get_with_auth(
+            &app,
+            format!("/projects/{RID}/webhooks/{id}/deliveries"),
+            Some(SESSION_ID.to_owned()),
+        )
+        .await;
+        assert_eq!(response.json().await, json!([]));
+
+        // Read-only tokens can list webhooks and deliveries of their repository, but not
+        // manage them.
+        let (_, read) = ctx
+            .profile()
+            .tokens()
+            .unwrap()
+            .create("ci", BTreeSet::from([Scope::Read]), None)
+            .unwrap();
+        let (_, other) = ctx
+            .profile()
+            .tokens()
+            .unwrap()
+            .create(
+                "bot",
+                BTreeSet::from([Scope::Read]),
+                Some(CONTRIBUTOR_RID.parse().unwrap()),
+            )
+            .unwrap();
+
+        let response = get_with_auth(
+            &app,
+            format!("/projects/{RID}/webhooks"),
+            Some(read.clone()),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = get_with_auth(
+            &app,
+            format!("/projects/{RID}/webhooks/{id}/deliveries"),
+            Some(read.clone()),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = get_with_auth(&app, format!("/projects/{RID}/webhooks"), Some(other)).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = delete(&app, format!("/projects/{RID}/webhooks/{id}"), Some(read)).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = delete(
+            &app,
+            format!("/projects/{RID}/webhooks/{id}"),
+            Some(SESSION_ID.to_owned()),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = delete(
+            &app,
+            format!("/projects/{RID}/webhooks/{id}"),
+            Some(SESSION_ID.to_owned()),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -10,6 +10,10 @@ use radicle_surf::Oid;
 #[derive(Clone)]
 pub struct Cache {
     pub tree: Arc<Mutex<LruCache<(Id, Oid, String), serde_json::Value>>>,
+    /// Code search results, keyed by the query.
+    pub search: Arc<Mutex<LruCache<(Id, Oid, String), serde_json::Value>>>,
+    /// File blames, keyed by the file path.
+    pub blame: Arc<Mutex<LruCache<(Id, Oid, String), serde_json::Value>>>,
 }
 
 impl Cache {
@@ -17,6 +21,8 @@ impl Cache {
     pub fn new(size: NonZeroUsize) -> Self {
         Cache {
             tree: Arc::new(Mutex::new(LruCache::new(size))),
+            search: Arc::new(Mutex::new(LruCache::new(size))),
+            blame: Arc::new(Mutex::new(LruCache::new(size))),
         }
     }
 }
@@ -12,14 +12,30 @@ pub enum GitError {
     #[error("i/o error: {0}")]
     Io(#[from] std::io::Error),
 
-    /// The service is not available.
-    #[error("service '{0}' not available")]
-    ServiceUnavailable(&'static str),
+    /// The request is not authorized.
+    #[error("unauthorized")]
+    Unauthorized,
+
+    /// Storage error.
+    #[error(transparent)]
+    Storage(#[from] radicle::storage::Error),
+
+    /// Identity error.
+    #[error(transparent)]
+    Identity(#[from] radicle::identity::IdentityError),
+
+    /// Profile error.
+    #[error(transparent)]
+    Profile(#[from] radicle::profile::Error),
 
     /// Invalid identifier.
     #[error("invalid radicle identifier: {0}")]
     Id(#[from] radicle::identity::IdError),
 
+    /// Blocking task error.
+    #[error(transparent)]
+    Join(#[from] tokio::task::JoinError),
+
     /// Git backend error.
     #[error("backend error")]
     Backend,
@@ -36,7 +52,7 @@ pub enum GitError {
 impl GitError {
     pub fn status(&self) -> http::StatusCode {
         match self {
-            GitError::ServiceUnavailable(_) => http::StatusCode::SERVICE_UNAVAILABLE,
+            GitError::Unauthorized => http::StatusCode::UNAUTHORIZED,
             GitError::Id(_) => http::StatusCode::NOT_FOUND,
             GitError::NotFound => http::StatusCode::NOT_FOUND,
             _ => http::StatusCode::INTERNAL_SERVER_ERROR,
@@ -48,6 +64,14 @@ impl IntoResponse for GitError {
     fn into_response(self) -> Response {
         tracing::error!("{}", self);
 
+        if let GitError::Unauthorized = self {
+            // Prompt git clients for credentials.
+            return (
+                self.status(),
+                [(http::header::WWW_AUTHENTICATE, "Basic realm=\"Radicle\"")],
+            )
+                .into_response();
+        }
         self.status().into_response()
     }
 }
@@ -3,12 +3,13 @@ use std::io::prelude::*;
 use std::net::SocketAddr;
 use std::path::Path;
 use std::process::{Command, Stdio};
-use std::sync::Arc;
 use std::{io, net, str};
 
 use axum::body::Bytes;
-use axum::extract::{ConnectInfo, Path as AxumPath, RawQuery, State};
-use axum::http::header::HeaderName;
+use axum::extract::{ConnectInfo, DefaultBodyLimit, Path as AxumPath, RawQuery, State};
+use axum::headers::authorization::{Basic, Bearer};
+use axum::headers::{Authorization, HeaderMapExt as _};
+use axum::http::header::{self, HeaderName};
 use axum::http::{HeaderMap, Method, StatusCode};
 use axum::response::IntoResponse;
 use axum::routing::any;
@@ -16,19 +17,25 @@ use axum::Router;
 use flate2::write::GzDecoder;
 use hyper::body::Buf as _;
 
+use radicle::crypto::PublicKey;
 use radicle::identity::Id;
+use radicle::node::Handle as _;
 use radicle::profile::Profile;
+use radicle::storage::{ReadStorage, SignRepository as _, WriteRepository as _};
 
+use crate::api::{auth, Context};
 use crate::error::GitError as Error;
 
-pub fn router(profile: Arc<Profile>, aliases: HashMap<String, Id>) -> Router {
+pub fn router(ctx: Context, aliases: HashMap<String, Id>) -> Router {
     Router::new()
         .route("/:project/*request", any(git_handler))
-        .with_state((profile, aliases))
+        // Packs easily exceed the default limit of 2 MB.
+        .layer(DefaultBodyLimit::disable())
+        .with_state((ctx, aliases))
 }
 
 async fn git_handler(
-    State((profile, aliases)): State<(Arc<Profile>, HashMap<String, Id>)>,
+    State((ctx, aliases)): State<(Context, HashMap<String, Id>)>,
     AxumPath((project, request)): AxumPath<(String, String)>,
     method: Method,
     headers: HeaderMap,
@@ -48,11 +55,40 @@ async fn git_handler(
         }
     };
 
+    let profile = ctx.profile();
+    // Only the node owner can push, since sessions are only issued for our key, and pushes go
+    // to our namespace.
+    let namespace = if is_push(&request, &query) {
+        authorize(&ctx, &headers).await?;
+        if !profile.storage.contains(&rid)? {
+            return Err(Error::NotFound);
+        }
+        Some(*profile.id())
+    } else {
+        None
+    };
+
     let (status, headers, body) = git_http_backend(
-        &profile, method, headers, body, remote, rid, &request, query,
+        profile,
+        method,
+        headers,
+        body,
+        remote,
+        rid,
+        &request,
+        query,
+        namespace.as_ref(),
     )
     .await?;
 
+    if request == "git-receive-pack" && status.is_success() {
+        tokio::task::spawn_blocking({
+            let profile = profile.clone();
+            move || receive_pack(&profile, rid)
+        })
+        .await??;
+    }
+
     let mut response_headers = HeaderMap::new();
     for (name, vec) in headers.iter() {
         for value in vec {
@@ -64,6 +100,47 @@ async fn git_handler(
     Ok::<_, Error>((status, response_headers, body))
 }
 
+/// Update the repository once a pack was received into our namespace.
+///
+/// Our refs are signed with our key, the same way a push to local storage does.
+fn receive_pack(profile: &Profile, rid: Id) -> Result<(), Error> {
+    let repo = profile.storage.repository(rid)?;
+    let signer = profile.signer()?;
+
+    repo.sign_refs(&signer)?;
+    repo.set_head()?;
+
+    // If our node isn't running, the refs will be announced when it starts.
+    let mut node = radicle::Node::new(profile.socket());
+    if node.is_running() {
+        if let Err(e) = node.announce_refs(rid) {
+            tracing::warn!("failed to announce refs of {rid}: {e}");
+        }
+    }
+    Ok(())
+}
+
+/// Whether the request is part of a push.
+fn is_push(path: &str, query: &str) -> bool {
+    path == "git-receive-pack" || query == "service=git-receive-pack"
+}
+
+/// Authorize a request with a session token, given either as a bearer token, or as the
+/// password of basic authentication, which is what git's credential helpers send.
+async fn authorize(ctx: &Context, headers: &HeaderMap) -> Result<auth::Session, Error> {
+    let token = if let Some(Authorization(bearer)) = headers.typed_get::<Authorization<Bearer>>() {
+        bearer.token().to_owned()
+    } else if let Some(Authorization(basic)) = headers.typed_get::<Authorization<Basic>>() {
+        basic.password().to_owned()
+    } else {
+        return Err(Error::Unauthorized);
+    };
+
+    auth::validate(ctx, &token)
+        .await
+        .map_err(|_| Error::Unauthorized)
+}
+
 async fn git_http_backend(
     profile: &Profile,
     method: Method,
@@ -73,6 +150,7 @@ async fn git_http_backend(
     id: Id,
     path: &str,
     query: String,
+    namespace: Option<&PublicKey>,
 ) -> Result<(StatusCode, HashMap<String, Vec<String>>, Vec<u8>), Error> {
     let git_dir = radicle::storage::git::paths::repository(&profile.storage, &id);
     let content_type =
@@ -82,21 +160,25 @@ async fn git_http_backend(
             ""
         };
 
-    // Reject push requests.
-    match (path, query.as_str()) {
-        ("git-receive-pack", _) | (_, "service=git-receive-pack") => {
-            return Err(Error::ServiceUnavailable("git-receive-pack"));
-        }
-        _ => {}
-    };
-
     tracing::debug!("id: {:?}", id);
-    tracing::debug!("headers: {:?}", headers);
+    tracing::debug!(
+        "headers: {:?}",
+        // Don't log credentials.
+        headers
+            .iter()
+            .filter(|(name, _)| *name != header::AUTHORIZATION)
+            .collect::<Vec<_>>()
+    );
     tracing::debug!("path: {:?}", path);
     tracing::debug!("method: {:?}", method.as_str());
     tracing::debug!("remote: {:?}", remote.to_string());
 
     let mut cmd = Command::new("git");
+    if let Some(namespace) = namespace {
+        // Only authenticated users can push, and refs are pushed to their namespace.
+        cmd.env("REMOTE_USER", namespace.to_string())
+            .env("GIT_NAMESPACE", namespace.to_string());
+    }
     let mut child = cmd
         .arg("http-backend")
         .env("REQUEST_METHOD", method.as_str())
@@ -210,17 +292,24 @@ mod routes {
     use std::net::SocketAddr;
     use std::str::FromStr;
 
+    use axum::body::Body;
     use axum::extract::connect_info::MockConnectInfo;
-    use axum::http::StatusCode;
+    use axum::headers::{Authorization, HeaderMapExt as _};
+    use std::process::Command;
+
+    use axum::http::{header, Request, StatusCode};
+    use radicle::git;
     use radicle::identity::Id;
+    use radicle::storage::{ReadRepository as _, ReadStorage as _};
+    use tower::ServiceExt as _;
 
-    use crate::test::{self, get, RID};
+    use crate::test::{self, get, RID, SESSION_ID};
 
     #[tokio::test]
     async fn test_info_request() {
         let tmp = tempfile::tempdir().unwrap();
         let ctx = test::seed(tmp.path());
-        let app = super::router(ctx.profile().to_owned(), HashMap::new())
+        let app = super::router(ctx.to_owned(), HashMap::new())
             .layer(MockConnectInfo(SocketAddr::from(([0, 0, 0, 0], 8080))));
 
         let response = get(&app, format!("/{RID}.git/info/refs")).await;
@@ -233,7 +322,7 @@ mod routes {
         let tmp = tempfile::tempdir().unwrap();
         let ctx = test::seed(tmp.path());
         let app = super::router(
-            ctx.profile().to_owned(),
+            ctx.to_owned(),
             HashMap::from_iter([(String::from("heartwood"), Id::from_str(RID).unwrap())]),
         )
         .layer(MockConnectInfo(SocketAddr::from(([0, 0, 0, 0], 8080))));
@@ -244,4 +333,169 @@ mod routes {
         let response = get(&app, "/heartwood.git/info/refs").await;
         assert_eq!(response.status(), StatusCode::OK);
     }
+
+    #[tokio::test]
+    async fn test_push_requires_session() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ctx = test::seed(tmp.path());
+        let app = super::router(ctx.to_owned(), HashMap::new())
+            .layer(MockConnectInfo(SocketAddr::from(([0, 0, 0, 0], 8080))));
+        let path = format!("/{RID}.git/info/refs?service=git-receive-pack");
+
+        let response = get(&app, &path).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        // Git sends the session token as the password of basic authentication.
+        let request = |token: &str| {
+            let mut request = Request::get(&path).body(Body::empty()).unwrap();
+            request
+                .headers_mut()
+                .typed_insert(Authorization::basic("radicle", token));
+            request
+        };
+        let response = app.clone().oneshot(request(SESSION_ID)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert!(response.headers().contains_key(header::WWW_AUTHENTICATE));
+
+        test::create_session(ctx).await;
+
+        let response = app.clone().oneshot(request(SESSION_ID)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Refs are advertised from our namespace.
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.contains("refs/heads/master"));
+        assert!(!body.contains("refs/namespaces"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_push() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ctx = test::seed(tmp.path());
+        let app = super::router(ctx.to_owned(), HashMap::new());
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        test::create_session(ctx.clone()).await;
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service_with_connect_info::<SocketAddr>()),
+        );
+
+        let profile = ctx.profile();
+        let rid = Id::from_str(RID).unwrap();
+        let nid = *profile.id();
+        let repo = profile.storage.repository(rid).unwrap();
+        let sigrefs = repo.remote(&nid).unwrap().refs.signature;
+
+        // Push the working copy's head to a new branch, with the session as password.
+        let workdir = tmp.path().join("hello-world");
+        let url = format!("http://radicle:{SESSION_ID}@{addr}/{RID}.git");
+        let output = tokio::task::spawn_blocking(move || {
+            Command::new("git")
+                .current_dir(workdir)
+                .args(["push", &url, "HEAD:refs/heads/pushed"])
+                .output()
+                .unwrap()
+        })
+        .await
+        .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        // The ref landed in our namespace, and our signed refs were updated to include it.
+        let pushed = repo
+            .backend
+            .refname_to_id(&format!("refs/namespaces/{nid}/refs/heads/pushed"))
+            .unwrap();
+        let remote = repo.remote(&nid).unwrap();
+
+        assert_ne!(remote.refs.signature, sigrefs);
+        assert_eq!(
+            remote
+                .refs
+                .head(git::RefString::try_from("pushed").unwrap()),
+            Some(pushed.into())
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_push_large() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ctx = test::seed(tmp.path());
+        let app = super::router(ctx.to_owned(), HashMap::new());
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        test::create_session(ctx.clone()).await;
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service_with_connect_info::<SocketAddr>()),
+        );
+
+        // Commit a file that doesn't compress, so that the pack is larger than 2 MB.
+        let workdir = tmp.path().join("hello-world");
+        let data = std::iter::repeat_with(|| fastrand::u8(..))
+            .take(3 * 1024 * 1024)
+            .collect::<Vec<_>>();
+        std::fs::write(workdir.join("large.bin"), data).unwrap();
+
+        let url = format!("http://radicle:{SESSION_ID}@{addr}/{RID}.git");
+        let output = tokio::task::spawn_blocking(move || {
+            for args in [
+                vec!["add", "large.bin"],
+                vec![
+                    "-c",
+                    "user.name=Radicle",
+                    "-c",
+                    "user.email=radicle@localhost",
+                    "commit",
+                    "-m",
+                    "Add large file",
+                ],
+                vec!["push", &url, "HEAD:refs/heads/large"],
+            ] {
+                let output = Command::new("git")
+                    .current_dir(&workdir)
+                    .args(args)
+                    .output()
+                    .unwrap();
+                if !output.status.success() {
+                    return output;
+                }
+            }
+            Command::new("git")
+                .current_dir(&workdir)
+                .args(["rev-parse", "HEAD"])
+                .output()
+                .unwrap()
+        })
+        .await
+        .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        let head = String::from_utf8(output.stdout).unwrap();
+        let profile = ctx.profile();
+        let nid = *profile.id();
+        let repo = profile
+            .storage
+            .repository(Id::from_str(RID).unwrap())
+            .unwrap();
+        let pushed = repo
+            .backend
+            .refname_to_id(&format!("refs/namespaces/{nid}/refs/heads/large"))
+            .unwrap();
+
+        assert_eq!(pushed.to_string(), head.trim());
+    }
 }
@@ -8,6 +8,7 @@ use std::num::NonZeroUsize;
 use std::process::Command;
 use std::str;
 use std::sync::Arc;
+use std::thread;
 use std::time::Duration;
 
 use anyhow::Context as _;
@@ -31,10 +32,14 @@ mod raw;
 #[cfg(test)]
 mod test;
 mod tracing_extra;
+mod webhooks;
 
 /// Default cache HTTP size.
 pub const DEFAULT_CACHE_SIZE: NonZeroUsize = unsafe { NonZeroUsize::new_unchecked(100) };
 
+/// Interval at which repository statistics are updated.
+pub const STATS_INTERVAL: Duration = Duration::from_secs(60);
+
 #[derive(Debug, Clone)]
 pub struct Options {
     pub aliases: HashMap<String, Id>,
@@ -61,8 +66,27 @@ pub async fn run(options: Options) -> anyhow::Result<()> {
 
     tracing::info!("using radicle home at {}", profile.home().display());
 
+    let profile = Arc::new(profile);
+    let ctx = api::Context::new(profile.clone(), &options)?;
+
+    thread::Builder::new()
+        .name(String::from("webhooks"))
+        .spawn({
+            let profile = profile.clone();
+            move || webhooks::run(profile, webhooks::Retry::default())
+        })?;
+    thread::Builder::new().name(String::from("stats")).spawn({
+        let stats = ctx.stats().clone();
+        move || loop {
+            if let Err(e) = stats.update(&profile.storage) {
+                tracing::error!("stats: failed to update repository statistics: {e}");
+            }
+            thread::sleep(STATS_INTERVAL);
+        }
+    })?;
+
     let app =
-        router(options, profile)?
+        router(options, ctx)?
         .layer(middleware::from_fn(tracing_middleware))
         .layer(
             TraceLayer::new_for_http()
@@ -105,12 +129,10 @@ pub async fn run(options: Options) -> anyhow::Result<()> {
 }
 
 /// Create a router consisting of other sub-routers.
-fn router(options: Options, profile: Profile) -> anyhow::Result<Router> {
-    let profile = Arc::new(profile);
-    let ctx = api::Context::new(profile.clone(), &options);
-
-    let api_router = api::router(ctx);
-    let git_router = git::router(profile.clone(), options.aliases);
+fn router(options: Options, ctx: api::Context) -> anyhow::Result<Router> {
+    let profile = ctx.profile().clone();
+    let api_router = api::router(ctx.clone());
+    let git_router = git::router(ctx, options.aliases);
     let raw_router = raw::router(profile);
 
     let app = Router::new()
@@ -150,25 +172,27 @@ pub mod logger {
 mod routes {
     use std::collections::HashMap;
     use std::net::SocketAddr;
+    use std::sync::Arc;
 
     use axum::extract::connect_info::MockConnectInfo;
     use axum::http::StatusCode;
 
+    use crate::api;
     use crate::test::{self, get};
 
     #[tokio::test]
     async fn test_invalid_route_returns_404() {
         let tmp = tempfile::tempdir().unwrap();
-        let app = super::router(
-            super::Options {
-                aliases: HashMap::new(),
-                listen: SocketAddr::from(([0, 0, 0, 0], 8080)),
-                cache: None,
-            },
-            test::profile(tmp.path(), [0xff; 32]),
-        )
-        .unwrap()
-        .layer(MockConnectInfo(SocketAddr::from(([0, 0, 0, 0], 8080))));
+        let options = super::Options {
+            aliases: HashMap::new(),
+            listen: SocketAddr::from(([0, 0, 0, 0], 8080)),
+            cache: None,
+        };
+        let profile = Arc::new(test::profile(tmp.path(), [0xff; 32]));
+        let ctx = api::Context::new(profile, &options).unwrap();
+        let app = super::router(options, ctx)
+            .unwrap()
+            .layer(MockConnectInfo(SocketAddr::from(([0, 0, 0, 0], 8080))));
 
         let response = get(&app, "/aa/a").await;
 
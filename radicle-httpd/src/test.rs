@@ -214,24 +214,26 @@ fn seed_with_signer<G: Signer>(dir: &Path, profile: radicle::Profile, signer: &G
         cache: Some(crate::DEFAULT_CACHE_SIZE),
     };
 
-    Context::new(Arc::new(profile), &options)
+    Context::new(Arc::new(profile), &options).unwrap()
 }
 
-/// Adds an authorized session to the Context::sessions HashMap.
+/// Adds an authorized session to the Context::sessions store.
 pub async fn create_session(ctx: Context) {
     let issued_at = OffsetDateTime::now_utc();
-    let mut sessions = ctx.sessions().write().await;
-    sessions.insert(
-        String::from(SESSION_ID),
-        auth::Session {
-            status: auth::AuthState::Authorized,
-            public_key: ctx.profile().public_key,
-            issued_at,
-            expires_at: issued_at
-                .checked_add(auth::AUTHORIZED_SESSIONS_EXPIRATION)
-                .unwrap(),
-        },
-    );
+    let mut sessions = ctx.sessions().lock().await;
+    sessions
+        .insert(
+            SESSION_ID,
+            &auth::Session {
+                status: auth::AuthState::Authorized,
+                public_key: ctx.profile().public_key,
+                issued_at,
+                expires_at: issued_at
+                    .checked_add(auth::AUTHORIZED_SESSIONS_EXPIRATION)
+                    .unwrap(),
+            },
+        )
+        .unwrap();
 }
 
 pub async fn get(app: &Router, path: impl ToString) -> Response {
@@ -243,6 +245,15 @@ pub async fn get(app: &Router, path: impl ToString) -> Response {
     )
 }
 
+pub async fn get_with_auth(app: &Router, path: impl ToString, auth: Option<String>) -> Response {
+    Response(
+        app.clone()
+            .oneshot(request(path, Method::GET, None, auth))
+            .await
+            .unwrap(),
+    )
+}
+
 pub async fn post(
     app: &Router,
     path: impl ToString,
@@ -285,6 +296,15 @@ pub async fn put(
     )
 }
 
+pub async fn delete(app: &Router, path: impl ToString, auth: Option<String>) -> Response {
+    Response(
+        app.clone()
+            .oneshot(request(path, Method::DELETE, None, auth))
+            .await
+            .unwrap(),
+    )
+}
+
 fn request(
     path: impl ToString,
     method: Method,
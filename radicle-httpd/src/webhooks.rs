@@ -0,0 +1,1180 @@
+//! Outgoing webhooks.
+//!
+//! Webhooks are configured per repository, and are triggered by node events: updated
+//! references, and changes to patches and issues. Each payload is sent as JSON in a `POST`
+//! request, signed with the webhook's secret using HMAC-SHA256. The hex-encoded signature is
+//! sent in the [`SIGNATURE_HEADER`] header, prefixed with `sha256=`.
+//!
+//! Failed deliveries are retried with exponential backoff, and the outcome of every delivery
+//! is recorded in a log, which can be inspected through the API. Deliveries are handled by a
+//! fixed number of workers; when too many deliveries are pending, new ones are dropped and
+//! logged as failed.
+//!
+//! Changes are detected by comparing the refs of repositories with webhooks to the ones last
+//! seen, whenever their signed refs change. Repositories are checked as soon as the node
+//! fetches them, and periodically for local changes, eg. pushes to the local node, or issues
+//! and patches changed through this API or `rad`. Changes made while `radicle-httpd` isn't
+//! running don't trigger webhooks.
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::iter::repeat_with;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+use std::{fmt, io, thread};
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use sqlite as sql;
+use thiserror::Error;
+use time::OffsetDateTime;
+
+use radicle::cob;
+use radicle::cob::{issue, patch};
+use radicle::crypto::Seed;
+use radicle::git;
+use radicle::git::RefString;
+use radicle::identity::Id;
+use radicle::node::{Event, Handle as _, NodeId};
+use radicle::storage::git::Repository;
+use radicle::storage::{ReadStorage, RefUpdate};
+use radicle::{Profile, Storage};
+
+use crate::api::v1::events::{Activity, Change, CobChanged};
+
+/// Webhooks database file name.
+pub const WEBHOOKS_DB_FILE: &str = "webhooks.db";
+/// Header containing the payload signature.
+pub const SIGNATURE_HEADER: &str = "X-Radicle-Signature";
+/// Header containing the event that triggered the delivery.
+pub const EVENT_HEADER: &str = "X-Radicle-Event";
+/// Header containing the delivery identifier.
+pub const DELIVERY_HEADER: &str = "X-Radicle-Delivery";
+/// Number of deliveries kept in the log, per webhook.
+pub const DELIVERY_LOG_SIZE: usize = 100;
+
+/// How long to wait for a receiver to respond.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// How often repositories are checked for local changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+/// How long to wait before re-connecting to the node.
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(5);
+/// Number of threads delivering payloads.
+const WORKERS: usize = 4;
+/// Maximum number of deliveries waiting for a worker.
+const QUEUE_SIZE: usize = 256;
+/// How long to wait for the database lock to be released before failing.
+const DB_TIMEOUT: Duration = Duration::from_secs(6);
+
+/// An error occuring with webhooks.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// An Internal error.
+    #[error("internal error: {0}")]
+    Internal(#[from] sql::Error),
+    /// Storage error.
+    #[error("storage error: {0}")]
+    Storage(#[from] radicle::storage::Error),
+    /// Git error.
+    #[error("git error: {0}")]
+    Git(#[from] radicle::git::raw::Error),
+    /// An invalid webhook event.
+    #[error("unknown webhook event '{0}', expected one of 'refs', 'patch' or 'issue'")]
+    Event(String),
+}
+
+/// Events webhooks can be triggered by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EventKind {
+    /// References were updated.
+    Refs,
+    /// A patch was opened, updated or deleted.
+    Patch,
+    /// An issue was opened, updated or deleted.
+    Issue,
+}
+
+impl EventKind {
+    /// All events.
+    pub fn all() -> BTreeSet<Self> {
+        BTreeSet::from([Self::Refs, Self::Patch, Self::Issue])
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Refs => "refs",
+            Self::Patch => "patch",
+            Self::Issue => "issue",
+        }
+    }
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for EventKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "refs" => Ok(Self::Refs),
+            "patch" => Ok(Self::Patch),
+            "issue" => Ok(Self::Issue),
+            other => Err(Error::Event(other.to_owned())),
+        }
+    }
+}
+
+/// Change to a patch or issue, as sent to webhooks.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CobPayload {
+    pub rid: Id,
+    pub remote: NodeId,
+    pub id: String,
+    pub change: Change,
+    /// Title of the patch or issue, unless it was deleted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// State of the patch or issue, unless it was deleted. This is how eg. merged patches
+    /// can be told apart from updated ones.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<serde_json::Value>,
+}
+
+/// Payload sent to webhooks.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "event")]
+pub enum Payload {
+    /// References were updated.
+    #[serde(rename_all = "camelCase")]
+    Refs {
+        rid: Id,
+        remote: NodeId,
+        updated: Vec<RefUpdate>,
+    },
+    /// A patch changed.
+    Patch(CobPayload),
+    /// An issue changed.
+    Issue(CobPayload),
+}
+
+impl Payload {
+    /// Get the payloads to send for a node event.
+    pub fn from_event(event: Event, storage: &Storage) -> Vec<Self> {
+        Activity::from_event(event, None)
+            .into_iter()
+            .filter_map(|activity| match activity {
+                Activity::Node(Event::RefsFetched {
+                    rid,
+                    remote,
+                    updated,
+                }) => {
+                    // Changes to collaborative objects are sent separately.
+                    let updated = updated
+                        .into_iter()
+                        .filter(|update| match update {
+                            RefUpdate::Created { name, .. }
+                            | RefUpdate::Updated { name, .. }
+                            | RefUpdate::Deleted { name, .. } => {
+                                cob::object::parse_refstr(name).is_none()
+                            }
+                            RefUpdate::Skipped { .. } => false,
+                        })
+                        .collect::<Vec<_>>();
+
+                    (!updated.is_empty()).then_some(Self::Refs {
+                        rid,
+                        remote,
+                        updated,
+                    })
+                }
+                Activity::Node(_) => None,
+                Activity::Cob(changed) => Self::from_cob(changed, storage),
+            })
+            .collect()
+    }
+
+    /// The event that triggered this payload.
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Self::Refs { .. } => EventKind::Refs,
+            Self::Patch(_) => EventKind::Patch,
+            Self::Issue(_) => EventKind::Issue,
+        }
+    }
+
+    /// The repository this payload is about.
+    pub fn rid(&self) -> Id {
+        match self {
+            Self::Refs { rid, .. } => *rid,
+            Self::Patch(cob) | Self::Issue(cob) => cob.rid,
+        }
+    }
+
+    fn from_cob(changed: CobChanged, storage: &Storage) -> Option<Self> {
+        let mut payload = CobPayload {
+            rid: changed.rid,
+            remote: changed.remote,
+            id: changed.id,
+            change: changed.change,
+            title: None,
+            state: None,
+        };
+        let is_patch = changed.type_name == *patch::TYPENAME;
+        let is_issue = changed.type_name == *issue::TYPENAME;
+
+        if !is_patch && !is_issue {
+            return None;
+        }
+        if changed.change != Change::Deleted {
+            // If the object can't be loaded, the payload is sent without its details.
+            let id = cob::ObjectId::from_str(&payload.id).ok();
+            let repo = storage.repository(payload.rid).ok();
+
+            if let (Some(id), Some(repo)) = (id, repo) {
+                if is_patch {
+                    if let Ok(Some(patch)) = patch::Patches::open(&repo).and_then(|p| p.get(&id)) {
+                        payload.title = Some(patch.title().to_owned());
+                        payload.state = serde_json::to_value(patch.state()).ok();
+                    }
+                } else if let Ok(Some(issue)) = issue::Issues::open(&repo).and_then(|i| i.get(&id))
+                {
+                    payload.title = Some(issue.title().to_owned());
+                    payload.state = serde_json::to_value(issue.state()).ok();
+                }
+            }
+        }
+
+        if is_patch {
+            Some(Self::Patch(payload))
+        } else {
+            Some(Self::Issue(payload))
+        }
+    }
+}
+
+/// A webhook.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Webhook {
+    pub id: String,
+    pub rid: Id,
+    pub url: String,
+    /// Secret used to sign payloads. Only shown when the webhook is created.
+    #[serde(skip)]
+    pub secret: String,
+    pub events: BTreeSet<EventKind>,
+    /// UNIX time at which the webhook was created.
+    pub created_at: i64,
+}
+
+/// Outcome of a payload delivery.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Delivery {
+    pub id: String,
+    pub webhook: String,
+    pub event: EventKind,
+    /// Status code of the last response, if any.
+    pub status: Option<u16>,
+    /// Error of the last attempt, if it failed.
+    pub error: Option<String>,
+    pub attempts: usize,
+    /// UNIX time at which the delivery completed.
+    pub timestamp: i64,
+}
+
+impl Delivery {
+    /// Whether the payload was delivered.
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Delivery retry policy.
+#[derive(Debug, Clone)]
+pub struct Retry {
+    /// Maximum number of attempts.
+    pub attempts: usize,
+    /// Delay before the first retry. Doubled after each retry.
+    pub backoff: Duration,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Self {
+            attempts: 5,
+            backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Persistent storage for webhooks and their delivery log.
+pub struct Store {
+    db: sql::Connection,
+}
+
+impl Store {
+    const SCHEMA: &str = include_str!("webhooks/schema.sql");
+
+    /// Open a webhook store at the given path. Creates a new empty store
+    /// if an existing store isn't found.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let mut db = sql::Connection::open(path)?;
+        db.set_busy_timeout(DB_TIMEOUT.as_millis() as usize)?;
+        db.execute(Self::SCHEMA)?;
+
+        Ok(Self { db })
+    }
+
+    /// Create a new in-memory webhook store.
+    #[cfg(test)]
+    pub fn memory() -> Result<Self, Error> {
+        let db = sql::Connection::open(":memory:")?;
+        db.execute(Self::SCHEMA)?;
+
+        Ok(Self { db })
+    }
+
+    /// Create a webhook.
+    pub fn create(
+        &mut self,
+        rid: Id,
+        url: &str,
+        secret: &str,
+        events: BTreeSet<EventKind>,
+    ) -> Result<Webhook, Error> {
+        let webhook = Webhook {
+            id: random_id(8),
+            rid,
+            url: url.to_owned(),
+            secret: secret.to_owned(),
+            events,
+            created_at: OffsetDateTime::now_utc().unix_timestamp(),
+        };
+        let mut stmt = self.db.prepare(
+            "INSERT INTO webhooks (id, repo, url, secret, events, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        stmt.bind((1, webhook.id.as_str()))?;
+        stmt.bind((2, &webhook.rid))?;
+        stmt.bind((3, webhook.url.as_str()))?;
+        stmt.bind((4, webhook.secret.as_str()))?;
+        stmt.bind((5, events_to_string(&webhook.events).as_str()))?;
+        stmt.bind((6, webhook.created_at))?;
+        stmt.next()?;
+
+        Ok(webhook)
+    }
+
+    /// Remove a webhook of a repository, along with its delivery log.
+    /// Returns whether the webhook existed.
+    pub fn remove(&mut self, rid: &Id, id: &str) -> Result<bool, Error> {
+        radicle::sql::transaction(&self.db, |db| {
+            let mut stmt = db.prepare("DELETE FROM webhooks WHERE repo = ?1 AND id = ?2")?;
+            stmt.bind((1, rid))?;
+            stmt.bind((2, id))?;
+            stmt.next()?;
+
+            let removed = db.change_count() > 0;
+            if removed {
+                let mut stmt = db.prepare("DELETE FROM deliveries WHERE webhook = ?")?;
+                stmt.bind((1, id))?;
+                stmt.next()?;
+            }
+            Ok(removed)
+        })
+        .map_err(Error::from)
+    }
+
+    /// Get a webhook of a repository.
+    pub fn get(&self, rid: &Id, id: &str) -> Result<Option<Webhook>, Error> {
+        let mut stmt = self.db.prepare(
+            "SELECT id, repo, url, secret, events, created_at FROM webhooks
+             WHERE repo = ?1 AND id = ?2",
+        )?;
+        stmt.bind((1, rid))?;
+        stmt.bind((2, id))?;
+
+        match stmt.into_iter().next() {
+            Some(row) => Ok(Some(webhook(&row?)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the webhooks of a repository.
+    pub fn webhooks(&self, rid: &Id) -> Result<Vec<Webhook>, Error> {
+        let mut stmt = self.db.prepare(
+            "SELECT id, repo, url, secret, events, created_at FROM webhooks
+             WHERE repo = ? ORDER BY created_at, rowid",
+        )?;
+        stmt.bind((1, rid))?;
+
+        let mut webhooks = Vec::new();
+        for row in stmt.into_iter() {
+            webhooks.push(webhook(&row?)?);
+        }
+        Ok(webhooks)
+    }
+
+    /// Get the repositories that have webhooks.
+    pub fn repos(&self) -> Result<BTreeSet<Id>, Error> {
+        let mut repos = BTreeSet::new();
+        for row in self
+            .db
+            .prepare("SELECT DISTINCT repo FROM webhooks")?
+            .into_iter()
+        {
+            repos.insert(row?.try_read::<Id, _>("repo")?);
+        }
+        Ok(repos)
+    }
+
+    /// Get the webhooks of a repository triggered by the given event.
+    pub fn triggered(&self, rid: &Id, event: EventKind) -> Result<Vec<Webhook>, Error> {
+        Ok(self
+            .webhooks(rid)?
+            .into_iter()
+            .filter(|w| w.events.contains(&event))
+            .collect())
+    }
+
+    /// Record a delivery in the log, dropping the oldest deliveries of the webhook if the log
+    /// is full.
+    pub fn log(&mut self, delivery: &Delivery) -> Result<(), Error> {
+        radicle::sql::transaction(&self.db, |db| {
+            let mut stmt = db.prepare(
+                "INSERT INTO deliveries (id, webhook, event, status, error, attempts, timestamp)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )?;
+            stmt.bind((1, delivery.id.as_str()))?;
+            stmt.bind((2, delivery.webhook.as_str()))?;
+            stmt.bind((3, delivery.event.as_str()))?;
+            match delivery.status {
+                Some(status) => stmt.bind((4, status as i64))?,
+                None => stmt.bind((4, sql::Value::Null))?,
+            }
+            match &delivery.error {
+                Some(error) => stmt.bind((5, error.as_str()))?,
+                None => stmt.bind((5, sql::Value::Null))?,
+            }
+            stmt.bind((6, delivery.attempts as i64))?;
+            stmt.bind((7, delivery.timestamp))?;
+            stmt.next()?;
+
+            let mut stmt = db.prepare(
+                "DELETE FROM deliveries WHERE webhook = ?1 AND rowid NOT IN (
+                   SELECT rowid FROM deliveries WHERE webhook = ?1 ORDER BY rowid DESC LIMIT ?2
+                 )",
+            )?;
+            stmt.bind((1, delivery.webhook.as_str()))?;
+            stmt.bind((2, DELIVERY_LOG_SIZE as i64))?;
+            stmt.next()?;
+
+            Ok(())
+        })
+        .map_err(Error::from)
+    }
+
+    /// Get the logged deliveries of a webhook, most recent first.
+    pub fn deliveries(&self, webhook: &str) -> Result<Vec<Delivery>, Error> {
+        let mut stmt = self.db.prepare(
+            "SELECT id, webhook, event, status, error, attempts, timestamp FROM deliveries
+             WHERE webhook = ? ORDER BY rowid DESC",
+        )?;
+        stmt.bind((1, webhook))?;
+
+        let mut deliveries = Vec::new();
+        for row in stmt.into_iter() {
+            let row = row?;
+
+            deliveries.push(Delivery {
+                id: row.read::<&str, _>("id").to_owned(),
+                webhook: row.read::<&str, _>("webhook").to_owned(),
+                event: row.read::<&str, _>("event").parse()?,
+                status: row.read::<Option<i64>, _>("status").map(|s| s as u16),
+                error: row.read::<Option<&str>, _>("error").map(ToOwned::to_owned),
+                attempts: row.read::<i64, _>("attempts") as usize,
+                timestamp: row.read::<i64, _>("timestamp"),
+            });
+        }
+        Ok(deliveries)
+    }
+}
+
+/// Sign a payload with a webhook secret. Returns the hex-encoded HMAC-SHA256.
+pub fn sign(secret: &str, payload: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(payload);
+
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Generate a random webhook secret, hex-encoded.
+pub fn secret() -> String {
+    Seed::generate()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Deliver a payload to a webhook, retrying on failure.
+///
+/// Connection errors, server errors and rate limiting are retried. Other responses are
+/// final.
+pub fn deliver(webhook: &Webhook, payload: &Payload, retry: &Retry) -> Delivery {
+    let mut delivery = Delivery {
+        id: random_id(16),
+        webhook: webhook.id.clone(),
+        event: payload.kind(),
+        status: None,
+        error: None,
+        attempts: 0,
+        timestamp: 0,
+    };
+    let body = match serde_json::to_string(payload) {
+        Ok(body) => body,
+        Err(e) => {
+            delivery.error = Some(e.to_string());
+            delivery.timestamp = OffsetDateTime::now_utc().unix_timestamp();
+
+            return delivery;
+        }
+    };
+    let signature = format!("sha256={}", sign(&webhook.secret, body.as_bytes()));
+    let agent = ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build();
+    let mut backoff = retry.backoff;
+
+    while delivery.attempts < retry.attempts {
+        delivery.attempts += 1;
+
+        let response = agent
+            .post(&webhook.url)
+            .set("Content-Type", "application/json")
+            .set(EVENT_HEADER, payload.kind().as_str())
+            .set(DELIVERY_HEADER, &delivery.id)
+            .set(SIGNATURE_HEADER, &signature)
+            .send_string(&body);
+        let retryable = match response {
+            Ok(response) => {
+                delivery.status = Some(response.status());
+                delivery.error = None;
+                false
+            }
+            Err(ureq::Error::Status(status, _)) => {
+                delivery.status = Some(status);
+                delivery.error = Some(format!("receiver responded with status {status}"));
+                status >= 500 || status == 429
+            }
+            Err(ureq::Error::Transport(e)) => {
+                delivery.status = None;
+                delivery.error = Some(e.to_string());
+                true
+            }
+        };
+        if !retryable || delivery.attempts == retry.attempts {
+            break;
+        }
+        thread::sleep(backoff);
+        backoff *= 2;
+    }
+    delivery.timestamp = OffsetDateTime::now_utc().unix_timestamp();
+    delivery
+}
+
+/// Deliver payloads to webhooks as repositories change. Never returns.
+///
+/// If the node isn't running, or stops, we keep checking for local changes, and trying to
+/// reconnect.
+pub fn run(profile: Arc<Profile>, retry: Retry) {
+    let path = profile.home.node().join(WEBHOOKS_DB_FILE);
+    let queue = workers(path.clone(), retry);
+    let mut snapshots = Snapshots::default();
+
+    loop {
+        let node = radicle::Node::new(profile.socket());
+        let mut checked = Instant::now();
+
+        snapshots.check(None, &profile.storage, &path, &queue);
+
+        match node.subscribe(POLL_INTERVAL) {
+            Ok(events) => {
+                for event in events {
+                    match event {
+                        Ok(Event::RefsFetched { rid, .. }) => {
+                            snapshots.check(Some(rid), &profile.storage, &path, &queue);
+                        }
+                        Ok(_) => {}
+                        Err(e) if e.kind() == io::ErrorKind::TimedOut => {}
+                        Err(e) => {
+                            tracing::debug!("webhooks: failed to read node event: {e}");
+                            break;
+                        }
+                    }
+                    if checked.elapsed() >= POLL_INTERVAL {
+                        snapshots.check(None, &profile.storage, &path, &queue);
+                        checked = Instant::now();
+                    }
+                }
+            }
+            Err(e) => tracing::debug!("webhooks: failed to subscribe to node events: {e}"),
+        }
+        thread::sleep(RECONNECT_INTERVAL);
+    }
+}
+
+/// The refs of the repositories with webhooks, as last seen.
+#[derive(Debug, Default)]
+struct Snapshots {
+    repos: HashMap<Id, Snapshot>,
+}
+
+/// The refs of a repository.
+#[derive(Debug)]
+struct Snapshot {
+    /// Fingerprint of the signed refs of the repository. Refs are only compared when it
+    /// changes.
+    fingerprint: git::Oid,
+    /// All namespaced refs.
+    refs: HashMap<RefString, git::Oid>,
+}
+
+impl Snapshots {
+    /// Dispatch the changes to the given repository, or to all repositories with webhooks.
+    fn check(
+        &mut self,
+        rid: Option<Id>,
+        storage: &Storage,
+        path: &Path,
+        queue: &mpsc::SyncSender<Job>,
+    ) {
+        let repos = match Store::open(path).and_then(|s| s.repos()) {
+            Ok(repos) => repos,
+            Err(e) => {
+                tracing::error!("webhooks: failed to get repositories: {e}");
+                return;
+            }
+        };
+        // Forget about repositories that no longer have webhooks.
+        self.repos.retain(|rid, _| repos.contains(rid));
+
+        for rid in repos
+            .into_iter()
+            .filter(|r| rid.map_or(true, |rid| rid == *r))
+        {
+            let events = match storage
+                .repository(rid)
+                .map_err(Error::from)
+                .and_then(|repo| self.changes(&repo))
+            {
+                Ok(events) => events,
+                Err(e) => {
+                    tracing::warn!("webhooks: failed to check {rid} for changes: {e}");
+                    continue;
+                }
+            };
+            for event in events {
+                if let Err(e) = dispatch(event, storage, path, queue) {
+                    tracing::error!("webhooks: failed to dispatch event: {e}");
+                }
+            }
+        }
+    }
+
+    /// Get the changes to a repository since it was last seen, as the node events they
+    /// correspond to, one per remote. There are no changes the first time a repository is
+    /// seen.
+    fn changes(&mut self, repo: &Repository) -> Result<Vec<Event>, Error> {
+        let fingerprint = repo.fingerprint()?;
+        if matches!(self.repos.get(&repo.id), Some(s) if s.fingerprint == fingerprint) {
+            return Ok(vec![]);
+        }
+        let mut refs = HashMap::new();
+        for r in repo.backend.references_glob("refs/namespaces/*")? {
+            let r = r?;
+            if let (Some(name), Some(oid)) = (r.name(), r.target()) {
+                if let Ok(name) = RefString::try_from(name) {
+                    refs.insert(name, git::Oid::from(oid));
+                }
+            }
+        }
+        let Some(previous) = self.repos.insert(
+            repo.id,
+            Snapshot {
+                fingerprint,
+                refs: refs.clone(),
+            },
+        ) else {
+            return Ok(vec![]);
+        };
+
+        let mut updates = BTreeMap::<NodeId, Vec<RefUpdate>>::new();
+        for (name, oid) in &refs {
+            let update = match previous.refs.get(name) {
+                None => RefUpdate::Created {
+                    name: name.clone(),
+                    oid: *oid,
+                },
+                Some(old) if old != oid => RefUpdate::Updated {
+                    name: name.clone(),
+                    old: *old,
+                    new: *oid,
+                },
+                Some(_) => continue,
+            };
+            if let Ok((Some(remote), _)) = git::parse_ref::<NodeId>(name.as_str()) {
+                updates.entry(remote).or_default().push(update);
+            }
+        }
+        for (name, oid) in previous.refs {
+            if refs.contains_key(&name) {
+                continue;
+            }
+            if let Ok((Some(remote), _)) = git::parse_ref::<NodeId>(name.as_str()) {
+                updates
+                    .entry(remote)
+                    .or_default()
+                    .push(RefUpdate::Deleted { name, oid });
+            }
+        }
+
+        Ok(updates
+            .into_iter()
+            .map(|(remote, updated)| Event::RefsFetched {
+                remote,
+                rid: repo.id,
+                updated,
+            })
+            .collect())
+    }
+}
+
+/// A payload waiting to be delivered to a webhook.
+struct Job {
+    webhook: Webhook,
+    payload: Payload,
+}
+
+/// Spawn the delivery workers. Returns the queue to send deliveries to.
+fn workers(path: PathBuf, retry: Retry) -> mpsc::SyncSender<Job> {
+    let (sender, receiver) = mpsc::sync_channel::<Job>(QUEUE_SIZE);
+    let receiver = Arc::new(Mutex::new(receiver));
+
+    for _ in 0..WORKERS {
+        let receiver = receiver.clone();
+        let path = path.clone();
+        let retry = retry.clone();
+
+        thread::spawn(move || loop {
+            let Ok(Job { webhook, payload }) = receiver.lock().expect("webhooks: lock is not poisoned").recv() else {
+                break;
+            };
+            let delivery = deliver(&webhook, &payload, &retry);
+            if !delivery.is_success() {
+                tracing::warn!(
+                    "webhooks: delivery {} to {} failed: {}",
+                    delivery.id,
+                    webhook.url,
+                    delivery.error.as_deref().unwrap_or_default()
+                );
+            }
+            if let Err(e) = Store::open(&path).and_then(|mut s| s.log(&delivery)) {
+                tracing::error!("webhooks: failed to log delivery {}: {e}", delivery.id);
+            }
+        });
+    }
+    sender
+}
+
+/// Queue the payloads of a node event for delivery to the webhooks it triggers. If the queue
+/// is full, the delivery is dropped and logged as failed.
+fn dispatch(
+    event: Event,
+    storage: &Storage,
+    path: &Path,
+    queue: &mpsc::SyncSender<Job>,
+) -> Result<(), Error> {
+    let payloads = Payload::from_event(event, storage);
+    if payloads.is_empty() {
+        return Ok(());
+    }
+    let mut store = Store::open(path)?;
+
+    for payload in payloads {
+        for webhook in store.triggered(&payload.rid(), payload.kind())? {
+            let job = Job {
+                webhook,
+                payload: payload.clone(),
+            };
+            if let Err(mpsc::TrySendError::Full(job) | mpsc::TrySendError::Disconnected(job)) =
+                queue.try_send(job)
+            {
+                let delivery = Delivery {
+                    id: random_id(16),
+                    webhook: job.webhook.id,
+                    event: job.payload.kind(),
+                    status: None,
+                    error: Some(String::from("too many pending deliveries")),
+                    attempts: 0,
+                    timestamp: OffsetDateTime::now_utc().unix_timestamp(),
+                };
+                tracing::warn!(
+                    "webhooks: dropping delivery {} to {}",
+                    delivery.id,
+                    job.webhook.url
+                );
+                store.log(&delivery)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn random_id(len: usize) -> String {
+    let mut rng = fastrand::Rng::new();
+
+    repeat_with(|| rng.alphanumeric())
+        .take(len)
+        .collect::<String>()
+        .to_lowercase()
+}
+
+fn events_to_string(events: &BTreeSet<EventKind>) -> String {
+    events
+        .iter()
+        .map(EventKind::as_str)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn webhook(row: &sql::Row) -> Result<Webhook, Error> {
+    let events = row
+        .read::<&str, _>("events")
+        .split(',')
+        .filter(|s| !s.is_empty())
+        .map(EventKind::from_str)
+        .collect::<Result<_, _>>()?;
+
+    Ok(Webhook {
+        id: row.read::<&str, _>("id").to_owned(),
+        rid: row.try_read::<Id, _>("repo")?,
+        url: row.read::<&str, _>("url").to_owned(),
+        secret: row.read::<&str, _>("secret").to_owned(),
+        events,
+        created_at: row.read::<i64, _>("created_at"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    use radicle::cob::issue::Issues;
+    use radicle::crypto::test::signer::MockSigner;
+    use radicle::crypto::Signer as _;
+    use radicle::git::raw::Oid;
+
+    use super::*;
+    use crate::test::{self, CONTRIBUTOR_NID, ISSUE_ID, RID};
+
+    /// A request received by a [`receiver`].
+    struct Received {
+        headers: Vec<(String, String)>,
+        body: String,
+    }
+
+    impl Received {
+        fn header(&self, name: &str) -> Option<&str> {
+            self.headers
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.as_str())
+        }
+    }
+
+    /// Spawn a local HTTP receiver, answering requests with the given status codes, in order.
+    fn receiver(statuses: Vec<u16>) -> (String, mpsc::Receiver<Received>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            for status in statuses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut headers = Vec::new();
+                let mut line = String::new();
+
+                reader.read_line(&mut line).unwrap();
+                loop {
+                    line.clear();
+                    reader.read_line(&mut line).unwrap();
+
+                    match line.trim_end().split_once(": ") {
+                        Some((k, v)) => headers.push((k.to_owned(), v.to_owned())),
+                        None => break,
+                    }
+                }
+                let length = headers
+                    .iter()
+                    .find(|(k, _)| k.eq_ignore_ascii_case("content-length"))
+                    .map(|(_, v)| v.parse::<usize>().unwrap())
+                    .unwrap_or_default();
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+
+                write!(
+                    stream,
+                    "HTTP/1.1 {status} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                )
+                .unwrap();
+                sender
+                    .send(Received {
+                        headers,
+                        body: String::from_utf8(body).unwrap(),
+                    })
+                    .unwrap();
+            }
+        });
+        (url, receiver)
+    }
+
+    fn webhook(url: String) -> Webhook {
+        Webhook {
+            id: String::from("hook"),
+            rid: Id::from_str(RID).unwrap(),
+            url,
+            secret: String::from("secret"),
+            events: EventKind::all(),
+            created_at: 0,
+        }
+    }
+
+    fn payload() -> Payload {
+        Payload::Patch(CobPayload {
+            rid: Id::from_str(RID).unwrap(),
+            remote: NodeId::from_str(CONTRIBUTOR_NID).unwrap(),
+            id: String::from(ISSUE_ID),
+            change: Change::Created,
+            title: None,
+            state: None,
+        })
+    }
+
+    fn retry() -> Retry {
+        Retry {
+            attempts: 3,
+            backoff: Duration::from_millis(1),
+        }
+    }
+
+    #[test]
+    fn test_deliver_signed() {
+        let (url, received) = receiver(vec![200]);
+        let delivery = deliver(&webhook(url), &payload(), &retry());
+
+        assert!(delivery.is_success());
+        assert_eq!(delivery.status, Some(200));
+        assert_eq!(delivery.attempts, 1);
+
+        let request = received.recv().unwrap();
+        let expected = format!("sha256={}", sign("secret", request.body.as_bytes()));
+        assert_eq!(request.header(SIGNATURE_HEADER), Some(expected.as_str()));
+        assert_eq!(request.header(EVENT_HEADER), Some("patch"));
+        assert_eq!(request.header(DELIVERY_HEADER), Some(delivery.id.as_str()));
+
+        let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(body["event"], "patch");
+        assert_eq!(body["rid"], RID);
+        assert_eq!(body["id"], ISSUE_ID);
+        assert_eq!(body["change"], "created");
+    }
+
+    #[test]
+    fn test_deliver_retries() {
+        let (url, _received) = receiver(vec![500, 503, 200]);
+        let delivery = deliver(&webhook(url), &payload(), &retry());
+
+        assert!(delivery.is_success());
+        assert_eq!(delivery.status, Some(200));
+        assert_eq!(delivery.attempts, 3);
+
+        // Client errors aren't retried.
+        let (url, _received) = receiver(vec![404]);
+        let delivery = deliver(&webhook(url), &payload(), &retry());
+
+        assert!(!delivery.is_success());
+        assert_eq!(delivery.status, Some(404));
+        assert_eq!(delivery.attempts, 1);
+    }
+
+    #[test]
+    fn test_sign() {
+        // Test vector from RFC 4231, test case 2.
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_store() {
+        let mut store = Store::memory().unwrap();
+        let rid = Id::from_str(RID).unwrap();
+        let webhook = store
+            .create(
+                rid,
+                "http://127.0.0.1/hook",
+                "secret",
+                BTreeSet::from([EventKind::Issue]),
+            )
+            .unwrap();
+
+        assert_eq!(store.webhooks(&rid).unwrap(), vec![webhook.clone()]);
+        assert_eq!(
+            store.triggered(&rid, EventKind::Issue).unwrap(),
+            vec![webhook.clone()]
+        );
+        assert!(store.triggered(&rid, EventKind::Patch).unwrap().is_empty());
+
+        for i in 0..DELIVERY_LOG_SIZE + 1 {
+            store
+                .log(&Delivery {
+                    id: i.to_string(),
+                    webhook: webhook.id.clone(),
+                    event: EventKind::Issue,
+                    status: Some(200),
+                    error: None,
+                    attempts: 1,
+                    timestamp: 0,
+                })
+                .unwrap();
+        }
+        let deliveries = store.deliveries(&webhook.id).unwrap();
+        assert_eq!(deliveries.len(), DELIVERY_LOG_SIZE);
+        assert_eq!(deliveries[0].id, DELIVERY_LOG_SIZE.to_string());
+
+        assert!(store.remove(&rid, &webhook.id).unwrap());
+        assert!(!store.remove(&rid, &webhook.id).unwrap());
+        assert!(store.deliveries(&webhook.id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_changes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ctx = test::seed(tmp.path());
+        let signer = MockSigner::from_seed([0xff; 32]);
+        let storage = &ctx.profile().storage;
+        let repo = storage.repository(Id::from_str(RID).unwrap()).unwrap();
+        let mut snapshots = Snapshots::default();
+
+        // Nothing changed the first time a repository is seen.
+        assert!(snapshots.changes(&repo).unwrap().is_empty());
+
+        // Issues opened locally are picked up.
+        Issues::open(&repo)
+            .unwrap()
+            .create("Local issue", "Opened locally", &[], &[], [], &signer)
+            .unwrap();
+
+        let events = snapshots.changes(&repo).unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            &events[0],
+            Event::RefsFetched { remote, .. } if remote == signer.public_key()
+        ));
+
+        let payloads = Payload::from_event(events[0].clone(), storage);
+        assert!(payloads.iter().any(|p| matches!(
+            p,
+            Payload::Issue(CobPayload { change: Change::Created, title: Some(title), .. })
+            if title == "Local issue"
+        )));
+        assert!(snapshots.changes(&repo).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_dispatch_queue_full() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ctx = test::seed(tmp.path());
+        let path = tmp.path().join(WEBHOOKS_DB_FILE);
+        let rid = Id::from_str(RID).unwrap();
+        let remote = NodeId::from_str(CONTRIBUTOR_NID).unwrap();
+        let oid = Oid::from_str(ISSUE_ID).unwrap();
+        let webhook = Store::open(&path)
+            .unwrap()
+            .create(rid, "http://127.0.0.1/hook", "secret", EventKind::all())
+            .unwrap();
+        let event = Event::RefsFetched {
+            remote,
+            rid,
+            updated: vec![RefUpdate::Updated {
+                name: git::RefString::try_from("refs/heads/master").unwrap(),
+                old: oid.into(),
+                new: oid.into(),
+            }],
+        };
+        // Nothing is receiving from the queue, so it's always full.
+        let (queue, _receiver) = mpsc::sync_channel(0);
+
+        dispatch(event, &ctx.profile().storage, &path, &queue).unwrap();
+
+        let deliveries = Store::open(&path).unwrap().deliveries(&webhook.id).unwrap();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].event, EventKind::Refs);
+        assert_eq!(deliveries[0].attempts, 0);
+        assert!(!deliveries[0].is_success());
+    }
+
+    #[test]
+    fn test_payload_from_event() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ctx = test::seed(tmp.path());
+        let rid = Id::from_str(RID).unwrap();
+        let remote = NodeId::from_str(CONTRIBUTOR_NID).unwrap();
+        let oid = Oid::from_str(ISSUE_ID).unwrap();
+        let event = Event::RefsFetched {
+            remote,
+            rid,
+            updated: vec![
+                RefUpdate::Updated {
+                    name: git::RefString::try_from(format!(
+                        "refs/namespaces/{remote}/refs/cobs/xyz.radicle.issue/{ISSUE_ID}"
+                    ))
+                    .unwrap(),
+                    old: oid.into(),
+                    new: oid.into(),
+                },
+                RefUpdate::Updated {
+                    name: git::RefString::try_from("refs/heads/master").unwrap(),
+                    old: oid.into(),
+                    new: oid.into(),
+                },
+            ],
+        };
+        let payloads = Payload::from_event(event, &ctx.profile().storage);
+
+        assert_eq!(payloads.len(), 2);
+        assert!(matches!(
+            &payloads[0],
+            Payload::Refs { updated, .. } if updated.len() == 1
+        ));
+        assert!(matches!(
+            &payloads[1],
+            Payload::Issue(CobPayload { title: Some(title), state: Some(_), .. })
+            if title == "Issue #1"
+        ));
+    }
+}
@@ -0,0 +1,34 @@
+--
+-- Webhooks SQL schema.
+--
+create table if not exists "webhooks" (
+  -- Webhook identifier.
+  "id"           text      primary key not null,
+  -- Repository the webhook is configured for.
+  "repo"         text      not null,
+  -- URL payloads are sent to.
+  "url"          text      not null,
+  -- Secret used to sign payloads.
+  "secret"       text      not null,
+  -- Comma-separated list of events the webhook is triggered by.
+  "events"       text      not null,
+  -- UNIX time at which the webhook was created.
+  "created_at"   integer   not null
+);
+
+create table if not exists "deliveries" (
+  -- Delivery identifier.
+  "id"           text      primary key not null,
+  -- Webhook the payload was delivered to.
+  "webhook"      text      not null,
+  -- Event that triggered the delivery.
+  "event"        text      not null,
+  -- HTTP status code returned by the receiver, if any.
+  "status"       integer,
+  -- Error that occured on the last attempt, if any.
+  "error"        text,
+  -- Number of attempts made.
+  "attempts"     integer   not null,
+  -- UNIX time at which the delivery completed.
+  "timestamp"    integer   not null
+);
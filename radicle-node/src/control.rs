@@ -115,6 +115,49 @@ where
 
             json::to_writer(writer, &sessions)?;
         }
+        Command::Reachability => {
+            let reachability = handle.reachability()?;
+
+            json::to_writer(writer, &reachability)?;
+        }
+        Command::Metrics => {
+            let metrics = handle.metrics()?;
+
+            json::to_writer(writer, &metrics)?;
+        }
+        Command::Replication => {
+            let replication = handle.replication()?;
+
+            json::to_writer(writer, &replication)?;
+        }
+        Command::Disconnect { nid } => {
+            if let Err(e) = handle.disconnect(nid) {
+                return Err(CommandError::Runtime(e));
+            }
+            CommandResult::ok().to_writer(writer).ok();
+        }
+        Command::Addresses => {
+            let addresses = handle.addresses()?;
+
+            json::to_writer(writer, &addresses)?;
+        }
+        Command::Routing { nid } => {
+            let rids = handle.routing(nid)?;
+
+            json::to_writer(writer, &rids)?;
+        }
+        Command::Config => {
+            // The configuration is shown to users, so secrets are left out.
+            let config = handle.config()?.redacted();
+
+            json::to_writer(writer, &config)?;
+        }
+        Command::UpdateLimits { limits } => {
+            if let Err(e) = handle.update_limits(limits) {
+                return Err(CommandError::Runtime(e));
+            }
+            CommandResult::ok().to_writer(writer).ok();
+        }
         Command::TrackRepo { rid, scope } => match handle.track_repo(rid, scope) {
             Ok(updated) => {
                 CommandResult::Okay { updated }.to_writer(writer)?;
@@ -147,6 +190,30 @@ where
                 return Err(CommandError::Runtime(e));
             }
         },
+        Command::TrackRemote { rid, nid } => match handle.track_remote(rid, nid) {
+            Ok(updated) => {
+                CommandResult::Okay { updated }.to_writer(writer)?;
+            }
+            Err(e) => {
+                return Err(CommandError::Runtime(e));
+            }
+        },
+        Command::UntrackRemote { rid, nid } => match handle.untrack_remote(rid, nid) {
+            Ok(updated) => {
+                CommandResult::Okay { updated }.to_writer(writer)?;
+            }
+            Err(e) => {
+                return Err(CommandError::Runtime(e));
+            }
+        },
+        Command::BlockRemote { rid, nid } => match handle.block_remote(rid, nid) {
+            Ok(updated) => {
+                CommandResult::Okay { updated }.to_writer(writer)?;
+            }
+            Err(e) => {
+                return Err(CommandError::Runtime(e));
+            }
+        },
         Command::AnnounceRefs { rid } => {
             if let Err(e) = handle.announce_refs(rid) {
                 return Err(CommandError::Runtime(e));
@@ -296,5 +363,12 @@ mod tests {
         assert!(!handle.track_node(peer, Some(Alias::new("alice"))).unwrap());
         assert!(handle.untrack_node(peer).unwrap());
         assert!(!handle.untrack_node(peer).unwrap());
+
+        assert!(handle.block_remote(proj, peer).unwrap());
+        assert!(!handle.block_remote(proj, peer).unwrap());
+        assert!(handle.track_remote(proj, peer).unwrap());
+        assert!(!handle.track_remote(proj, peer).unwrap());
+        assert!(handle.untrack_remote(proj, peer).unwrap());
+        assert!(!handle.untrack_remote(proj, peer).unwrap());
     }
 }
@@ -0,0 +1,340 @@
+//! DNS bootstrap: resolving bootstrap nodes from `TXT` records.
+//!
+//! Seed operators can list their nodes under a domain, with one `TXT` record per node holding
+//! its address, eg. `z6Mk..@seed.example.com:8776`. This lets the set of bootstrap nodes change
+//! without a new release. The client is minimal: queries are sent over UDP to a single name
+//! server, and truncated responses are not retried over TCP.
+use std::str::FromStr;
+use std::{fs, io, net, time};
+
+use cyphernet::addr::PeerAddr;
+use radicle::node::config::{ConnectAddress, Dns};
+use radicle::node::{Address, Alias, NodeId};
+
+/// Port name servers listen on.
+pub const PORT: u16 = 53;
+/// Number of times a query is sent before giving up.
+pub const ATTEMPTS: u32 = 3;
+/// Initial time to wait for a response. Doubles after every attempt.
+pub const INITIAL_TIMEOUT: time::Duration = time::Duration::from_millis(500);
+
+/// The `TXT` record type.
+const TYPE_TXT: u16 = 16;
+/// The Internet class.
+const CLASS_IN: u16 = 1;
+/// Response code for a domain that doesn't exist.
+const RCODE_NXDOMAIN: u16 = 3;
+
+/// Resolve bootstrap nodes from the configured domains.
+/// Domains that fail to resolve, and records that aren't node addresses, are skipped.
+pub fn seeds(config: &Dns) -> Vec<(Alias, ConnectAddress)> {
+    let nameserver = match config.nameserver.map(Ok).unwrap_or_else(nameserver) {
+        Ok(nameserver) => nameserver,
+        Err(e) => {
+            log::warn!(target: "dns", "Unable to find a name server: {e}");
+            return vec![];
+        }
+    };
+    let mut seeds = Vec::new();
+
+    for domain in &config.seeds {
+        let records = match txt(nameserver, domain) {
+            Ok(records) => records,
+            Err(e) => {
+                log::warn!(target: "dns", "Failed to resolve bootstrap nodes of {domain}: {e}");
+                continue;
+            }
+        };
+        for record in records {
+            match PeerAddr::<NodeId, Address>::from_str(&record) {
+                Ok(addr) => {
+                    let alias = Alias::from_str(&addr.addr.host.to_string())
+                        .unwrap_or_else(|_| Alias::new("seed"));
+
+                    seeds.push((alias, addr.into()));
+                }
+                Err(e) => {
+                    log::debug!(target: "dns", "Ignoring record {record:?} of {domain}: {e}");
+                }
+            }
+        }
+    }
+    seeds
+}
+
+/// Find the system's name server, by reading `/etc/resolv.conf`.
+pub fn nameserver() -> io::Result<net::SocketAddr> {
+    let conf = fs::read_to_string("/etc/resolv.conf")?;
+
+    parse_resolv_conf(&conf)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no name server was found"))
+}
+
+/// Query the `TXT` records of a domain. Returns an empty list if the domain doesn't exist.
+pub fn txt(nameserver: net::SocketAddr, domain: &str) -> io::Result<Vec<String>> {
+    let id = fastrand::u16(..);
+    let query = encode_query(id, domain, TYPE_TXT)?;
+    let local: net::SocketAddr = if nameserver.is_ipv4() {
+        (net::Ipv4Addr::UNSPECIFIED, 0).into()
+    } else {
+        (net::Ipv6Addr::UNSPECIFIED, 0).into()
+    };
+    let socket = net::UdpSocket::bind(local)?;
+    socket.connect(nameserver)?;
+
+    let mut timeout = INITIAL_TIMEOUT;
+    let mut buf = [0u8; 4096];
+
+    for _ in 0..ATTEMPTS {
+        socket.send(&query)?;
+        socket.set_read_timeout(Some(timeout))?;
+
+        match socket.recv(&mut buf) {
+            Ok(n) => {
+                if let Some(records) = parse_response(id, &buf[..n])? {
+                    return Ok(records);
+                }
+                // Response to another query, eg. a previous attempt. Try again.
+            }
+            Err(e)
+                if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut =>
+            {
+                timeout *= 2;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::TimedOut,
+        "name server did not respond",
+    ))
+}
+
+/// Parse the first name server out of `/etc/resolv.conf`.
+fn parse_resolv_conf(conf: &str) -> Option<net::SocketAddr> {
+    conf.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        if fields.next()? != "nameserver" {
+            return None;
+        }
+        let ip = net::IpAddr::from_str(fields.next()?).ok()?;
+
+        Some(net::SocketAddr::new(ip, PORT))
+    })
+}
+
+/// Encode a recursive query for the given domain and record type.
+fn encode_query(id: u16, domain: &str, qtype: u16) -> io::Result<Vec<u8>> {
+    let mut query = Vec::with_capacity(12 + domain.len() + 6);
+
+    query.extend_from_slice(&id.to_be_bytes());
+    query.extend_from_slice(&0x0100u16.to_be_bytes()); // Recursion desired.
+    query.extend_from_slice(&1u16.to_be_bytes()); // One question.
+    query.extend_from_slice(&[0; 6]); // No answer, authority or additional records.
+
+    for label in domain.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid domain name {domain:?}"),
+            ));
+        }
+        query.push(label.len() as u8);
+        query.extend_from_slice(label.as_bytes());
+    }
+    query.push(0);
+    query.extend_from_slice(&qtype.to_be_bytes());
+    query.extend_from_slice(&CLASS_IN.to_be_bytes());
+
+    Ok(query)
+}
+
+/// Parse the `TXT` records out of a response. Returns `None` if the response isn't for the
+/// query with the given id.
+fn parse_response(id: u16, msg: &[u8]) -> io::Result<Option<Vec<String>>> {
+    if read_u16(msg, 0)? != id {
+        return Ok(None);
+    }
+    let flags = read_u16(msg, 2)?;
+    if flags & 0x8000 == 0 {
+        return Err(invalid("message is not a response"));
+    }
+    if flags & 0x0200 != 0 {
+        return Err(invalid("response was truncated"));
+    }
+    match flags & 0xf {
+        0 => {}
+        RCODE_NXDOMAIN => return Ok(Some(vec![])),
+        rcode => {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("query failed with response code {rcode}"),
+            ))
+        }
+    }
+    let questions = read_u16(msg, 4)?;
+    let answers = read_u16(msg, 6)?;
+    let mut pos = 12;
+
+    for _ in 0..questions {
+        pos = skip_name(msg, pos)? + 4;
+    }
+    let mut records = Vec::new();
+
+    for _ in 0..answers {
+        pos = skip_name(msg, pos)?;
+
+        let rtype = read_u16(msg, pos)?;
+        let class = read_u16(msg, pos + 2)?;
+        let len = read_u16(msg, pos + 8)? as usize;
+        let data = msg
+            .get(pos + 10..pos + 10 + len)
+            .ok_or_else(|| invalid("record data is out of bounds"))?;
+        pos += 10 + len;
+
+        if rtype != TYPE_TXT || class != CLASS_IN {
+            continue;
+        }
+        // A record is made of one or more length-prefixed strings, which are concatenated.
+        let mut record = Vec::with_capacity(data.len());
+        let mut data = data;
+        while let Some((len, rest)) = data.split_first() {
+            let len = *len as usize;
+            let string = rest
+                .get(..len)
+                .ok_or_else(|| invalid("record string is out of bounds"))?;
+
+            record.extend_from_slice(string);
+            data = &rest[len..];
+        }
+        match String::from_utf8(record) {
+            Ok(record) => records.push(record),
+            Err(_) => log::debug!(target: "dns", "Ignoring non-UTF-8 TXT record"),
+        }
+    }
+    Ok(Some(records))
+}
+
+/// Skip over a domain name, returning the position after it.
+fn skip_name(msg: &[u8], mut pos: usize) -> io::Result<usize> {
+    loop {
+        let len = *msg
+            .get(pos)
+            .ok_or_else(|| invalid("name is out of bounds"))?;
+
+        match len {
+            0 => return Ok(pos + 1),
+            // A compression pointer ends the name.
+            len if len & 0xc0 == 0xc0 => return Ok(pos + 2),
+            len => pos += 1 + len as usize,
+        }
+    }
+}
+
+fn read_u16(msg: &[u8], pos: usize) -> io::Result<u16> {
+    msg.get(pos..pos + 2)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]))
+        .ok_or_else(|| invalid("message is too short"))
+}
+
+fn invalid(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    /// Answer a single query with the given `TXT` records, each made of one or more strings.
+    fn stub(records: Vec<Vec<String>>) -> net::SocketAddr {
+        let socket = net::UdpSocket::bind((net::Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let addr = socket.local_addr().unwrap();
+
+        thread::spawn(move || {
+            let mut buf = [0u8; 512];
+            let (n, from) = socket.recv_from(&mut buf).unwrap();
+            let query = &buf[..n];
+
+            let mut response = Vec::new();
+            response.extend_from_slice(&query[..2]);
+            response.extend_from_slice(&0x8180u16.to_be_bytes());
+            response.extend_from_slice(&1u16.to_be_bytes());
+            response.extend_from_slice(&(records.len() as u16).to_be_bytes());
+            response.extend_from_slice(&[0; 4]);
+            response.extend_from_slice(&query[12..]);
+
+            for strings in records {
+                let data = strings
+                    .iter()
+                    .flat_map(|s| [&[s.len() as u8][..], s.as_bytes()].concat())
+                    .collect::<Vec<_>>();
+
+                response.extend_from_slice(&[0xc0, 12]); // Pointer to the question name.
+                response.extend_from_slice(&TYPE_TXT.to_be_bytes());
+                response.extend_from_slice(&CLASS_IN.to_be_bytes());
+                response.extend_from_slice(&300u32.to_be_bytes());
+                response.extend_from_slice(&(data.len() as u16).to_be_bytes());
+                response.extend_from_slice(&data);
+            }
+            socket.send_to(&response, from).unwrap();
+        });
+
+        addr
+    }
+
+    #[test]
+    fn test_txt() {
+        let nameserver = stub(vec![
+            vec!["v=spf1 -all".to_owned()],
+            vec!["hello ".to_owned(), "world".to_owned()],
+        ]);
+        let records = txt(nameserver, "example.com").unwrap();
+
+        assert_eq!(records, vec!["v=spf1 -all", "hello world"]);
+    }
+
+    #[test]
+    fn test_seeds() {
+        let nid = "z6MkrLMMsiPWUcNPHcRajuMi9mDfYckSoJyPwwnknocNYPm7";
+        let record = format!("{nid}@seed.example.com:8776");
+        let nameserver = stub(vec![
+            vec![record.clone()],
+            vec!["not a node address".to_owned()],
+        ]);
+        let seeds = seeds(&Dns {
+            seeds: vec![String::from("seeds.example.com")],
+            nameserver: Some(nameserver),
+        });
+
+        assert_eq!(seeds.len(), 1);
+        let (alias, addr) = &seeds[0];
+        assert_eq!(alias.as_ref(), "seed.example.com");
+        assert_eq!(addr.to_string(), record);
+    }
+
+    #[test]
+    fn test_encode_query() {
+        assert!(encode_query(1, "seeds..example.com", TYPE_TXT).is_err());
+        assert_eq!(
+            &encode_query(1, "radicle.xyz.", TYPE_TXT).unwrap()[12..],
+            b"\x07radicle\x03xyz\x00\x00\x10\x00\x01"
+        );
+    }
+
+    #[test]
+    fn test_parse_resolv_conf() {
+        let conf = "\
+# Generated by NetworkManager
+search lan
+nameserver 192.168.0.1
+nameserver 1.1.1.1
+";
+        assert_eq!(
+            parse_resolv_conf(conf),
+            Some(net::SocketAddr::from(([192, 168, 0, 1], PORT)))
+        );
+        assert_eq!(parse_resolv_conf("search lan\n"), None);
+    }
+}
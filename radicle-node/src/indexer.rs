@@ -0,0 +1,58 @@
+//! Search index maintenance.
+//!
+//! Repositories are indexed from a dedicated thread as their refs are fetched, so that
+//! searching never has to index anything. Since local changes don't come with events, the
+//! whole index is also brought up to date periodically, which only re-indexes repositories
+//! whose signed refs changed.
+use std::path::Path;
+use std::time;
+
+use crossbeam_channel as chan;
+
+use radicle::search;
+use radicle::storage::ReadStorage as _;
+use radicle::Storage;
+
+use crate::runtime::Handle;
+use crate::service::Event;
+
+/// How often the whole index is brought up to date.
+pub const SYNC_INTERVAL: time::Duration = time::Duration::from_secs(60);
+
+/// Keep the search index at the given path up to date, until the node shuts down.
+pub fn run(path: &Path, storage: Storage, handle: Handle) {
+    let mut index = match search::Index::open(path) {
+        Ok(index) => index,
+        Err(e) => {
+            log::error!(target: "search", "Failed to open search index: {e}");
+            return;
+        }
+    };
+    let events = handle.events();
+
+    loop {
+        match index.sync(&storage) {
+            Ok(0) => {}
+            Ok(n) => log::debug!(target: "search", "Indexed {n} repositories"),
+            Err(e) => log::error!(target: "search", "Failed to update search index: {e}"),
+        }
+        let deadline = time::Instant::now() + SYNC_INTERVAL;
+
+        loop {
+            match events.recv_deadline(deadline) {
+                Ok(Event::RefsFetched { rid, updated, .. }) if !updated.is_empty() => {
+                    if let Err(e) = storage
+                        .repository(rid)
+                        .map_err(search::Error::from)
+                        .and_then(|repo| index.index(&repo))
+                    {
+                        log::warn!(target: "search", "Failed to index {rid}: {e}");
+                    }
+                }
+                Ok(_) => {}
+                Err(chan::RecvTimeoutError::Timeout) => break,
+                Err(chan::RecvTimeoutError::Disconnected) => return,
+            }
+        }
+    }
+}
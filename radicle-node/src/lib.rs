@@ -1,7 +1,11 @@
 pub mod bounded;
 pub mod control;
 pub mod deserializer;
+pub mod dns;
+pub mod indexer;
 pub mod logger;
+pub mod metrics;
+pub mod nat;
 pub mod runtime;
 pub mod service;
 pub mod signals;
@@ -23,6 +23,9 @@ Usage
    If you're running a public seed node, make sure to use `--listen` to bind a listening socket to
    eg. `0.0.0.0:8776`, and add your external addresses in your configuration.
 
+   Send `SIGHUP` to the node to reload its configuration. Changes to peers, persistent peers,
+   limits, relaying and the default tracking policy are applied without restarting.
+
 Options
 
     --config             <path>         Config file to use (default ~/.radicle/config.json)
@@ -106,21 +109,29 @@ fn execute() -> anyhow::Result<()> {
 
     log::info!(target: "node", "Node ID is {}", signer.public_key());
 
-    let config = options.config.unwrap_or_else(|| home.config());
-    let config = profile::Config::load(&config)?.node;
-    let proxy = net::SocketAddr::new(net::Ipv4Addr::LOCALHOST.into(), 9050);
+    let config_path = options.config.unwrap_or_else(|| home.config());
+    let config = profile::Config::load(&config_path)?.node;
     let daemon = options.daemon.unwrap_or_else(|| {
         net::SocketAddr::new(net::Ipv4Addr::LOCALHOST.into(), radicle::git::PROTOCOL_PORT)
     });
 
-    let (notify, signals) = chan::bounded(1);
+    let (notify, signals) = chan::bounded(4);
     signals::install(notify)?;
 
     if options.force {
         log::debug!(target: "node", "Removing existing control socket..");
         fs::remove_file(home.socket()).ok();
     }
-    Runtime::init(home, config, options.listen, proxy, daemon, signals, signer)?.run()?;
+    Runtime::init(
+        home,
+        config,
+        config_path,
+        options.listen,
+        daemon,
+        signals,
+        signer,
+    )?
+    .run()?;
 
     Ok(())
 }
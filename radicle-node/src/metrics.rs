@@ -0,0 +1,81 @@
+//! Metrics HTTP listener.
+//!
+//! Serves node metrics in the Prometheus text exposition format, on `GET /metrics`.
+use std::io::{BufRead, BufReader, Write};
+use std::{io, net, time};
+
+use radicle::node::metrics;
+use radicle::node::Handle as _;
+
+use crate::runtime::Handle;
+
+/// Time to wait for a scraper to send its request.
+pub const READ_TIMEOUT: time::Duration = time::Duration::from_secs(6);
+/// Maximum number of request header lines we read.
+pub const MAX_HEADERS: usize = 64;
+
+/// Listen for metrics requests until the node shuts down.
+pub fn listen(listener: net::TcpListener, handle: Handle) {
+    log::debug!(target: "metrics", "Metrics listener running on {:?}..", listener.local_addr());
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if let Err(e) = respond(stream, &handle) {
+                    log::debug!(target: "metrics", "Failed to respond to metrics request: {e}");
+                }
+            }
+            Err(e) => log::error!(target: "metrics", "Failed to accept incoming connection: {e}"),
+        }
+        if !handle.is_running() {
+            break;
+        }
+    }
+    log::debug!(target: "metrics", "Exiting metrics listener..");
+}
+
+fn respond(stream: net::TcpStream, handle: &Handle) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+
+    let mut reader = BufReader::new(&stream);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+
+    // Skip the request headers; we don't use them.
+    for _ in 0..MAX_HEADERS {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+            break;
+        }
+    }
+    let mut parts = request.split_whitespace();
+    let (method, path) = (parts.next(), parts.next());
+    let mut writer = &stream;
+
+    if method != Some("GET") || !matches!(path, Some("/metrics") | Some("/")) {
+        return write!(
+            writer,
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+        );
+    }
+    match handle.metrics() {
+        Ok(metrics) => {
+            let body = metrics.encode();
+
+            write!(
+                writer,
+                "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                metrics::CONTENT_TYPE,
+                body.len(),
+            )
+        }
+        Err(e) => {
+            log::error!(target: "metrics", "Failed to get node metrics: {e}");
+
+            write!(
+                writer,
+                "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            )
+        }
+    }
+}
@@ -0,0 +1,248 @@
+//! NAT traversal: dial-back probes and port mapping.
+//!
+//! When a peer asks us to dial it back, we probe the address it connected from with a plain TCP
+//! connection, from a dedicated thread, and report the result to the service. Separately, if
+//! configured, we try to map our listening ports on the gateway using NAT-PMP (RFC 6886).
+use std::time;
+use std::{fs, io, net, thread};
+
+use crossbeam_channel as chan;
+
+use crate::node::NodeId;
+use crate::runtime::Handle;
+
+/// Time to wait for a dial-back probe to connect.
+pub const PROBE_TIMEOUT: time::Duration = time::Duration::from_secs(6);
+/// Maximum number of probes waiting to be processed. Further probes are dropped.
+pub const MAX_PENDING_PROBES: usize = 16;
+/// Time to wait before retrying a failed port mapping.
+pub const MAPPING_RETRY: time::Duration = time::Duration::from_secs(5 * 60);
+
+/// Request to probe the address a peer asked to be dialed back on.
+#[derive(Debug, Clone, Copy)]
+pub struct Probe {
+    /// The peer that asked to be dialed back.
+    pub remote: NodeId,
+    /// The address to dial.
+    pub addr: net::SocketAddr,
+}
+
+/// Process probe requests until the channel is disconnected.
+pub fn probe(probes: chan::Receiver<Probe>, mut handle: Handle) {
+    while let Ok(Probe { remote, addr }) = probes.recv() {
+        log::debug!(target: "nat", "Probing {remote} at {addr}..");
+
+        // Nb. We only check that something is listening on the other end. Completing a
+        // handshake would conflict with the session we already have with this peer.
+        let reachable = net::TcpStream::connect_timeout(&addr, PROBE_TIMEOUT).is_ok();
+
+        if handle.probed(remote, addr, reachable).is_err() {
+            break;
+        }
+    }
+    log::debug!(target: "nat", "Exiting probe loop..");
+}
+
+/// Map the given listening ports on the gateway, and keep the mappings alive until the node
+/// shuts down. The mapped external addresses are reported to the service.
+pub fn map(gateway: Option<net::Ipv4Addr>, ports: Vec<u16>, mut handle: Handle) {
+    let gateway = match gateway.map(Ok).unwrap_or_else(pmp::gateway) {
+        Ok(gateway) => gateway,
+        Err(e) => {
+            log::warn!(target: "nat", "Unable to find gateway for NAT-PMP: {e}");
+            return;
+        }
+    };
+    log::info!(target: "nat", "Mapping listening port(s) {ports:?} via NAT-PMP gateway {gateway}..");
+
+    loop {
+        let mut lifetime = pmp::LIFETIME;
+
+        for port in &ports {
+            match pmp::external_address(gateway)
+                .and_then(|ip| pmp::map_tcp(gateway, *port, pmp::LIFETIME).map(|m| (ip, m)))
+            {
+                Ok((ip, mapping)) => {
+                    let addr = net::SocketAddr::new(ip.into(), mapping.external_port);
+
+                    log::debug!(
+                        target: "nat",
+                        "Mapped port {port} to {addr} for {}s", mapping.lifetime
+                    );
+                    lifetime = lifetime.min(mapping.lifetime);
+
+                    if handle.mapped(addr).is_err() {
+                        return;
+                    }
+                }
+                Err(e) => {
+                    log::warn!(target: "nat", "Failed to map port {port} via NAT-PMP: {e}");
+                    lifetime = 0;
+                }
+            }
+        }
+        // Renew mappings half-way through their lifetime, as recommended by the RFC.
+        if lifetime == 0 {
+            thread::sleep(MAPPING_RETRY);
+        } else {
+            thread::sleep(time::Duration::from_secs(lifetime as u64 / 2));
+        }
+    }
+}
+
+/// A minimal NAT-PMP client.
+pub mod pmp {
+    use super::*;
+
+    /// Port NAT-PMP gateways listen on.
+    pub const PORT: u16 = 5351;
+    /// Requested mapping lifetime, in seconds.
+    pub const LIFETIME: u32 = 7200;
+    /// Number of times a request is sent before giving up.
+    pub const ATTEMPTS: u32 = 4;
+    /// Initial time to wait for a response. Doubles after every attempt.
+    pub const INITIAL_TIMEOUT: time::Duration = time::Duration::from_millis(250);
+
+    /// A port mapping on the gateway.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Mapping {
+        /// The external port mapped to our internal port.
+        pub external_port: u16,
+        /// Lifetime of the mapping, in seconds.
+        pub lifetime: u32,
+    }
+
+    /// Get the gateway's external IP address.
+    pub fn external_address(gateway: net::Ipv4Addr) -> io::Result<net::Ipv4Addr> {
+        let response = request(gateway, &[0, 0], 12)?;
+        let ip = [response[8], response[9], response[10], response[11]];
+
+        Ok(net::Ipv4Addr::from(ip))
+    }
+
+    /// Map an internal TCP port on the gateway.
+    pub fn map_tcp(gateway: net::Ipv4Addr, port: u16, lifetime: u32) -> io::Result<Mapping> {
+        let mut req = [0u8; 12];
+        req[1] = 2; // Map TCP.
+        req[4..6].copy_from_slice(&port.to_be_bytes());
+        req[6..8].copy_from_slice(&port.to_be_bytes()); // Suggested external port.
+        req[8..12].copy_from_slice(&lifetime.to_be_bytes());
+
+        let response = request(gateway, &req, 16)?;
+        let internal_port = u16::from_be_bytes([response[8], response[9]]);
+        if internal_port != port {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "mapping response is for a different port",
+            ));
+        }
+
+        Ok(Mapping {
+            external_port: u16::from_be_bytes([response[10], response[11]]),
+            lifetime: u32::from_be_bytes([response[12], response[13], response[14], response[15]]),
+        })
+    }
+
+    /// Find the default gateway, by reading the kernel routing table.
+    /// Only supported on Linux; on other systems, the gateway has to be configured.
+    pub fn gateway() -> io::Result<net::Ipv4Addr> {
+        let routes = fs::read_to_string("/proc/net/route")?;
+
+        parse_routes(&routes)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no default route was found"))
+    }
+
+    /// Send a request to the gateway and wait for a successful response of the given length.
+    fn request(gateway: net::Ipv4Addr, req: &[u8], len: usize) -> io::Result<Vec<u8>> {
+        let socket = net::UdpSocket::bind((net::Ipv4Addr::UNSPECIFIED, 0))?;
+        socket.connect((gateway, PORT))?;
+
+        let mut timeout = INITIAL_TIMEOUT;
+        let mut buf = [0u8; 16];
+
+        for _ in 0..ATTEMPTS {
+            socket.send(req)?;
+            socket.set_read_timeout(Some(timeout))?;
+
+            match socket.recv(&mut buf) {
+                Ok(n) if n >= len => {
+                    // Responses have the same opcode as the request, plus 128.
+                    if buf[0] != 0 || buf[1] != req[1] + 128 {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "unexpected NAT-PMP response",
+                        ));
+                    }
+                    let result = u16::from_be_bytes([buf[2], buf[3]]);
+                    if result != 0 {
+                        return Err(io::Error::new(
+                            io::ErrorKind::Other,
+                            format!("NAT-PMP request failed with result code {result}"),
+                        ));
+                    }
+                    return Ok(buf[..len].to_vec());
+                }
+                Ok(_) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "NAT-PMP response is too short",
+                    ));
+                }
+                Err(e)
+                    if e.kind() == io::ErrorKind::WouldBlock
+                        || e.kind() == io::ErrorKind::TimedOut =>
+                {
+                    timeout *= 2;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "NAT-PMP gateway did not respond",
+        ))
+    }
+
+    /// Parse the default gateway out of `/proc/net/route`.
+    fn parse_routes(routes: &str) -> Option<net::Ipv4Addr> {
+        // The gateway flag: the route goes through a gateway.
+        const RTF_GATEWAY: u16 = 0x2;
+
+        routes.lines().skip(1).find_map(|line| {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            let (destination, gateway, flags) = (fields.get(1)?, fields.get(2)?, fields.get(3)?);
+            let flags = u16::from_str_radix(flags, 16).ok()?;
+
+            if *destination != "00000000" || flags & RTF_GATEWAY == 0 {
+                return None;
+            }
+            // Addresses are in host byte order.
+            let gateway = u32::from_str_radix(gateway, 16).ok()?;
+
+            Some(net::Ipv4Addr::from(gateway.to_ne_bytes()))
+        })
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        #[cfg(target_endian = "little")]
+        fn test_parse_routes() {
+            let routes = "\
+Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT
+wlan0\t0000A8C0\t00000000\t0001\t0\t0\t600\t00FFFFFF\t0\t0\t0
+wlan0\t00000000\t0100A8C0\t0003\t0\t0\t600\t00000000\t0\t0\t0
+";
+            assert_eq!(
+                parse_routes(routes),
+                Some(net::Ipv4Addr::new(192, 168, 0, 1))
+            );
+            assert_eq!(
+                parse_routes(&routes.lines().take(2).collect::<Vec<_>>().join("\n")),
+                None
+            );
+        }
+    }
+}
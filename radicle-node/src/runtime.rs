@@ -3,7 +3,7 @@ pub mod thread;
 
 use std::io::{BufRead, BufReader};
 use std::os::unix::net::UnixListener;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::{fs, io, net, time};
 
@@ -18,16 +18,25 @@ use radicle::git;
 use radicle::node;
 use radicle::node::address;
 use radicle::node::address::Store as _;
+use radicle::node::succession;
+use radicle::node::succession::Succession;
 use radicle::node::Handle as _;
-use radicle::node::{ADDRESS_DB_FILE, NODE_ANNOUNCEMENT_FILE, ROUTING_DB_FILE, TRACKING_DB_FILE};
+use radicle::node::{
+    ADDRESS_DB_FILE, GOSSIP_DB_FILE, NODE_ANNOUNCEMENT_FILE, ROUTING_DB_FILE, SUCCESSION_FILE,
+    TRACKING_DB_FILE,
+};
+use radicle::profile;
 use radicle::profile::Home;
 use radicle::Storage;
 
 use crate::control;
 use crate::crypto::Signer;
+use crate::dns;
+use crate::nat;
 use crate::node::{routing, NodeId};
 use crate::service::message::NodeAnnouncement;
-use crate::service::{tracking, Event};
+use crate::service::{gossip, tracking, Event};
+use crate::signals::Signal;
 use crate::wire::Wire;
 use crate::wire::{self, Decode};
 use crate::worker;
@@ -48,6 +57,12 @@ pub enum Error {
     /// A tracking database error.
     #[error("tracking database error: {0}")]
     Tracking(#[from] tracking::Error),
+    /// A gossip database error.
+    #[error("gossip database error: {0}")]
+    Gossip(#[from] gossip::Error),
+    /// A key succession error.
+    #[error("key succession error: {0}")]
+    Succession(#[from] succession::Error),
     /// An I/O error.
     #[error("i/o error: {0}")]
     Io(#[from] io::Error),
@@ -64,6 +79,9 @@ pub enum Error {
     /// A git version error.
     #[error("git version error: {0}")]
     GitVersion(#[from] git::VersionError),
+    /// A configuration error.
+    #[error("configuration error: {0}")]
+    Config(#[from] profile::ConfigError),
 }
 
 /// Publishes events to subscribers.
@@ -110,7 +128,9 @@ pub struct Runtime {
     pub daemon: net::SocketAddr,
     pub pool: worker::Pool,
     pub local_addrs: Vec<net::SocketAddr>,
-    pub signals: chan::Receiver<()>,
+    pub signals: chan::Receiver<Signal>,
+    /// Path to the configuration file, which is reloaded on `SIGHUP`.
+    pub config: PathBuf,
 }
 
 impl Runtime {
@@ -120,10 +140,10 @@ impl Runtime {
     pub fn init<G: Signer + Ecdh + 'static>(
         home: Home,
         config: service::Config,
+        config_path: PathBuf,
         listen: Vec<net::SocketAddr>,
-        proxy: net::SocketAddr,
         daemon: net::SocketAddr,
-        signals: chan::Receiver<()>,
+        signals: chan::Receiver<Signal>,
         signer: G,
     ) -> Result<Runtime, Error>
     where
@@ -132,12 +152,16 @@ impl Runtime {
         let id = *signer.public_key();
         let node_dir = home.node();
         let network = config.network;
+        let nat = config.nat.clone();
+        let metrics = config.metrics;
+        let search = config.search;
         let rng = fastrand::Rng::new();
         let clock = LocalTime::now();
         let storage = Storage::open(home.storage())?;
         let address_db = node_dir.join(ADDRESS_DB_FILE);
         let routing_db = node_dir.join(ROUTING_DB_FILE);
         let tracking_db = node_dir.join(TRACKING_DB_FILE);
+        let gossip_db = node_dir.join(GOSSIP_DB_FILE);
 
         log::info!(target: "node", "Opening address book {}..", address_db.display());
         let mut addresses = address::Book::open(address_db)?;
@@ -149,6 +173,9 @@ impl Runtime {
         let tracking = tracking::Store::open(tracking_db)?;
         let tracking = tracking::Config::new(config.policy, config.scope, tracking);
 
+        log::info!(target: "node", "Opening gossip store {}..", gossip_db.display());
+        let gossip = gossip::Store::open(gossip_db)?;
+
         log::info!(target: "node", "Default tracking policy set to '{}'", &config.policy);
         log::info!(target: "node", "Initializing service ({:?})..", network);
 
@@ -179,9 +206,28 @@ impl Runtime {
         };
 
         if config.connect.is_empty() && addresses.is_empty()? {
-            log::info!(target: "node", "Address book is empty. Adding bootstrap nodes..");
+            let mut bootstrap = Vec::new();
+
+            if !config.dns.seeds.is_empty() {
+                log::info!(target: "node", "Address book is empty. Resolving bootstrap nodes via DNS..");
+
+                bootstrap = dns::seeds(&config.dns)
+                    .into_iter()
+                    .map(|(alias, addr)| (alias, addr, address::Source::Dns))
+                    .collect();
+            }
+            if bootstrap.is_empty() {
+                log::info!(target: "node", "Address book is empty. Adding bootstrap nodes..");
+
+                bootstrap = config
+                    .network
+                    .bootstrap()
+                    .into_iter()
+                    .map(|(alias, addr)| (alias, addr, address::Source::Bootstrap))
+                    .collect();
+            }
 
-            for (alias, addr) in config.network.bootstrap() {
+            for (alias, addr, source) in bootstrap {
                 let (id, addr) = addr.into();
 
                 addresses.insert(
@@ -190,28 +236,46 @@ impl Runtime {
                     alias,
                     0,
                     clock.as_secs(),
-                    [node::KnownAddress::new(addr, address::Source::Bootstrap)],
+                    [node::KnownAddress::new(addr, source)],
                 )?;
             }
             log::info!(target: "node", "{} nodes added to address book", addresses.len()?);
         }
 
         let emitter: Emitter<Event> = Default::default();
-        let service = service::Service::new(
+        let mut service = service::Service::new(
             config,
             clock,
             routing,
             storage.clone(),
             addresses,
             tracking,
+            gossip,
             signer.clone(),
             rng,
             announcement,
             emitter.clone(),
         );
 
+        let succession_path = node_dir.join(SUCCESSION_FILE);
+        if succession_path.exists() {
+            let succession = Succession::load(&succession_path)?;
+
+            if succession.new == id {
+                log::info!(target: "node", "Node key succeeds {}", succession.old);
+                service.succeeded(succession);
+            } else {
+                log::warn!(
+                    target: "node",
+                    "Ignoring key succession record for {}: node key is {id}",
+                    succession.new
+                );
+            }
+        }
+
         let (worker_send, worker_recv) = chan::unbounded::<worker::Task>();
-        let mut wire = Wire::new(service, worker_send, signer, proxy, clock);
+        let (probe_send, probe_recv) = chan::bounded::<nat::Probe>(nat::MAX_PENDING_PROBES);
+        let mut wire = Wire::new(service, worker_send, probe_send, signer, clock);
         let mut local_addrs = Vec::new();
 
         for addr in listen {
@@ -225,6 +289,42 @@ impl Runtime {
         }
         let reactor = Reactor::named(wire, popol::Poller::new(), thread::name(&id, "service"))?;
         let handle = Handle::new(home.clone(), reactor.controller(), emitter);
+
+        thread::spawn(&id, "probe", {
+            let handle = handle.clone();
+            || nat::probe(probe_recv, handle)
+        });
+        if nat.pmp {
+            let ports = local_addrs
+                .iter()
+                .filter(|a| a.is_ipv4() && !a.ip().is_loopback())
+                .map(|a| a.port())
+                .collect::<Vec<_>>();
+
+            if !ports.is_empty() {
+                thread::spawn(&id, "nat", {
+                    let handle = handle.clone();
+                    move || nat::map(nat.gateway, ports, handle)
+                });
+            }
+        }
+        if let Some(addr) = metrics {
+            let listener = net::TcpListener::bind(addr)?;
+            log::info!(target: "node", "Serving metrics on {}..", listener.local_addr()?);
+
+            thread::spawn(&id, "metrics", {
+                let handle = handle.clone();
+                || crate::metrics::listen(listener, handle)
+            });
+        }
+        if search {
+            thread::spawn(&id, "search", {
+                let path = node_dir.join(radicle::search::SEARCH_DB_FILE);
+                let storage = storage.clone();
+                let handle = handle.clone();
+                move || crate::indexer::run(&path, storage, handle)
+            });
+        }
         let atomic = git::version()? >= git::VERSION_REQUIRED;
 
         if !atomic {
@@ -267,6 +367,7 @@ impl Runtime {
             pool,
             signals,
             local_addrs,
+            config: config_path,
         })
     }
 
@@ -281,9 +382,24 @@ impl Runtime {
             || control::listen(self.control, handle)
         });
         let _signals = thread::spawn(&self.id, "signals", move || {
-            if let Ok(()) = self.signals.recv() {
-                log::info!(target: "node", "Termination signal received; shutting down..");
-                self.handle.shutdown().ok();
+            while let Ok(signal) = self.signals.recv() {
+                match signal {
+                    Signal::Terminate => {
+                        log::info!(target: "node", "Termination signal received; shutting down..");
+                        self.handle.shutdown().ok();
+                        break;
+                    }
+                    Signal::Hangup => {
+                        log::info!(
+                            target: "node",
+                            "Hangup signal received; reloading configuration from {}..",
+                            self.config.display()
+                        );
+                        if let Err(e) = reload(&self.config, &mut self.handle.clone()) {
+                            log::error!(target: "node", "Failed to reload configuration: {e}");
+                        }
+                    }
+                }
             }
         });
 
@@ -321,6 +437,23 @@ impl Runtime {
     }
 }
 
+/// Reload the node configuration from the given path, and apply it to the running service.
+fn reload(path: &Path, handle: &mut Handle) -> Result<(), Error> {
+    // Nb. When the file is missing, `Config::load` falls back to a default configuration,
+    // which would drop all our persistent peers.
+    if !path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("configuration file {} not found", path.display()),
+        )
+        .into());
+    }
+    let config = profile::Config::load(path)?.node;
+    handle.reload(config)?;
+
+    Ok(())
+}
+
 pub mod daemon {
     use std::path::Path;
     use std::process::{Child, Command, Stdio};
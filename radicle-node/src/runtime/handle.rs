@@ -1,15 +1,18 @@
 use std::os::unix::net::UnixStream;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::{fmt, io, time};
+use std::{fmt, io, net, time};
 
 use crossbeam_channel as chan;
-use radicle::node::{ConnectOptions, ConnectResult, Seeds};
+use radicle::node::config::Limits;
+use radicle::node::{
+    ConnectOptions, ConnectResult, Metrics, Reachability, Replication, Seed, Seeds,
+};
 use reactor::poller::popol::PopolWaker;
 use thiserror::Error;
 
 use crate::identity::Id;
-use crate::node::{Alias, Command, FetchResult};
+use crate::node::{Alias, Command, Config, FetchResult};
 use crate::profile::Home;
 use crate::runtime::Emitter;
 use crate::service;
@@ -115,6 +118,28 @@ impl Handle {
         self.controller.cmd(wire::Control::Flush { remote, stream })
     }
 
+    pub fn probed(
+        &mut self,
+        remote: NodeId,
+        addr: net::SocketAddr,
+        reachable: bool,
+    ) -> Result<(), io::Error> {
+        self.controller.cmd(wire::Control::Probed {
+            remote,
+            addr,
+            reachable,
+        })
+    }
+
+    pub fn mapped(&mut self, addr: net::SocketAddr) -> Result<(), io::Error> {
+        self.controller.cmd(wire::Control::Mapped(addr))
+    }
+
+    /// Apply a reloaded configuration to the running service.
+    pub fn reload(&mut self, config: Config) -> Result<(), io::Error> {
+        self.command(service::Command::Reload(Box::new(config)))
+    }
+
     pub(crate) fn command(&self, cmd: service::Command) -> Result<(), io::Error> {
         self.controller.cmd(wire::Control::User(cmd))
     }
@@ -177,6 +202,45 @@ impl radicle::node::Handle for Handle {
             .map_err(Error::from)
     }
 
+    fn disconnect(&mut self, node: NodeId) -> Result<(), Error> {
+        self.command(service::Command::Disconnect(node))?;
+
+        Ok(())
+    }
+
+    fn addresses(&self) -> Result<Vec<Seed>, Error> {
+        let (sender, receiver) = chan::bounded(1);
+        self.command(service::Command::Addresses(sender))?;
+        receiver.recv().map_err(Error::from)
+    }
+
+    fn routing(&self, node: NodeId) -> Result<Vec<Id>, Error> {
+        let (sender, receiver) = chan::bounded(1);
+        self.command(service::Command::Routing(node, sender))?;
+        receiver.recv().map_err(Error::from)
+    }
+
+    fn config(&self) -> Result<Config, Error> {
+        let (sender, receiver) = chan::bounded(1);
+        let query: Arc<QueryState> = Arc::new(move |state| {
+            sender.send(state.config().clone()).ok();
+            Ok(())
+        });
+        let (err_sender, err_receiver) = chan::bounded(1);
+        self.command(service::Command::QueryState(query, err_sender))?;
+        err_receiver.recv()??;
+
+        let config = receiver.recv()?;
+
+        Ok(config)
+    }
+
+    fn update_limits(&mut self, limits: Limits) -> Result<(), Error> {
+        self.command(service::Command::UpdateLimits(limits))?;
+
+        Ok(())
+    }
+
     fn seeds(&mut self, id: Id) -> Result<Seeds, Self::Error> {
         let (sender, receiver) = chan::bounded(1);
         self.command(service::Command::Seeds(id, sender))?;
@@ -213,6 +277,24 @@ impl radicle::node::Handle for Handle {
         receiver.recv().map_err(Error::from)
     }
 
+    fn track_remote(&mut self, rid: Id, nid: NodeId) -> Result<bool, Error> {
+        let (sender, receiver) = chan::bounded(1);
+        self.command(service::Command::TrackRemote(rid, nid, sender))?;
+        receiver.recv().map_err(Error::from)
+    }
+
+    fn untrack_remote(&mut self, rid: Id, nid: NodeId) -> Result<bool, Error> {
+        let (sender, receiver) = chan::bounded(1);
+        self.command(service::Command::UntrackRemote(rid, nid, sender))?;
+        receiver.recv().map_err(Error::from)
+    }
+
+    fn block_remote(&mut self, rid: Id, nid: NodeId) -> Result<bool, Error> {
+        let (sender, receiver) = chan::bounded(1);
+        self.command(service::Command::BlockRemote(rid, nid, sender))?;
+        receiver.recv().map_err(Error::from)
+    }
+
     fn announce_refs(&mut self, id: Id) -> Result<(), Error> {
         self.command(service::Command::AnnounceRefs(id))
             .map_err(Error::from)
@@ -261,6 +343,51 @@ impl radicle::node::Handle for Handle {
         Ok(sessions)
     }
 
+    fn reachability(&self) -> Result<Reachability, Error> {
+        let (sender, receiver) = chan::bounded(1);
+        let query: Arc<QueryState> = Arc::new(move |state| {
+            sender.send(state.reachability().clone()).ok();
+            Ok(())
+        });
+        let (err_sender, err_receiver) = chan::bounded(1);
+        self.command(service::Command::QueryState(query, err_sender))?;
+        err_receiver.recv()??;
+
+        let reachability = receiver.recv()?;
+
+        Ok(reachability)
+    }
+
+    fn metrics(&self) -> Result<Metrics, Error> {
+        let (sender, receiver) = chan::bounded(1);
+        let query: Arc<QueryState> = Arc::new(move |state| {
+            sender.send(state.metrics()).ok();
+            Ok(())
+        });
+        let (err_sender, err_receiver) = chan::bounded(1);
+        self.command(service::Command::QueryState(query, err_sender))?;
+        err_receiver.recv()??;
+
+        let metrics = receiver.recv()?;
+
+        Ok(metrics)
+    }
+
+    fn replication(&self) -> Result<Vec<Replication>, Error> {
+        let (sender, receiver) = chan::bounded(1);
+        let query: Arc<QueryState> = Arc::new(move |state| {
+            sender.send(state.replication().to_vec()).ok();
+            Ok(())
+        });
+        let (err_sender, err_receiver) = chan::bounded(1);
+        self.command(service::Command::QueryState(query, err_sender))?;
+        err_receiver.recv()??;
+
+        let replication = receiver.recv()?;
+
+        Ok(replication)
+    }
+
     fn shutdown(self) -> Result<(), Error> {
         // If the current value is `false`, set it to `true`, otherwise error.
         if self
@@ -2,17 +2,19 @@
 #![allow(clippy::collapsible_match)]
 #![allow(clippy::collapsible_if)]
 pub mod filter;
+pub mod gossip;
 pub mod io;
 pub mod limitter;
 pub mod message;
+pub mod reconcile;
 pub mod session;
 pub mod tracking;
 
 use std::collections::hash_map::Entry;
-use std::collections::{BTreeMap, HashMap, HashSet};
-use std::fmt;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
+use std::{fmt, net};
 
 use crossbeam_channel as chan;
 use fastrand::Rng;
@@ -21,9 +23,11 @@ use log::*;
 use nonempty::NonEmpty;
 
 use radicle::node::address;
-use radicle::node::address::{AddressBook, KnownAddress};
-use radicle::node::config::PeerConfig;
-use radicle::node::ConnectOptions;
+use radicle::node::address::{
+    AddressBook, KnownAddress, Misbehavior, Reputation, BAN_THRESHOLD, RATE_LIMITED_MAX_SCORE,
+};
+use radicle::node::config::{Limits, PeerConfig};
+use radicle::node::{ConnectOptions, Metrics, Reachability, Replication};
 
 use crate::crypto;
 use crate::crypto::{Signer, Verified};
@@ -31,11 +35,12 @@ use crate::identity::IdentityError;
 use crate::identity::{Doc, Id};
 use crate::node::routing;
 use crate::node::routing::InsertResult;
+use crate::node::succession::Succession;
 use crate::node::{Address, Alias, Features, FetchResult, HostName, Seed, Seeds};
 use crate::prelude::*;
 use crate::runtime::Emitter;
 use crate::service::message::{Announcement, AnnouncementMessage, Ping};
-use crate::service::message::{NodeAnnouncement, RefsAnnouncement};
+use crate::service::message::{NodeAnnouncement, RefsAnnouncement, SuccessionAnnouncement};
 use crate::service::tracking::{store::Write, Scope};
 use crate::storage;
 use crate::storage::{Namespaces, ReadStorage};
@@ -48,10 +53,10 @@ pub use crate::node::{config::Network, Config, NodeId};
 pub use crate::service::message::{Message, ZeroBytes};
 pub use crate::service::session::Session;
 
-use self::gossip::Gossip;
 use self::io::Outbox;
 use self::limitter::RateLimiter;
 use self::message::InventoryAnnouncement;
+use self::reconcile::Digests;
 use self::tracking::NamespacesError;
 
 /// How often to run the "idle" task.
@@ -62,6 +67,10 @@ pub const ANNOUNCE_INTERVAL: LocalDuration = LocalDuration::from_mins(60);
 pub const SYNC_INTERVAL: LocalDuration = LocalDuration::from_secs(60);
 /// How often to run the "prune" task.
 pub const PRUNE_INTERVAL: LocalDuration = LocalDuration::from_mins(30);
+/// How often to run the "replication" task, when a replication factor is configured.
+pub const REPLICATION_INTERVAL: LocalDuration = LocalDuration::from_mins(10);
+/// Maximum time to wait before offering an under-replicated repository to seeds again.
+pub const MAX_REPLICATION_DELTA: LocalDuration = LocalDuration::from_mins(60 * 24);
 /// Duration to wait on an unresponsive peer before dropping its connection.
 pub const STALE_CONNECTION_TIMEOUT: LocalDuration = LocalDuration::from_mins(2);
 /// How much time should pass after a peer was last active for a *ping* to be sent.
@@ -78,6 +87,19 @@ pub const MIN_RECONNECTION_DELTA: LocalDuration = LocalDuration::from_secs(3);
 pub const MAX_RECONNECTION_DELTA: LocalDuration = LocalDuration::from_mins(60);
 /// Connection retry delta used for ephemeral peers that failed to connect previously.
 pub const CONNECTION_RETRY_DELTA: LocalDuration = LocalDuration::from_mins(10);
+/// How often to check whether we are reachable by other nodes.
+pub const REACHABILITY_INTERVAL: LocalDuration = LocalDuration::from_mins(30);
+/// How long to wait for a peer to answer a dial-back request.
+pub const DIAL_BACK_TIMEOUT: LocalDuration = LocalDuration::from_mins(1);
+/// Minimum amount of time between two probes of the same address on behalf of peers.
+pub const DIAL_BACK_COOLDOWN: LocalDuration = LocalDuration::from_mins(10);
+/// Number of peers, on distinct hosts, we ask to dial us back during a reachability check.
+pub const DIAL_BACK_PEERS: usize = 3;
+/// Number of peers, on distinct hosts, that must be able to dial us back on an observed
+/// address before we announce it.
+pub const MIN_DIAL_BACK_AGREEMENT: usize = 2;
+/// Maximum number of external addresses observed by peers to keep track of.
+pub const MAX_OBSERVED_ADDRESSES: usize = 8;
 
 /// Maximum external address limit imposed by message size limits.
 pub use message::ADDRESS_LIMIT;
@@ -86,6 +108,26 @@ pub use message::INVENTORY_LIMIT;
 /// Maximum number of project git references imposed by message size limits.
 pub use message::REF_REMOTE_LIMIT;
 
+/// Penalties incurred by a peer, that are not yet recorded in the address book.
+#[derive(Debug)]
+struct Penalties {
+    /// The peer's reputation, as recorded when it was first penalized.
+    recorded: Reputation,
+    /// Penalty points accumulated since.
+    pending: u32,
+}
+
+/// Offers of an under-replicated repository to other seeds.
+#[derive(Debug, Default)]
+struct Offers {
+    /// Seeds the repository was offered to.
+    seeds: HashSet<NodeId>,
+    /// Number of times the repository was offered.
+    attempts: u32,
+    /// Time before which the repository isn't offered again.
+    retry_at: LocalTime,
+}
+
 /// Result of syncing our routing table with a node's inventory.
 #[derive(Default)]
 struct SyncedRouting {
@@ -143,6 +185,20 @@ pub enum Command {
     TrackNode(NodeId, Option<Alias>, chan::Sender<bool>),
     /// Untrack the given node.
     UntrackNode(NodeId, chan::Sender<bool>),
+    /// Track the given remote, for the given repository.
+    TrackRemote(Id, NodeId, chan::Sender<bool>),
+    /// Remove the tracking policy of the given remote, for the given repository.
+    UntrackRemote(Id, NodeId, chan::Sender<bool>),
+    /// Block the given remote, for the given repository.
+    BlockRemote(Id, NodeId, chan::Sender<bool>),
+    /// Get the nodes in the address book.
+    Addresses(chan::Sender<Vec<Seed>>),
+    /// Lookup the repositories seeded by the given node in the routing table.
+    Routing(NodeId, chan::Sender<Vec<Id>>),
+    /// Change the service limits.
+    UpdateLimits(Limits),
+    /// Apply a reloaded configuration.
+    Reload(Box<Config>),
     /// Query the internal service state.
     QueryState(Arc<QueryState>, chan::Sender<Result<(), CommandError>>),
 }
@@ -161,6 +217,13 @@ impl fmt::Debug for Command {
             Self::UntrackRepo(id, _) => write!(f, "UntrackRepo({id})"),
             Self::TrackNode(id, _, _) => write!(f, "TrackNode({id})"),
             Self::UntrackNode(id, _) => write!(f, "UntrackNode({id})"),
+            Self::TrackRemote(rid, nid, _) => write!(f, "TrackRemote({rid}, {nid})"),
+            Self::UntrackRemote(rid, nid, _) => write!(f, "UntrackRemote({rid}, {nid})"),
+            Self::BlockRemote(rid, nid, _) => write!(f, "BlockRemote({rid}, {nid})"),
+            Self::Addresses(_) => write!(f, "Addresses(..)"),
+            Self::Routing(nid, _) => write!(f, "Routing({nid})"),
+            Self::UpdateLimits(limits) => write!(f, "UpdateLimits({limits:?})"),
+            Self::Reload(_) => write!(f, "Reload(..)"),
             Self::QueryState { .. } => write!(f, "QueryState(..)"),
         }
     }
@@ -191,8 +254,8 @@ pub struct Service<R, A, S, G> {
     addresses: A,
     /// Tracking policy configuration.
     tracking: tracking::Config<Write>,
-    /// State relating to gossip.
-    gossip: Gossip,
+    /// Latest announcements received, by node.
+    gossip: gossip::Store,
     /// Peer sessions, currently or recently connected.
     sessions: Sessions,
     /// Clock. Tells the time.
@@ -217,8 +280,32 @@ pub struct Service<R, A, S, G> {
     last_prune: LocalTime,
     /// Last time the service announced its inventory.
     last_announce: LocalTime,
+    /// Last time the service checked the replication of its repositories.
+    last_replication: LocalTime,
     /// Time when the service was initialized.
     start_time: LocalTime,
+    /// Addresses we are listening on for inbound connections.
+    listening: Vec<net::SocketAddr>,
+    /// Result of our reachability self-check.
+    reachability: Reachability,
+    /// Repositories replicated on fewer seeds than the configured replication factor.
+    replication: Vec<Replication>,
+    /// Offers of under-replicated repositories to other seeds.
+    offers: HashMap<Id, Offers>,
+    /// Penalties incurred by peers since they were last recorded in the address book.
+    penalties: HashMap<NodeId, Penalties>,
+    /// Peers we asked to dial us back, and when we asked.
+    dial_backs: HashMap<NodeId, LocalTime>,
+    /// Answers to our dial-back requests since the last completed reachability check, by host.
+    dial_back_results: HashMap<HostName, (Address, bool)>,
+    /// Addresses we probed on behalf of peers, and when.
+    probes: HashMap<net::IpAddr, LocalTime>,
+    /// Record of our key succeeding a previous key, if any.
+    succession: Option<Succession>,
+    /// Service metrics.
+    metrics: Metrics,
+    /// Fetches in progress, and when they were started.
+    fetches: HashMap<(Id, NodeId), LocalTime>,
     /// Publishes events to subscribers.
     emitter: Emitter<Event>,
 }
@@ -252,6 +339,7 @@ where
         storage: S,
         addresses: A,
         tracking: tracking::Config<Write>,
+        gossip: gossip::Store,
         signer: G,
         rng: Rng,
         node: NodeAnnouncement,
@@ -269,7 +357,7 @@ where
             node,
             clock,
             routing,
-            gossip: Gossip::default(),
+            gossip,
             outbox: Outbox::default(),
             limiter: RateLimiter::default(),
             sessions,
@@ -279,7 +367,19 @@ where
             last_sync: LocalTime::default(),
             last_prune: LocalTime::default(),
             last_announce: LocalTime::default(),
+            last_replication: LocalTime::default(),
             start_time: LocalTime::default(),
+            listening: Vec::new(),
+            reachability: Reachability::default(),
+            replication: Vec::new(),
+            offers: HashMap::new(),
+            penalties: HashMap::new(),
+            dial_backs: HashMap::new(),
+            dial_back_results: HashMap::new(),
+            probes: HashMap::new(),
+            succession: None,
+            metrics: Metrics::default(),
+            fetches: HashMap::new(),
             emitter,
         }
     }
@@ -346,6 +446,11 @@ where
         &self.routing
     }
 
+    /// Get the mutable routing store.
+    pub fn routing_mut(&mut self) -> &mut R {
+        &mut self.routing
+    }
+
     /// Get the storage instance.
     pub fn storage(&self) -> &S {
         &self.storage
@@ -459,6 +564,8 @@ where
             self.keep_alive(&now);
             self.disconnect_unresponsive_peers(&now);
             self.maintain_connections();
+            self.check_reachability();
+            self.record_penalties();
             self.outbox.wakeup(IDLE_INTERVAL);
             self.last_idle = now;
         }
@@ -488,9 +595,21 @@ where
             if let Err(err) = self.prune_routing_entries(&now) {
                 error!("Error pruning routing entries: {}", err);
             }
+            if let Err(err) = self.prune_gossip(&now) {
+                error!(target: "service", "Error pruning gossip entries: {err}");
+            }
             self.outbox.wakeup(PRUNE_INTERVAL);
             self.last_prune = now;
         }
+        if self.config.replicas.is_some() && now - self.last_replication >= REPLICATION_INTERVAL {
+            trace!(target: "service", "Running 'replication' task...");
+
+            if let Err(err) = self.maintain_replication() {
+                error!(target: "service", "Error maintaining replication: {err}");
+            }
+            self.outbox.wakeup(REPLICATION_INTERVAL);
+            self.last_replication = now;
+        }
 
         // Always check whether there are persistent peers that need reconnecting.
         self.maintain_persistent();
@@ -563,6 +682,27 @@ where
                     .expect("Service::command: error untracking node");
                 resp.send(untracked).ok();
             }
+            Command::TrackRemote(rid, nid, resp) => {
+                let tracked = self
+                    .tracking
+                    .set_remote_policy(&rid, &nid, tracking::Policy::Track)
+                    .expect("Service::command: error tracking remote");
+                resp.send(tracked).ok();
+            }
+            Command::UntrackRemote(rid, nid, resp) => {
+                let untracked = self
+                    .tracking
+                    .unset_remote_policy(&rid, &nid)
+                    .expect("Service::command: error untracking remote");
+                resp.send(untracked).ok();
+            }
+            Command::BlockRemote(rid, nid, resp) => {
+                let blocked = self
+                    .tracking
+                    .set_remote_policy(&rid, &nid, tracking::Policy::Block)
+                    .expect("Service::command: error blocking remote");
+                resp.send(blocked).ok();
+            }
             Command::AnnounceRefs(id) => {
                 if let Err(err) = self.announce_refs(id, [self.node_id()]) {
                     error!("Error announcing refs: {}", err);
@@ -584,12 +724,113 @@ where
                 resp.send(synced.added.len() + synced.removed.len() > 0)
                     .ok();
             }
+            Command::Addresses(resp) => match self.addresses.entries() {
+                Ok(entries) => {
+                    let mut nodes: BTreeMap<NodeId, Vec<KnownAddress>> = BTreeMap::new();
+                    for (nid, addr) in entries {
+                        nodes.entry(nid).or_default().push(addr);
+                    }
+                    let nodes = nodes
+                        .into_iter()
+                        .map(|(nid, addrs)| {
+                            let state = self.sessions.get(&nid).map(|s| s.state.clone());
+                            Seed::new(nid, addrs, state)
+                        })
+                        .collect();
+
+                    resp.send(nodes).ok();
+                }
+                Err(e) => {
+                    error!(target: "service", "Error reading address book: {e}");
+                }
+            },
+            Command::Routing(nid, resp) => match self.routing.get_resources(&nid) {
+                Ok(rids) => {
+                    let mut rids = rids.into_iter().collect::<Vec<_>>();
+                    rids.sort();
+
+                    resp.send(rids).ok();
+                }
+                Err(e) => {
+                    error!(target: "service", "Error reading routing table for {nid}: {e}");
+                }
+            },
+            Command::UpdateLimits(limits) => {
+                self.update_limits(limits);
+            }
+            Command::Reload(config) => {
+                self.reload(*config);
+            }
             Command::QueryState(query, sender) => {
                 sender.send(query(self)).ok();
             }
         }
     }
 
+    /// Apply a reloaded configuration. Changes to peer settings, persistent peers, limits,
+    /// relaying and the default tracking policy take effect immediately. Other changes
+    /// require a restart.
+    pub fn reload(&mut self, config: Config) {
+        let removed = self
+            .config
+            .connect
+            .iter()
+            .filter(|ca| config.peer(&ca.id).is_none())
+            .map(|ca| ca.id)
+            .collect::<Vec<_>>();
+        let added = config
+            .connect
+            .iter()
+            .filter(|ca| !self.config.is_persistent(&ca.id))
+            .map(|ca| (ca.id, ca.addr.clone()))
+            .collect::<Vec<_>>();
+        let resubscribe = config.policy != self.config.policy;
+
+        self.config.peers = config.peers;
+        self.config.connect = config.connect;
+        self.config.relay = config.relay;
+        self.config.policy = config.policy;
+        self.config.scope = config.scope;
+        self.tracking.set_defaults(config.policy, config.scope);
+        self.update_limits(config.limits);
+
+        for nid in removed {
+            let Some(session) = self.sessions.get_mut(&nid) else {
+                continue;
+            };
+            info!(target: "service", "Peer {nid} is no longer persistent; disconnecting..");
+
+            session.persistent = false;
+            if session.is_connected() {
+                self.outbox.disconnect(nid, DisconnectReason::Command);
+            }
+        }
+        for (nid, addr) in added {
+            if let Some(session) = self.sessions.get_mut(&nid) {
+                session.persistent = true;
+            } else {
+                info!(target: "service", "Connecting to new persistent peer {nid} ({addr})..");
+
+                self.connect(nid, addr);
+            }
+        }
+        if resubscribe {
+            // The filter we sent our peers depends on the default policy.
+            self.outbox.broadcast(
+                Message::subscribe(self.filter(), self.time(), Timestamp::MAX),
+                self.sessions.connected().map(|(_, s)| s),
+            );
+        }
+        info!(target: "service", "Configuration reloaded");
+    }
+
+    fn update_limits(&mut self, limits: Limits) {
+        for (_, session) in self.sessions.iter_mut() {
+            session.set_limits(limits.clone());
+        }
+        self.config.limits = limits;
+    }
+
     pub fn fetch(&mut self, rid: Id, from: &NodeId) {
         let Some(session) = self.sessions.get_mut(from) else {
             error!(target: "service", "Session {from} does not exist; cannot initiate fetch");
@@ -610,9 +851,21 @@ where
             session::FetchResult::Ready => {
                 debug!(target: "service", "Fetch initiated for {rid} with {seed}..");
 
-                match self.tracking.namespaces_for(&self.storage, &rid) {
-                    Ok(namespaces) => {
-                        self.outbox.fetch(session, rid, namespaces);
+                let namespaces = self
+                    .tracking
+                    .namespaces_for(&self.storage, &rid)
+                    .and_then(|ns| {
+                        let blocked = self
+                            .tracking
+                            .blocked_remotes(&rid)
+                            .map_err(|err| NamespacesError::FailedNodes { rid, err })?;
+                        Ok((ns, blocked))
+                    });
+                match namespaces {
+                    Ok((namespaces, blocked)) => {
+                        self.outbox.fetch(session, rid, namespaces, blocked);
+                        self.metrics.fetches_started += 1;
+                        self.fetches.insert((rid, seed), self.clock);
                     }
                     Err(err) => {
                         error!(target: "service", "Error getting namespaces for {rid}: {err}");
@@ -641,6 +894,14 @@ where
         remote: NodeId,
         result: Result<(Vec<RefUpdate>, HashSet<NodeId>), FetchError>,
     ) {
+        if let Some(started) = self.fetches.remove(&(rid, remote)) {
+            self.metrics.fetch_duration += (self.clock - started).as_millis() as u64;
+        }
+        if result.is_ok() {
+            self.metrics.fetches_succeeded += 1;
+        } else {
+            self.metrics.fetches_failed += 1;
+        }
         let result = match result {
             Ok((updated, namespaces)) => {
                 debug!(target: "service", "Fetched {rid} from {remote} successfully");
@@ -663,10 +924,16 @@ where
                 let reason = err.to_string();
                 error!(target: "service", "Fetch failed for {rid} from {remote}: {reason}");
 
-                // For now, we only disconnect the remote in case of timeout. In the future,
-                // there may be other reasons to disconnect.
+                // Only penalize the remote for failures it is responsible for.
+                let banned = err.is_remote() && self.penalize(&remote, Misbehavior::FetchFailed);
+
+                // For now, we only disconnect the remote in case of timeout, or if it was
+                // banned. In the future, there may be other reasons to disconnect.
                 if err.is_timeout() {
                     self.outbox.disconnect(remote, DisconnectReason::Fetch(err));
+                } else if banned {
+                    self.outbox
+                        .disconnect(remote, DisconnectReason::Session(session::Error::Banned));
                 }
                 FetchResult::Failed { reason }
             }
@@ -726,6 +993,7 @@ where
 
         if self.limiter.limit(host.clone(), &Link::Inbound, self.clock) {
             trace!(target: "service", "Rate limitting inbound connection from {host}..");
+            self.metrics.rate_limited += 1;
             return false;
         }
         true
@@ -759,6 +1027,8 @@ where
                 }
             }
         } else {
+            let banned = self.reputation(&remote).is_banned(self.clock);
+
             match self.sessions.entry(remote) {
                 Entry::Occupied(e) => {
                     warn!(
@@ -767,15 +1037,25 @@ where
                     );
                 }
                 Entry::Vacant(e) => {
+                    let persistent = self.config.is_persistent(&remote);
                     let peer = e.insert(Session::inbound(
                         remote,
                         addr,
-                        self.config.is_persistent(&remote),
+                        persistent,
                         self.rng.clone(),
                         self.clock,
                         self.config.limits.clone(),
                     ));
-                    self.outbox.write_all(peer, msgs);
+                    // Nb. we only find out who the peer is after the handshake, so banned
+                    // peers are disconnected here, rather than when the connection is accepted.
+                    if !persistent && banned {
+                        debug!(target: "service", "Disconnecting banned peer {remote}..");
+
+                        self.outbox
+                            .disconnect(remote, DisconnectReason::Session(session::Error::Banned));
+                    } else {
+                        self.outbox.write_all(peer, msgs);
+                    }
                 }
             }
         }
@@ -790,6 +1070,15 @@ where
             reason: reason.to_string(),
         });
 
+        if let DisconnectReason::Session(err) = reason {
+            if let Some(misbehavior) = err.misbehavior() {
+                self.penalize(&remote, misbehavior);
+            }
+        }
+        self.dial_backs.remove(&remote);
+        self.metrics.peers.remove(&remote);
+        self.fetches.retain(|(_, nid), _| *nid != remote);
+
         let Some(session) = self.sessions.get_mut(&remote) else {
             if cfg!(debug_assertions) {
                 panic!("Service::disconnected: unknown session {remote}");
@@ -832,6 +1121,59 @@ where
         }
     }
 
+    /// Register an address we are listening on for inbound connections.
+    pub fn listening(&mut self, addr: net::SocketAddr) {
+        self.listening.push(addr);
+    }
+
+    /// Register a record of our key succeeding a previous key. The record is announced to
+    /// peers that understand it, as they connect.
+    pub fn succeeded(&mut self, succession: Succession) {
+        debug_assert_eq!(succession.new, self.node_id());
+
+        self.succession = Some(succession);
+    }
+
+    /// Called when we are done probing the address a peer asked to be dialed back on.
+    pub fn probed(&mut self, remote: NodeId, addr: net::SocketAddr, reachable: bool) {
+        debug!(target: "service", "Probed {remote} at {addr} (reachable={reachable})");
+
+        if let Some(peer) = self.sessions.get(&remote).filter(|s| s.is_connected()) {
+            self.outbox.write(
+                peer,
+                Message::DialBackResult {
+                    observed: addr.into(),
+                    reachable,
+                },
+            );
+        }
+    }
+
+    /// Called when one of our listening ports was mapped on the gateway.
+    pub fn mapped(&mut self, addr: net::SocketAddr) {
+        let addr = Address::from(addr);
+
+        if self.reachability.mapped.as_ref() != Some(&addr) {
+            info!(target: "service", "Listening port mapped to external address {addr}");
+
+            self.reachability.mapped = Some(addr);
+            // Our last check was done without the mapping, so it's no longer relevant.
+            self.reachability.checked = None;
+            self.dial_back_results.clear();
+            self.check_reachability();
+        }
+    }
+
+    /// Bytes were received from a peer.
+    pub fn received_bytes(&mut self, remote: NodeId, bytes: usize) {
+        self.metrics.peers.entry(remote).or_default().received += bytes as u64;
+    }
+
+    /// Bytes were sent to a peer.
+    pub fn sent_bytes(&mut self, remote: NodeId, bytes: usize) {
+        self.metrics.peers.entry(remote).or_default().sent += bytes as u64;
+    }
+
     pub fn received_message(&mut self, remote: NodeId, message: Message) {
         if let Err(err) = self.handle_message(&remote, message) {
             // If there's an error, stop processing messages from this peer.
@@ -844,6 +1186,28 @@ where
         }
     }
 
+    /// Handle a gossip message of a type we don't know.
+    ///
+    /// This is tolerated from peers that advertized a newer protocol version or features
+    /// we don't know about, since they may be sending us messages from the future. Otherwise
+    /// the peer is misbehaving.
+    pub fn received_unknown(&mut self, remote: NodeId, type_id: u16) {
+        let Some(peer) = self.sessions.get(&remote) else {
+            warn!(target: "service", "Session not found for {remote}");
+            return;
+        };
+        if peer.capabilities.map_or(false, |c| c.is_newer()) {
+            debug!(target: "service", "Ignoring unknown message type {type_id} from {remote}");
+            return;
+        }
+        warn!(target: "service", "Received unknown message type {type_id} from {remote}");
+
+        self.outbox.disconnect(
+            remote,
+            DisconnectReason::Session(session::Error::Misbehavior),
+        );
+    }
+
     /// Handle an announcement message.
     ///
     /// Returns `true` if this announcement should be stored and relayed to connected peers,
@@ -855,7 +1219,7 @@ where
         announcement: &Announcement,
     ) -> Result<bool, session::Error> {
         if !announcement.verify() {
-            return Err(session::Error::Misbehavior);
+            return Err(session::Error::InvalidSignature);
         }
         let Announcement {
             node: announcer,
@@ -870,11 +1234,6 @@ where
         let now = self.clock;
         let timestamp = message.timestamp();
         let relay = self.config.relay;
-        let peer = self
-            .gossip
-            .nodes
-            .entry(*announcer)
-            .or_insert_with(Node::default);
 
         // Don't allow messages from too far in the future.
         if timestamp.saturating_sub(now.as_millis()) > MAX_TIME_DELTA.as_millis() as u64 {
@@ -885,12 +1244,22 @@ where
             AnnouncementMessage::Inventory(message) => {
                 // Discard inventory messages we've already seen, otherwise update
                 // out last seen time.
-                if !peer.inventory_announced(announcement.clone()) {
+                if !self.store_announcement(announcement) {
                     trace!(target: "service", "Ignoring stale inventory announcement from {announcer} (t={})", self.time());
                     return Ok(false);
                 }
 
-                match self.sync_routing(&message.inventory, *announcer, message.timestamp) {
+                // An announcement at the limit may have been truncated, in which case it can't
+                // be used to prune routing entries. Peers supporting inventory reconciliation
+                // sync the rest with us directly.
+                let truncated = message.inventory.len() >= message::INVENTORY_LIMIT;
+
+                match self.sync_routing_where(
+                    &message.inventory,
+                    *announcer,
+                    message.timestamp,
+                    |_| !truncated,
+                ) {
                     Ok(synced) => {
                         if synced.is_empty() {
                             trace!(target: "service", "No routes updated by inventory announcement from {announcer}");
@@ -902,40 +1271,7 @@ where
                         return Ok(false);
                     }
                 }
-
-                for id in message.inventory.as_slice() {
-                    // TODO: Move this out (good luck with the borrow checker).
-                    if let Some(sess) = self.sessions.get_mut(announcer) {
-                        // If we are connected to the announcer of this inventory, update the peer's
-                        // subscription filter to include all inventory items. This way, we'll
-                        // relay messages relating to the peer's inventory.
-                        if let Some(sub) = &mut sess.subscribe {
-                            sub.filter.insert(id);
-                        }
-
-                        // If we're tracking and connected to the announcer, and we don't have
-                        // the inventory, fetch it from the announcer.
-                        if self.tracking.is_repo_tracked(id).expect(
-                            "Service::handle_announcement: error accessing tracking configuration",
-                        ) {
-                            // Only if we do not have the repository locally do we fetch here.
-                            // If we do have it, only fetch after receiving a ref announcement.
-                            match self.storage.contains(id) {
-                                Ok(true) => {
-                                    // Do nothing.
-                                }
-                                Ok(false) => {
-                                    debug!(target: "service", "Missing tracked inventory {id}; initiating fetch..");
-
-                                    self.fetch(*id, announcer);
-                                }
-                                Err(e) => {
-                                    error!(target: "service", "Error checking local inventory: {e}");
-                                }
-                            }
-                        }
-                    }
-                }
+                self.process_inventory(announcer, message.inventory.as_slice());
 
                 return Ok(relay);
             }
@@ -944,7 +1280,7 @@ where
                 for theirs in message.refs.iter() {
                     if theirs.verify(&theirs.id).is_err() {
                         warn!(target: "service", "Peer {relayer} relayed refs announcement with invalid signature for {}", theirs.id);
-                        return Err(session::Error::Misbehavior);
+                        return Err(session::Error::InvalidSignature);
                     }
                 }
 
@@ -964,7 +1300,7 @@ where
                 }
                 // Discard announcement messages we've already seen, otherwise update
                 // our last seen time.
-                if !peer.refs_announced(message.rid, announcement.clone()) {
+                if !self.store_announcement(announcement) {
                     trace!(target: "service", "Ignoring stale refs announcement from {announcer} (time={timestamp})");
                     return Ok(false);
                 }
@@ -1026,18 +1362,29 @@ where
                     ..
                 },
             ) => {
+                // Peers that understand hello messages advertize it in their own node
+                // announcement, which is part of the handshake. Only then do we say hello,
+                // since older peers can't decode it.
+                if announcer == relayer && features.has(Features::HELLO) {
+                    if let Some(peer) = self.sessions.get_mut(relayer).filter(|s| !s.hello) {
+                        self.outbox.write(peer, Message::hello());
+                        peer.hello = true;
+                    }
+                }
                 // Discard node messages we've already seen, otherwise update
                 // our last seen time.
-                if !peer.node_announced(announcement.clone()) {
+                if !self.store_announcement(announcement) {
                     trace!(target: "service", "Ignoring stale node announcement from {announcer}");
                     return Ok(false);
                 }
 
-                // If this node isn't a seed, we're not interested in adding it
-                // to our address book, but other nodes may be, so we relay the message anyway.
-                if !features.has(Features::SEED) {
-                    return Ok(relay);
-                }
+                // If this node isn't a seed, we're not interested in dialing it, so we
+                // don't keep its addresses. We still keep track of its alias.
+                let addresses = if features.has(Features::SEED) {
+                    addresses.as_ref()
+                } else {
+                    &[]
+                };
 
                 match self.addresses.insert(
                     announcer,
@@ -1066,6 +1413,55 @@ where
                     }
                 }
             }
+            AnnouncementMessage::Succession(
+                succession @ SuccessionAnnouncement {
+                    successor,
+                    timestamp: rotated,
+                    ..
+                },
+            ) => {
+                // The successor must have co-signed the succession, otherwise any node could
+                // name another node as its successor.
+                if successor == announcer || !succession.verify(announcer) {
+                    return Err(session::Error::Misbehavior);
+                }
+                if !self.store_announcement(announcement) {
+                    trace!(target: "service", "Ignoring stale succession announcement from {announcer}");
+                    return Ok(false);
+                }
+                // A successor is a fresh key: if we already have policies for it, carrying
+                // over the old key's would clobber them.
+                match self.tracking.has_policies(successor) {
+                    Ok(false) => {}
+                    Ok(true) => {
+                        debug!(target: "service", "Ignoring succession of {announcer} to {successor}: successor already has tracking policies");
+                        return Ok(false);
+                    }
+                    Err(e) => {
+                        error!(target: "service", "Error processing succession announcement from {announcer}: {e}");
+                        return Ok(false);
+                    }
+                }
+                // Record the old key's pending penalties, so that they're carried over too.
+                self.record_penalty(announcer);
+
+                match self.addresses.rotate(announcer, successor, *rotated) {
+                    Ok(true) => {
+                        info!(target: "service", "Node {announcer} was succeeded by {successor}");
+
+                        if let Err(e) = self.tracking.rotate(announcer, successor) {
+                            error!(target: "service", "Error migrating tracking policies of {announcer} to {successor}: {e}");
+                        }
+                        return Ok(relay);
+                    }
+                    Ok(false) => {
+                        debug!(target: "service", "Ignoring succession of {announcer} to {successor}: key was already succeeded, or successor isn't new");
+                    }
+                    Err(err) => {
+                        error!(target: "service", "Error processing succession announcement from {announcer}: {err}");
+                    }
+                }
+            }
         }
         Ok(false)
     }
@@ -1085,7 +1481,12 @@ where
 
         // Second, check the scope.
         match scope {
-            tracking::Scope::All => Ok(true),
+            tracking::Scope::All => {
+                let blocked = self.tracking.blocked_remotes(&message.rid)?;
+
+                // Check if there is at least one ref from a remote that isn't blocked.
+                Ok(message.refs.iter().any(|refs| !blocked.contains(&refs.id)))
+            }
             tracking::Scope::Trusted => {
                 match self.tracking.namespaces_for(&self.storage, &message.rid) {
                     Ok(Namespaces::All) => Ok(true),
@@ -1118,11 +1519,22 @@ where
             warn!(target: "service", "Session not found for {remote}");
             return Ok(());
         };
-        if self
-            .limiter
-            .limit(peer.addr.clone().into(), &peer.link, self.clock)
+        // Trusted peers aren't rate-limited, and neither is a peer's first hello, since it
+        // negotiates the protocol and isn't repeated.
+        let exempt = peer.addr.is_trusted()
+            || matches!(message, Message::Hello { .. }) && peer.capabilities.is_none();
+
+        if !exempt
+            && self
+                .limiter
+                .limit(peer.addr.clone().into(), &peer.link, self.clock)
         {
             trace!(target: "service", "Rate limiting message from {remote} ({})", peer.addr);
+            self.metrics.rate_limited += 1;
+
+            if self.penalize(remote, Misbehavior::RateLimited) {
+                return Err(session::Error::Banned);
+            }
             return Ok(());
         }
         peer.last_active = self.clock;
@@ -1131,37 +1543,100 @@ where
         trace!(target: "service", "Received message {:?} from {}", &message, peer.id);
 
         match (&mut peer.state, message) {
+            (session::State::Connected { .. }, Message::Hello { version, features }) => {
+                if version < message::MIN_PROTOCOL_VERSION {
+                    return Err(session::Error::UnsupportedVersion(version));
+                }
+                debug!(target: "service", "Peer {remote} speaks protocol version {version} ({features})");
+
+                peer.capabilities = Some(session::Capabilities { version, features });
+
+                // The peer may have said hello before we saw its node announcement, eg. if
+                // the latter was rate-limited.
+                if !peer.hello {
+                    self.outbox.write(peer, Message::hello());
+                    peer.hello = true;
+                }
+
+                // If our key succeeded another, let the peer know, so that it can carry over
+                // what it knows about our old key.
+                if let Some(succession) = &self.succession {
+                    if features.has(Features::SUCCESSION) {
+                        self.outbox
+                            .write(peer, Announcement::from(succession.clone()).into());
+                    }
+                }
+                // Let the peer know what's in our inventory, so that it can request what
+                // it's missing.
+                if features.has(Features::INVENTORY_SYNC) {
+                    match self.storage.inventory() {
+                        Ok(inventory) => self
+                            .outbox
+                            .write(peer, Message::InventoryDigest(Digests::new(&inventory))),
+                        Err(e) => {
+                            error!(target: "service", "Error getting local inventory: {e}");
+                        }
+                    }
+                }
+                // Now that we know what the peer supports, we can ask it to dial us back.
+                if peer.link.is_outbound() && features.has(Features::DIAL_BACK) {
+                    self.check_reachability();
+                }
+            }
             // Process a peer announcement.
             (session::State::Connected { .. }, Message::Announcement(ann)) => {
                 let relayer = peer.id;
                 let relayer_addr = peer.addr.clone();
                 let announcer = ann.node;
 
+                self.metrics.gossip_received += 1;
+
                 // Returning true here means that the message should be relayed.
-                if self.handle_announcement(&relayer, &relayer_addr, &ann)? {
+                let relay = self
+                    .handle_announcement(&relayer, &relayer_addr, &ann)
+                    .map_err(|e| {
+                        self.metrics.gossip_dropped += 1;
+                        e
+                    })?;
+
+                if relay {
+                    let features = ann.message.features();
                     // Choose peers we should relay this message to.
                     // 1. Don't relay to the peer who sent us this message.
                     // 2. Don't relay to the peer who signed this announcement.
+                    // 3. Don't relay to peers who wouldn't understand this announcement.
                     let relay_to = self
                         .sessions
                         .connected()
-                        .filter(|(id, _)| *id != remote && *id != &announcer)
+                        .filter(|(id, p)| {
+                            *id != remote && *id != &announcer && p.supports(features)
+                        })
                         .map(|(_, p)| p);
 
                     self.outbox.relay(ann, relay_to);
+                    self.metrics.gossip_relayed += 1;
 
                     return Ok(());
                 }
             }
             (session::State::Connected { .. }, Message::Subscribe(subscribe)) => {
-                for ann in self
+                // Filter announcements by interest.
+                match self
                     .gossip
-                    // Filter announcements by interest.
                     .filtered(&subscribe.filter, subscribe.since, subscribe.until)
-                    // Don't send announcements authored by the remote, back to the remote.
-                    .filter(|ann| &ann.node != remote)
                 {
-                    self.outbox.write(peer, ann.into());
+                    Ok(anns) => {
+                        for ann in anns {
+                            // Don't send announcements authored by the remote, back to the remote,
+                            // or announcements the remote wouldn't understand.
+                            if &ann.node != remote && peer.supports(ann.message.features()) {
+                                self.outbox.write(peer, ann.into());
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        error!(target: "service", "Error querying gossip store: {e}");
+                    }
                 }
                 peer.subscribe = Some(subscribe);
             }
@@ -1184,6 +1659,135 @@ where
                     }
                 }
             }
+            (session::State::Connected { .. }, Message::DialBack { port }) => {
+                // Only peers that dialed us can be dialed back, since we know which address
+                // they connected from.
+                if !peer.link.is_inbound() {
+                    debug!(target: "service", "Ignoring dial-back request from outbound peer {remote}");
+                    return Ok(());
+                }
+                let HostName::Ip(ip) = peer.addr.host else {
+                    debug!(target: "service", "Ignoring dial-back request from {remote}: no IP address");
+                    return Ok(());
+                };
+                // Nb. Probes are limited by address rather than by peer, since node ids are
+                // cheap to come by.
+                let now = self.clock;
+                self.probes
+                    .retain(|_, probed| now - *probed < DIAL_BACK_COOLDOWN);
+
+                if self.probes.contains_key(&ip) {
+                    debug!(target: "service", "Ignoring dial-back request from {remote}: {ip} was probed recently");
+                    return Ok(());
+                }
+                self.probes.insert(ip, now);
+                self.outbox.probe(peer, net::SocketAddr::new(ip, port));
+            }
+            (
+                session::State::Connected { .. },
+                Message::DialBackResult {
+                    observed,
+                    reachable,
+                },
+            ) => {
+                if self.dial_backs.remove(remote).is_none() {
+                    debug!(target: "service", "Ignoring unsolicited dial-back result from {remote}");
+                    return Ok(());
+                }
+                let host = peer.addr.host.clone();
+                self.reachable(host, observed, reachable);
+            }
+            (session::State::Connected { .. }, Message::InventoryDigest(digests)) => {
+                let known = match self.routing.get_resources(remote) {
+                    Ok(known) => known,
+                    Err(e) => {
+                        error!(target: "service", "Error getting inventory of {remote}: {e}");
+                        return Ok(());
+                    }
+                };
+                let buckets = Digests::new(&known).diff(&digests);
+
+                if !buckets.is_empty() {
+                    debug!(target: "service", "Requesting {} inventory bucket(s) from {remote}", buckets.len());
+
+                    self.outbox.write(
+                        peer,
+                        Message::InventoryRequest {
+                            buckets: BoundedVec::truncate(buckets),
+                        },
+                    );
+                }
+            }
+            (session::State::Connected { .. }, Message::InventoryRequest { buckets }) => {
+                let inventory = match self.storage.inventory() {
+                    Ok(inventory) => inventory,
+                    Err(e) => {
+                        error!(target: "service", "Error getting local inventory: {e}");
+                        return Ok(());
+                    }
+                };
+                let buckets = buckets.iter().copied().collect::<BTreeSet<_>>();
+                let mut grouped: BTreeMap<u8, Vec<Id>> = BTreeMap::new();
+
+                for rid in inventory {
+                    let bucket = reconcile::bucket(&rid);
+
+                    if buckets.contains(&bucket) {
+                        grouped.entry(bucket).or_default().push(rid);
+                    }
+                }
+
+                for bucket in buckets {
+                    let items = grouped.remove(&bucket).unwrap_or_default();
+
+                    if items.len() > message::INVENTORY_LIMIT {
+                        warn!(
+                            target: "service",
+                            "Inventory bucket {bucket} exceeds the inventory limit ({}), only some of it will be sent",
+                            items.len()
+                        );
+                    }
+                    self.outbox.write(
+                        peer,
+                        Message::InventoryBucket {
+                            bucket,
+                            inventory: BoundedVec::truncate(items),
+                        },
+                    );
+                }
+            }
+            (session::State::Connected { .. }, Message::InventoryBucket { bucket, inventory }) => {
+                // A bucket at the limit may have been truncated by the sender, in which case
+                // it can't be used to prune routing entries.
+                let truncated = inventory.len() >= message::INVENTORY_LIMIT;
+                let in_bucket = |rid: &Id| reconcile::bucket(rid) == bucket;
+                let inventory = inventory
+                    .iter()
+                    .copied()
+                    .filter(in_bucket)
+                    .collect::<Vec<_>>();
+
+                match self.sync_routing_where(&inventory, *remote, self.time(), |rid| {
+                    !truncated && in_bucket(rid)
+                }) {
+                    Ok(synced) => {
+                        if !synced.is_empty() {
+                            debug!(
+                                target: "service",
+                                "Routing table updated with inventory bucket {bucket} of {remote} \
+                                (added={}, removed={})",
+                                synced.added.len(),
+                                synced.removed.len()
+                            );
+                        }
+                    }
+                    Err(e) => {
+                        error!(target: "service", "Error processing inventory bucket from {remote}: {e}");
+                        return Ok(());
+                    }
+                }
+                self.process_inventory(remote, &inventory);
+            }
             (session::State::Attempted { .. } | session::State::Initial, msg) => {
                 error!(target: "service", "Received {:?} from connecting peer {}", msg, peer.id);
             }
@@ -1194,6 +1798,127 @@ where
         Ok(())
     }
 
+    /// Ask peers to dial us back, if we don't know whether we're reachable, or our last check
+    /// is out of date.
+    fn check_reachability(&mut self) {
+        let now = self.clock;
+        // If we have a port mapping, that's the port peers should try.
+        let Some(port) = self
+            .reachability
+            .mapped
+            .as_ref()
+            .map(|a| a.port)
+            .or_else(|| self.listening.first().map(|a| a.port()))
+        else {
+            return;
+        };
+        if let Some(checked) = self.reachability.checked {
+            if now - LocalTime::from_millis(checked as u128) < REACHABILITY_INTERVAL {
+                return;
+            }
+        }
+        self.dial_backs
+            .retain(|_, asked| now - *asked < DIAL_BACK_TIMEOUT);
+
+        // Peers on the same host don't count as independent, so we only ask one peer per host.
+        let mut hosts = self
+            .dial_backs
+            .keys()
+            .filter_map(|nid| self.sessions.get(nid))
+            .map(|s| s.addr.host.clone())
+            .chain(self.dial_back_results.keys().cloned())
+            .collect::<HashSet<_>>();
+        let mut asked = Vec::new();
+
+        // Only peers we dialed see the address we're connecting from, and only peers
+        // advertizing the feature know how to answer.
+        for (nid, peer) in self
+            .sessions
+            .connected()
+            .filter(|(_, s)| s.link.is_outbound() && s.supports(Features::DIAL_BACK))
+        {
+            if hosts.len() >= DIAL_BACK_PEERS {
+                break;
+            }
+            if self.dial_backs.contains_key(nid) || !hosts.insert(peer.addr.host.clone()) {
+                continue;
+            }
+            self.outbox.write(peer, Message::DialBack { port });
+            asked.push(*nid);
+        }
+        for nid in asked {
+            self.dial_backs.insert(nid, now);
+        }
+    }
+
+    /// Record the result of a dial-back request. The check completes once enough peers agree
+    /// on an address we're reachable on, or enough peers have answered.
+    fn reachable(&mut self, host: HostName, observed: Address, reachable: bool) {
+        info!(target: "service", "Observed external address {observed} (reachable={reachable})");
+
+        if !self.reachability.observed.contains(&observed) {
+            if self.reachability.observed.len() >= MAX_OBSERVED_ADDRESSES {
+                self.reachability.observed.remove(0);
+            }
+            self.reachability.observed.push(observed.clone());
+        }
+        self.dial_back_results.insert(host, (observed, reachable));
+
+        let mut agreement = HashMap::<&Address, usize>::new();
+        for (observed, _) in self.dial_back_results.values().filter(|(_, r)| *r) {
+            *agreement.entry(observed).or_default() += 1;
+        }
+        let confirmed = agreement
+            .into_iter()
+            .find(|(_, n)| *n >= MIN_DIAL_BACK_AGREEMENT)
+            .map(|(observed, _)| observed.clone());
+
+        if confirmed.is_none() && self.dial_back_results.len() < DIAL_BACK_PEERS {
+            // Ask other peers, in case the ones we asked disconnected.
+            self.check_reachability();
+            return;
+        }
+        self.dial_back_results.clear();
+        self.reachability.reachable = Some(confirmed.is_some());
+        self.reachability.checked = Some(self.time());
+
+        // Addresses configured by the user always take precedence.
+        if !self.config.external_addresses.is_empty() {
+            return;
+        }
+        let addresses = match confirmed {
+            Some(observed) if observed.is_routable() => vec![observed],
+            _ => vec![],
+        };
+        if self.node.addresses.as_ref() != addresses.as_slice() {
+            self.announce_addresses(addresses);
+        }
+    }
+
+    /// Update our node announcement with the given addresses, and announce it to our peers.
+    fn announce_addresses(&mut self, addresses: Vec<Address>) {
+        let mut ann = gossip::node(&self.config, self.time());
+        ann.addresses = addresses
+            .try_into()
+            .expect("Service::announce_addresses: addresses are within the limit");
+        // We're a seed as long as peers can reach us.
+        ann.features = if ann.addresses.is_empty() {
+            ann.features.without(Features::SEED)
+        } else {
+            ann.features.with(Features::SEED)
+        };
+
+        let Some(ann) = ann.solve(0) else {
+            error!(target: "service", "Unable to solve proof-of-work for node announcement");
+            return;
+        };
+        self.node = ann;
+        self.outbox.broadcast(
+            Message::node(self.node.clone(), &self.signer),
+            self.sessions.connected().map(|(_, s)| s),
+        );
+    }
+
     /// Set of initial messages to send to a peer.
     fn initial(&self, _link: Link) -> Vec<Message> {
         let filter = self.filter();
@@ -1201,6 +1926,8 @@ where
         // TODO: Only subscribe to outbound connections, otherwise we will consume too
         // much bandwidth.
 
+        // Nb. We don't say hello yet, since the peer may not understand it. We do once it
+        // advertizes support for it in its node announcement.
         gossip::handshake(
             self.node.clone(),
             self.clock.as_millis(),
@@ -1226,6 +1953,18 @@ where
         inventory: &[Id],
         from: NodeId,
         timestamp: Timestamp,
+    ) -> Result<SyncedRouting, Error> {
+        self.sync_routing_where(inventory, from, timestamp, |_| true)
+    }
+
+    /// Like [`Service::sync_routing`], but only entries matching the predicate are pruned.
+    /// This is used when the given inventory is only part of the peer's inventory.
+    fn sync_routing_where(
+        &mut self,
+        inventory: &[Id],
+        from: NodeId,
+        timestamp: Timestamp,
+        prunable: impl Fn(&Id) -> bool,
     ) -> Result<SyncedRouting, Error> {
         let mut synced = SyncedRouting::default();
         let included: HashSet<&Id> = HashSet::from_iter(inventory);
@@ -1251,7 +1990,7 @@ where
             }
         }
         for rid in self.routing.get_resources(&from)?.into_iter() {
-            if !included.contains(&rid) {
+            if !included.contains(&rid) && prunable(&rid) {
                 if self.routing.remove(&rid, &from)? {
                     synced.removed.push(rid);
                     self.emitter.emit(Event::SeedDropped { rid, nid: from });
@@ -1261,14 +2000,66 @@ where
         Ok(synced)
     }
 
+    /// Process inventory received from a peer, once our routing table is updated with it.
+    fn process_inventory(&mut self, from: &NodeId, inventory: &[Id]) {
+        for id in inventory {
+            // TODO: Move this out (good luck with the borrow checker).
+            if let Some(sess) = self.sessions.get_mut(from) {
+                // If we are connected to the announcer of this inventory, update the peer's
+                // subscription filter to include all inventory items. This way, we'll
+                // relay messages relating to the peer's inventory.
+                if let Some(sub) = &mut sess.subscribe {
+                    sub.filter.insert(id);
+                }
+
+                // If we're tracking and connected to the announcer, and we don't have
+                // the inventory, fetch it from the announcer.
+                if self
+                    .tracking
+                    .is_repo_tracked(id)
+                    .expect("Service::process_inventory: error accessing tracking configuration")
+                {
+                    // Only if we do not have the repository locally do we fetch here.
+                    // If we do have it, only fetch after receiving a ref announcement.
+                    match self.storage.contains(id) {
+                        Ok(true) => {
+                            // Do nothing.
+                        }
+                        Ok(false) => {
+                            debug!(target: "service", "Missing tracked inventory {id}; initiating fetch..");
+
+                            self.fetch(*id, from);
+                        }
+                        Err(e) => {
+                            error!(target: "service", "Error checking local inventory: {e}");
+                        }
+                    }
+                }
+            }
+        }
+    }
+
     /// Announce local refs for given id.
     fn announce_refs(
         &mut self,
         rid: Id,
         remotes: impl IntoIterator<Item = NodeId>,
     ) -> Result<(), storage::Error> {
-        let repo = self.storage.repository(rid)?;
+        let ann = self.refs_announcement(rid, remotes)?;
         let peers = self.sessions.connected().map(|(_, p)| p);
+
+        self.outbox.broadcast(ann, peers);
+
+        Ok(())
+    }
+
+    /// Create a signed refs announcement for the given remotes of a repository.
+    fn refs_announcement(
+        &self,
+        rid: Id,
+        remotes: impl IntoIterator<Item = NodeId>,
+    ) -> Result<Announcement, storage::Error> {
+        let repo = self.storage.repository(rid)?;
         let timestamp = self.time();
         let mut refs = BoundedVec::<_, REF_REMOTE_LIMIT>::new();
 
@@ -1291,11 +2082,8 @@ where
             refs,
             timestamp,
         });
-        let ann = msg.signed(&self.signer);
-
-        self.outbox.broadcast(ann, peers);
 
-        Ok(())
+        Ok(msg.signed(&self.signer))
     }
 
     fn sync_and_announce(&mut self) {
@@ -1400,6 +2188,117 @@ where
         self.clock.as_millis()
     }
 
+    /// Get a peer's current reputation.
+    fn reputation(&self, nid: &NodeId) -> Reputation {
+        let mut reputation = self
+            .addresses
+            .reputation(nid, self.time())
+            .unwrap_or_else(|e| {
+                error!(target: "service", "Error looking up reputation of {nid}: {e}");
+                Reputation::default()
+            });
+        if let Some(penalties) = self.penalties.get(nid) {
+            reputation.score = reputation.score.saturating_add(penalties.pending);
+        }
+        reputation
+    }
+
+    /// Get the current reputation of all peers that misbehaved.
+    fn reputations(&self) -> HashMap<NodeId, Reputation> {
+        let mut reputations = match self.addresses.reputations(self.time()) {
+            Ok(reputations) => reputations.collect(),
+            Err(e) => {
+                error!(target: "service", "Error looking up peer reputations: {e}");
+                HashMap::new()
+            }
+        };
+        for (nid, penalties) in &self.penalties {
+            let reputation = reputations
+                .entry(*nid)
+                .or_insert_with(|| penalties.recorded.clone());
+            reputation.score = reputation.score.saturating_add(penalties.pending);
+        }
+        reputations
+    }
+
+    /// Penalize a peer for misbehaving.
+    ///
+    /// Penalties are kept in memory and recorded periodically, unless they get the peer
+    /// banned, in which case they are recorded right away.
+    ///
+    /// Returns `true` if the peer should be disconnected because it is now banned.
+    /// Persistent peers are never banned.
+    fn penalize(&mut self, nid: &NodeId, misbehavior: Misbehavior) -> bool {
+        let time = self.time();
+        let penalties = match self.penalties.entry(*nid) {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => {
+                let recorded = self.addresses.reputation(nid, time).unwrap_or_else(|err| {
+                    error!(target: "service", "Error looking up reputation of {nid}: {err}");
+                    Reputation::default()
+                });
+                e.insert(Penalties {
+                    recorded,
+                    pending: 0,
+                })
+            }
+        };
+        let score = penalties.recorded.score.saturating_add(penalties.pending);
+        let penalty = match misbehavior {
+            // A busy peer may go over the rate limit for a while, which shouldn't get it
+            // banned on its own.
+            Misbehavior::RateLimited => misbehavior
+                .penalty()
+                .min(RATE_LIMITED_MAX_SCORE.saturating_sub(score)),
+            _ => misbehavior.penalty(),
+        };
+        penalties.pending = penalties.pending.saturating_add(penalty);
+
+        let score = score.saturating_add(penalty);
+        debug!(
+            target: "service",
+            "Penalized {nid} for misbehavior ({misbehavior}), score={score}"
+        );
+
+        let banned = if penalties.recorded.is_banned(self.clock) {
+            true
+        } else if score >= BAN_THRESHOLD {
+            self.record_penalty(nid)
+                .map_or(false, |r| r.is_banned(self.clock))
+        } else {
+            false
+        };
+        if banned && !self.config.is_persistent(nid) {
+            warn!(target: "service", "Peer {nid} is banned for repeated misbehavior");
+            return true;
+        }
+        false
+    }
+
+    /// Record a peer's pending penalties in the address book.
+    ///
+    /// Returns the peer's updated reputation.
+    fn record_penalty(&mut self, nid: &NodeId) -> Option<Reputation> {
+        let penalties = self.penalties.remove(nid)?;
+
+        match self.addresses.penalize(nid, penalties.pending, self.time()) {
+            Ok(reputation) => Some(reputation),
+            Err(e) => {
+                error!(target: "service", "Error penalizing {nid} for misbehavior: {e}");
+                None
+            }
+        }
+    }
+
+    /// Record all pending penalties in the address book.
+    fn record_penalties(&mut self) {
+        let nids = self.penalties.keys().copied().collect::<Vec<_>>();
+
+        for nid in nids {
+            self.record_penalty(&nid);
+        }
+    }
+
     ////////////////////////////////////////////////////////////////////////////
     // Periodic tasks
     ////////////////////////////////////////////////////////////////////////////
@@ -1407,13 +2306,118 @@ where
     /// Announce our inventory to all connected peers.
     fn announce_inventory(&mut self, inventory: Vec<Id>) -> Result<(), storage::Error> {
         let time = self.time();
+        let digests = Message::InventoryDigest(Digests::new(&inventory));
         let inv = Message::inventory(gossip::inventory(time, inventory), &self.signer);
         for (_, sess) in self.sessions.connected() {
             self.outbox.write(sess, inv.clone());
+
+            // Peers reconciling inventories with us also get our digests, so that they
+            // can request what didn't fit in the announcement.
+            if sess.supports(Features::INVENTORY_SYNC) {
+                self.outbox.write(sess, digests.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that the repositories we track are replicated on enough other seeds. Those that
+    /// aren't are offered to the connected seeds that don't have them, so that they fetch them.
+    ///
+    /// Each repository is only offered once to a given seed, and offers of the same repository
+    /// are spaced out exponentially.
+    fn maintain_replication(&mut self) -> Result<(), Error> {
+        let Some(target) = self.config.replicas else {
+            return Ok(());
+        };
+        let now = self.clock;
+        let local = self.node_id();
+        let mut replication = Vec::new();
+        let mut offers = HashMap::new();
+
+        for rid in self.storage.inventory()? {
+            if !self.tracking.is_repo_tracked(&rid)? {
+                continue;
+            }
+            // Nb. The routing table includes our own inventory, as well as the inventories of
+            // nodes that aren't seeds.
+            let nodes = self.routing.get(&rid)?;
+            let replicas = nodes
+                .iter()
+                .filter(|nid| **nid != local && self.is_seed(nid))
+                .count();
+            if replicas >= target {
+                continue;
+            }
+            replication.push(Replication {
+                rid,
+                replicas,
+                target,
+            });
+
+            let mut offer = self.offers.remove(&rid).unwrap_or_default();
+            let seeds = self
+                .sessions
+                .connected()
+                .map(|(nid, _)| *nid)
+                .filter(|nid| !nodes.contains(nid) && !offer.seeds.contains(nid))
+                .filter(|nid| self.is_seed(nid))
+                .collect::<Vec<_>>();
+
+            if now < offer.retry_at || seeds.is_empty() {
+                debug!(
+                    target: "service",
+                    "Repository {rid} is under-replicated ({replicas}/{target} seeds)"
+                );
+                offers.insert(rid, offer);
+                continue;
+            }
+            warn!(
+                target: "service",
+                "Repository {rid} is under-replicated ({replicas}/{target} seeds), offering it to {} seed(s)..",
+                seeds.len()
+            );
+            // Nb. Seeds usually don't have their own fork, so we announce the refs of all
+            // the remotes we have.
+            let remotes = self
+                .storage
+                .repository(rid)?
+                .remotes()
+                .map_err(storage::Error::from)?
+                .keys()
+                .copied()
+                .collect::<Vec<_>>();
+            let ann = self.refs_announcement(rid, remotes)?;
+
+            for nid in &seeds {
+                if let Some(sess) = self.sessions.get(nid) {
+                    self.outbox.write(sess, ann.clone().into());
+                }
+            }
+            let delay = (REPLICATION_INTERVAL * 2u64.saturating_pow(offer.attempts))
+                .clamp(REPLICATION_INTERVAL, MAX_REPLICATION_DELTA);
+
+            offer.seeds.extend(seeds);
+            offer.attempts += 1;
+            offer.retry_at = now + delay;
+            offers.insert(rid, offer);
         }
+        self.offers = offers;
+        self.replication = replication;
+
         Ok(())
     }
 
+    /// Check whether a node is a known seed.
+    fn is_seed(&self, nid: &NodeId) -> bool {
+        match self.addresses.get(nid) {
+            Ok(node) => node.map_or(false, |n| n.features.has(Features::SEED)),
+            Err(e) => {
+                error!(target: "service", "Error looking up node {nid}: {e}");
+                false
+            }
+        }
+    }
+
     fn prune_routing_entries(&mut self, now: &LocalTime) -> Result<(), routing::Error> {
         let count = self.routing.len()?;
         if count <= self.config.limits.routing_max_size {
@@ -1428,6 +2432,33 @@ where
         Ok(())
     }
 
+    fn prune_gossip(&mut self, now: &LocalTime) -> Result<(), gossip::Error> {
+        let pruned = self
+            .gossip
+            .prune((*now - self.config.limits.gossip_max_age).as_millis())?;
+
+        if pruned > 0 {
+            debug!(target: "service", "Pruned {pruned} announcement(s) from gossip store");
+        }
+        Ok(())
+    }
+
+    /// Store an announcement in the gossip store. Returns `false` if we've already seen
+    /// this announcement or a more recent one from the same node.
+    fn store_announcement(&mut self, ann: &Announcement) -> bool {
+        let stored = match self.gossip.announced(ann) {
+            Ok(stored) => stored,
+            Err(e) => {
+                error!(target: "service", "Error storing announcement from {}: {e}", ann.node);
+                false
+            }
+        };
+        if !stored {
+            self.metrics.gossip_dropped += 1;
+        }
+        stored
+    }
+
     fn disconnect_unresponsive_peers(&mut self, now: &LocalTime) {
         let stale = self
             .sessions
@@ -1456,6 +2487,9 @@ where
 
     /// Get a list of peers available to connect to.
     fn available_peers(&mut self) -> HashMap<NodeId, Vec<KnownAddress>> {
+        let now = self.clock;
+        let reputations = self.reputations();
+
         match self.addresses.entries() {
             Ok(entries) => {
                 // Nb. we don't want to connect to any peers that already have a session with us,
@@ -1463,6 +2497,7 @@ where
                 entries
                     .filter(|(nid, _)| !self.sessions.contains_key(nid))
                     .filter(|(nid, _)| nid != &self.node_id())
+                    .filter(|(nid, _)| !reputations.get(nid).map_or(false, |r| r.is_banned(now)))
                     .fold(HashMap::new(), |mut acc, (nid, addr)| {
                         acc.entry(nid).or_insert_with(Vec::new).push(addr);
                         acc
@@ -1532,7 +2567,8 @@ where
             return;
         }
 
-        for (id, ka) in self
+        let reputations = self.reputations();
+        let mut available = self
             .available_peers()
             .into_iter()
             .flat_map(|(nid, kas)| kas.into_iter().map(move |ka| (nid, ka)))
@@ -1544,8 +2580,12 @@ where
                 // If we've never tried this address, it's worth a try.
                 (None, None) => true,
             })
-            .take(wanted)
-        {
+            .collect::<Vec<_>>();
+
+        // Prefer peers that have misbehaved the least.
+        available.sort_by_key(|(nid, _)| reputations.get(nid).map_or(0, |r| r.score));
+
+        for (id, ka) in available.into_iter().take(wanted) {
             self.connect(id, ka.addr.clone());
         }
     }
@@ -1592,6 +2632,12 @@ pub trait ServiceState {
     fn clock_mut(&mut self) -> &mut LocalTime;
     /// Get service configuration.
     fn config(&self) -> &Config;
+    /// Get the result of the reachability self-check.
+    fn reachability(&self) -> &Reachability;
+    /// Get the repositories replicated on fewer seeds than the configured replication factor.
+    fn replication(&self) -> &[Replication];
+    /// Get service metrics.
+    fn metrics(&self) -> Metrics;
 }
 
 impl<R, A, S, G> ServiceState for Service<R, A, S, G>
@@ -1623,6 +2669,30 @@ where
     fn config(&self) -> &Config {
         &self.config
     }
+
+    fn reachability(&self) -> &Reachability {
+        &self.reachability
+    }
+
+    fn replication(&self) -> &[Replication] {
+        &self.replication
+    }
+
+    fn metrics(&self) -> Metrics {
+        let mut metrics = self.metrics.clone();
+
+        for (_, session) in self.sessions.connected() {
+            match session.link {
+                Link::Inbound => metrics.inbound_sessions += 1,
+                Link::Outbound => metrics.outbound_sessions += 1,
+            }
+        }
+        metrics.routing_entries = self.routing.len().unwrap_or_else(|e| {
+            error!(target: "service", "Error getting routing table size: {e}");
+            0
+        });
+        metrics
+    }
 }
 
 /// Disconnect reason.
@@ -1695,75 +2765,6 @@ pub enum LookupError {
     Identity(#[from] IdentityError),
 }
 
-/// Keeps track of the most recent announcements of a node.
-#[derive(Default, Debug)]
-pub struct Node {
-    /// Last ref announcements (per project).
-    pub last_refs: HashMap<Id, Announcement>,
-    /// Last inventory announcement.
-    pub last_inventory: Option<Announcement>,
-    /// Last node announcement.
-    pub last_node: Option<Announcement>,
-}
-
-impl Node {
-    /// Process a refs announcement for the given node.
-    /// Returns `true` if the timestamp was updated.
-    pub fn refs_announced(&mut self, id: Id, ann: Announcement) -> bool {
-        match self.last_refs.entry(id) {
-            Entry::Vacant(e) => {
-                e.insert(ann);
-                return true;
-            }
-            Entry::Occupied(mut e) => {
-                let last = e.get_mut();
-
-                if ann.timestamp() > last.timestamp() {
-                    *last = ann;
-                    return true;
-                }
-            }
-        }
-        false
-    }
-
-    /// Process an inventory announcement for the given node.
-    /// Returns `true` if the timestamp was updated.
-    pub fn inventory_announced(&mut self, ann: Announcement) -> bool {
-        match &mut self.last_inventory {
-            Some(last) => {
-                if ann.timestamp() > last.timestamp() {
-                    *last = ann;
-                    return true;
-                }
-            }
-            None => {
-                self.last_inventory = Some(ann);
-                return true;
-            }
-        }
-        false
-    }
-
-    /// Process a node announcement for the given node.
-    /// Returns `true` if the timestamp was updated.
-    pub fn node_announced(&mut self, ann: Announcement) -> bool {
-        match &mut self.last_node {
-            Some(last) => {
-                if ann.timestamp() > last.timestamp() {
-                    *last = ann;
-                    return true;
-                }
-            }
-            None => {
-                self.last_node = Some(ann);
-                return true;
-            }
-        }
-        false
-    }
-}
-
 #[derive(Debug, Clone)]
 /// Holds currently (or recently) connected peers.
 pub struct Sessions(AddressBook<NodeId, Session>);
@@ -1817,100 +2818,3 @@ impl DerefMut for Sessions {
         &mut self.0
     }
 }
-
-pub mod gossip {
-    use super::*;
-    use crate::service::filter::Filter;
-
-    #[derive(Default, Debug)]
-    pub struct Gossip {
-        // FIXME: This should be loaded from the address store.
-        /// Keeps track of node announcements.
-        pub nodes: BTreeMap<NodeId, Node>,
-    }
-
-    impl Gossip {
-        pub fn filtered<'a>(
-            &'a self,
-            filter: &'a Filter,
-            start: Timestamp,
-            end: Timestamp,
-        ) -> impl Iterator<Item = Announcement> + '_ {
-            self.nodes
-                .values()
-                .flat_map(|n| {
-                    [&n.last_node, &n.last_inventory]
-                        .into_iter()
-                        .flatten()
-                        .chain(n.last_refs.values())
-                        .cloned()
-                        .collect::<Vec<_>>()
-                })
-                .filter(move |ann| ann.timestamp() >= start && ann.timestamp() < end)
-                .filter(move |ann| ann.matches(filter))
-        }
-    }
-
-    pub fn handshake<G: Signer, S: ReadStorage>(
-        node: NodeAnnouncement,
-        now: Timestamp,
-        storage: &S,
-        signer: &G,
-        filter: Filter,
-    ) -> Vec<Message> {
-        let inventory = match storage.inventory() {
-            Ok(i) => i,
-            Err(e) => {
-                error!("Error getting local inventory for handshake: {}", e);
-                // Other than crashing the node completely, there's nothing we can do
-                // here besides returning an empty inventory and logging an error.
-                vec![]
-            }
-        };
-
-        vec![
-            Message::node(node, signer),
-            Message::inventory(gossip::inventory(now, inventory), signer),
-            Message::subscribe(
-                filter,
-                now - SUBSCRIBE_BACKLOG_DELTA.as_millis() as u64,
-                Timestamp::MAX,
-            ),
-        ]
-    }
-
-    pub fn node(config: &Config, timestamp: Timestamp) -> NodeAnnouncement {
-        let features = config.features();
-        let alias = config.alias.clone();
-        let addresses: BoundedVec<_, ADDRESS_LIMIT> = config
-            .external_addresses
-            .clone()
-            .try_into()
-            .expect("external addresses are within the limit");
-
-        NodeAnnouncement {
-            features,
-            timestamp,
-            alias,
-            addresses,
-            nonce: 0,
-        }
-    }
-
-    pub fn inventory(timestamp: Timestamp, inventory: Vec<Id>) -> InventoryAnnouncement {
-        type Inventory = BoundedVec<Id, INVENTORY_LIMIT>;
-
-        if inventory.len() > Inventory::max() {
-            error!(
-                target: "service",
-                "inventory announcement limit ({}) exceeded, other nodes will see only some of your projects",
-                inventory.len()
-            );
-        }
-
-        InventoryAnnouncement {
-            inventory: BoundedVec::truncate(inventory),
-            timestamp,
-        }
-    }
-}
@@ -0,0 +1,20 @@
+--
+-- Gossip store SQL schema.
+--
+create table if not exists "announcements" (
+  -- Node ID of the announcer.
+  "node"         text      not null,
+  -- Repository ID, for refs announcements. Empty otherwise.
+  "repo"         text      not null,
+  -- Announcement type, as encoded on the wire.
+  "type"         integer   not null,
+  -- Signed announcement, as encoded on the wire.
+  "message"      blob      not null,
+  -- Announcement timestamp.
+  "timestamp"    integer   not null,
+
+  -- Only the latest announcement of each kind is kept.
+  primary key ("node", "repo", "type")
+) strict;
+
+create index if not exists "announcements_timestamp" on "announcements" ("timestamp");
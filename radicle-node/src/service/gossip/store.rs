@@ -0,0 +1,235 @@
+use std::path::Path;
+use std::{fmt, time};
+
+use sqlite as sql;
+use thiserror::Error;
+
+use crate::prelude::Timestamp;
+use crate::service::filter::Filter;
+use crate::service::message::{Announcement, AnnouncementMessage, Message};
+use crate::wire;
+
+/// How long to wait for the database lock to be released before failing a write.
+const DB_WRITE_TIMEOUT: time::Duration = time::Duration::from_secs(6);
+
+/// An error occuring in the gossip store.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// An Internal error.
+    #[error("internal error: {0}")]
+    Internal(#[from] sql::Error),
+    /// A stored announcement could not be decoded.
+    #[error("invalid stored announcement: {0}")]
+    Decode(#[from] wire::Error),
+    /// A stored message is not an announcement.
+    #[error("invalid stored announcement: not an announcement")]
+    NotAnnouncement,
+    /// Internal unit overflow.
+    #[error("the unit overflowed")]
+    UnitOverflow,
+}
+
+/// Persistent store of the latest announcements received from each node.
+/// Used to replay announcements to peers that subscribe to them.
+pub struct Store {
+    db: sql::Connection,
+}
+
+impl fmt::Debug for Store {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Store(..)")
+    }
+}
+
+impl Store {
+    const SCHEMA: &str = include_str!("schema.sql");
+
+    /// Open a gossip store at the given path. Creates a new empty store
+    /// if an existing store isn't found.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let mut db = sql::Connection::open(path)?;
+        db.set_busy_timeout(DB_WRITE_TIMEOUT.as_millis() as usize)?;
+        db.execute(Self::SCHEMA)?;
+
+        Ok(Self { db })
+    }
+
+    /// Create a new in-memory gossip store.
+    pub fn memory() -> Result<Self, Error> {
+        let db = sql::Connection::open(":memory:")?;
+        db.execute(Self::SCHEMA)?;
+
+        Ok(Self { db })
+    }
+
+    /// Store an announcement, unless we already have a more recent announcement of the same
+    /// kind from the same node. Returns `true` if the announcement was stored.
+    pub fn announced(&mut self, ann: &Announcement) -> Result<bool, Error> {
+        let timestamp: i64 = ann
+            .timestamp()
+            .try_into()
+            .map_err(|_| Error::UnitOverflow)?;
+        let repo = match &ann.message {
+            AnnouncementMessage::Refs(refs) => refs.rid.urn(),
+            AnnouncementMessage::Node(_)
+            | AnnouncementMessage::Inventory(_)
+            | AnnouncementMessage::Succession(_) => String::new(),
+        };
+        let message = Message::from(ann.clone());
+        let mut stmt = self.db.prepare(
+            "INSERT INTO announcements (node, repo, type, message, timestamp)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT DO UPDATE
+             SET message = ?4, timestamp = ?5
+             WHERE timestamp < ?5",
+        )?;
+
+        stmt.bind((1, &ann.node))?;
+        stmt.bind((2, repo.as_str()))?;
+        stmt.bind((3, message.type_id() as i64))?;
+        stmt.bind((4, wire::serialize(&message).as_slice()))?;
+        stmt.bind((5, timestamp))?;
+        stmt.next()?;
+
+        Ok(self.db.change_count() > 0)
+    }
+
+    /// Get the stored announcements matching the filter, with a timestamp in the given range.
+    pub fn filtered(
+        &self,
+        filter: &Filter,
+        start: Timestamp,
+        end: Timestamp,
+    ) -> Result<Vec<Announcement>, Error> {
+        // Nb. `Timestamp::MAX` is commonly used as the end of the range, which doesn't fit.
+        let start = i64::try_from(start).unwrap_or(i64::MAX);
+        let end = i64::try_from(end).unwrap_or(i64::MAX);
+        let mut stmt = self.db.prepare(
+            "SELECT message FROM announcements
+             WHERE timestamp >= ?1 AND timestamp < ?2
+             ORDER BY timestamp",
+        )?;
+        stmt.bind((1, start))?;
+        stmt.bind((2, end))?;
+
+        let mut anns = Vec::new();
+        for row in stmt.into_iter() {
+            let row = row?;
+            let message = wire::deserialize::<Message>(row.read::<&[u8], _>("message"))?;
+            let Message::Announcement(ann) = message else {
+                return Err(Error::NotAnnouncement);
+            };
+            if ann.matches(filter) {
+                anns.push(ann);
+            }
+        }
+        Ok(anns)
+    }
+
+    /// Prune announcements older than the given timestamp.
+    /// Returns the number of announcements removed.
+    pub fn prune(&mut self, oldest: Timestamp) -> Result<usize, Error> {
+        let oldest: i64 = oldest.try_into().map_err(|_| Error::UnitOverflow)?;
+        let mut stmt = self
+            .db
+            .prepare("DELETE FROM announcements WHERE timestamp < ?")?;
+
+        stmt.bind((1, oldest))?;
+        stmt.next()?;
+
+        Ok(self.db.change_count())
+    }
+
+    /// Get the number of stored announcements.
+    pub fn len(&self) -> Result<usize, Error> {
+        let stmt = self.db.prepare("SELECT COUNT(1) FROM announcements")?;
+        let count: i64 = stmt
+            .into_iter()
+            .next()
+            .expect("COUNT will always return a single row")?
+            .read(0);
+        let count: usize = count.try_into().map_err(|_| Error::UnitOverflow)?;
+
+        Ok(count)
+    }
+
+    /// Check whether the store is empty.
+    pub fn is_empty(&self) -> Result<bool, Error> {
+        Ok(self.len()? == 0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crypto::test::signer::MockSigner;
+    use crate::prelude::BoundedVec;
+    use crate::service::message::{InventoryAnnouncement, RefsAnnouncement};
+    use crate::test::arbitrary;
+
+    fn inventory(signer: &MockSigner, timestamp: Timestamp) -> Announcement {
+        AnnouncementMessage::from(InventoryAnnouncement {
+            inventory: BoundedVec::new(),
+            timestamp,
+        })
+        .signed(signer)
+    }
+
+    #[test]
+    fn test_announced() {
+        let mut store = Store::memory().unwrap();
+        let alice = MockSigner::default();
+        let bob = MockSigner::default();
+
+        assert!(store.announced(&inventory(&alice, 2)).unwrap());
+        assert!(store.announced(&inventory(&bob, 2)).unwrap());
+        assert!(
+            !store.announced(&inventory(&alice, 2)).unwrap(),
+            "Same timestamp"
+        );
+        assert!(
+            !store.announced(&inventory(&alice, 1)).unwrap(),
+            "Older timestamp"
+        );
+        assert!(store.announced(&inventory(&alice, 3)).unwrap());
+        assert_eq!(store.len().unwrap(), 2);
+
+        // Refs announcements are stored per repository.
+        for rid in arbitrary::set::<crate::identity::Id>(3..=3) {
+            let ann = AnnouncementMessage::from(RefsAnnouncement {
+                rid,
+                refs: BoundedVec::new(),
+                timestamp: 1,
+            })
+            .signed(&alice);
+            assert!(store.announced(&ann).unwrap());
+        }
+        assert_eq!(store.len().unwrap(), 5);
+    }
+
+    #[test]
+    fn test_filtered_and_prune() {
+        let mut store = Store::memory().unwrap();
+        let signers = [
+            MockSigner::default(),
+            MockSigner::default(),
+            MockSigner::default(),
+        ];
+        for (i, signer) in signers.iter().enumerate() {
+            store
+                .announced(&inventory(signer, i as Timestamp * 10))
+                .unwrap();
+        }
+        let filter = Filter::default();
+
+        assert_eq!(store.filtered(&filter, 0, Timestamp::MAX).unwrap().len(), 3);
+        assert_eq!(store.filtered(&filter, 10, 20).unwrap().len(), 1);
+        assert_eq!(
+            store.filtered(&filter, 10, Timestamp::MAX).unwrap(),
+            vec![inventory(&signers[1], 10), inventory(&signers[2], 20)]
+        );
+        assert_eq!(store.prune(10).unwrap(), 1);
+        assert_eq!(store.filtered(&filter, 0, 10).unwrap(), vec![]);
+        assert_eq!(store.len().unwrap(), 2);
+    }
+}
@@ -1,4 +1,5 @@
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
+use std::net;
 
 use log::*;
 
@@ -26,9 +27,13 @@ pub enum Io {
         remote: NodeId,
         /// Namespaces being fetched.
         namespaces: Namespaces,
+        /// Remotes blocked for this repository, whose refs are not fetched.
+        blocked: HashSet<NodeId>,
     },
     /// Ask for a wakeup in a specified amount of time.
     Wakeup(LocalDuration),
+    /// Check whether a peer that asked to be dialed back can be reached at the given address.
+    Probe(NodeId, net::SocketAddr),
 }
 
 /// Interface to the network.
@@ -77,10 +82,21 @@ impl Outbox {
         self.io.push_back(Io::Wakeup(after));
     }
 
-    pub fn fetch(&mut self, remote: &mut Session, rid: Id, namespaces: Namespaces) {
+    pub fn probe(&mut self, remote: &Session, addr: net::SocketAddr) {
+        self.io.push_back(Io::Probe(remote.id, addr));
+    }
+
+    pub fn fetch(
+        &mut self,
+        remote: &mut Session,
+        rid: Id,
+        namespaces: Namespaces,
+        blocked: HashSet<NodeId>,
+    ) {
         self.io.push_back(Io::Fetch {
             rid,
             namespaces,
+            blocked,
             remote: remote.id,
         });
     }
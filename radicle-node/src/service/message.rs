@@ -4,15 +4,27 @@ use crate::crypto;
 use crate::crypto::Unverified;
 use crate::identity::Id;
 use crate::node;
+use crate::node::succession::Succession;
 use crate::node::{Address, Alias};
 use crate::prelude::BoundedVec;
 use crate::service::filter::Filter;
+use crate::service::reconcile::{Digests, BUCKETS};
 use crate::service::{Link, NodeId, Timestamp};
 use crate::storage;
 use crate::storage::refs::SignedRefs;
 use crate::storage::{ReadRepository, ReadStorage};
 use crate::wire;
 
+/// Gossip protocol version spoken by this node.
+pub const PROTOCOL_VERSION: u8 = 1;
+/// Oldest gossip protocol version we can talk to.
+pub const MIN_PROTOCOL_VERSION: u8 = 1;
+/// Protocol features supported by this node.
+pub const PROTOCOL_FEATURES: node::Features = node::Features::DIAL_BACK
+    .with(node::Features::SUCCESSION)
+    .with(node::Features::INVENTORY_SYNC)
+    .with(node::Features::HELLO);
+
 /// Maximum number of addresses which can be announced to other nodes.
 pub const ADDRESS_LIMIT: usize = 16;
 /// Maximum number of repository remotes that can be included in a [`RefsAnnouncement`] message.
@@ -217,6 +229,30 @@ pub struct InventoryAnnouncement {
     pub timestamp: Timestamp,
 }
 
+/// Node announcing that its key was succeeded by a new key. Signed by the old key, and
+/// co-signed by the new key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SuccessionAnnouncement {
+    /// The new node key.
+    pub successor: NodeId,
+    /// Time of the key rotation.
+    pub timestamp: Timestamp,
+    /// Signature of the new key, see [`Succession::cosigned`].
+    pub cosignature: crypto::Signature,
+}
+
+impl SuccessionAnnouncement {
+    /// Verify the co-signature of the successor, given the announcing node.
+    pub fn verify(&self, announcer: &NodeId) -> bool {
+        self.successor
+            .verify(
+                Succession::cosigned(announcer, &self.successor, self.timestamp),
+                &self.cosignature,
+            )
+            .is_ok()
+    }
+}
+
 /// Announcement messages are messages that are relayed between peers.
 #[derive(Clone, PartialEq, Eq)]
 pub enum AnnouncementMessage {
@@ -226,6 +262,8 @@ pub enum AnnouncementMessage {
     Node(NodeAnnouncement),
     /// Refs announcement.
     Refs(RefsAnnouncement),
+    /// Key succession announcement.
+    Succession(SuccessionAnnouncement),
 }
 
 impl AnnouncementMessage {
@@ -246,6 +284,15 @@ impl AnnouncementMessage {
             Self::Inventory(InventoryAnnouncement { timestamp, .. }) => *timestamp,
             Self::Refs(RefsAnnouncement { timestamp, .. }) => *timestamp,
             Self::Node(NodeAnnouncement { timestamp, .. }) => *timestamp,
+            Self::Succession(SuccessionAnnouncement { timestamp, .. }) => *timestamp,
+        }
+    }
+
+    /// Protocol features a peer must support to understand this message.
+    pub fn features(&self) -> node::Features {
+        match self {
+            Self::Succession(_) => node::Features::SUCCESSION,
+            Self::Inventory(_) | Self::Node(_) | Self::Refs(_) => node::Features::NONE,
         }
     }
 }
@@ -268,6 +315,27 @@ impl From<RefsAnnouncement> for AnnouncementMessage {
     }
 }
 
+impl From<SuccessionAnnouncement> for AnnouncementMessage {
+    fn from(ann: SuccessionAnnouncement) -> Self {
+        Self::Succession(ann)
+    }
+}
+
+impl From<Succession> for Announcement {
+    fn from(succession: Succession) -> Self {
+        Self {
+            node: succession.old,
+            message: SuccessionAnnouncement {
+                successor: succession.new,
+                timestamp: succession.timestamp,
+                cosignature: succession.cosignature,
+            }
+            .into(),
+            signature: succession.signature,
+        }
+    }
+}
+
 impl fmt::Debug for AnnouncementMessage {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -292,6 +360,13 @@ impl fmt::Debug for AnnouncementMessage {
                     message.rid, message.timestamp, message.refs
                 )
             }
+            Self::Succession(message) => {
+                write!(
+                    f,
+                    "Succession({}, {})",
+                    message.successor, message.timestamp
+                )
+            }
         }
     }
 }
@@ -335,6 +410,7 @@ impl Announcement {
         match &self.message {
             AnnouncementMessage::Inventory(_) => true,
             AnnouncementMessage::Node(_) => true,
+            AnnouncementMessage::Succession(_) => true,
             AnnouncementMessage::Refs(RefsAnnouncement { rid, .. }) => filter.contains(rid),
         }
     }
@@ -354,6 +430,17 @@ impl Announcement {
 /// These are the messages peers send to each other.
 #[derive(Clone, PartialEq, Eq)]
 pub enum Message {
+    /// Advertize our protocol version and supported features.
+    ///
+    /// This is the first message sent on a new session, by both sides. Until it is received,
+    /// the peer is assumed to speak the base protocol only.
+    Hello {
+        /// Protocol version spoken by the sender.
+        version: u8,
+        /// Features supported by the sender.
+        features: node::Features,
+    },
+
     /// Subscribe to gossip messages matching the filter and time range.
     Subscribe(Subscribe),
 
@@ -372,6 +459,43 @@ pub enum Message {
         /// The pong payload.
         zeroes: ZeroBytes,
     },
+
+    /// Ask a connected peer to dial us back on the given port, at the address it sees us
+    /// connecting from.
+    ///
+    /// Used to check whether we can be reached by other nodes, eg. when behind a NAT.
+    DialBack {
+        /// The port we're listening on.
+        port: u16,
+    },
+
+    /// Response to `DialBack` message.
+    DialBackResult {
+        /// Our address, as observed by the peer, with the requested port.
+        observed: Address,
+        /// Whether the peer was able to connect to the observed address.
+        reachable: bool,
+    },
+
+    /// Digests of our inventory, sent to peers supporting inventory reconciliation.
+    ///
+    /// The peer compares them with what it knows of our inventory, and requests the buckets
+    /// that differ with [`Message::InventoryRequest`].
+    InventoryDigest(Digests),
+
+    /// Ask a peer for the contents of some of its inventory buckets.
+    InventoryRequest {
+        /// The requested buckets.
+        buckets: BoundedVec<u8, BUCKETS>,
+    },
+
+    /// Response to `InventoryRequest` message, sent once per requested bucket.
+    InventoryBucket {
+        /// The bucket.
+        bucket: u8,
+        /// The repositories in the bucket.
+        inventory: BoundedVec<Id, INVENTORY_LIMIT>,
+    },
 }
 
 impl PartialOrd for Message {
@@ -411,6 +535,14 @@ impl Message {
         AnnouncementMessage::from(message).signed(signer).into()
     }
 
+    /// Our own protocol version and features.
+    pub fn hello() -> Self {
+        Self::Hello {
+            version: PROTOCOL_VERSION,
+            features: PROTOCOL_FEATURES,
+        }
+    }
+
     pub fn subscribe(filter: Filter, since: Timestamp, until: Timestamp) -> Self {
         Self::Subscribe(Subscribe {
             filter,
@@ -429,6 +561,9 @@ impl Message {
             ("Sending", "to")
         };
         let msg = match self {
+            Self::Hello { version, features } => {
+                format!("{verb} hello (version={version}, {features}) {prep} {remote}")
+            }
             Self::Announcement(Announcement { node, message, .. }) => match message {
                 AnnouncementMessage::Node(NodeAnnouncement { addresses, .. }) => format!(
                     "{verb} node announcement of {node} with {} address(es) {prep} {remote}",
@@ -444,12 +579,28 @@ impl Message {
                         inventory.len()
                     )
                 }
+                AnnouncementMessage::Succession(SuccessionAnnouncement { successor, .. }) => {
+                    format!("{verb} succession announcement of {node} to {successor} {prep} {remote}")
+                }
             },
             Self::Ping { .. } => format!("{verb} ping {prep} {remote}"),
             Self::Pong { .. } => format!("{verb} pong {prep} {remote}"),
+            Self::DialBack { port } => format!("{verb} dial-back request on port {port} {prep} {remote}"),
+            Self::DialBackResult { observed, reachable } => format!(
+                "{verb} dial-back result for {observed} (reachable={reachable}) {prep} {remote}"
+            ),
             Self::Subscribe(Subscribe { .. }) => {
                 format!("{verb} subscription filter {prep} {remote}")
             }
+            Self::InventoryDigest(_) => format!("{verb} inventory digest {prep} {remote}"),
+            Self::InventoryRequest { buckets } => format!(
+                "{verb} request for {} inventory bucket(s) {prep} {remote}",
+                buckets.len()
+            ),
+            Self::InventoryBucket { bucket, inventory } => format!(
+                "{verb} inventory bucket {bucket} with {} item(s) {prep} {remote}",
+                inventory.len()
+            ),
         };
         log::log!(target: "service", level, "{msg}");
     }
@@ -494,6 +645,7 @@ impl From<Announcement> for Message {
 impl fmt::Debug for Message {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            Self::Hello { version, features } => write!(f, "Hello({version}, {features})"),
             Self::Subscribe(Subscribe { since, until, .. }) => {
                 write!(f, "Subscribe({since}..{until})")
             }
@@ -502,6 +654,16 @@ impl fmt::Debug for Message {
             }
             Self::Ping(Ping { ponglen, zeroes }) => write!(f, "Ping({ponglen}, {zeroes:?})"),
             Self::Pong { zeroes } => write!(f, "Pong({zeroes:?})"),
+            Self::DialBack { port } => write!(f, "DialBack({port})"),
+            Self::DialBackResult {
+                observed,
+                reachable,
+            } => write!(f, "DialBackResult({observed}, {reachable})"),
+            Self::InventoryDigest(digests) => write!(f, "InventoryDigest({digests:?})"),
+            Self::InventoryRequest { buckets } => write!(f, "InventoryRequest({buckets:?})"),
+            Self::InventoryBucket { bucket, inventory } => {
+                write!(f, "InventoryBucket({bucket}, {inventory:?})")
+            }
         }
     }
 }
@@ -624,4 +786,23 @@ mod tests {
         assert_eq!(ann.clone().solve(8).unwrap().work(), 9);
         assert_eq!(ann.solve(14).unwrap().work(), 14);
     }
+
+    #[test]
+    fn test_succession_announcement_verify() {
+        let signer = MockSigner::default();
+        let successor = MockSigner::default();
+        let succession = Succession::new(42491841, &signer, &successor);
+        let ann = Announcement::from(succession.clone());
+
+        assert!(ann.verify());
+        assert_eq!(ann.node, succession.old);
+        assert_eq!(
+            wire::serialize(&ann.message),
+            Succession::payload(
+                &succession.new,
+                succession.timestamp,
+                &succession.cosignature
+            )
+        );
+    }
 }
@@ -0,0 +1,113 @@
+//! Inventory reconciliation.
+//!
+//! Inventory announcements are bounded by [`INVENTORY_LIMIT`], so nodes hosting more
+//! repositories than that can't announce all of them. Instead, peers supporting
+//! [`Features::INVENTORY_SYNC`] reconcile their view of each other's inventory directly.
+//!
+//! Repositories are partitioned into [`BUCKETS`] buckets by the first byte of their id, and each
+//! bucket is summarized by a digest. A node sends its [`Digests`] to a peer, which compares them
+//! with the digests of the inventory it has on record for that node, and requests the buckets
+//! that differ. Only the contents of those buckets are then sent over.
+//!
+//! [`INVENTORY_LIMIT`]: crate::service::message::INVENTORY_LIMIT
+//! [`Features::INVENTORY_SYNC`]: radicle::node::Features::INVENTORY_SYNC
+use std::fmt;
+
+use crate::prelude::Id;
+
+/// Number of buckets an inventory is partitioned into.
+pub const BUCKETS: usize = 256;
+
+/// The bucket a repository falls into.
+pub fn bucket(rid: &Id) -> u8 {
+    rid.as_bytes()[0]
+}
+
+/// The digest of a single repository. Since repository ids are hashes, any of their bytes
+/// can be used. We skip the first byte, which is the same for all repositories of a bucket.
+fn digest(rid: &Id) -> u64 {
+    let bytes = rid.as_bytes();
+    let mut digest = [0; 8];
+    digest.copy_from_slice(&bytes[bytes.len() - 8..]);
+
+    u64::from_be_bytes(digest)
+}
+
+/// Per-bucket digests of an inventory.
+///
+/// A bucket digest is the XOR of the digests of the repositories in the bucket, which makes
+/// it independent of the order repositories are listed in.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Digests(Box<[u64; BUCKETS]>);
+
+impl Digests {
+    /// Compute the digests of an inventory.
+    pub fn new<'a>(inventory: impl IntoIterator<Item = &'a Id>) -> Self {
+        let mut digests = Box::new([0; BUCKETS]);
+
+        for rid in inventory {
+            digests[bucket(rid) as usize] ^= digest(rid);
+        }
+        Self(digests)
+    }
+
+    /// The buckets whose digests differ from the other digests.
+    pub fn diff(&self, other: &Self) -> Vec<u8> {
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .enumerate()
+            .filter(|(_, (a, b))| a != b)
+            .map(|(i, _)| i as u8)
+            .collect()
+    }
+
+    /// Iterate over the bucket digests, in bucket order.
+    pub fn iter(&self) -> impl Iterator<Item = &u64> {
+        self.0.iter()
+    }
+}
+
+impl From<[u64; BUCKETS]> for Digests {
+    fn from(digests: [u64; BUCKETS]) -> Self {
+        Self(Box::new(digests))
+    }
+}
+
+impl fmt::Debug for Digests {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let buckets = self.0.iter().filter(|d| **d != 0).count();
+
+        write!(f, "Digests({buckets} non-empty bucket(s))")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::arbitrary;
+
+    #[test]
+    fn test_digests_diff() {
+        let inventory = arbitrary::vec::<Id>(64);
+        let digests = Digests::new(&inventory);
+
+        // Digests don't depend on order.
+        let mut reversed = inventory.clone();
+        reversed.reverse();
+        assert_eq!(Digests::new(&reversed), digests);
+        assert!(digests.diff(&Digests::new(&reversed)).is_empty());
+
+        // Only the buckets of added or removed repositories differ.
+        let extra = arbitrary::gen::<Id>(1);
+        let mut added = inventory.clone();
+        added.push(extra);
+        assert_eq!(digests.diff(&Digests::new(&added)), vec![bucket(&extra)]);
+
+        let removed = &inventory[1..];
+        assert_eq!(
+            digests.diff(&Digests::new(removed)),
+            vec![bucket(&inventory[0])]
+        );
+    }
+}
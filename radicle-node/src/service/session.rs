@@ -1,7 +1,9 @@
 use std::collections::{HashSet, VecDeque};
 use std::fmt;
 
+use crate::node::address::Misbehavior;
 use crate::node::config::Limits;
+use crate::node::Features;
 use crate::service::message;
 use crate::service::message::Message;
 use crate::service::{Address, Id, LocalTime, NodeId, Outbox, Rng};
@@ -32,9 +34,18 @@ pub enum Error {
     /// gossip messages. Or vice-versa.
     #[error("protocol mismatch")]
     ProtocolMismatch,
+    /// The remote peer speaks a protocol version we no longer support.
+    #[error("unsupported protocol version {0}")]
+    UnsupportedVersion(u8),
+    /// The remote peer sent or relayed a message with an invalid signature.
+    #[error("invalid signature")]
+    InvalidSignature,
     /// The remote peer did something that violates the protocol rules.
     #[error("peer misbehaved")]
     Misbehavior,
+    /// The remote peer misbehaved too often, and is temporarily banned.
+    #[error("peer is banned")]
+    Banned,
     /// The remote peer timed out.
     #[error("peer timed out")]
     Timeout,
@@ -46,10 +57,43 @@ impl Error {
         match self {
             Self::InvalidTimestamp(_) => false,
             Self::ProtocolMismatch => true,
+            Self::UnsupportedVersion(_) => false,
+            Self::InvalidSignature => false,
             Self::Misbehavior => false,
+            Self::Banned => false,
             Self::Timeout => true,
         }
     }
+
+    /// The misbehavior this error is the result of, if any.
+    pub fn misbehavior(&self) -> Option<Misbehavior> {
+        match self {
+            Self::InvalidTimestamp(_) => Some(Misbehavior::InvalidAnnouncement),
+            Self::InvalidSignature => Some(Misbehavior::InvalidSignature),
+            Self::Misbehavior => Some(Misbehavior::InvalidAnnouncement),
+            Self::ProtocolMismatch | Self::UnsupportedVersion(_) | Self::Banned | Self::Timeout => {
+                None
+            }
+        }
+    }
+}
+
+/// Protocol capabilities advertized by a peer during the handshake.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Protocol version spoken by the peer.
+    pub version: u8,
+    /// Features supported by the peer.
+    pub features: Features,
+}
+
+impl Capabilities {
+    /// Check whether the peer may send messages we don't understand, because it speaks
+    /// a newer protocol version, or supports features we don't know about.
+    pub fn is_newer(&self) -> bool {
+        self.version > message::PROTOCOL_VERSION
+            || self.features.difference(message::PROTOCOL_FEATURES) != Features::NONE
+    }
 }
 
 /// A peer session. Each connected peer will have one session.
@@ -72,6 +116,10 @@ pub struct Session {
     pub last_active: LocalTime,
     /// Fetch queue.
     pub queue: VecDeque<Id>,
+    /// Peer capabilities, known once the peer has said hello.
+    pub capabilities: Option<Capabilities>,
+    /// Whether we said hello to the peer.
+    pub hello: bool,
 
     /// Connection attempts. For persistent peers, Tracks
     /// how many times we've attempted to connect. We reset this to zero
@@ -113,6 +161,8 @@ impl Session {
             persistent,
             last_active: LocalTime::default(),
             queue: VecDeque::default(),
+            capabilities: None,
+            hello: false,
             attempts: 1,
             rng,
             limits,
@@ -140,6 +190,8 @@ impl Session {
             persistent,
             last_active: LocalTime::default(),
             queue: VecDeque::default(),
+            capabilities: None,
+            hello: false,
             attempts: 0,
             rng,
             limits,
@@ -162,6 +214,14 @@ impl Session {
         matches!(self.state, State::Initial)
     }
 
+    /// Check whether the peer advertized support for the given features.
+    /// Peers that haven't said hello yet are assumed to support no features.
+    pub fn supports(&self, features: Features) -> bool {
+        self.capabilities
+            .map_or(Features::NONE, |c| c.features)
+            .has(features)
+    }
+
     pub fn attempts(&self) -> usize {
         self.attempts
     }
@@ -216,12 +276,16 @@ impl Session {
             ping: PingState::default(),
             fetching: HashSet::default(),
         };
+        self.capabilities = None;
+        self.hello = false;
     }
 
     /// Move the session state to "disconnected". Returns any pending RID
     /// that was requested.
     pub fn to_disconnected(&mut self, since: LocalTime, retry_at: LocalTime) {
         self.state = State::Disconnected { since, retry_at };
+        self.capabilities = None;
+        self.hello = false;
     }
 
     /// Return to initial state from disconnected state. This state transition
@@ -234,6 +298,11 @@ impl Session {
         self.state = State::Initial;
     }
 
+    /// Update the protocol limits of this session.
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
     pub fn fetching(&self) -> HashSet<Id> {
         if let State::Connected { fetching, .. } = &self.state {
             fetching.clone()
@@ -15,7 +15,7 @@ use crate::service::NodeId;
 pub use crate::node::tracking::store;
 pub use crate::node::tracking::store::Config as Store;
 pub use crate::node::tracking::store::Error;
-pub use crate::node::tracking::{Alias, Node, Policy, Repo, Scope};
+pub use crate::node::tracking::{Alias, Node, Policy, Remote, Repo, Scope};
 
 #[derive(Debug, Error)]
 pub enum NamespacesError {
@@ -75,6 +75,12 @@ impl<T> Config<T> {
         }
     }
 
+    /// Change the default policy and scope.
+    pub fn set_defaults(&mut self, policy: Policy, scope: Scope) {
+        self.policy = policy;
+        self.scope = scope;
+    }
+
     /// Check if a repository is tracked.
     pub fn is_repo_tracked(&self, id: &Id) -> Result<bool, Error> {
         self.repo_policy(id)
@@ -107,6 +113,14 @@ impl<T> Config<T> {
         }))
     }
 
+    /// Get the set of remotes that are explicitly blocked for the given repository.
+    pub fn blocked_remotes(&self, rid: &Id) -> Result<HashSet<NodeId>, Error> {
+        Ok(self
+            .remote_policies(rid)?
+            .filter_map(|r| (r.policy == Policy::Block).then_some(r.nid))
+            .collect())
+    }
+
     pub fn namespaces_for<S>(&self, storage: &S, rid: &Id) -> Result<Namespaces, NamespacesError>
     where
         S: ReadStorage,
@@ -116,6 +130,15 @@ impl<T> Config<T> {
         let entry = self
             .repo_policy(rid)
             .map_err(|err| FailedPolicy { rid: *rid, err })?;
+        let remotes = self
+            .remote_policies(rid)
+            .map_err(|err| FailedNodes { rid: *rid, err })?
+            .collect::<Vec<_>>();
+        let blocked = remotes
+            .iter()
+            .filter_map(|r| (r.policy == Policy::Block).then_some(r.nid))
+            .collect::<HashSet<_>>();
+
         match entry.policy {
             Policy::Block => {
                 error!(target: "service", "Attempted to fetch untracked repo {rid}");
@@ -131,6 +154,16 @@ impl<T> Config<T> {
                         .filter_map(|node| (node.policy == Policy::Track).then_some(node.id))
                         .collect();
 
+                    // Remotes tracked only for this repository.
+                    trusted.extend(
+                        remotes
+                            .iter()
+                            .filter_map(|r| (r.policy == Policy::Track).then_some(r.nid)),
+                    );
+                    // Remotes blocked for this repository. Nb. delegates are added after
+                    // this, since they can't be blocked: we need them to verify the repository.
+                    trusted.retain(|nid| !blocked.contains(nid));
+
                     if let Ok(repo) = storage.repository(*rid) {
                         let delegates = repo
                             .delegates()
@@ -2,13 +2,14 @@ use bloomy::BloomFilter;
 use qcheck::Arbitrary;
 
 use crate::crypto;
-use crate::node::Alias;
+use crate::node::{Address, Alias};
 use crate::prelude::{BoundedVec, Id, NodeId, Timestamp};
 use crate::service::filter::{Filter, FILTER_SIZE_L, FILTER_SIZE_M, FILTER_SIZE_S};
 use crate::service::message::{
     Announcement, InventoryAnnouncement, Message, NodeAnnouncement, Ping, RefsAnnouncement,
-    Subscribe, ZeroBytes,
+    Subscribe, SuccessionAnnouncement, ZeroBytes,
 };
+use crate::service::reconcile::{Digests, BUCKETS};
 use crate::wire::MessageType;
 
 pub use radicle::test::arbitrary::*;
@@ -37,6 +38,13 @@ impl Arbitrary for Message {
                 MessageType::Subscribe,
                 MessageType::Ping,
                 MessageType::Pong,
+                MessageType::DialBack,
+                MessageType::DialBackResult,
+                MessageType::Hello,
+                MessageType::SuccessionAnnouncement,
+                MessageType::InventoryDigest,
+                MessageType::InventoryRequest,
+                MessageType::InventoryBucket,
             ])
             .unwrap();
 
@@ -94,7 +102,47 @@ impl Arbitrary for Message {
             MessageType::Pong => Self::Pong {
                 zeroes: ZeroBytes::new(u16::arbitrary(g).min(Ping::MAX_PONG_ZEROES)),
             },
+            MessageType::DialBack => Self::DialBack {
+                port: u16::arbitrary(g),
+            },
+            MessageType::DialBackResult => Self::DialBackResult {
+                observed: Address::arbitrary(g),
+                reachable: bool::arbitrary(g),
+            },
+            MessageType::SuccessionAnnouncement => Announcement {
+                node: NodeId::arbitrary(g),
+                message: SuccessionAnnouncement {
+                    successor: NodeId::arbitrary(g),
+                    timestamp: Timestamp::arbitrary(g),
+                    cosignature: crypto::Signature::from(<[u8; 64]>::arbitrary(g)),
+                }
+                .into(),
+                signature: crypto::Signature::from(<[u8; 64]>::arbitrary(g)),
+            }
+            .into(),
+            MessageType::Hello => Self::Hello {
+                version: u8::arbitrary(g),
+                features: u64::arbitrary(g).into(),
+            },
+            MessageType::InventoryDigest => Self::InventoryDigest(Digests::arbitrary(g)),
+            MessageType::InventoryRequest => Self::InventoryRequest {
+                buckets: BoundedVec::arbitrary(g),
+            },
+            MessageType::InventoryBucket => Self::InventoryBucket {
+                bucket: u8::arbitrary(g),
+                inventory: BoundedVec::arbitrary(g),
+            },
+        }
+    }
+}
+
+impl Arbitrary for Digests {
+    fn arbitrary(g: &mut qcheck::Gen) -> Self {
+        let mut digests = [0; BUCKETS];
+        for digest in digests.iter_mut() {
+            *digest = u64::arbitrary(g);
         }
+        Self::from(digests)
     }
 }
 
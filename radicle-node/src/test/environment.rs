@@ -353,7 +353,6 @@ impl<G: cyphernet::Ecdh<Pk = NodeId> + Signer + Clone> Node<G> {
     /// Spawn a node in its own thread.
     pub fn spawn(self) -> NodeHandle<G> {
         let listen = vec![([0, 0, 0, 0], 0).into()];
-        let proxy = net::SocketAddr::new(net::Ipv4Addr::LOCALHOST.into(), 9050);
         let daemon: net::SocketAddr = {
             // Find free port for git-daemon to bind to.
             // This is a somewhat racy solution, though it works much better than assigning a random
@@ -365,8 +364,8 @@ impl<G: cyphernet::Ecdh<Pk = NodeId> + Signer + Clone> Node<G> {
         let rt = Runtime::init(
             self.home.clone(),
             self.config,
+            self.home.config(),
             listen,
-            proxy,
             daemon,
             signals,
             self.signer.clone(),
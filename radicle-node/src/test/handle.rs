@@ -1,10 +1,14 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 use std::{io, time};
 
 use crate::identity::Id;
-use crate::node::{Alias, ConnectOptions, ConnectResult, Event, FetchResult, Seeds};
+use crate::node::config::Limits;
+use crate::node::{
+    Alias, Config, ConnectOptions, ConnectResult, Event, FetchResult, Metrics, Reachability,
+    Replication, Seed, Seeds,
+};
 use crate::runtime::HandleError;
 use crate::service::tracking;
 use crate::service::NodeId;
@@ -14,6 +18,7 @@ pub struct Handle {
     pub updates: Arc<Mutex<Vec<Id>>>,
     pub tracking_repos: Arc<Mutex<HashSet<Id>>>,
     pub tracking_nodes: Arc<Mutex<HashSet<NodeId>>>,
+    pub tracking_remotes: Arc<Mutex<HashMap<(Id, NodeId), tracking::Policy>>>,
 }
 
 impl radicle::node::Handle for Handle {
@@ -71,6 +76,33 @@ impl radicle::node::Handle for Handle {
         Ok(self.tracking_nodes.lock().unwrap().remove(&id))
     }
 
+    fn track_remote(&mut self, rid: Id, nid: NodeId) -> Result<bool, Self::Error> {
+        Ok(self
+            .tracking_remotes
+            .lock()
+            .unwrap()
+            .insert((rid, nid), tracking::Policy::Track)
+            != Some(tracking::Policy::Track))
+    }
+
+    fn untrack_remote(&mut self, rid: Id, nid: NodeId) -> Result<bool, Self::Error> {
+        Ok(self
+            .tracking_remotes
+            .lock()
+            .unwrap()
+            .remove(&(rid, nid))
+            .is_some())
+    }
+
+    fn block_remote(&mut self, rid: Id, nid: NodeId) -> Result<bool, Self::Error> {
+        Ok(self
+            .tracking_remotes
+            .lock()
+            .unwrap()
+            .insert((rid, nid), tracking::Policy::Block)
+            != Some(tracking::Policy::Block))
+    }
+
     fn announce_refs(&mut self, id: Id) -> Result<(), Self::Error> {
         self.updates.lock().unwrap().push(id);
 
@@ -89,6 +121,38 @@ impl radicle::node::Handle for Handle {
         unimplemented!();
     }
 
+    fn reachability(&self) -> Result<Reachability, Self::Error> {
+        Ok(Reachability::default())
+    }
+
+    fn metrics(&self) -> Result<Metrics, Self::Error> {
+        Ok(Metrics::default())
+    }
+
+    fn replication(&self) -> Result<Vec<Replication>, Self::Error> {
+        Ok(vec![])
+    }
+
+    fn disconnect(&mut self, _node: NodeId) -> Result<(), Self::Error> {
+        unimplemented!();
+    }
+
+    fn addresses(&self) -> Result<Vec<Seed>, Self::Error> {
+        Ok(vec![])
+    }
+
+    fn routing(&self, _node: NodeId) -> Result<Vec<Id>, Self::Error> {
+        Ok(vec![])
+    }
+
+    fn config(&self) -> Result<Config, Self::Error> {
+        unimplemented!();
+    }
+
+    fn update_limits(&mut self, _limits: Limits) -> Result<(), Self::Error> {
+        unimplemented!();
+    }
+
     fn shutdown(self) -> Result<(), Self::Error> {
         Ok(())
     }
@@ -155,6 +155,7 @@ where
         let routing = routing::Table::memory().unwrap();
         let tracking = tracking::Store::<tracking::store::Write>::memory().unwrap();
         let tracking = tracking::Config::new(config.policy, config.scope, tracking);
+        let gossip = gossip::Store::memory().unwrap();
         let tempdir = tempfile::tempdir().unwrap();
         let id = *config.signer.public_key();
         let ip = ip.into();
@@ -172,6 +173,7 @@ where
             storage,
             config.addrs,
             tracking,
+            gossip,
             config.signer,
             config.rng.clone(),
             announcement,
@@ -259,9 +261,13 @@ where
     }
 
     pub fn node_announcement(&self) -> Message {
+        self.node_announcement_with(node::Features::SEED)
+    }
+
+    pub fn node_announcement_with(&self, features: node::Features) -> Message {
         Message::node(
             NodeAnnouncement {
-                features: node::Features::SEED,
+                features,
                 timestamp: self.timestamp(),
                 alias: Alias::from_str(self.name).unwrap(),
                 addresses: Some(net::SocketAddr::from((self.ip, node::DEFAULT_PORT)).into()).into(),
@@ -390,6 +396,7 @@ where
                 rid,
                 remote,
                 namespaces,
+                ..
             } = io
             {
                 Some((rid, remote, namespaces))
@@ -622,10 +622,19 @@ impl<S: WriteStorage + 'static, G: Signer> Simulation<S, G> {
                     );
                 }
             }
+            Io::Probe(remote, addr) => {
+                // Dial-back probes are not simulated.
+                log::info!(
+                    target: "sim",
+                    "{:05} {} ~> {} ({}): Probe outgoing",
+                    self.elapsed().as_millis(), node, remote, addr
+                );
+            }
             Io::Fetch {
                 rid,
                 remote,
                 namespaces,
+                ..
             } => {
                 log::info!(
                     target: "sim",
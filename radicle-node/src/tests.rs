@@ -1,14 +1,18 @@
 mod e2e;
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashSet};
 use std::default::*;
 use std::io;
+use std::net;
 use std::sync::Arc;
 use std::time;
 
 use crossbeam_channel as chan;
 use netservices::Direction as Link;
+use radicle::node::address::Store as _;
+use radicle::node::address::{Misbehavior, BAN_THRESHOLD, RATE_LIMITED_MAX_SCORE};
 use radicle::node::routing::Store as _;
+use radicle::node::succession::Succession;
 use radicle::node::ConnectOptions;
 use radicle::storage::ReadRepository;
 
@@ -26,7 +30,7 @@ use crate::service::ServiceState as _;
 use crate::service::*;
 use crate::storage::git::transport::{local, remote};
 use crate::storage::git::Storage;
-use crate::storage::ReadStorage;
+use crate::storage::{Namespaces, ReadStorage};
 use crate::test::arbitrary;
 use crate::test::assert_matches;
 use crate::test::fixtures;
@@ -37,6 +41,7 @@ use crate::test::peer::Peer;
 use crate::test::simulator;
 use crate::test::simulator::{Peer as _, Simulation};
 use crate::test::storage::MockStorage;
+use crate::wire;
 use crate::wire::Decode;
 use crate::wire::Encode;
 use crate::LocalTime;
@@ -241,6 +246,50 @@ fn test_persistent_peer_connect() {
         .unwrap();
 }
 
+#[test]
+fn test_config_reload() {
+    use std::collections::HashSet;
+
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let eve = Peer::new("eve", [9, 9, 9, 9]);
+    let mut alice = Peer::with_storage("alice", [7, 7, 7, 7], MockStorage::empty());
+
+    alice.connect_to(&bob);
+
+    let config = Config {
+        connect: HashSet::from_iter([(eve.id(), eve.address()).into()]),
+        limits: Limits {
+            fetch_concurrency: 3,
+            ..Limits::default()
+        },
+        ..alice.config().clone()
+    };
+    alice.command(Command::Reload(Box::new(config)));
+
+    // Eve is a new persistent peer, so we connect to her.
+    assert_matches!(
+        alice.outbox().find(|o| matches!(o, Io::Connect(..))),
+        Some(Io::Connect(nid, _)) if nid == eve.id()
+    );
+    assert!(alice.config().is_persistent(&eve.id()));
+    assert_eq!(alice.config().limits.fetch_concurrency, 3);
+
+    // Eve is removed from the persistent peers, so we disconnect from her.
+    alice.attempted(eve.id(), eve.address());
+    alice.connected(eve.id(), eve.address(), Link::Outbound);
+
+    let config = Config {
+        connect: HashSet::new(),
+        ..alice.config().clone()
+    };
+    alice.command(Command::Reload(Box::new(config)));
+    assert_matches!(
+        alice.outbox().find(|o| matches!(o, Io::Disconnect(..))),
+        Some(Io::Disconnect(nid, DisconnectReason::Command)) if nid == eve.id()
+    );
+    assert!(!alice.config().is_persistent(&eve.id()));
+}
+
 #[test]
 fn test_inventory_sync() {
     let tmp = tempfile::tempdir().unwrap();
@@ -274,6 +323,153 @@ fn test_inventory_sync() {
     }
 }
 
+#[test]
+fn test_inventory_reconciliation() {
+    let tmp = tempfile::tempdir().unwrap();
+    let mut alice = Peer::config(
+        "alice",
+        [7, 7, 7, 7],
+        Storage::open(tmp.path().join("alice")).unwrap(),
+        peer::Config::default(),
+    );
+    let bob_signer = MockSigner::default();
+    let bob_storage = fixtures::storage(tmp.path().join("bob"), &bob_signer).unwrap();
+    let bob = Peer::config("bob", [8, 8, 8, 8], bob_storage, peer::Config::default());
+    let projs = bob.storage().inventory().unwrap();
+
+    // Alice sends her digests to peers that reconcile inventories.
+    alice.connect_to(&bob);
+    alice.receive(bob.id(), Message::hello());
+    assert_matches!(
+        alice
+            .messages(bob.id())
+            .find(|m| matches!(m, Message::InventoryDigest(_))),
+        Some(Message::InventoryDigest(_))
+    );
+
+    // Alice doesn't know anything about Bob's inventory, so she requests the buckets
+    // his repositories are in.
+    alice.receive(
+        bob.id(),
+        Message::InventoryDigest(reconcile::Digests::new(&projs)),
+    );
+    let expected = projs
+        .iter()
+        .map(reconcile::bucket)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    let buckets = alice
+        .messages(bob.id())
+        .find_map(|m| match m {
+            Message::InventoryRequest { buckets } => Some(buckets.to_vec()),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(buckets, expected);
+
+    for bucket in buckets {
+        let inventory = projs
+            .iter()
+            .filter(|rid| reconcile::bucket(rid) == bucket)
+            .copied()
+            .collect::<Vec<_>>();
+        alice.receive(
+            bob.id(),
+            Message::InventoryBucket {
+                bucket,
+                inventory: inventory.try_into().unwrap(),
+            },
+        );
+    }
+    for proj in &projs {
+        let seeds = alice.routing().get(proj).unwrap();
+        assert!(seeds.contains(&bob.node_id()));
+    }
+
+    // Now that Alice is in sync, there's nothing to request.
+    alice.receive(
+        bob.id(),
+        Message::InventoryDigest(reconcile::Digests::new(&projs)),
+    );
+    assert_matches!(
+        alice
+            .messages(bob.id())
+            .find(|m| matches!(m, Message::InventoryRequest { .. })),
+        None
+    );
+
+    // A bucket only prunes the entries that fall in it.
+    let (removed, rest) = projs.split_first().unwrap();
+    let bucket = reconcile::bucket(removed);
+    alice.receive(
+        bob.id(),
+        Message::InventoryBucket {
+            bucket,
+            inventory: rest
+                .iter()
+                .filter(|rid| reconcile::bucket(rid) == bucket)
+                .copied()
+                .collect::<Vec<_>>()
+                .try_into()
+                .unwrap(),
+        },
+    );
+    assert!(!alice
+        .routing()
+        .get(removed)
+        .unwrap()
+        .contains(&bob.node_id()));
+
+    for proj in rest {
+        let seeds = alice.routing().get(proj).unwrap();
+        assert!(seeds.contains(&bob.node_id()));
+    }
+}
+
+#[test]
+fn test_inventory_reconciliation_truncated_bucket() {
+    let tmp = tempfile::tempdir().unwrap();
+    let mut alice = Peer::config(
+        "alice",
+        [7, 7, 7, 7],
+        Storage::open(tmp.path().join("alice")).unwrap(),
+        peer::Config::default(),
+    );
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let bucket = 42;
+    // Bob seeds more repositories in the bucket than fit in a single message.
+    let projs = (0..=INVENTORY_LIMIT)
+        .map(|_| {
+            let mut bytes = arbitrary::gen::<[u8; 20]>(1);
+            bytes[0] = bucket;
+
+            Id::from(git::Oid::try_from(bytes.as_slice()).unwrap())
+        })
+        .collect::<Vec<_>>();
+    let (sent, rest) = projs.split_at(INVENTORY_LIMIT);
+
+    alice.connect_to(&bob);
+    alice.receive(bob.id(), Message::hello());
+    alice
+        .routing_mut()
+        .insert(rest, bob.id(), bob.local_time().as_millis())
+        .unwrap();
+
+    // The bucket is truncated to the limit, so it can't be used to prune Alice's routes.
+    alice.receive(
+        bob.id(),
+        Message::InventoryBucket {
+            bucket,
+            inventory: sent.to_vec().try_into().unwrap(),
+        },
+    );
+    for proj in &projs {
+        let seeds = alice.routing().get(proj).unwrap();
+        assert!(seeds.contains(&bob.node_id()));
+    }
+}
+
 #[test]
 fn test_inventory_pruning() {
     struct Test {
@@ -402,6 +598,576 @@ fn test_tracking() {
     assert!(!alice.tracking().is_repo_tracked(&proj_id).unwrap());
 }
 
+#[test]
+fn test_misbehaving_peer_banned() {
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let reason = DisconnectReason::Session(session::Error::InvalidSignature);
+
+    // Bob misbehaves until he gets banned.
+    for _ in 0..2 {
+        alice.connect_from(&bob);
+        alice.disconnected(bob.id(), &reason);
+    }
+    assert!(alice
+        .addresses()
+        .reputation(&bob.id(), alice.timestamp())
+        .unwrap()
+        .is_banned(*alice.clock()));
+    alice.outbox().for_each(drop);
+
+    // When he connects again, he is disconnected.
+    alice.connected(bob.id(), bob.address(), Link::Inbound);
+    assert_matches!(
+        alice.outbox().find(|io| matches!(io, Io::Disconnect(..))),
+        Some(Io::Disconnect(addr, DisconnectReason::Session(session::Error::Banned)))
+        if addr == bob.id()
+    );
+}
+
+#[test]
+fn test_penalties_recorded_periodically() {
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let reason = DisconnectReason::Session(session::Error::Misbehavior);
+
+    alice.connect_from(&bob);
+    alice.disconnected(bob.id(), &reason);
+
+    // Bob's penalty isn't recorded right away.
+    assert_eq!(
+        alice
+            .addresses()
+            .reputation(&bob.id(), alice.timestamp())
+            .unwrap()
+            .score,
+        0
+    );
+
+    // It is on the next idle task.
+    alice.elapse(IDLE_INTERVAL);
+    assert_eq!(
+        alice
+            .addresses()
+            .reputation(&bob.id(), alice.timestamp())
+            .unwrap()
+            .score,
+        Misbehavior::InvalidAnnouncement.penalty()
+    );
+}
+
+#[test]
+fn test_rate_limited_peer_not_banned() {
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+
+    alice.connect_from(&bob);
+    for _ in 0..BAN_THRESHOLD * 2 {
+        alice.receive(
+            bob.id(),
+            Message::Ping(Ping {
+                ponglen: 0,
+                zeroes: ZeroBytes::new(0),
+            }),
+        );
+    }
+    assert!(alice
+        .outbox()
+        .all(|io| !matches!(io, Io::Disconnect(nid, _) if nid == bob.id())));
+
+    alice.elapse(IDLE_INTERVAL);
+    let reputation = alice
+        .addresses()
+        .reputation(&bob.id(), alice.timestamp())
+        .unwrap();
+
+    assert_eq!(reputation.score, RATE_LIMITED_MAX_SCORE);
+    assert!(!reputation.is_banned(alice.local_time()));
+}
+
+#[test]
+fn test_dial_back() {
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let eve = Peer::new("eve", [9, 9, 9, 9]);
+    let addr = net::SocketAddr::from(([8, 8, 8, 8], 8777));
+
+    // Bob connected to us, so we can dial him back.
+    alice.connect_from(&bob);
+    alice.receive(bob.id(), Message::DialBack { port: 8777 });
+    assert_matches!(
+        alice.outbox().find(|io| matches!(io, Io::Probe(..))),
+        Some(Io::Probe(remote, a)) if remote == bob.id() && a == addr
+    );
+    alice.probed(bob.id(), addr, true);
+    assert_matches!(
+        alice.messages(bob.id()).next(),
+        Some(Message::DialBackResult { observed, reachable: true }) if observed == addr.into()
+    );
+
+    // Bob's address isn't probed again until the cooldown is over.
+    alice.receive(bob.id(), Message::DialBack { port: 8777 });
+    assert_matches!(alice.outbox().find(|io| matches!(io, Io::Probe(..))), None);
+
+    alice.elapse(DIAL_BACK_COOLDOWN);
+    alice.receive(bob.id(), Message::DialBack { port: 8777 });
+    assert_matches!(
+        alice.outbox().find(|io| matches!(io, Io::Probe(..))),
+        Some(Io::Probe(..))
+    );
+
+    // We connected to Eve, so we don't know where she's reachable.
+    alice.connect_to(&eve);
+    alice.receive(eve.id(), Message::DialBack { port: 8777 });
+    assert_matches!(alice.outbox().find(|io| matches!(io, Io::Probe(..))), None);
+}
+
+#[test]
+fn test_reachability_check() {
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let eve = Peer::new("eve", [9, 9, 9, 9]);
+    let carol = Peer::new("carol", [10, 10, 10, 10]);
+    let observed = Address::from(net::SocketAddr::from(([7, 7, 7, 7], 8776)));
+
+    for peer in [&bob, &eve, &carol] {
+        alice.connect_to(peer);
+        alice.receive(peer.id(), Message::hello());
+    }
+    alice.listening(net::SocketAddr::from(([0, 0, 0, 0], 8776)));
+    assert_eq!(alice.reachability().reachable, None);
+
+    // Unsolicited results are ignored.
+    alice.receive(
+        bob.id(),
+        Message::DialBackResult {
+            observed: observed.clone(),
+            reachable: true,
+        },
+    );
+    assert_eq!(alice.reachability().reachable, None);
+
+    // We ask all of them to dial us back.
+    alice.elapse(IDLE_INTERVAL);
+    for peer in [&bob, &eve, &carol] {
+        assert_matches!(
+            alice
+                .messages(peer.id())
+                .find(|m| matches!(m, Message::DialBack { .. })),
+            Some(Message::DialBack { port: 8776 })
+        );
+    }
+
+    // A single peer isn't enough to conclude anything.
+    alice.receive(
+        bob.id(),
+        Message::DialBackResult {
+            observed: observed.clone(),
+            reachable: true,
+        },
+    );
+    assert_eq!(alice.reachability().reachable, None);
+
+    alice.receive(
+        eve.id(),
+        Message::DialBackResult {
+            observed: observed.clone(),
+            reachable: false,
+        },
+    );
+    assert_eq!(alice.reachability().reachable, None);
+    assert_eq!(alice.reachability().observed, vec![observed.clone()]);
+
+    // Once two peers agree, we're reachable on the observed address.
+    alice.receive(
+        carol.id(),
+        Message::DialBackResult {
+            observed: observed.clone(),
+            reachable: true,
+        },
+    );
+    assert_eq!(alice.reachability().reachable, Some(true));
+    assert_eq!(alice.reachability().observed, vec![observed]);
+
+    // We don't ask again until the check is out of date.
+    alice.elapse(IDLE_INTERVAL);
+    assert_matches!(
+        alice
+            .messages(bob.id())
+            .find(|m| matches!(m, Message::DialBack { .. })),
+        None
+    );
+    alice.elapse(REACHABILITY_INTERVAL);
+    assert_matches!(
+        alice
+            .messages(bob.id())
+            .find(|m| matches!(m, Message::DialBack { .. })),
+        Some(Message::DialBack { .. })
+    );
+}
+
+#[test]
+fn test_reachability_check_disagreement() {
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let eve = Peer::new("eve", [9, 9, 9, 9]);
+    let carol = Peer::new("carol", [10, 10, 10, 10]);
+
+    for peer in [&bob, &eve, &carol] {
+        alice.connect_to(peer);
+        alice.receive(peer.id(), Message::hello());
+    }
+    alice.listening(net::SocketAddr::from(([0, 0, 0, 0], 8776)));
+    alice.elapse(IDLE_INTERVAL);
+    alice.outbox().for_each(drop);
+
+    // Each peer claims we're reachable on a different address.
+    for (i, peer) in [&bob, &eve, &carol].into_iter().enumerate() {
+        alice.receive(
+            peer.id(),
+            Message::DialBackResult {
+                observed: Address::from(net::SocketAddr::from(([7, 7, 7, i as u8], 8776))),
+                reachable: true,
+            },
+        );
+    }
+    assert_eq!(alice.reachability().reachable, Some(false));
+    assert_eq!(alice.reachability().observed.len(), 3);
+}
+
+#[test]
+fn test_replication_factor() {
+    let tmp = tempfile::tempdir().unwrap();
+    let signer = MockSigner::default();
+    let storage = fixtures::storage(tmp.path().join("alice"), &signer).unwrap();
+    let mut alice = Peer::config(
+        "alice",
+        [7, 7, 7, 7],
+        storage,
+        peer::Config {
+            config: service::Config {
+                replicas: Some(1),
+                ..service::Config::test(node::Alias::new("alice"))
+            },
+            signer,
+            ..peer::Config::default()
+        },
+    );
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let eve = Peer::new("eve", [9, 9, 9, 9]);
+    let carol = Peer::new("carol", [10, 10, 10, 10]);
+    let dave = Peer::new("dave", [11, 11, 11, 11]);
+    let rids = alice.storage().inventory().unwrap();
+    let inventory = |peer: &Peer<_, _>| {
+        Message::inventory(
+            InventoryAnnouncement {
+                inventory: rids.clone().try_into().unwrap(),
+                timestamp: peer.local_time().as_millis(),
+            },
+            peer.signer(),
+        )
+    };
+    let offered = |alice: &mut Peer<_, _>, peer: &Peer<_, _>| {
+        alice
+            .messages(peer.id())
+            .filter_map(|m| match m {
+                Message::Announcement(Announcement {
+                    message: AnnouncementMessage::Refs(RefsAnnouncement { rid, .. }),
+                    ..
+                }) => Some(rid),
+                _ => None,
+            })
+            .collect::<BTreeSet<_>>()
+    };
+
+    for rid in &rids {
+        alice.track_repo(rid, tracking::Scope::All).unwrap();
+    }
+    alice.connect_to(&bob);
+    alice.receive(bob.id(), bob.node_announcement());
+    alice.connect_to(&eve);
+    alice.receive(eve.id(), eve.node_announcement_with(node::Features::NONE));
+    // Eve has our repositories, but isn't a seed.
+    alice.receive(eve.id(), inventory(&eve));
+    alice.elapse(REPLICATION_INTERVAL);
+
+    // No other seed has our repositories: they are offered to the seeds we're connected to.
+    let under = alice
+        .replication()
+        .iter()
+        .map(|r| r.rid)
+        .collect::<BTreeSet<_>>();
+    assert_eq!(under, rids.iter().copied().collect());
+    assert!(alice.replication().iter().all(|r| r.replicas == 0));
+    assert_eq!(offered(&mut alice, &bob), under);
+    assert!(offered(&mut alice, &eve).is_empty());
+
+    // They aren't offered to the same seed twice.
+    alice.elapse(REPLICATION_INTERVAL);
+    assert!(offered(&mut alice, &bob).is_empty());
+
+    // Newly connected seeds are offered them, with an exponential backoff between offers.
+    alice.connect_to(&carol);
+    alice.receive(carol.id(), carol.node_announcement());
+    alice.elapse(REPLICATION_INTERVAL);
+    assert_eq!(offered(&mut alice, &carol), under);
+
+    alice.connect_to(&dave);
+    alice.receive(dave.id(), dave.node_announcement());
+    alice.elapse(REPLICATION_INTERVAL);
+    assert!(offered(&mut alice, &dave).is_empty());
+    alice.elapse(REPLICATION_INTERVAL);
+    assert_eq!(offered(&mut alice, &dave), under);
+
+    // Once another seed has them, they're no longer under-replicated.
+    alice.receive(bob.id(), inventory(&bob));
+    alice.elapse(REPLICATION_INTERVAL);
+    assert!(alice.replication().is_empty());
+}
+
+#[test]
+fn test_hello() {
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let eve = Peer::new("eve", [9, 9, 9, 9]);
+
+    // We only say hello once the peer advertizes support for it.
+    alice.initialize();
+    alice.connected(bob.id(), bob.address(), Link::Inbound);
+    assert!(!alice
+        .messages(bob.id())
+        .any(|m| matches!(m, Message::Hello { .. })));
+
+    alice.receive(
+        bob.id(),
+        bob.node_announcement_with(node::Features::SEED.with(node::Features::HELLO)),
+    );
+    assert_eq!(alice.messages(bob.id()).next(), Some(Message::hello()));
+    assert_eq!(alice.sessions().get(&bob.id()).unwrap().capabilities, None);
+
+    alice.receive(
+        bob.id(),
+        Message::Hello {
+            version: 2,
+            features: node::Features::DIAL_BACK,
+        },
+    );
+    assert_eq!(
+        alice.sessions().get(&bob.id()).unwrap().capabilities,
+        Some(session::Capabilities {
+            version: 2,
+            features: node::Features::DIAL_BACK,
+        })
+    );
+
+    // Peers speaking a version we don't support are disconnected.
+    alice.connect_from(&eve);
+    alice.receive(
+        eve.id(),
+        Message::Hello {
+            version: message::MIN_PROTOCOL_VERSION - 1,
+            features: node::Features::NONE,
+        },
+    );
+    assert_matches!(
+        alice.outbox().find(|io| matches!(io, Io::Disconnect(..))),
+        Some(Io::Disconnect(nid, DisconnectReason::Session(session::Error::UnsupportedVersion(0))))
+        if nid == eve.id()
+    );
+}
+
+#[test]
+fn test_hello_baseline_peer() {
+    // Message types known to peers speaking the first version of the protocol. They fail
+    // to decode anything else.
+    const BASELINE: &[u16] = &[2, 4, 6, 8, 10, 12];
+
+    fn decode_baseline(bytes: &[u8]) -> Result<Message, wire::Error> {
+        let type_id = u16::from_be_bytes([bytes[0], bytes[1]]);
+
+        if !BASELINE.contains(&type_id) {
+            return Err(wire::Error::UnknownMessageType(type_id));
+        }
+        wire::deserialize(bytes)
+    }
+
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+
+    // Bob's node announcement doesn't advertize hello support, like a baseline node's.
+    alice.initialize();
+    alice.connected(bob.id(), bob.address(), Link::Inbound);
+    alice.receive(bob.id(), bob.node_announcement());
+    alice.receive(bob.id(), bob.inventory_announcement());
+    alice.elapse(REACHABILITY_INTERVAL);
+    alice.elapse(KEEP_ALIVE_DELTA);
+
+    let msgs = alice.messages(bob.id()).collect::<Vec<_>>();
+    assert!(!msgs.is_empty());
+
+    for msg in msgs {
+        let bytes = wire::serialize(&msg);
+        assert_eq!(decode_baseline(&bytes).unwrap(), msg);
+    }
+}
+
+#[test]
+fn test_unknown_message_type() {
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let eve = Peer::new("eve", [9, 9, 9, 9]);
+
+    // Bob speaks a newer protocol, so he may send us messages we don't know.
+    alice.connect_from(&bob);
+    alice.receive(
+        bob.id(),
+        Message::Hello {
+            version: message::PROTOCOL_VERSION + 1,
+            features: message::PROTOCOL_FEATURES,
+        },
+    );
+    alice.received_unknown(bob.id(), 0xffff);
+    assert_matches!(
+        alice.outbox().find(|io| matches!(io, Io::Disconnect(..))),
+        None
+    );
+
+    // Eve doesn't, so she's misbehaving.
+    alice.connect_from(&eve);
+    alice.receive(eve.id(), Message::hello());
+    alice.received_unknown(eve.id(), 0xffff);
+    assert_matches!(
+        alice.outbox().find(|io| matches!(io, Io::Disconnect(..))),
+        Some(Io::Disconnect(nid, DisconnectReason::Session(session::Error::Misbehavior)))
+        if nid == eve.id()
+    );
+}
+
+#[test]
+fn test_dial_back_requires_feature() {
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+
+    alice.listening(net::SocketAddr::from(([0, 0, 0, 0], 8776)));
+    alice.connect_to(&bob);
+    alice.receive(
+        bob.id(),
+        Message::Hello {
+            version: message::PROTOCOL_VERSION,
+            features: node::Features::NONE,
+        },
+    );
+    alice.elapse(IDLE_INTERVAL);
+    assert_matches!(
+        alice
+            .messages(bob.id())
+            .find(|m| matches!(m, Message::DialBack { .. })),
+        None
+    );
+}
+
+#[test]
+fn test_succession_announcement() {
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let eve = Peer::new("eve", [9, 9, 9, 9]);
+    let carol = Peer::new("carol", [10, 10, 10, 10]);
+    let successor = MockSigner::default();
+    let succession = Succession::new(alice.timestamp(), bob.signer(), &successor);
+    let successor = *successor.public_key();
+
+    let (sender, receiver) = chan::bounded(1);
+    alice.command(Command::TrackNode(
+        bob.id(),
+        Some(node::Alias::new("bob")),
+        sender,
+    ));
+    assert!(receiver.recv().unwrap());
+
+    alice.connect_to(&bob);
+    alice.connect_from(&eve);
+    alice.receive(eve.id(), Message::hello());
+    alice.connect_from(&carol);
+    alice.receive(
+        carol.id(),
+        Message::Hello {
+            version: message::PROTOCOL_VERSION,
+            features: node::Features::NONE,
+        },
+    );
+    alice.receive(bob.id(), Announcement::from(succession).into());
+
+    // Bob's policies and alias are carried over to his new key.
+    let policy = alice.tracking().node_policy(&successor).unwrap();
+    assert_eq!(policy.policy, tracking::Policy::Track);
+    assert_eq!(policy.alias, Some(node::Alias::new("bob")));
+    assert_eq!(
+        alice.addresses().successor(&bob.id()).unwrap(),
+        Some(successor)
+    );
+
+    // The announcement is only relayed to peers that understand it.
+    let is_succession = |m: &Message| {
+        matches!(
+            m,
+            Message::Announcement(Announcement {
+                message: AnnouncementMessage::Succession(_),
+                ..
+            })
+        )
+    };
+    assert!(alice.messages(eve.id()).any(|m| is_succession(&m)));
+    assert!(!alice.messages(carol.id()).any(|m| is_succession(&m)));
+}
+
+#[test]
+fn test_succession_announcement_not_cosigned() {
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let eve = Peer::new("eve", [9, 9, 9, 9]);
+    let victim = MockSigner::default();
+    let timestamp = alice.timestamp();
+
+    // Eve names a victim as her successor, co-signing it herself.
+    let cosignature = eve.signer().sign(&Succession::cosigned(
+        &eve.id(),
+        victim.public_key(),
+        timestamp,
+    ));
+    let succession = SuccessionAnnouncement {
+        successor: *victim.public_key(),
+        timestamp,
+        cosignature,
+    };
+    alice.connect_to(&eve);
+    alice.receive(
+        eve.id(),
+        AnnouncementMessage::from(succession)
+            .signed(eve.signer())
+            .into(),
+    );
+
+    assert_eq!(alice.addresses().successor(&eve.id()).unwrap(), None);
+    assert_matches!(
+        alice.outbox().find(|io| matches!(io, Io::Disconnect(..))),
+        Some(Io::Disconnect(nid, DisconnectReason::Session(session::Error::Misbehavior)))
+        if nid == eve.id()
+    );
+
+    // A properly co-signed succession to a key that already has policies is ignored.
+    let successor = MockSigner::default();
+    let (sender, receiver) = chan::bounded(1);
+    alice.command(Command::TrackNode(*successor.public_key(), None, sender));
+    assert!(receiver.recv().unwrap());
+
+    alice.connect_to(&bob);
+    alice.receive(
+        bob.id(),
+        Announcement::from(Succession::new(timestamp, bob.signer(), &successor)).into(),
+    );
+    assert_eq!(alice.addresses().successor(&bob.id()).unwrap(), None);
+}
+
 #[test]
 fn test_inventory_relay_bad_timestamp() {
     let mut alice = Peer::new("alice", [7, 7, 7, 7]);
@@ -785,6 +1551,39 @@ fn test_refs_announcement_trusted() {
     assert_matches!(alice.outbox().next(), Some(Io::Fetch { .. }));
 }
 
+/// Alice tracks all remotes of a repo, except Eve, who is blocked.
+///
+/// Alice still fetches all namespaces from Bob, but lets the fetch know to leave Eve's
+/// refs out.
+#[test]
+fn test_refs_announcement_blocked_remote() {
+    let storage_alice = arbitrary::nonempty_storage(1);
+    let rid = *storage_alice.inventory.keys().next().unwrap();
+    let storage_bob = storage_alice.clone();
+    let mut alice = Peer::with_storage("alice", [7, 7, 7, 7], storage_alice);
+    let mut bob = Peer::with_storage("bob", [8, 8, 8, 8], storage_bob);
+    let eve = Peer::new("eve", [9, 9, 9, 9]);
+
+    let refs = arbitrary::gen::<Refs>(8);
+    let signed_refs = refs.signed(bob.signer()).unwrap();
+    let node_id = bob.id;
+    bob.storage_mut().insert_remote(rid, node_id, signed_refs);
+
+    alice.connect_to(&bob);
+    alice.track_repo(&rid, tracking::Scope::All).unwrap();
+
+    let (sender, receiver) = chan::bounded(1);
+    alice.command(Command::BlockRemote(rid, eve.id(), sender));
+    assert!(receiver.recv().unwrap());
+
+    alice.receive(bob.id(), bob.refs_announcement(rid));
+    assert_matches!(
+        alice.outbox().next(),
+        Some(Io::Fetch { namespaces: Namespaces::All, blocked, .. })
+        if blocked == HashSet::from([eve.id()])
+    );
+}
+
 #[test]
 fn test_refs_announcement_no_subscribe() {
     let storage = arbitrary::nonempty_storage(1);
@@ -898,6 +1697,37 @@ fn test_inventory_relay() {
     );
 }
 
+#[test]
+fn test_gossip_metrics() {
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let eve = Peer::new("eve", [9, 9, 9, 9]);
+    let inv = BoundedVec::try_from(arbitrary::vec(1)).unwrap();
+    let now = LocalTime::now().as_millis();
+    let ann = Message::inventory(
+        InventoryAnnouncement {
+            inventory: inv,
+            timestamp: now,
+        },
+        bob.signer(),
+    );
+
+    alice.connect_to(&bob);
+    alice.connect_from(&eve);
+    alice.receive(bob.id(), ann.clone());
+    alice.receive(bob.id(), ann);
+
+    let metrics = alice.metrics();
+    assert_eq!(metrics.inbound_sessions, 1);
+    assert_eq!(metrics.outbound_sessions, 1);
+    assert_eq!(metrics.gossip_received, 2);
+    assert_eq!(metrics.gossip_relayed, 1);
+    assert_eq!(
+        metrics.gossip_dropped, 1,
+        "The duplicate announcement is dropped"
+    );
+}
+
 #[test]
 fn test_persistent_peer_reconnect_attempt() {
     use std::collections::HashSet;
@@ -1197,6 +2027,38 @@ fn test_queued_fetch() {
     assert_matches!(alice.fetches().next(), Some((rid, _, _)) if rid == rid3);
 }
 
+#[test]
+fn test_update_limits() {
+    let storage = arbitrary::nonempty_storage(3);
+    let mut repo_keys = storage.inventory.keys();
+    let rid1 = *repo_keys.next().unwrap();
+    let rid2 = *repo_keys.next().unwrap();
+    let rid3 = *repo_keys.next().unwrap();
+    let mut alice = Peer::with_storage("alice", [7, 7, 7, 7], storage);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+
+    alice.connect_to(&bob);
+    alice.command(Command::UpdateLimits(Limits {
+        fetch_concurrency: 2,
+        ..Limits::default()
+    }));
+    assert_eq!(alice.config().limits.fetch_concurrency, 2);
+
+    for rid in [rid1, rid2, rid3] {
+        let (send, _recv) = chan::bounded::<node::FetchResult>(1);
+        alice.command(Command::Fetch(rid, bob.id, send));
+    }
+    // Limits apply to existing sessions: two fetches are initiated, the third is queued.
+    assert_matches!(alice.fetches().next(), Some((rid, _, _)) if rid == rid1);
+    assert_matches!(alice.fetches().next(), Some((rid, _, _)) if rid == rid2);
+    assert_matches!(alice.fetches().next(), None);
+
+    // The routing table can be queried per node.
+    let (send, recv) = chan::bounded(1);
+    alice.command(Command::Routing(bob.id, send));
+    assert_eq!(recv.recv().unwrap(), vec![]);
+}
+
 #[test]
 fn test_refs_synced_event() {
     let temp = tempfile::tempdir().unwrap();
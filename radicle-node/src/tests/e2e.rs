@@ -1,19 +1,27 @@
-use std::{collections::HashSet, thread, time};
+use std::io::{Read as _, Write as _};
+use std::{collections::HashSet, fs, io, net, thread, time};
 
+use cyphernet::addr::{HostName, NetAddr};
+
+use localtime::LocalTime;
 use radicle::crypto::{test::signer::MockSigner, Signer};
 use radicle::git;
-use radicle::node::{Alias, FetchResult, Handle as _};
+use radicle::node::address::{self, Store as _};
+use radicle::node::{Address, Alias, ConnectResult, FetchResult, Handle as _};
+use radicle::node::{Features, ADDRESS_DB_FILE, NODE_ANNOUNCEMENT_FILE};
 use radicle::storage::{ReadRepository, ReadStorage, WriteRepository, WriteStorage};
 use radicle::test::fixtures;
 use radicle::{assert_matches, rad};
 
-use crate::node::config::Limits;
+use crate::node::config::{Limits, Proxy, Tor};
 use crate::node::{Config, ConnectOptions};
 use crate::service;
+use crate::service::message::NodeAnnouncement;
 use crate::service::tracking::Scope;
 use crate::storage::git::transport;
 use crate::test::environment::{converge, Environment, Node};
 use crate::test::logger;
+use crate::wire;
 
 #[test]
 //
@@ -142,6 +150,62 @@ fn test_inventory_sync_star() {
     assert_eq!(routes.len(), 5);
 }
 
+#[test]
+fn test_cached_node_announcement() {
+    logger::init(log::Level::Debug);
+
+    let tmp = tempfile::tempdir().unwrap();
+    // Alice and Bob are seeds, so that they're stored in Eve's address book.
+    let seed = |alias: &str, ip: [u8; 4]| Config {
+        external_addresses: vec![Address::from(net::SocketAddr::from((ip, 8776)))],
+        ..Config::test(Alias::new(alias))
+    };
+    let mut alice = Node::init(tmp.path(), seed("alice", [192, 0, 2, 1]));
+    let mut bob = Node::init(tmp.path(), seed("bob", [192, 0, 2, 2]));
+    let mut eve = Node::init(tmp.path(), Config::test(Alias::new("eve")));
+    let timestamp = LocalTime::now().as_secs() - 60;
+
+    alice.project("alice", "");
+    bob.project("bob", "");
+    eve.project("eve", "");
+
+    // Alice's cached announcement is up to date, so it's reused on startup.
+    let current = service::gossip::node(&alice.config, timestamp)
+        .solve(0)
+        .unwrap();
+    // Bob's was cached before nodes advertized the `HELLO` feature, so it's regenerated.
+    let outdated = NodeAnnouncement {
+        features: Features::SEED,
+        ..service::gossip::node(&bob.config, timestamp)
+    }
+    .solve(0)
+    .unwrap();
+
+    for (node, ann) in [(&alice, &current), (&bob, &outdated)] {
+        fs::write(
+            node.home.node().join(NODE_ANNOUNCEMENT_FILE),
+            wire::serialize(ann),
+        )
+        .unwrap();
+    }
+    let alice = alice.spawn();
+    let bob = bob.spawn();
+    let mut eve = eve.spawn();
+
+    eve.connect(&alice);
+    eve.connect(&bob);
+    converge([&alice, &bob, &eve]);
+
+    let addresses = address::Book::open(eve.home.node().join(ADDRESS_DB_FILE)).unwrap();
+    let alice = addresses.get(&alice.id).unwrap().unwrap();
+    let bob = addresses.get(&bob.id).unwrap().unwrap();
+
+    assert_eq!(alice.timestamp, timestamp);
+    assert!(alice.features.has(Features::HELLO));
+    assert!(bob.timestamp > timestamp);
+    assert!(bob.features.has(Features::HELLO));
+}
+
 #[test]
 fn test_replication() {
     logger::init(log::Level::Debug);
@@ -417,6 +481,52 @@ fn test_fetch_trusted_remotes() {
     assert!(bob_remotes.contains(&alice.id));
 }
 
+#[test]
+fn test_fetch_blocked_remotes() {
+    logger::init(log::Level::Debug);
+
+    let tmp = tempfile::tempdir().unwrap();
+    let mut alice = Node::init(tmp.path(), Config::test(Alias::new("alice")));
+    let bob = Node::init(tmp.path(), Config::test(Alias::new("bob")));
+    let acme = alice.project("acme", "");
+    let mut signers = Vec::with_capacity(2);
+    {
+        for _ in 0..2 {
+            let signer = MockSigner::default();
+            rad::fork_remote(acme, &alice.id, &signer, &alice.storage).unwrap();
+            signers.push(signer);
+        }
+    }
+    let blocked = *signers[0].public_key();
+    let tracked = *signers[1].public_key();
+
+    let mut alice = alice.spawn();
+    let mut bob = bob.spawn();
+
+    alice.connect(&bob);
+    converge([&alice, &bob]);
+
+    assert!(bob.handle.track_repo(acme, Scope::All).unwrap());
+    assert!(bob.handle.block_remote(acme, blocked).unwrap());
+
+    // The first fetch is a clone, which only fetches the delegates. The second one fetches
+    // all other remotes, except the blocked one.
+    for _ in 0..2 {
+        let result = bob.handle.fetch(acme, alice.id).unwrap();
+        assert!(result.is_success());
+    }
+    log::debug!(target: "test", "Fetch complete with {}", bob.id);
+
+    let bob_repo = bob.storage.repository(acme).unwrap();
+    let bob_remotes = bob_repo
+        .remote_ids()
+        .unwrap()
+        .collect::<Result<HashSet<_>, _>>()
+        .unwrap();
+
+    assert_eq!(bob_remotes, HashSet::from([alice.id, tracked]));
+}
+
 #[test]
 fn test_missing_remote() {
     logger::init(log::Level::Debug);
@@ -906,3 +1016,151 @@ fn test_outdated_sigrefs() {
     assert_ne!(eves_refs, old_refs);
     assert_eq!(eves_refs_expected, eves_refs);
 }
+
+/// Spawn a SOCKS5 proxy stand-in, which forwards every connection to the given target,
+/// regardless of the destination requested.
+fn socks5_proxy(target: net::SocketAddr) -> net::SocketAddr {
+    let listener = net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut client = stream.unwrap();
+
+            // Greeting, with "no authentication" as the only method.
+            let mut greeting = [0u8; 3];
+            client.read_exact(&mut greeting).unwrap();
+            assert_eq!(greeting, [0x5, 0x1, 0x0]);
+            client.write_all(&[0x5, 0x0]).unwrap();
+
+            // Connect request, with the onion address as destination.
+            let mut header = [0u8; 5];
+            client.read_exact(&mut header).unwrap();
+            assert_eq!(header[..4], [0x5, 0x1, 0x0, 0x3]);
+
+            let mut dest = vec![0u8; header[4] as usize + 2];
+            client.read_exact(&mut dest).unwrap();
+            assert!(dest[..dest.len() - 2].ends_with(b".onion"));
+
+            let server = net::TcpStream::connect(target).unwrap();
+            client
+                .write_all(&[0x5, 0x0, 0x0, 0x1, 127, 0, 0, 1, 0, 0])
+                .unwrap();
+
+            pipe(client, server);
+        }
+    });
+    addr
+}
+
+/// Spawn an HTTP proxy stand-in, which forwards every `CONNECT` request to the given target,
+/// regardless of the destination requested.
+fn http_proxy(target: net::SocketAddr) -> net::SocketAddr {
+    let listener = net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut client = stream.unwrap();
+            let mut request = Vec::new();
+            let mut byte = [0u8; 1];
+
+            while !request.ends_with(b"\r\n\r\n") {
+                client.read_exact(&mut byte).unwrap();
+                request.push(byte[0]);
+            }
+            assert!(request.starts_with(b"CONNECT "));
+
+            let server = net::TcpStream::connect(target).unwrap();
+            client
+                .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+                .unwrap();
+
+            pipe(client, server);
+        }
+    });
+    addr
+}
+
+/// Forward data between two streams, in both directions.
+fn pipe(client: net::TcpStream, server: net::TcpStream) {
+    let (mut client_r, mut server_w) = (client.try_clone().unwrap(), server.try_clone().unwrap());
+    let (mut server_r, mut client_w) = (server, client);
+
+    thread::spawn(move || io::copy(&mut client_r, &mut server_w));
+    thread::spawn(move || io::copy(&mut server_r, &mut client_w));
+}
+
+#[test]
+fn test_connect_onion() {
+    logger::init(log::Level::Debug);
+
+    let tmp = tempfile::tempdir().unwrap();
+    let bob = Node::init(tmp.path(), Config::test(Alias::new("bob"))).spawn();
+    let proxy = socks5_proxy(([127, 0, 0, 1], bob.addr.port()).into());
+    let mut alice = Node::init(
+        tmp.path(),
+        Config {
+            tor: Tor {
+                proxy,
+                force: false,
+            },
+            ..Config::test(Alias::new("alice"))
+        },
+    )
+    .spawn();
+
+    let onion = "2gzyxa5ihm7nsggfxnu52rck2vv4rvmdlkiu3zzui5du4xyclen53wid.onion"
+        .parse()
+        .unwrap();
+    let addr = Address::from(NetAddr {
+        host: HostName::Tor(onion),
+        port: 8776,
+    });
+    let result = alice
+        .handle
+        .connect(
+            bob.id,
+            addr,
+            ConnectOptions {
+                persistent: false,
+                timeout: time::Duration::from_secs(6),
+            },
+        )
+        .unwrap();
+
+    assert_matches!(result, ConnectResult::Connected);
+}
+
+#[test]
+fn test_connect_http_proxy() {
+    logger::init(log::Level::Debug);
+
+    let tmp = tempfile::tempdir().unwrap();
+    let bob = Node::init(tmp.path(), Config::test(Alias::new("bob"))).spawn();
+    let proxy = http_proxy(([127, 0, 0, 1], bob.addr.port()).into());
+    let mut alice = Node::init(
+        tmp.path(),
+        Config {
+            proxy: Some(Proxy::Http { addr: proxy }),
+            ..Config::test(Alias::new("alice"))
+        },
+    )
+    .spawn();
+
+    // Nb. The stand-in forwards to bob, whatever the destination.
+    let addr = Address::from(net::SocketAddr::from(([192, 0, 2, 1], 8776)));
+    let result = alice
+        .handle
+        .connect(
+            bob.id,
+            addr,
+            ConnectOptions {
+                persistent: false,
+                timeout: time::Duration::from_secs(6),
+            },
+        )
+        .unwrap();
+
+    assert_matches!(result, ConnectResult::Connected);
+}
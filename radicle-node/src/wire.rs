@@ -1,6 +1,7 @@
 mod frame;
 mod message;
 mod protocol;
+mod proxy;
 mod varint;
 
 pub use frame::StreamId;
@@ -24,6 +25,7 @@ use crate::node;
 use crate::node::Alias;
 use crate::prelude::*;
 use crate::service::filter;
+use crate::service::reconcile;
 use crate::storage::refs::Refs;
 use crate::storage::refs::SignedRefs;
 
@@ -57,8 +59,12 @@ pub enum Error {
     InvalidProtocolVersion([u8; 4]),
     #[error("unknown address type `{0}`")]
     UnknownAddressType(u8),
+    #[error("invalid onion address")]
+    InvalidOnionAddress,
     #[error("unknown message type `{0}`")]
     UnknownMessageType(u16),
+    #[error("invalid boolean `{0}`")]
+    InvalidBool(u8),
     #[error("unexpected bytes")]
     UnexpectedBytes,
 }
@@ -104,6 +110,12 @@ pub fn deserialize<T: Decode>(data: &[u8]) -> Result<T, Error> {
     Ok(obj)
 }
 
+impl Encode for bool {
+    fn encode<W: io::Write + ?Sized>(&self, writer: &mut W) -> Result<usize, io::Error> {
+        u8::from(*self).encode(writer)
+    }
+}
+
 impl Encode for u8 {
     fn encode<W: io::Write + ?Sized>(&self, writer: &mut W) -> Result<usize, io::Error> {
         writer.write_u8(*self)?;
@@ -347,6 +359,16 @@ impl Decode for Signature {
     }
 }
 
+impl Decode for bool {
+    fn decode<R: io::Read + ?Sized>(reader: &mut R) -> Result<Self, Error> {
+        match u8::decode(reader)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            other => Err(Error::InvalidBool(other)),
+        }
+    }
+}
+
 impl Decode for u8 {
     fn decode<R: io::Read + ?Sized>(reader: &mut R) -> Result<Self, Error> {
         reader.read_u8().map_err(Error::from)
@@ -447,6 +469,28 @@ impl Decode for filter::Filter {
     }
 }
 
+impl Encode for reconcile::Digests {
+    fn encode<W: io::Write + ?Sized>(&self, writer: &mut W) -> Result<usize, io::Error> {
+        let mut n = 0;
+
+        for digest in self.iter() {
+            n += digest.encode(writer)?;
+        }
+        Ok(n)
+    }
+}
+
+impl Decode for reconcile::Digests {
+    fn decode<R: io::Read + ?Sized>(reader: &mut R) -> Result<Self, Error> {
+        let mut digests = [0; reconcile::BUCKETS];
+
+        for digest in digests.iter_mut() {
+            *digest = u64::decode(reader)?;
+        }
+        Ok(Self::from(digests))
+    }
+}
+
 impl<V> Encode for SignedRefs<V> {
     fn encode<W: io::Write + ?Sized>(&self, writer: &mut W) -> Result<usize, io::Error> {
         let mut n = 0;
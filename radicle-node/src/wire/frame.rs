@@ -236,6 +236,15 @@ pub enum FrameData {
     Gossip(Message),
     /// Git frame payload. May contain packet-lines as well as packfile data.
     Git(Vec<u8>),
+    /// Gossip frame payload with a message type we don't know. This can happen when
+    /// the remote speaks a newer protocol version; it's up to the service to decide
+    /// whether that's acceptable.
+    Unknown {
+        /// The unknown message type.
+        type_id: u16,
+        /// The raw message, including the type.
+        data: Vec<u8>,
+    },
 }
 
 /// A control message sent over a control stream.
@@ -321,11 +330,18 @@ impl wire::Decode for Frame {
             Ok(StreamKind::Gossip) => {
                 let data = varint::payload::decode(reader)?;
                 let mut cursor = io::Cursor::new(data);
-                let msg = Message::decode(&mut cursor)?;
+                let data = match Message::decode(&mut cursor) {
+                    Ok(msg) => FrameData::Gossip(msg),
+                    Err(wire::Error::UnknownMessageType(type_id)) => FrameData::Unknown {
+                        type_id,
+                        data: cursor.into_inner(),
+                    },
+                    Err(e) => return Err(e),
+                };
                 let frame = Frame {
                     version,
                     stream,
-                    data: FrameData::Gossip(msg),
+                    data,
                 };
 
                 // Nb. If there is data after the `Message` that is not decoded,
@@ -352,6 +368,7 @@ impl wire::Encode for Frame {
             FrameData::Control(ctrl) => ctrl.encode(writer)?,
             FrameData::Git(data) => varint::payload::encode(data, writer)?,
             FrameData::Gossip(msg) => varint::payload::encode(&wire::serialize(msg), writer)?,
+            FrameData::Unknown { data, .. } => varint::payload::encode(data, writer)?,
         };
 
         Ok(n)
@@ -380,4 +397,21 @@ mod test {
         assert_eq!(StreamId::control(Link::Inbound), StreamId(VarInt(0b001)));
         assert_eq!(StreamId::gossip(Link::Inbound), StreamId(VarInt(0b011)));
     }
+
+    #[test]
+    fn test_unknown_message_type() {
+        let data = vec![0xff, 0xfe, 0x1, 0x2, 0x3];
+        let frame = Frame {
+            version: PROTOCOL_VERSION,
+            stream: StreamId::gossip(Link::Outbound),
+            data: FrameData::Unknown {
+                type_id: 0xfffe,
+                data,
+            },
+        };
+        let bytes = frame.to_bytes();
+        let decoded: Frame = wire::deserialize(&bytes).unwrap();
+
+        assert_eq!(decoded, frame);
+    }
 }
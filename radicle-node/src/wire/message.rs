@@ -1,11 +1,14 @@
 use std::{io, mem, net};
 
 use byteorder::{NetworkEndian, ReadBytesExt};
+use cyphernet::addr::tor::{OnionAddrV3, ONION_V3_RAW_LEN};
 use cyphernet::addr::{Addr, HostName, NetAddr};
-use radicle::node::Address;
+use cyphernet::EcPk as _;
+use radicle::node::{Address, Features};
 
 use crate::prelude::*;
 use crate::service::message::*;
+use crate::service::reconcile::Digests;
 use crate::wire;
 use crate::wire::{Decode, Encode};
 
@@ -19,6 +22,13 @@ pub enum MessageType {
     Subscribe = 8,
     Ping = 10,
     Pong = 12,
+    DialBack = 14,
+    DialBackResult = 16,
+    Hello = 18,
+    SuccessionAnnouncement = 20,
+    InventoryDigest = 22,
+    InventoryRequest = 24,
+    InventoryBucket = 26,
 }
 
 impl From<MessageType> for u16 {
@@ -38,6 +48,13 @@ impl TryFrom<u16> for MessageType {
             8 => Ok(MessageType::Subscribe),
             10 => Ok(MessageType::Ping),
             12 => Ok(MessageType::Pong),
+            14 => Ok(MessageType::DialBack),
+            16 => Ok(MessageType::DialBackResult),
+            18 => Ok(MessageType::Hello),
+            20 => Ok(MessageType::SuccessionAnnouncement),
+            22 => Ok(MessageType::InventoryDigest),
+            24 => Ok(MessageType::InventoryRequest),
+            26 => Ok(MessageType::InventoryBucket),
             _ => Err(other),
         }
     }
@@ -50,14 +67,21 @@ impl Message {
 
     pub fn type_id(&self) -> u16 {
         match self {
+            Self::Hello { .. } => MessageType::Hello,
             Self::Subscribe { .. } => MessageType::Subscribe,
             Self::Announcement(Announcement { message, .. }) => match message {
                 AnnouncementMessage::Node(_) => MessageType::NodeAnnouncement,
                 AnnouncementMessage::Inventory(_) => MessageType::InventoryAnnouncement,
                 AnnouncementMessage::Refs(_) => MessageType::RefsAnnouncement,
+                AnnouncementMessage::Succession(_) => MessageType::SuccessionAnnouncement,
             },
             Self::Ping { .. } => MessageType::Ping,
             Self::Pong { .. } => MessageType::Pong,
+            Self::DialBack { .. } => MessageType::DialBack,
+            Self::DialBackResult { .. } => MessageType::DialBackResult,
+            Self::InventoryDigest(_) => MessageType::InventoryDigest,
+            Self::InventoryRequest { .. } => MessageType::InventoryRequest,
+            Self::InventoryBucket { .. } => MessageType::InventoryBucket,
         }
         .into()
     }
@@ -127,10 +151,37 @@ impl wire::Encode for AnnouncementMessage {
             Self::Node(ann) => ann.encode(writer),
             Self::Inventory(ann) => ann.encode(writer),
             Self::Refs(ann) => ann.encode(writer),
+            Self::Succession(ann) => ann.encode(writer),
         }
     }
 }
 
+impl wire::Encode for SuccessionAnnouncement {
+    fn encode<W: io::Write + ?Sized>(&self, writer: &mut W) -> Result<usize, io::Error> {
+        let mut n = 0;
+
+        n += self.successor.encode(writer)?;
+        n += self.timestamp.encode(writer)?;
+        n += self.cosignature.encode(writer)?;
+
+        Ok(n)
+    }
+}
+
+impl wire::Decode for SuccessionAnnouncement {
+    fn decode<R: std::io::Read + ?Sized>(reader: &mut R) -> Result<Self, wire::Error> {
+        let successor = NodeId::decode(reader)?;
+        let timestamp = Timestamp::decode(reader)?;
+        let cosignature = Signature::decode(reader)?;
+
+        Ok(Self {
+            successor,
+            timestamp,
+            cosignature,
+        })
+    }
+}
+
 impl wire::Encode for RefsAnnouncement {
     fn encode<W: io::Write + ?Sized>(&self, writer: &mut W) -> Result<usize, io::Error> {
         let mut n = 0;
@@ -210,6 +261,30 @@ impl wire::Encode for Message {
             Self::Pong { zeroes } => {
                 n += zeroes.encode(writer)?;
             }
+            Self::DialBack { port } => {
+                n += port.encode(writer)?;
+            }
+            Self::Hello { version, features } => {
+                n += version.encode(writer)?;
+                n += features.encode(writer)?;
+            }
+            Self::DialBackResult {
+                observed,
+                reachable,
+            } => {
+                n += observed.encode(writer)?;
+                n += reachable.encode(writer)?;
+            }
+            Self::InventoryDigest(digests) => {
+                n += digests.encode(writer)?;
+            }
+            Self::InventoryRequest { buckets } => {
+                n += buckets.encode(writer)?;
+            }
+            Self::InventoryBucket { bucket, inventory } => {
+                n += bucket.encode(writer)?;
+                n += inventory.encode(writer)?;
+            }
         }
 
         if n > wire::Size::MAX as usize {
@@ -274,6 +349,18 @@ impl wire::Decode for Message {
                 }
                 .into())
             }
+            Ok(MessageType::SuccessionAnnouncement) => {
+                let node = NodeId::decode(reader)?;
+                let message = SuccessionAnnouncement::decode(reader)?.into();
+                let signature = Signature::decode(reader)?;
+
+                Ok(Announcement {
+                    node,
+                    message,
+                    signature,
+                }
+                .into())
+            }
             Ok(MessageType::Ping) => {
                 let ponglen = u16::decode(reader)?;
                 let zeroes = ZeroBytes::decode(reader)?;
@@ -283,6 +370,36 @@ impl wire::Decode for Message {
                 let zeroes = ZeroBytes::decode(reader)?;
                 Ok(Self::Pong { zeroes })
             }
+            Ok(MessageType::DialBack) => {
+                let port = u16::decode(reader)?;
+                Ok(Self::DialBack { port })
+            }
+            Ok(MessageType::DialBackResult) => {
+                let observed = Address::decode(reader)?;
+                let reachable = bool::decode(reader)?;
+                Ok(Self::DialBackResult {
+                    observed,
+                    reachable,
+                })
+            }
+            Ok(MessageType::Hello) => {
+                let version = u8::decode(reader)?;
+                let features = Features::decode(reader)?;
+                Ok(Self::Hello { version, features })
+            }
+            Ok(MessageType::InventoryDigest) => {
+                let digests = Digests::decode(reader)?;
+                Ok(Self::InventoryDigest(digests))
+            }
+            Ok(MessageType::InventoryRequest) => {
+                let buckets = BoundedVec::decode(reader)?;
+                Ok(Self::InventoryRequest { buckets })
+            }
+            Ok(MessageType::InventoryBucket) => {
+                let bucket = u8::decode(reader)?;
+                let inventory = BoundedVec::decode(reader)?;
+                Ok(Self::InventoryBucket { bucket, inventory })
+            }
             Err(other) => Err(wire::Error::UnknownMessageType(other)),
         }
     }
@@ -305,6 +422,10 @@ impl wire::Encode for Address {
                 n += u8::from(AddressType::Dns).encode(writer)?;
                 n += dns.encode(writer)?;
             }
+            HostName::Tor(onion) => {
+                n += u8::from(AddressType::Onion).encode(writer)?;
+                n += onion.into_raw_bytes().encode(writer)?;
+            }
             _ => {
                 todo!();
             }
@@ -337,7 +458,20 @@ impl wire::Decode for Address {
                 HostName::Dns(dns)
             }
             Ok(AddressType::Onion) => {
-                todo!();
+                let raw: [u8; ONION_V3_RAW_LEN] = wire::Decode::decode(reader)?;
+                let mut key = [0u8; 32];
+                key.copy_from_slice(&raw[..32]);
+
+                let pk = cyphernet::ed25519::PublicKey::from_pk_compressed(key)
+                    .map_err(|_| wire::Error::InvalidOnionAddress)?;
+                let onion = OnionAddrV3::from(pk);
+
+                // Nb. The checksum and version are derived from the key, so the encoding
+                // is only valid if they match.
+                if onion.into_raw_bytes() != raw {
+                    return Err(wire::Error::InvalidOnionAddress);
+                }
+                HostName::Tor(onion)
             }
             Err(other) => return Err(wire::Error::UnknownAddressType(other)),
         };
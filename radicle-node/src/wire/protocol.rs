@@ -11,27 +11,28 @@ use std::{io, net, time};
 
 use amplify::Wrapper as _;
 use crossbeam_channel as chan;
-use cyphernet::addr::{HostName, InetHost, NetAddr};
+use cyphernet::addr::{Host as _, HostName, NetAddr};
 use cyphernet::encrypt::noise::{HandshakePattern, Keyset, NoiseState};
-use cyphernet::proxy::socks5;
 use cyphernet::{Digest, EcSk, Ecdh, Sha256};
 use localtime::LocalTime;
 use netservices::resource::{ListenerEvent, NetAccept, NetTransport, SessionEvent};
-use netservices::session::{ProtocolArtifact, Socks5Session};
+use netservices::session::ProtocolArtifact;
 use netservices::{NetConnection, NetProtocol, NetReader, NetWriter};
 use reactor::Timestamp;
 
 use radicle::collections::RandomMap;
-use radicle::node::{address, routing, NodeId};
+use radicle::node::{address, config, routing, NodeId};
 use radicle::storage::WriteStorage;
 
 use crate::crypto::Signer;
+use crate::nat;
 use crate::prelude::Deserializer;
 use crate::service;
 use crate::service::io::Io;
-use crate::service::{session, DisconnectReason, Service};
+use crate::service::{session, DisconnectReason, Service, ServiceState as _};
 use crate::wire::frame;
 use crate::wire::frame::{Frame, FrameData, StreamId};
+use crate::wire::proxy::{HttpConnect, Proxy, ProxySession, Socks5};
 use crate::wire::Encode;
 use crate::worker;
 use crate::worker::{ChannelEvent, FetchRequest, FetchResult, Task, TaskResult};
@@ -57,14 +58,22 @@ pub enum Control {
     Worker(TaskResult),
     /// Flush data in the given stream to the remote.
     Flush { remote: NodeId, stream: StreamId },
+    /// A dial-back probe completed.
+    Probed {
+        remote: NodeId,
+        addr: net::SocketAddr,
+        reachable: bool,
+    },
+    /// A listening port was mapped to the given external address.
+    Mapped(net::SocketAddr),
 }
 
 /// Peer session type.
-pub type WireSession<G> = NetProtocol<NoiseState<G, Sha256>, Socks5Session<net::TcpStream>>;
+pub type WireSession<G> = NetProtocol<NoiseState<G, Sha256>, ProxySession<net::TcpStream>>;
 /// Peer session type (read-only).
-pub type WireReader = NetReader<Socks5Session<net::TcpStream>>;
+pub type WireReader = NetReader<ProxySession<net::TcpStream>>;
 /// Peer session type (write-only).
-pub type WireWriter<G> = NetWriter<NoiseState<G, Sha256>, Socks5Session<net::TcpStream>>;
+pub type WireWriter<G> = NetWriter<NoiseState<G, Sha256>, ProxySession<net::TcpStream>>;
 
 /// Reactor action.
 type Action<G> = reactor::Action<NetAccept<WireSession<G>>, NetTransport<WireSession<G>>>;
@@ -318,14 +327,14 @@ pub struct Wire<R, S, W, G: Signer + Ecdh> {
     service: Service<R, S, W, G>,
     /// Worker pool interface.
     worker: chan::Sender<Task>,
+    /// Dial-back probe requests.
+    probes: chan::Sender<nat::Probe>,
     /// Used for authentication.
     signer: G,
     /// Internal queue of actions to send to the reactor.
     actions: VecDeque<Action<G>>,
     /// Peer sessions.
     peers: Peers,
-    /// SOCKS5 proxy address.
-    proxy: net::SocketAddr,
 }
 
 impl<R, S, W, G> Wire<R, S, W, G>
@@ -338,8 +347,8 @@ where
     pub fn new(
         mut service: Service<R, S, W, G>,
         worker: chan::Sender<Task>,
+        probes: chan::Sender<nat::Probe>,
         signer: G,
-        proxy: net::SocketAddr,
         clock: LocalTime,
     ) -> Self {
         service
@@ -349,14 +358,15 @@ where
         Self {
             service,
             worker,
+            probes,
             signer,
-            proxy,
             actions: VecDeque::new(),
             peers: Peers(RandomMap::default()),
         }
     }
 
     pub fn listen(&mut self, socket: NetAccept<WireSession<G>>) {
+        self.service.listening(socket.local_addr());
         self.actions.push_back(Action::RegisterListener(socket));
     }
 
@@ -552,6 +562,7 @@ where
                     ..
                 }) = self.peers.get_mut(&fd)
                 {
+                    self.service.received_bytes(*nid, data.len());
                     inbox.input(&data);
 
                     loop {
@@ -606,6 +617,12 @@ where
                             })) => {
                                 self.service.received_message(*nid, msg);
                             }
+                            Ok(Some(Frame {
+                                data: FrameData::Unknown { type_id, .. },
+                                ..
+                            })) => {
+                                self.service.received_unknown(*nid, type_id);
+                            }
                             Ok(Some(Frame {
                                 stream,
                                 data: FrameData::Git(data),
@@ -652,6 +669,12 @@ where
             Control::User(cmd) => self.service.command(cmd),
             Control::Worker(result) => self.worker_result(result),
             Control::Flush { remote, stream } => self.flush(remote, stream),
+            Control::Probed {
+                remote,
+                addr,
+                reachable,
+            } => self.service.probed(remote, addr, reachable),
+            Control::Mapped(addr) => self.service.mapped(addr),
         }
     }
 
@@ -783,12 +806,14 @@ where
                         break;
                     }
 
+                    let config = self.service.config();
+
                     match dial::<G>(
                         addr.to_inner(),
                         node_id,
                         self.signer.clone(),
-                        self.proxy.into(),
-                        false,
+                        &config.tor,
+                        config.proxy.as_ref(),
                     )
                     .and_then(|session| {
                         NetTransport::<WireSession<G>>::with_session(session, Link::Outbound)
@@ -824,10 +849,22 @@ where
                 Io::Wakeup(d) => {
                     self.actions.push_back(reactor::Action::SetTimer(d.into()));
                 }
+                Io::Probe(remote, addr) => {
+                    match self.probes.try_send(nat::Probe { remote, addr }) {
+                        Ok(()) => {}
+                        Err(chan::TrySendError::Full(_)) => {
+                            log::warn!(target: "wire", "Too many pending probes; dropping probe of {addr}");
+                        }
+                        Err(chan::TrySendError::Disconnected(_)) => {
+                            log::error!(target: "wire", "Probe thread is disconnected; cannot probe {addr}");
+                        }
+                    }
+                }
                 Io::Fetch {
                     rid,
                     remote,
                     namespaces,
+                    blocked,
                 } => {
                     log::trace!(target: "wire", "Processing fetch for {rid} from {remote}..");
 
@@ -849,6 +886,7 @@ where
                         fetch: FetchRequest::Initiator {
                             rid,
                             namespaces,
+                            blocked,
                             remote,
                         },
                         stream,
@@ -871,30 +909,49 @@ where
                 }
             }
         }
-        self.actions.pop_front()
+        let action = self.actions.pop_front();
+
+        if let Some(Action::Send(fd, data)) = &action {
+            if let Some(Peer::Connected { nid, .. }) = self.peers.get_mut(fd) {
+                self.service.sent_bytes(*nid, data.len());
+            }
+        }
+        action
     }
 }
 
 /// Establish a new outgoing connection.
+///
+/// Connections to `.onion` addresses always go through the Tor proxy. Other connections go
+/// through the Tor proxy if it is forced, otherwise through the given proxy, if any.
 pub fn dial<G: Signer + Ecdh<Pk = NodeId>>(
     remote_addr: NetAddr<HostName>,
     remote_id: <G as EcSk>::Pk,
     signer: G,
-    proxy_addr: NetAddr<InetHost>,
-    force_proxy: bool,
+    tor: &config::Tor,
+    proxy: Option<&config::Proxy>,
 ) -> io::Result<WireSession<G>> {
-    let connection = if force_proxy {
-        net::TcpStream::connect_nonblocking(proxy_addr)?
+    let (connection, handshake) = if tor.force || remote_addr.requires_proxy() {
+        (
+            net::TcpStream::connect_nonblocking(tor.proxy.into())?,
+            Proxy::Socks5(Socks5::new(remote_addr, None)),
+        )
+    } else if let Some(proxy) = proxy {
+        let connection = net::TcpStream::connect_nonblocking(proxy.addr().into())?;
+        let handshake = match proxy {
+            config::Proxy::Socks5 { auth, .. } => {
+                Proxy::Socks5(Socks5::new(remote_addr, auth.clone()))
+            }
+            config::Proxy::Http { .. } => Proxy::Http(HttpConnect::new(remote_addr)),
+        };
+        (connection, handshake)
     } else {
-        net::TcpStream::connect_nonblocking(remote_addr.connection_addr(proxy_addr))?
+        (
+            net::TcpStream::connect_nonblocking(remote_addr.connection_addr(tor.proxy.into()))?,
+            Proxy::Direct,
+        )
     };
-    Ok(session::<G>(
-        remote_addr,
-        Some(remote_id),
-        connection,
-        signer,
-        force_proxy,
-    ))
+    Ok(session::<G>(Some(remote_id), connection, signer, handshake))
 }
 
 /// Accept a new connection.
@@ -902,25 +959,17 @@ pub fn accept<G: Signer + Ecdh<Pk = NodeId>>(
     connection: net::TcpStream,
     signer: G,
 ) -> WireSession<G> {
-    session::<G>(
-        connection.remote_addr().into(),
-        None,
-        connection,
-        signer,
-        false,
-    )
+    session::<G>(None, connection, signer, Proxy::Direct)
 }
 
 /// Create a new [`WireSession`].
 fn session<G: Signer + Ecdh<Pk = NodeId>>(
-    remote_addr: NetAddr<HostName>,
     remote_id: Option<NodeId>,
     connection: net::TcpStream,
     signer: G,
-    force_proxy: bool,
+    handshake: Proxy,
 ) -> WireSession<G> {
-    let socks5 = socks5::Socks5::with(remote_addr, force_proxy);
-    let proxy = Socks5Session::with(connection, socks5);
+    let proxy = ProxySession::with(connection, handshake);
     let pair = G::generate_keypair();
     let keyset = Keyset {
         e: pair.0,
@@ -0,0 +1,470 @@
+//! Proxy protocols used to establish outbound connections.
+//!
+//! The handshake is driven by the reactor, as part of the session handshake, which means it
+//! must never block. The proxy handshake completes before the Noise handshake starts.
+use std::net;
+
+use cyphernet::addr::{HostName, NetAddr};
+use netservices::session::ZeroInit;
+use netservices::{NetProtocol, NetStateMachine};
+use thiserror::Error;
+
+use radicle::node::config::ProxyAuth;
+
+/// SOCKS protocol version.
+const SOCKS_VERSION: u8 = 0x05;
+/// "No authentication" method.
+const METHOD_NO_AUTH: u8 = 0x00;
+/// "Username/password" authentication method.
+const METHOD_USER_PASS: u8 = 0x02;
+/// Username/password authentication sub-negotiation version.
+const USER_PASS_VERSION: u8 = 0x01;
+/// `CONNECT` command.
+const COMMAND_CONNECT: u8 = 0x01;
+/// IPv4 address type.
+const ATYP_IPV4: u8 = 0x01;
+/// Domain name address type.
+const ATYP_DOMAIN: u8 = 0x03;
+/// IPv6 address type.
+const ATYP_IPV6: u8 = 0x04;
+/// Maximum size of an HTTP proxy response header.
+const MAX_HTTP_RESPONSE_SIZE: usize = 8 * 1024;
+
+/// A session that is (maybe) established through a proxy.
+pub type ProxySession<S> = NetProtocol<Proxy, S>;
+
+/// Proxy handshake error.
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("unsupported SOCKS version {0}")]
+    UnsupportedVersion(u8),
+    #[error("SOCKS5 proxy requires an unsupported authentication method ({0:#x})")]
+    UnsupportedMethod(u8),
+    #[error("SOCKS5 proxy authentication failed")]
+    AuthenticationFailed,
+    #[error("SOCKS5 proxy refused connection with reply code {0:#x}")]
+    Refused(u8),
+    #[error("invalid SOCKS5 address type {0:#x}")]
+    InvalidAddressType(u8),
+    #[error("invalid SOCKS5 reply")]
+    InvalidReply,
+    #[error("HTTP proxy refused connection: {0}")]
+    HttpRefused(String),
+    #[error("invalid HTTP proxy response")]
+    InvalidHttpResponse,
+    #[error("`{0}` is too long")]
+    TooLong(String),
+}
+
+/// Proxy handshake.
+#[derive(Debug)]
+pub enum Proxy {
+    /// No proxy is used; there is nothing to do.
+    Direct,
+    /// Connect via a SOCKS5 proxy.
+    Socks5(Socks5),
+    /// Connect via an HTTP proxy.
+    Http(HttpConnect),
+}
+
+impl NetStateMachine for Proxy {
+    const NAME: &'static str = "proxy";
+
+    type Init = ZeroInit;
+    type Artifact = ();
+    type Error = Error;
+
+    fn init(&mut self, _: Self::Init) {}
+
+    fn next_read_len(&self) -> usize {
+        match self {
+            Self::Direct => 0,
+            Self::Socks5(socks5) => socks5.next_read_len(),
+            Self::Http(http) => http.next_read_len(),
+        }
+    }
+
+    fn advance(&mut self, input: &[u8]) -> Result<Vec<u8>, Self::Error> {
+        match self {
+            Self::Direct => Ok(vec![]),
+            Self::Socks5(socks5) => socks5.advance(input),
+            Self::Http(http) => http.advance(input),
+        }
+    }
+
+    fn artifact(&self) -> Option<Self::Artifact> {
+        let established = match self {
+            Self::Direct => true,
+            Self::Socks5(socks5) => socks5.is_established(),
+            Self::Http(http) => http.is_established(),
+        };
+        established.then_some(())
+    }
+
+    fn is_init(&self) -> bool {
+        true
+    }
+}
+
+/// SOCKS5 handshake state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum State {
+    /// Nothing was sent yet.
+    Initial,
+    /// Greeting was sent, awaiting the method selection.
+    Greeted,
+    /// Credentials were sent, awaiting the authentication status.
+    Authenticating,
+    /// Connect request was sent, awaiting the reply header.
+    Requested,
+    /// Awaiting the length of the bound domain name.
+    DomainLen,
+    /// Awaiting the bound address and port, of the given length.
+    BoundAddr(usize),
+    /// The handshake is complete.
+    Established,
+}
+
+/// SOCKS5 client handshake (RFC 1928), with optional username/password
+/// authentication (RFC 1929).
+#[derive(Debug)]
+pub struct Socks5 {
+    /// Address of the remote peer, as requested from the proxy.
+    addr: NetAddr<HostName>,
+    /// Credentials, if the proxy requires authentication.
+    auth: Option<ProxyAuth>,
+    /// Handshake state.
+    state: State,
+}
+
+impl Socks5 {
+    /// Create a new handshake for connecting to the given address.
+    pub fn new(addr: NetAddr<HostName>, auth: Option<ProxyAuth>) -> Self {
+        Self {
+            addr,
+            auth,
+            state: State::Initial,
+        }
+    }
+
+    /// Whether the handshake is complete.
+    pub fn is_established(&self) -> bool {
+        self.state == State::Established
+    }
+
+    fn next_read_len(&self) -> usize {
+        match self.state {
+            State::Initial => 0,
+            State::Greeted => 2,
+            State::Authenticating => 2,
+            State::Requested => 4,
+            State::DomainLen => 1,
+            State::BoundAddr(len) => len,
+            State::Established => 0,
+        }
+    }
+
+    fn advance(&mut self, input: &[u8]) -> Result<Vec<u8>, Error> {
+        match self.state {
+            State::Initial => {
+                self.state = State::Greeted;
+
+                if self.auth.is_some() {
+                    Ok(vec![SOCKS_VERSION, 0x2, METHOD_NO_AUTH, METHOD_USER_PASS])
+                } else {
+                    Ok(vec![SOCKS_VERSION, 0x1, METHOD_NO_AUTH])
+                }
+            }
+            State::Greeted => {
+                let [version, method] = input else {
+                    return Err(Error::InvalidReply);
+                };
+                if *version != SOCKS_VERSION {
+                    return Err(Error::UnsupportedVersion(*version));
+                }
+                match (*method, &self.auth) {
+                    (METHOD_NO_AUTH, _) => {
+                        self.state = State::Requested;
+                        self.request()
+                    }
+                    (METHOD_USER_PASS, Some(auth)) => {
+                        self.state = State::Authenticating;
+                        credentials(auth)
+                    }
+                    (other, _) => Err(Error::UnsupportedMethod(other)),
+                }
+            }
+            State::Authenticating => {
+                let [version, status] = input else {
+                    return Err(Error::InvalidReply);
+                };
+                if *version != USER_PASS_VERSION {
+                    return Err(Error::InvalidReply);
+                }
+                if *status != 0x0 {
+                    return Err(Error::AuthenticationFailed);
+                }
+                self.state = State::Requested;
+                self.request()
+            }
+            State::Requested => {
+                let [version, reply, _, atyp] = input else {
+                    return Err(Error::InvalidReply);
+                };
+                if *version != SOCKS_VERSION {
+                    return Err(Error::UnsupportedVersion(*version));
+                }
+                if *reply != 0x0 {
+                    return Err(Error::Refused(*reply));
+                }
+                // Nb. The bound address is followed by a two-byte port.
+                self.state = match *atyp {
+                    ATYP_IPV4 => State::BoundAddr(4 + 2),
+                    ATYP_IPV6 => State::BoundAddr(16 + 2),
+                    ATYP_DOMAIN => State::DomainLen,
+                    other => return Err(Error::InvalidAddressType(other)),
+                };
+                Ok(vec![])
+            }
+            State::DomainLen => {
+                let [len] = input else {
+                    return Err(Error::InvalidReply);
+                };
+                self.state = State::BoundAddr(*len as usize + 2);
+
+                Ok(vec![])
+            }
+            State::BoundAddr(_) => {
+                // We don't care about the address the proxy bound to.
+                self.state = State::Established;
+
+                Ok(vec![])
+            }
+            State::Established => Ok(vec![]),
+        }
+    }
+
+    /// Encode the connect request for our remote address.
+    fn request(&self) -> Result<Vec<u8>, Error> {
+        let mut req = vec![SOCKS_VERSION, COMMAND_CONNECT, 0x0];
+
+        match &self.addr.host {
+            HostName::Ip(net::IpAddr::V4(ip)) => {
+                req.push(ATYP_IPV4);
+                req.extend(ip.octets());
+            }
+            HostName::Ip(net::IpAddr::V6(ip)) => {
+                req.push(ATYP_IPV6);
+                req.extend(ip.octets());
+            }
+            // Domain names and onion addresses are resolved by the proxy.
+            other => {
+                req.push(ATYP_DOMAIN);
+                push_short(&mut req, &other.to_string())?;
+            }
+        }
+        req.extend(self.addr.port.to_be_bytes());
+
+        Ok(req)
+    }
+}
+
+/// Encode the username/password authentication request.
+fn credentials(auth: &ProxyAuth) -> Result<Vec<u8>, Error> {
+    let mut req = vec![USER_PASS_VERSION];
+
+    push_short(&mut req, &auth.username)?;
+    push_short(&mut req, &auth.password)?;
+
+    Ok(req)
+}
+
+/// Push a string prefixed by its one-byte length.
+fn push_short(buf: &mut Vec<u8>, s: &str) -> Result<(), Error> {
+    let len = u8::try_from(s.len()).map_err(|_| Error::TooLong(s.to_owned()))?;
+
+    buf.push(len);
+    buf.extend(s.as_bytes());
+
+    Ok(())
+}
+
+/// HTTP proxy handshake, using the `CONNECT` method (RFC 9110).
+#[derive(Debug)]
+pub struct HttpConnect {
+    /// Address of the remote peer, as requested from the proxy.
+    addr: NetAddr<HostName>,
+    /// Response received so far, or `None` if the request wasn't sent yet.
+    response: Option<Vec<u8>>,
+    /// Whether the handshake is complete.
+    established: bool,
+}
+
+impl HttpConnect {
+    /// Create a new handshake for connecting to the given address.
+    pub fn new(addr: NetAddr<HostName>) -> Self {
+        Self {
+            addr,
+            response: None,
+            established: false,
+        }
+    }
+
+    /// Whether the handshake is complete.
+    pub fn is_established(&self) -> bool {
+        self.established
+    }
+
+    fn next_read_len(&self) -> usize {
+        // Since we can't know the length of the response in advance, and mustn't read past it,
+        // we read it byte by byte. The response is small, so this is fine.
+        if self.response.is_none() || self.established {
+            0
+        } else {
+            1
+        }
+    }
+
+    fn advance(&mut self, input: &[u8]) -> Result<Vec<u8>, Error> {
+        if self.established {
+            return Ok(vec![]);
+        }
+        let Some(response) = &mut self.response else {
+            let target = match &self.addr.host {
+                HostName::Ip(net::IpAddr::V6(ip)) => format!("[{ip}]:{}", self.addr.port),
+                _ => self.addr.to_string(),
+            };
+            self.response = Some(Vec::new());
+
+            return Ok(format!("CONNECT {target} HTTP/1.1\r\nHost: {target}\r\n\r\n").into_bytes());
+        };
+        response.extend_from_slice(input);
+
+        if response.len() > MAX_HTTP_RESPONSE_SIZE {
+            return Err(Error::InvalidHttpResponse);
+        }
+        if !response.ends_with(b"\r\n\r\n") {
+            return Ok(vec![]);
+        }
+        let response = String::from_utf8_lossy(response);
+        let status = response.lines().next().ok_or(Error::InvalidHttpResponse)?;
+        let mut parts = status.splitn(3, ' ');
+
+        match (parts.next(), parts.next()) {
+            (Some(version), Some(code)) if version.starts_with("HTTP/1.") => {
+                if !code.starts_with('2') {
+                    return Err(Error::HttpRefused(status.to_owned()));
+                }
+            }
+            _ => return Err(Error::InvalidHttpResponse),
+        }
+        self.established = true;
+
+        Ok(vec![])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_direct() {
+        assert_eq!(Proxy::Direct.artifact(), Some(()));
+    }
+
+    #[test]
+    fn test_socks5() {
+        let addr: NetAddr<HostName> = "seed.radicle.xyz:8776".parse().unwrap();
+        let mut socks5 = Socks5::new(addr, None);
+
+        assert!(!socks5.is_established());
+        assert_eq!(socks5.advance(&[]).unwrap(), vec![0x5, 0x1, 0x0]);
+        assert_eq!(socks5.next_read_len(), 2);
+
+        let req = socks5.advance(&[0x5, 0x0]).unwrap();
+        assert_eq!(&req[..5], &[0x5, 0x1, 0x0, ATYP_DOMAIN, 16]);
+        assert_eq!(&req[5..21], b"seed.radicle.xyz");
+        assert_eq!(&req[21..], &8776u16.to_be_bytes());
+
+        assert!(socks5
+            .advance(&[0x5, 0x0, 0x0, ATYP_IPV4])
+            .unwrap()
+            .is_empty());
+        assert_eq!(socks5.next_read_len(), 6);
+        assert!(socks5.advance(&[0; 6]).unwrap().is_empty());
+        assert!(socks5.is_established());
+    }
+
+    #[test]
+    fn test_socks5_auth() {
+        let addr: NetAddr<HostName> = "127.0.0.1:8776".parse().unwrap();
+        let auth = ProxyAuth {
+            username: String::from("alice"),
+            password: String::from("hunter2"),
+        };
+        let mut socks5 = Socks5::new(addr, Some(auth));
+
+        assert_eq!(socks5.advance(&[]).unwrap(), vec![0x5, 0x2, 0x0, 0x2]);
+        assert_eq!(
+            socks5.advance(&[0x5, 0x2]).unwrap(),
+            [&[0x1, 5][..], b"alice", &[7], b"hunter2"].concat()
+        );
+        assert_eq!(socks5.next_read_len(), 2);
+        assert_eq!(
+            socks5.advance(&[0x1, 0x0]).unwrap(),
+            vec![0x5, 0x1, 0x0, ATYP_IPV4, 127, 0, 0, 1, 0x22, 0x48]
+        );
+
+        let mut socks5 = Socks5::new("127.0.0.1:8776".parse().unwrap(), None);
+        socks5.advance(&[]).unwrap();
+
+        assert!(matches!(
+            socks5.advance(&[0x5, 0x2]),
+            Err(Error::UnsupportedMethod(0x2))
+        ));
+    }
+
+    #[test]
+    fn test_socks5_refused() {
+        let addr: NetAddr<HostName> = "seed.radicle.xyz:8776".parse().unwrap();
+        let mut socks5 = Socks5::new(addr, None);
+
+        socks5.advance(&[]).unwrap();
+        socks5.advance(&[0x5, 0x0]).unwrap();
+
+        assert!(matches!(
+            socks5.advance(&[0x5, 0x4, 0x0, ATYP_IPV4]),
+            Err(Error::Refused(0x4))
+        ));
+    }
+
+    #[test]
+    fn test_http_connect() {
+        let addr: NetAddr<HostName> = "seed.radicle.xyz:8776".parse().unwrap();
+        let mut http = HttpConnect::new(addr.clone());
+
+        assert_eq!(http.next_read_len(), 0);
+        assert_eq!(
+            http.advance(&[]).unwrap(),
+            b"CONNECT seed.radicle.xyz:8776 HTTP/1.1\r\nHost: seed.radicle.xyz:8776\r\n\r\n"
+        );
+        for byte in b"HTTP/1.1 200 Connection established\r\n\r\n" {
+            assert!(!http.is_established());
+            assert_eq!(http.next_read_len(), 1);
+            assert!(http.advance(&[*byte]).unwrap().is_empty());
+        }
+        assert!(http.is_established());
+        assert_eq!(http.next_read_len(), 0);
+
+        let mut http = HttpConnect::new(addr);
+        http.advance(&[]).unwrap();
+
+        let result = b"HTTP/1.1 403 Forbidden\r\n\r\n"
+            .iter()
+            .map(|b| http.advance(&[*b]))
+            .last()
+            .unwrap();
+        assert!(matches!(result, Err(Error::HttpRefused(_))));
+    }
+}
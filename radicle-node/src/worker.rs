@@ -55,6 +55,20 @@ impl FetchError {
     pub fn is_timeout(&self) -> bool {
         matches!(self, FetchError::Io(e) if e.kind() == io::ErrorKind::TimedOut)
     }
+
+    /// Check if the remote is to blame for the error, eg. because it timed out or sent
+    /// invalid data, as opposed to a local failure.
+    pub fn is_remote(&self) -> bool {
+        use fetch::error::{Transfer, Transition};
+
+        match self {
+            Self::CommandFailed { .. } => true,
+            Self::Io(_) => self.is_timeout(),
+            Self::StagingInit(_) => false,
+            Self::StagingTransition(e) => !matches!(e, Transition::Git(_)),
+            Self::StagingTransfer(e) => matches!(e, Transfer::NoDelegates),
+        }
+    }
 }
 
 /// Error returned by fetch responder.
@@ -85,6 +99,8 @@ pub enum FetchRequest {
         rid: Id,
         /// Namespaces to fetch.
         namespaces: Namespaces,
+        /// Remotes blocked for this repository, whose refs are not fetched.
+        blocked: HashSet<NodeId>,
         /// Remote peer we are interacting with.
         remote: NodeId,
     },
@@ -190,10 +206,11 @@ impl Worker {
             FetchRequest::Initiator {
                 rid,
                 namespaces,
+                blocked,
                 remote,
             } => {
                 log::debug!(target: "worker", "Worker processing outgoing fetch for {}", rid);
-                let result = self.fetch(rid, remote, stream, &namespaces, channels);
+                let result = self.fetch(rid, remote, stream, namespaces, blocked, channels);
 
                 FetchResult::Initiator { rid, result }
             }
@@ -222,11 +239,12 @@ impl Worker {
         rid: Id,
         remote: NodeId,
         stream: StreamId,
-        namespaces: &Namespaces,
+        namespaces: Namespaces,
+        blocked: HashSet<NodeId>,
         mut channels: Channels,
     ) -> Result<(Vec<RefUpdate>, HashSet<NodeId>), FetchError> {
         let staging =
-            fetch::StagingPhaseInitial::new(&self.storage, rid, self.nid, namespaces.clone())?;
+            fetch::StagingPhaseInitial::new(&self.storage, rid, self.nid, namespaces, blocked)?;
         let refs = if staging.repo.is_cloning() {
             match self._fetch(
                 &staging.repo,
@@ -35,6 +35,8 @@ pub struct StagingPhaseInitial<'a> {
     nid: NodeId,
     /// The `Namespaces` passed by the fetching caller.
     pub(super) namespaces: Namespaces,
+    /// The remotes blocked for this repository, whose refs are not fetched.
+    blocked: HashSet<NodeId>,
     _tmp: tempfile::TempDir,
 }
 
@@ -133,6 +135,7 @@ impl<'a> StagingPhaseInitial<'a> {
         rid: Id,
         nid: NodeId,
         namespaces: Namespaces,
+        blocked: HashSet<NodeId>,
     ) -> Result<Self, error::Init> {
         let tmp = tempfile::TempDir::new()?;
         log::debug!(target: "worker", "Staging fetch in {:?}", tmp.path());
@@ -143,6 +146,7 @@ impl<'a> StagingPhaseInitial<'a> {
             nid,
             production,
             namespaces,
+            blocked,
             _tmp: tmp,
         })
     }
@@ -198,7 +202,22 @@ impl<'a> StagingPhaseInitial<'a> {
                 trusted.extend(delegates);
                 FinalStagedRepository::Cloning { repo, trusted }
             }
-            StagedRepository::Fetching(repo) => FinalStagedRepository::Fetching { repo, refs },
+            StagedRepository::Fetching(repo) => {
+                // Nb. blocked remotes' special refs may have been fetched by the initial phase,
+                // since we can't exclude them from a glob. Dropping their refs here ensures
+                // they are neither fetched nor transferred. Delegates can't be blocked, since
+                // we need them to verify the repository.
+                let delegates = repo.delegates()?;
+                let refs = refs
+                    .into_iter()
+                    .filter(|r| match NodeId::from_namespaced(r) {
+                        Ok(nid) => !self.blocked.contains(&nid) || delegates.contains(&nid.into()),
+                        Err(_) => true,
+                    })
+                    .collect();
+
+                FinalStagedRepository::Fetching { repo, refs }
+            }
         };
 
         Ok(StagingPhaseFinal {
@@ -363,6 +382,7 @@ impl<'a> StagingPhaseFinal<'a> {
                         // Then add the special refs.
                         let id = ns.join(&*radicle::git::refs::storage::IDENTITY_BRANCH);
                         let sigrefs = ns.join(&*radicle::git::refs::storage::SIGREFS_BRANCH);
+                        let person = ns.join(&*radicle::git::refs::storage::PERSON_BRANCH);
 
                         refspecs.push((
                             remote.id,
@@ -374,6 +394,19 @@ impl<'a> StagingPhaseFinal<'a> {
                             }
                             .to_string(),
                         ));
+                        // Nb. The person branch is optional, so only transfer it if it was
+                        // fetched. Like the identity branch, it can be force-updated.
+                        if self.repo.backend.find_reference(person.as_str()).is_ok() {
+                            refspecs.push((
+                                remote.id,
+                                Refspec {
+                                    src: person.clone().into(),
+                                    dst: person.into(),
+                                    force: true,
+                                }
+                                .to_string(),
+                            ));
+                        }
                         refspecs.push((
                             remote.id,
                             Refspec {
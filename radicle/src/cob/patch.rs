@@ -23,6 +23,7 @@ use crate::crypto::{PublicKey, Signer};
 use crate::git;
 use crate::identity;
 use crate::identity::doc::DocError;
+use crate::identity::person::{self, Persona};
 use crate::identity::PayloadError;
 use crate::prelude::*;
 
@@ -113,6 +114,9 @@ pub enum Action {
         verdict: Option<Verdict>,
         #[serde(default, skip_serializing_if = "Vec::is_empty")]
         labels: Vec<Label>,
+        /// Code comments submitted along with the review.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        comments: Vec<CodeComment>,
     },
     #[serde(rename = "review.edit")]
     ReviewEdit {
@@ -583,17 +587,31 @@ impl store::FromHistory for Patch {
                     ref summary,
                     verdict,
                     labels,
+                    comments,
                 } => {
                     let Some(rev) = self.revisions.get_mut(&revision) else {
                         return Err(Error::Missing(revision));
                     };
                     if let Some(rev) = rev {
+                        let mut review =
+                            Review::new(verdict, summary.to_owned(), labels, timestamp);
+
+                        for (i, CodeComment { location, body }) in comments.into_iter().enumerate()
+                        {
+                            thread::comment(
+                                &mut review.comments,
+                                review_comment_id(op.id, i),
+                                author.id.into(),
+                                timestamp,
+                                body,
+                                None,
+                                Some(location),
+                                vec![],
+                            )?;
+                        }
                         // Nb. Applying two reviews by the same author is not allowed and
                         // results in the review being redacted.
-                        rev.reviews.insert(
-                            op.author,
-                            Some(Review::new(verdict, summary.to_owned(), labels, timestamp)),
-                        );
+                        rev.reviews.insert(op.author, Some(review));
                         // Update reviews index.
                         self.reviews.insert(op.id, Some((revision, op.author)));
                     }
@@ -673,7 +691,11 @@ impl store::FromHistory for Patch {
 
                     match self.target() {
                         MergeTarget::Delegates => {
-                            if !doc.is_delegate(&op.author) {
+                            // Nb. Devices of a delegate's person identity may merge on its
+                            // behalf. Person branches aren't pinned by the operation, so devices
+                            // are those of the *current* person identities: once a device is
+                            // unlinked, its merges are rejected, retroactively.
+                            if person::delegate(&doc, &op.author, repo)?.is_none() {
                                 return Err(Error::InvalidMerge(op.id));
                             }
                             let proj = doc.project()?;
@@ -702,15 +724,18 @@ impl store::FromHistory for Patch {
                         },
                     );
 
-                    let mut merges = self.merges.iter().fold(
-                        HashMap::<(RevisionId, git::Oid), usize>::new(),
-                        |mut acc, (_, merge)| {
-                            *acc.entry((merge.revision, merge.commit)).or_default() += 1;
-                            acc
-                        },
-                    );
+                    // Count each delegate once, even if several of its devices merged.
+                    let mut merges = HashMap::<(RevisionId, git::Oid), BTreeSet<ActorId>>::new();
+                    for (author, merge) in self.merges.iter() {
+                        if let Some(delegate) = person::delegate(&doc, author, repo)? {
+                            merges
+                                .entry((merge.revision, merge.commit))
+                                .or_default()
+                                .insert(delegate);
+                        }
+                    }
                     // Discard revisions that weren't merged by a threshold of delegates.
-                    merges.retain(|_, count| *count >= doc.threshold);
+                    merges.retain(|_, delegates| delegates.len() >= doc.threshold);
 
                     match merges.into_keys().collect::<Vec<_>>().as_slice() {
                         [] => {
@@ -923,6 +948,43 @@ impl Revision {
     pub fn review(&self, author: &ActorId) -> Option<&Review> {
         self.reviews.get(author).and_then(|o| o.as_ref())
     }
+
+    /// Review verdicts of this revision, counting each person once.
+    ///
+    /// When several devices of the same person reviewed the revision, only the latest of
+    /// their reviews counts. Verdicts are keyed by the device that gave them.
+    pub fn verdicts<R: ReadRepository>(
+        &self,
+        repo: &R,
+    ) -> Result<BTreeMap<ActorId, Option<Verdict>>, DocError> {
+        let mut verdicts = BTreeMap::new();
+        // The identity of each person that reviewed, with their latest review.
+        let mut persons: Vec<(Persona, ActorId, &Review)> = Vec::new();
+
+        for (reviewer, review) in self.reviews() {
+            let Some(persona) = Persona::load(reviewer, repo)? else {
+                verdicts.insert(*reviewer, review.verdict());
+                continue;
+            };
+            match persons.iter_mut().find(|(p, _, _)| p.is_same(&persona)) {
+                Some((_, _, latest)) if latest.timestamp() >= review.timestamp() => {}
+                Some((_, device, latest)) => {
+                    *device = *reviewer;
+                    *latest = review;
+                }
+                None => {
+                    persons.push((persona, *reviewer, review));
+                }
+            }
+        }
+        verdicts.extend(
+            persons
+                .into_iter()
+                .map(|(_, reviewer, review)| (reviewer, review.verdict())),
+        );
+
+        Ok(verdicts)
+    }
 }
 
 /// Patch state.
@@ -1025,6 +1087,29 @@ pub struct CodeLocation {
     pub new: Option<CodeRange>,
 }
 
+/// Comment on a code location, submitted as part of a review.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeComment {
+    /// Location of the code commented on.
+    pub location: CodeLocation,
+    /// Comment body.
+    pub body: String,
+}
+
+/// Get the id of a code comment submitted as part of a review.
+///
+/// Since all comments of a review are created by the same operation, they can't use the
+/// operation id like other comments do. Instead, their id is derived from the review id and
+/// the position of the comment in the review.
+pub fn review_comment_id(review: EntryId, index: usize) -> CommentId {
+    let data = [review.as_bytes(), &(index as u64).to_be_bytes()].concat();
+    let oid = git::raw::Oid::hash_object(git::raw::ObjectType::Blob, &data)
+        .expect("review_comment_id: hashing is infallible");
+
+    oid.into()
+}
+
 /// A patch review on a revision.
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct Review {
@@ -1261,12 +1346,25 @@ impl store::Transaction<Patch> {
         verdict: Option<Verdict>,
         summary: Option<String>,
         labels: Vec<Label>,
+    ) -> Result<(), store::Error> {
+        self.review_with_comments(revision, verdict, summary, labels, vec![])
+    }
+
+    /// Review a patch revision, commenting on its code.
+    pub fn review_with_comments(
+        &mut self,
+        revision: RevisionId,
+        verdict: Option<Verdict>,
+        summary: Option<String>,
+        labels: Vec<Label>,
+        comments: Vec<CodeComment>,
     ) -> Result<(), store::Error> {
         self.push(Action::Review {
             revision,
             summary,
             verdict,
             labels,
+            comments,
         })
     }
 
@@ -1902,6 +2000,125 @@ mod test {
         assert_eq!(merge.commit, branch.base);
     }
 
+    #[test]
+    fn test_patch_merge_person_quorum() {
+        let alice = test::setup::NodeWithRepo::default();
+        let laptop = MockSigner::from_seed([0xfe; 32]);
+        let bob = MockSigner::from_seed([0xfd; 32]);
+        let checkout = alice.repo.checkout();
+        let branch = checkout.branch_with([("README", b"Hello World!")]);
+
+        // Bob is a delegate too, and merges need both delegates.
+        let mut doc = alice.repo.identity_doc().unwrap().1.verified().unwrap();
+        doc.delegate(bob.public_key());
+        doc.threshold = 2;
+        let (_, sig) = doc.sign(&alice.signer).unwrap();
+        doc.update(
+            alice.signer.public_key(),
+            "Add bob",
+            &[(alice.signer.public_key(), sig)],
+            alice.repo.raw(),
+        )
+        .unwrap();
+        alice.repo.set_identity_head().unwrap();
+
+        // Alice's laptop is one of her devices.
+        let person = identity::Doc::new_person(
+            identity::Person::new(String::from("Alice")).unwrap(),
+            nonempty::NonEmpty::from_vec(vec![
+                Did::from(alice.signer.public_key()),
+                Did::from(laptop.public_key()),
+            ])
+            .unwrap(),
+        )
+        .verified()
+        .unwrap();
+        let (_, node_sig) = person.sign(&alice.signer).unwrap();
+        let (_, laptop_sig) = person.sign(&laptop).unwrap();
+        let signatures = [
+            (alice.signer.public_key(), node_sig),
+            (laptop.public_key(), laptop_sig),
+        ];
+        for device in [alice.signer.public_key(), laptop.public_key()] {
+            person::publish(
+                &person,
+                device,
+                "Initialize person",
+                &signatures,
+                alice.repo.raw(),
+            )
+            .unwrap();
+        }
+
+        // Every merger has the merge commit on their default branch.
+        for device in [laptop.public_key(), bob.public_key()] {
+            alice
+                .repo
+                .raw()
+                .reference(
+                    &format!("refs/namespaces/{device}/refs/heads/master"),
+                    *branch.base,
+                    true,
+                    "Set default branch",
+                )
+                .unwrap();
+        }
+
+        let mut patches = Patches::open(&*alice.repo).unwrap();
+        let mut patch = patches
+            .create(
+                "My first patch",
+                "Blah blah blah.",
+                MergeTarget::Delegates,
+                branch.base,
+                branch.oid,
+                &[],
+                &alice.signer,
+            )
+            .unwrap();
+        let id = patch.id;
+        let (rid, _) = patch.latest();
+        let rid = *rid;
+
+        // Both of Alice's devices merging only counts as one delegate.
+        patch.merge(rid, branch.base, &alice.signer).unwrap();
+        patch.merge(rid, branch.base, &laptop).unwrap();
+
+        let patch = patches.get(&id).unwrap().unwrap();
+        assert_eq!(patch.merges().count(), 2);
+        assert!(patch.is_open());
+
+        let mut patch = patches.get_mut(&id).unwrap();
+        patch.merge(rid, branch.base, &bob).unwrap();
+
+        let patch = patches.get(&id).unwrap().unwrap();
+        assert!(patch.is_merged());
+
+        // Once Alice unlinks her laptop, its merge is rejected, and the patch is no longer
+        // merged.
+        let person = identity::Doc::new_person(
+            identity::Person::new(String::from("Alice")).unwrap(),
+            nonempty::NonEmpty::new(Did::from(alice.signer.public_key())),
+        )
+        .verified()
+        .unwrap();
+        let (_, sig) = person.sign(&alice.signer).unwrap();
+        person::publish(
+            &person,
+            alice.signer.public_key(),
+            "Unlink laptop",
+            &[(alice.signer.public_key(), sig)],
+            alice.repo.raw(),
+        )
+        .unwrap();
+
+        let patch = patches.get(&id).unwrap().unwrap();
+        assert!(!patch
+            .merges()
+            .any(|(author, _)| author == laptop.public_key()));
+        assert!(patch.is_open());
+    }
+
     #[test]
     fn test_patch_review() {
         let alice = test::setup::NodeWithRepo::default();
@@ -1941,6 +2158,175 @@ mod test {
         assert_eq!(review.summary(), Some("LGTM"));
     }
 
+    #[test]
+    fn test_patch_review_verdicts() {
+        let alice = test::setup::NodeWithRepo::default();
+        let laptop = MockSigner::from_seed([0xfe; 32]);
+        let bob = MockSigner::from_seed([0xfd; 32]);
+        let checkout = alice.repo.checkout();
+        let branch = checkout.branch_with([("README", b"Hello World!")]);
+
+        // Alice's node and laptop belong to the same person.
+        let doc = identity::Doc::new_person(
+            identity::Person::new(String::from("Alice")).unwrap(),
+            nonempty::NonEmpty::from_vec(vec![
+                Did::from(alice.signer.public_key()),
+                Did::from(laptop.public_key()),
+            ])
+            .unwrap(),
+        )
+        .verified()
+        .unwrap();
+        let (_, node_sig) = doc.sign(&alice.signer).unwrap();
+        let (_, laptop_sig) = doc.sign(&laptop).unwrap();
+        let signatures = [
+            (alice.signer.public_key(), node_sig),
+            (laptop.public_key(), laptop_sig),
+        ];
+        for device in [alice.signer.public_key(), laptop.public_key()] {
+            person::publish(
+                &doc,
+                device,
+                "Initialize person",
+                &signatures,
+                alice.repo.raw(),
+            )
+            .unwrap();
+        }
+
+        let mut patches = Patches::open(&*alice.repo).unwrap();
+        let mut patch = patches
+            .create(
+                "My first patch",
+                "Blah blah blah.",
+                MergeTarget::Delegates,
+                branch.base,
+                branch.oid,
+                &[],
+                &alice.signer,
+            )
+            .unwrap();
+
+        let (rid, _) = patch.latest();
+        let rid = *rid;
+        patch
+            .review(rid, Some(Verdict::Accept), None, vec![], &alice.signer)
+            .unwrap();
+        patch
+            .review(rid, Some(Verdict::Accept), None, vec![], &laptop)
+            .unwrap();
+        patch
+            .review(rid, Some(Verdict::Reject), None, vec![], &bob)
+            .unwrap();
+
+        let id = patch.id;
+        let patch = patches.get(&id).unwrap().unwrap();
+        let (_, revision) = patch.latest();
+        assert_eq!(revision.reviews().count(), 3);
+
+        let verdicts = revision.verdicts(&*alice.repo).unwrap();
+        assert_eq!(verdicts.len(), 2);
+        assert_eq!(verdicts.get(bob.public_key()), Some(&Some(Verdict::Reject)));
+        assert_eq!(
+            verdicts
+                .values()
+                .filter(|v| **v == Some(Verdict::Accept))
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_patch_review_verdicts_impersonation() {
+        let alice = test::setup::NodeWithRepo::default();
+        let eve = MockSigner::from_seed([0xfc; 32]);
+        let checkout = alice.repo.checkout();
+        let branch = checkout.branch_with([("README", b"Hello World!")]);
+
+        // Alice publishes an identity with only her node in it.
+        let doc = identity::Doc::new_person(
+            identity::Person::new(String::from("Alice")).unwrap(),
+            nonempty::NonEmpty::new(Did::from(alice.signer.public_key())),
+        )
+        .verified()
+        .unwrap();
+        let (_, sig) = doc.sign(&alice.signer).unwrap();
+        let alice_person = person::publish(
+            &doc,
+            alice.signer.public_key(),
+            "Initialize person",
+            &[(alice.signer.public_key(), sig)],
+            alice.repo.raw(),
+        )
+        .unwrap();
+
+        // Eve builds on top of Alice's identity, and adds herself to it.
+        alice
+            .repo
+            .raw()
+            .reference(
+                git::refs::storage::person(eve.public_key()).as_str(),
+                *alice_person,
+                true,
+                "Copy Alice's person",
+            )
+            .unwrap();
+        let doc = identity::Doc::new_person(
+            identity::Person::new(String::from("Alice")).unwrap(),
+            nonempty::NonEmpty::from_vec(vec![
+                Did::from(alice.signer.public_key()),
+                Did::from(eve.public_key()),
+            ])
+            .unwrap(),
+        )
+        .verified()
+        .unwrap();
+        let (_, sig) = doc.sign(&eve).unwrap();
+        person::publish(
+            &doc,
+            eve.public_key(),
+            "Impersonate Alice",
+            &[(eve.public_key(), sig)],
+            alice.repo.raw(),
+        )
+        .unwrap();
+
+        let mut patches = Patches::open(&*alice.repo).unwrap();
+        let mut patch = patches
+            .create(
+                "My first patch",
+                "Blah blah blah.",
+                MergeTarget::Delegates,
+                branch.base,
+                branch.oid,
+                &[],
+                &alice.signer,
+            )
+            .unwrap();
+
+        let (rid, _) = patch.latest();
+        let rid = *rid;
+        patch
+            .review(rid, Some(Verdict::Accept), None, vec![], &alice.signer)
+            .unwrap();
+        patch
+            .review(rid, Some(Verdict::Reject), None, vec![], &eve)
+            .unwrap();
+
+        let id = patch.id;
+        let patch = patches.get(&id).unwrap().unwrap();
+        let (_, revision) = patch.latest();
+
+        // Eve's review doesn't replace Alice's.
+        let verdicts = revision.verdicts(&*alice.repo).unwrap();
+        assert_eq!(verdicts.len(), 2);
+        assert_eq!(
+            verdicts.get(alice.signer.public_key()),
+            Some(&Some(Verdict::Accept))
+        );
+        assert_eq!(verdicts.get(eve.public_key()), Some(&Some(Verdict::Reject)));
+    }
+
     #[test]
     fn test_revision_review_merge_redacted() {
         let base = git::Oid::from_str("cb18e95ada2bb38aadd8e6cef0963ce37a87add3").unwrap();
@@ -1961,6 +2347,7 @@ mod test {
             summary: None,
             verdict: Some(Verdict::Accept),
             labels: vec![],
+            comments: vec![],
         });
         let a4 = alice.op::<Patch>(Action::Merge {
             revision: a1.id(),
@@ -2113,6 +2500,94 @@ mod test {
         assert_eq!(comment.location(), Some(&location));
     }
 
+    #[test]
+    fn test_patch_review_with_comments() {
+        let alice = test::setup::NodeWithRepo::default();
+        let checkout = alice.repo.checkout();
+        let branch = checkout.branch_with([("README", b"Hello World!")]);
+        let mut patches = Patches::open(&*alice.repo).unwrap();
+        let mut patch = patches
+            .create(
+                "My first patch",
+                "Blah blah blah.",
+                MergeTarget::Delegates,
+                branch.base,
+                branch.oid,
+                &[],
+                &alice.signer,
+            )
+            .unwrap();
+
+        let (rid, _) = patch.latest();
+        let rid = *rid;
+        let comments = vec![
+            CodeComment {
+                location: CodeLocation {
+                    path: PathBuf::from_str("README").unwrap(),
+                    old: None,
+                    new: Some(CodeRange::Lines { range: 1..2 }),
+                },
+                body: "Nice greeting".to_owned(),
+            },
+            CodeComment {
+                location: CodeLocation {
+                    path: PathBuf::from_str("README").unwrap(),
+                    old: None,
+                    new: Some(CodeRange::Chars {
+                        line: 1,
+                        range: 0..5,
+                    }),
+                },
+                body: "Maybe 'Hi'?".to_owned(),
+            },
+        ];
+        let review = patch
+            .transaction("Review", &alice.signer, |tx| {
+                tx.review_with_comments(
+                    rid,
+                    Some(Verdict::Accept),
+                    Some("LGTM".to_owned()),
+                    vec![],
+                    comments.clone(),
+                )
+            })
+            .unwrap();
+
+        let (_, revision) = patch.latest();
+        let r = revision.review(alice.signer.public_key()).unwrap();
+        assert_eq!(r.verdict(), Some(Verdict::Accept));
+        assert_eq!(r.summary(), Some("LGTM"));
+        assert_eq!(
+            r.comments()
+                .map(|(id, c)| (*id, c.body().to_owned(), c.location().cloned()))
+                .collect::<Vec<_>>(),
+            comments
+                .iter()
+                .enumerate()
+                .map(|(i, c)| (
+                    review_comment_id(review, i),
+                    c.body.clone(),
+                    Some(c.location.clone())
+                ))
+                .collect::<Vec<_>>()
+        );
+
+        // Comments submitted with the review can be edited like any other.
+        patch
+            .edit_review_comment(
+                review,
+                review_comment_id(review, 1),
+                "Or 'Hey'?",
+                &alice.signer,
+            )
+            .unwrap();
+
+        let (_, revision) = patch.latest();
+        let r = revision.review(alice.signer.public_key()).unwrap();
+        let (_, comment) = r.comments().nth(1).unwrap();
+        assert_eq!(comment.body(), "Or 'Hey'?");
+    }
+
     #[test]
     fn test_patch_review_remove_summary() {
         let alice = test::setup::NodeWithRepo::default();
@@ -2252,6 +2727,7 @@ mod test {
                 summary: None,
                 verdict: None,
                 labels: vec![],
+                comments: vec![],
             })
             .unwrap(),
             json!({
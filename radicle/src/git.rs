@@ -176,6 +176,14 @@ pub mod refs {
             Qualified::from_components(name::component!("rad"), name::component!("id"), None)
         });
 
+        /// Where a device's person identity document is stored.
+        ///
+        /// `refs/rad/person`
+        ///
+        pub static PERSON_BRANCH: Lazy<Qualified> = Lazy::new(|| {
+            Qualified::from_components(name::component!("rad"), name::component!("person"), None)
+        });
+
         /// Where the project's signed references are stored.
         ///
         /// `refs/rad/sigrefs`
@@ -200,6 +208,14 @@ pub mod refs {
             IDENTITY_BRANCH.with_namespace(remote.into())
         }
 
+        /// Get the branch where the device's person identity document is stored.
+        ///
+        /// `refs/namespaces/<remote>/refs/rad/person`
+        ///
+        pub fn person(remote: &RemoteId) -> Namespaced {
+            PERSON_BRANCH.with_namespace(remote.into())
+        }
+
         /// The collaborative object reference, identified by `typename` and `object_id`, under the given `remote`.
         ///
         /// `refs/namespaces/<remote>/refs/cobs/<typename>/<object_id>`
@@ -468,6 +484,21 @@ pub fn head(repo: &git2::Repository) -> Result<git2::Commit, git2::Error> {
     Ok(head)
 }
 
+/// Get the key a commit was signed with, if the commit has a valid SSH signature.
+/// Commits without a signature, or signed with GPG, return `None`.
+pub fn signer(repo: &git2::Repository, commit: Oid) -> Result<Option<PublicKey>, git2::Error> {
+    let (signature, data) = match repo.extract_signature(&commit, None) {
+        Ok(extracted) => extracted,
+        Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let signer = crate::crypto::ssh::ExtendedSignature::verify_pem(&*signature, "git", &data)
+        .ok()
+        .map(|sig| sig.key);
+
+    Ok(signer)
+}
+
 /// Write a tree with the given blob at the given path.
 pub fn write_tree<'r>(
     path: &Path,
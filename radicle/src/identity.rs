@@ -1,5 +1,6 @@
 pub mod did;
 pub mod doc;
+pub mod person;
 pub mod project;
 
 use std::collections::HashMap;
@@ -16,6 +17,7 @@ use crate::storage::{refs, ReadRepository, RemoteId};
 pub use crypto::PublicKey;
 pub use did::Did;
 pub use doc::{Doc, Id, IdError, PayloadError};
+pub use person::Person;
 pub use project::Project;
 
 /// Untrusted, well-formed input.
@@ -17,7 +17,7 @@ use crate::canonical::formatter::CanonicalFormatter;
 use crate::crypto;
 use crate::crypto::{Signature, Unverified, Verified};
 use crate::git;
-use crate::identity::{project::Project, Did};
+use crate::identity::{person::Person, project::Project, Did};
 use crate::storage;
 use crate::storage::git::trailers;
 use crate::storage::{ReadRepository, RemoteId};
@@ -83,6 +83,11 @@ impl PayloadId {
     pub fn project() -> Self {
         Self(String::from("xyz.radicle.project"))
     }
+
+    /// Person payload type.
+    pub fn person() -> Self {
+        Self(String::from("xyz.radicle.person"))
+    }
 }
 
 #[derive(Debug, Error)]
@@ -224,6 +229,17 @@ impl Doc<Verified> {
         Ok(proj)
     }
 
+    /// Get the person payload, if it exists and is valid, out of this document.
+    pub fn person(&self) -> Result<Person, PayloadError> {
+        let value = self
+            .payload
+            .get(&PayloadId::person())
+            .ok_or_else(|| PayloadError::NotFound(PayloadId::person()))?;
+        let person: Person = serde_json::from_value((**value).clone())?;
+
+        Ok(person)
+    }
+
     pub fn sign<G: crypto::Signer>(&self, signer: &G) -> Result<(git::Oid, Signature), DocError> {
         let (oid, _) = self.encode()?;
         let sig = signer.sign(oid.as_bytes());
@@ -265,7 +281,16 @@ impl Doc<Verified> {
         repo: &git2::Repository,
     ) -> Result<git::Oid, DocError> {
         let tree = git::write_tree(*PATH, doc, repo)?;
-        let oid = Doc::commit(remote, &tree, "Initialize Radicle\n", &[], signatures, repo)?;
+        let id_ref = git::refs::storage::id(remote);
+        let oid = Doc::commit(
+            remote,
+            &id_ref,
+            &tree,
+            "Initialize Radicle\n",
+            &[],
+            signatures,
+            repo,
+        )?;
 
         Ok(oid)
     }
@@ -281,13 +306,15 @@ impl Doc<Verified> {
         let tree = git::write_tree(*PATH, doc.as_slice(), repo)?;
         let id_ref = git::refs::storage::id(remote);
         let head = repo.find_reference(&id_ref)?.peel_to_commit()?;
-        let oid = Doc::commit(remote, &tree, msg, &[&head], signatures, repo)?;
+        let oid = Doc::commit(remote, &id_ref, &tree, msg, &[&head], signatures, repo)?;
 
         Ok(oid)
     }
 
-    fn commit(
+    /// Commit a document tree to the given identity branch, with the signatures as trailers.
+    pub(super) fn commit(
         remote: &RemoteId,
+        refname: &git::Namespaced,
         tree: &git2::Tree,
         msg: &str,
         parents: &[&git2::Commit],
@@ -315,8 +342,7 @@ impl Doc<Verified> {
                 .expect("in-memory writes don't fail");
         }
 
-        let id_ref = git::refs::storage::id(remote);
-        let oid = repo.commit(Some(&id_ref), &sig, &sig, &msg, tree, parents)?;
+        let oid = repo.commit(Some(refname), &sig, &sig, &msg, tree, parents)?;
 
         Ok(oid.into())
     }
@@ -349,6 +375,22 @@ impl Doc<Unverified> {
         }
     }
 
+    /// Create a person identity document, with the person's devices as delegates.
+    ///
+    /// Every device must sign the document for it to be a member of the person identity.
+    pub fn new_person(person: Person, devices: NonEmpty<Did>) -> Self {
+        let threshold = devices.len();
+        let person =
+            serde_json::to_value(person).expect("Doc::new_person: payload must be serializable");
+
+        Self {
+            payload: BTreeMap::from_iter([(PayloadId::person(), Payload::from(person))]),
+            delegates: devices,
+            threshold,
+            verified: PhantomData,
+        }
+    }
+
     pub fn from_json(bytes: &[u8]) -> Result<Self, DocError> {
         serde_json::from_slice(bytes).map_err(DocError::from)
     }
@@ -0,0 +1,280 @@
+//! Person identities.
+//!
+//! A person identity groups the device keys of a single person, so that someone working
+//! from several devices is treated as one author. It is an identity document with a
+//! `xyz.radicle.person` payload, whose delegates are the person's devices. Each device
+//! publishes the document under its own namespace, on the `rad/person` branch of the
+//! repositories it contributes to.
+//!
+//! A device is only a member of a person identity if it signed the current version of the
+//! document. When granting privileges, membership is resolved through the person branch of
+//! an already trusted key, eg. a repository delegate, so that a device can't vouch for itself.
+//! Likewise, two devices are only considered the same person if each is a member of the
+//! identity published by the other.
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::crypto::{PublicKey, Signature, Verified};
+use crate::git;
+use crate::identity::doc::{Doc, DocError, MAX_STRING_LENGTH, PATH};
+use crate::storage::{ReadRepository, RemoteId};
+
+/// A person-related error.
+#[derive(Debug, Error)]
+pub enum PersonError {
+    #[error("invalid name: {0}")]
+    Name(&'static str),
+}
+
+/// A "person" payload in an identity document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Person {
+    /// Person name.
+    name: String,
+}
+
+impl Person {
+    /// Create a new person payload.
+    pub fn new(name: String) -> Result<Self, PersonError> {
+        if name.is_empty() {
+            return Err(PersonError::Name("cannot be empty"));
+        }
+        if name.len() > MAX_STRING_LENGTH {
+            return Err(PersonError::Name("cannot exceed 255 bytes"));
+        }
+        Ok(Self { name })
+    }
+
+    /// Person name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// A person identity, as published by one of its devices.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Persona {
+    /// The device that published the identity.
+    pub device: PublicKey,
+    /// The person payload.
+    pub person: Person,
+    /// The devices that signed the current document, including the publishing device.
+    pub devices: BTreeSet<PublicKey>,
+}
+
+impl Persona {
+    /// Load the person identity published by `device` in the given repository.
+    ///
+    /// Returns `None` if the device didn't publish a person identity, or if it isn't a
+    /// signing member of the one it published.
+    pub fn load<R: ReadRepository>(device: &PublicKey, repo: &R) -> Result<Option<Self>, DocError> {
+        let head = match repo
+            .reference_oid(device, &git::refs::storage::PERSON_BRANCH)
+            .map_err(DocError::from)
+        {
+            Ok(head) => head,
+            Err(e) if e.is_not_found() => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let doc = Doc::<Verified>::load_at(head, repo)?;
+        let Ok(person) = doc.person() else {
+            return Ok(None);
+        };
+        let devices = doc
+            .delegates
+            .iter()
+            .map(|did| *did.as_key())
+            .filter(|key| doc.sigs.contains_key(key))
+            .collect::<BTreeSet<_>>();
+
+        if !devices.contains(device) {
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            device: *device,
+            person,
+            devices,
+        }))
+    }
+
+    /// Check whether the given key is one of this person's devices.
+    pub fn is_device(&self, key: &PublicKey) -> bool {
+        self.devices.contains(key)
+    }
+
+    /// Check whether `other` was published by the same person. Each publishing device must
+    /// be a member of the other's identity, so that a device can't pass itself off as
+    /// someone else's.
+    pub fn is_same(&self, other: &Persona) -> bool {
+        self.is_device(&other.device) && other.is_device(&self.device)
+    }
+}
+
+/// Get the delegate of the document that `key` acts for: `key` itself if it's a delegate,
+/// or the delegate whose person identity has `key` as one of its devices.
+pub fn delegate<V, R: ReadRepository>(
+    doc: &Doc<V>,
+    key: &PublicKey,
+    repo: &R,
+) -> Result<Option<PublicKey>, DocError> {
+    if doc.is_delegate(key) {
+        return Ok(Some(*key));
+    }
+    for delegate in doc.delegates.iter() {
+        if let Some(persona) = Persona::load(delegate.as_key(), repo)? {
+            if persona.is_device(key) {
+                return Ok(Some(*delegate.as_key()));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Check whether `key` is a delegate of the document, either directly, or as a device of
+/// a delegate's person identity.
+pub fn is_delegate<V, R: ReadRepository>(
+    doc: &Doc<V>,
+    key: &PublicKey,
+    repo: &R,
+) -> Result<bool, DocError> {
+    delegate(doc, key, repo).map(|d| d.is_some())
+}
+
+/// Publish a person identity document on the person branch of the `remote` device.
+///
+/// The signatures should be those of the person's devices, over the document blob.
+pub fn publish(
+    doc: &Doc<Verified>,
+    remote: &RemoteId,
+    msg: &str,
+    signatures: &[(&PublicKey, Signature)],
+    repo: &git2::Repository,
+) -> Result<git::Oid, DocError> {
+    let (_, bytes) = doc.encode()?;
+    let tree = git::write_tree(*PATH, bytes.as_slice(), repo)?;
+    let person_ref = git::refs::storage::person(remote);
+    let head = match repo.find_reference(&person_ref) {
+        Ok(r) => Some(r.peel_to_commit()?),
+        Err(e) if git::is_not_found_err(&e) => None,
+        Err(e) => return Err(e.into()),
+    };
+    let parents = head.iter().collect::<Vec<_>>();
+
+    Doc::commit(remote, &person_ref, &tree, msg, &parents, signatures, repo)
+}
+
+#[cfg(test)]
+mod test {
+    use nonempty::NonEmpty;
+    use radicle_crypto::test::signer::MockSigner;
+    use radicle_crypto::Signer as _;
+
+    use crate::identity::Did;
+    use crate::rad;
+    use crate::storage::git::transport;
+    use crate::storage::git::Storage;
+    use crate::storage::{ReadRepository as _, WriteRepository as _, WriteStorage as _};
+    use crate::test::fixtures;
+
+    use super::*;
+
+    #[test]
+    fn test_person_delegation() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let storage = Storage::open(tempdir.path().join("storage")).unwrap();
+
+        transport::local::register(storage.clone());
+
+        let laptop = MockSigner::from_seed([0xff; 32]);
+        let desktop = MockSigner::from_seed([0xfe; 32]);
+        let eve = MockSigner::from_seed([0xfd; 32]);
+        let (working, _) = fixtures::repository(tempdir.path().join("working"));
+        let (id, _, _) = rad::init(
+            &working,
+            "heartwood",
+            "Radicle Heartwood Protocol & Stack",
+            git::refname!("master"),
+            &laptop,
+            &storage,
+        )
+        .unwrap();
+        let repo = storage.repository_mut(id).unwrap();
+        let project = repo.identity_doc().unwrap().1.verified().unwrap();
+
+        assert!(is_delegate(&project, laptop.public_key(), &repo).unwrap());
+        assert!(!is_delegate(&project, desktop.public_key(), &repo).unwrap());
+
+        let person = Person::new(String::from("Alice")).unwrap();
+        let doc = Doc::new_person(
+            person.clone(),
+            NonEmpty::from_vec(vec![
+                Did::from(laptop.public_key()),
+                Did::from(desktop.public_key()),
+            ])
+            .unwrap(),
+        )
+        .verified()
+        .unwrap();
+
+        // Only signed by the desktop: the laptop isn't a member yet.
+        let (_, desktop_sig) = doc.sign(&desktop).unwrap();
+        publish(
+            &doc,
+            laptop.public_key(),
+            "Initialize person",
+            &[(desktop.public_key(), desktop_sig)],
+            repo.raw(),
+        )
+        .unwrap();
+
+        assert_eq!(Persona::load(laptop.public_key(), &repo).unwrap(), None);
+        assert!(!is_delegate(&project, desktop.public_key(), &repo).unwrap());
+
+        // Signed by both devices.
+        let (_, laptop_sig) = doc.sign(&laptop).unwrap();
+        publish(
+            &doc,
+            laptop.public_key(),
+            "Sign person",
+            &[
+                (laptop.public_key(), laptop_sig),
+                (desktop.public_key(), desktop_sig),
+            ],
+            repo.raw(),
+        )
+        .unwrap();
+
+        let persona = Persona::load(laptop.public_key(), &repo).unwrap().unwrap();
+        assert_eq!(persona.person, person);
+        assert!(persona.is_device(desktop.public_key()));
+        assert!(is_delegate(&project, desktop.public_key(), &repo).unwrap());
+        assert!(!is_delegate(&project, eve.public_key(), &repo).unwrap());
+
+        // A person branch published by a non-delegate doesn't grant delegation.
+        let doc = Doc::new_person(
+            Person::new(String::from("Eve")).unwrap(),
+            NonEmpty::from_vec(vec![
+                Did::from(eve.public_key()),
+                Did::from(laptop.public_key()),
+            ])
+            .unwrap(),
+        )
+        .verified()
+        .unwrap();
+        let (_, eve_sig) = doc.sign(&eve).unwrap();
+        publish(
+            &doc,
+            eve.public_key(),
+            "Initialize person",
+            &[(eve.public_key(), eve_sig)],
+            repo.raw(),
+        )
+        .unwrap();
+
+        assert!(!is_delegate(&project, eve.public_key(), &repo).unwrap());
+    }
+}
@@ -16,11 +16,14 @@ pub mod identity;
 pub mod node;
 pub mod profile;
 pub mod rad;
+pub mod search;
 pub mod serde_ext;
 pub mod sql;
+pub mod stats;
 pub mod storage;
 #[cfg(any(test, feature = "test"))]
 pub mod test;
+pub mod tokens;
 pub mod version;
 
 pub use node::Node;
@@ -3,7 +3,9 @@ mod features;
 pub mod address;
 pub mod config;
 pub mod events;
+pub mod metrics;
 pub mod routing;
+pub mod succession;
 pub mod tracking;
 
 use std::collections::{BTreeSet, HashMap, HashSet};
@@ -31,6 +33,7 @@ pub use config::Config;
 pub use cyphernet::addr::{HostName, PeerAddr};
 pub use events::{Event, Events};
 pub use features::Features;
+pub use metrics::Metrics;
 
 /// Default name for control socket file.
 pub const DEFAULT_SOCKET_NAME: &str = "control.sock";
@@ -46,6 +49,10 @@ pub const ROUTING_DB_FILE: &str = "routing.db";
 pub const ADDRESS_DB_FILE: &str = "addresses.db";
 /// Filename of tracking table database under the node directory.
 pub const TRACKING_DB_FILE: &str = "tracking.db";
+/// Filename of gossip database under the node directory.
+pub const GOSSIP_DB_FILE: &str = "gossip.db";
+/// Filename of the node key succession record under the node directory.
+pub const SUCCESSION_FILE: &str = "succession.json";
 /// Filename of last node announcement, when running in debug mode.
 #[cfg(debug_assertions)]
 pub const NODE_ANNOUNCEMENT_FILE: &str = "announcement.wire.debug";
@@ -353,6 +360,33 @@ pub enum Command {
     /// Get the current peer sessions.
     Sessions,
 
+    /// Get the result of the node's reachability self-check.
+    Reachability,
+
+    /// Get node metrics.
+    Metrics,
+
+    /// Get the repositories replicated on fewer seeds than the configured replication factor.
+    Replication,
+
+    /// Disconnect from the given node.
+    #[serde(rename_all = "camelCase")]
+    Disconnect { nid: NodeId },
+
+    /// Get the entries of the node's address book.
+    Addresses,
+
+    /// Lookup the repositories seeded by the given node in the routing table.
+    #[serde(rename_all = "camelCase")]
+    Routing { nid: NodeId },
+
+    /// Get the node's running configuration.
+    Config,
+
+    /// Change the node's limits, until it is restarted.
+    #[serde(rename_all = "camelCase")]
+    UpdateLimits { limits: config::Limits },
+
     /// Fetch the given repository from the network.
     #[serde(rename_all = "camelCase")]
     Fetch { rid: Id, nid: NodeId },
@@ -373,6 +407,18 @@ pub enum Command {
     #[serde(rename_all = "camelCase")]
     UntrackNode { nid: NodeId },
 
+    /// Track the given remote, for the given repository only.
+    #[serde(rename_all = "camelCase")]
+    TrackRemote { rid: Id, nid: NodeId },
+
+    /// Remove the tracking policy of the given remote, for the given repository.
+    #[serde(rename_all = "camelCase")]
+    UntrackRemote { rid: Id, nid: NodeId },
+
+    /// Block the given remote, for the given repository only.
+    #[serde(rename_all = "camelCase")]
+    BlockRemote { rid: Id, nid: NodeId },
+
     /// Get the node's status.
     Status,
 
@@ -402,6 +448,32 @@ pub struct Session {
     pub state: State,
 }
 
+/// Whether the node can be reached by other nodes, as reported by its peers.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reachability {
+    /// Whether a peer was able to dial us back. `None` if no check has completed yet.
+    pub reachable: Option<bool>,
+    /// Our external addresses, as observed by peers.
+    pub observed: Vec<Address>,
+    /// External address mapped on the gateway via NAT-PMP, if any.
+    pub mapped: Option<Address>,
+    /// When the last check completed.
+    pub checked: Option<Timestamp>,
+}
+
+/// A repository replicated on fewer seeds than the configured replication factor.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Replication {
+    /// The under-replicated repository.
+    pub rid: Id,
+    /// Number of other seeds known to have the repository.
+    pub replicas: usize,
+    /// Configured replication factor.
+    pub target: usize,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Seed {
@@ -662,6 +734,14 @@ pub trait Handle: Clone + Sync + Send {
     fn untrack_repo(&mut self, id: Id) -> Result<bool, Self::Error>;
     /// Untrack the given node.
     fn untrack_node(&mut self, id: NodeId) -> Result<bool, Self::Error>;
+    /// Track the given remote for the given repository, regardless of the repository scope
+    /// and the remote's node policy.
+    fn track_remote(&mut self, rid: Id, nid: NodeId) -> Result<bool, Self::Error>;
+    /// Remove the tracking policy of the given remote for the given repository, if any.
+    fn untrack_remote(&mut self, rid: Id, nid: NodeId) -> Result<bool, Self::Error>;
+    /// Block the given remote for the given repository, regardless of the repository scope
+    /// and the remote's node policy.
+    fn block_remote(&mut self, rid: Id, nid: NodeId) -> Result<bool, Self::Error>;
     /// Notify the service that a project has been updated, and announce local refs.
     fn announce_refs(&mut self, id: Id) -> Result<(), Self::Error>;
     /// Announce local inventory.
@@ -672,6 +752,22 @@ pub trait Handle: Clone + Sync + Send {
     fn shutdown(self) -> Result<(), Self::Error>;
     /// Query the peer session state.
     fn sessions(&self) -> Result<Self::Sessions, Self::Error>;
+    /// Query the result of the reachability self-check.
+    fn reachability(&self) -> Result<Reachability, Self::Error>;
+    /// Get node metrics.
+    fn metrics(&self) -> Result<Metrics, Self::Error>;
+    /// Get the repositories replicated on fewer seeds than the configured replication factor.
+    fn replication(&self) -> Result<Vec<Replication>, Self::Error>;
+    /// Disconnect from a peer.
+    fn disconnect(&mut self, node: NodeId) -> Result<(), Self::Error>;
+    /// Get the nodes in the address book, with their known addresses and session state.
+    fn addresses(&self) -> Result<Vec<Seed>, Self::Error>;
+    /// Lookup the repositories seeded by a given node in the routing table.
+    fn routing(&self, node: NodeId) -> Result<Vec<Id>, Self::Error>;
+    /// Get the running node configuration.
+    fn config(&self) -> Result<Config, Self::Error>;
+    /// Change the node limits at runtime. The change isn't persisted.
+    fn update_limits(&mut self, limits: config::Limits) -> Result<(), Self::Error>;
     /// Subscribe to node events.
     fn subscribe(
         &self,
@@ -861,6 +957,27 @@ impl Handle for Node {
         response.into()
     }
 
+    fn track_remote(&mut self, rid: Id, nid: NodeId) -> Result<bool, Error> {
+        let mut line = self.call(Command::TrackRemote { rid, nid }, DEFAULT_TIMEOUT)?;
+        let response: CommandResult = line.next().ok_or(Error::EmptyResponse)??;
+
+        response.into()
+    }
+
+    fn untrack_remote(&mut self, rid: Id, nid: NodeId) -> Result<bool, Error> {
+        let mut line = self.call(Command::UntrackRemote { rid, nid }, DEFAULT_TIMEOUT)?;
+        let response: CommandResult = line.next().ok_or(Error::EmptyResponse)??;
+
+        response.into()
+    }
+
+    fn block_remote(&mut self, rid: Id, nid: NodeId) -> Result<bool, Error> {
+        let mut line = self.call(Command::BlockRemote { rid, nid }, DEFAULT_TIMEOUT)?;
+        let response: CommandResult = line.next().ok_or(Error::EmptyResponse)??;
+
+        response.into()
+    }
+
     fn announce_refs(&mut self, rid: Id) -> Result<(), Error> {
         for line in self.call::<CommandResult>(Command::AnnounceRefs { rid }, DEFAULT_TIMEOUT)? {
             line?;
@@ -907,6 +1024,74 @@ impl Handle for Node {
         Ok(sessions)
     }
 
+    fn reachability(&self) -> Result<Reachability, Error> {
+        let reachability = self
+            .call::<Reachability>(Command::Reachability, DEFAULT_TIMEOUT)?
+            .next()
+            .ok_or(Error::EmptyResponse {})??;
+
+        Ok(reachability)
+    }
+
+    fn replication(&self) -> Result<Vec<Replication>, Error> {
+        let replication = self
+            .call::<Vec<Replication>>(Command::Replication, DEFAULT_TIMEOUT)?
+            .next()
+            .ok_or(Error::EmptyResponse {})??;
+
+        Ok(replication)
+    }
+
+    fn metrics(&self) -> Result<Metrics, Error> {
+        let metrics = self
+            .call::<Metrics>(Command::Metrics, DEFAULT_TIMEOUT)?
+            .next()
+            .ok_or(Error::EmptyResponse {})??;
+
+        Ok(metrics)
+    }
+
+    fn disconnect(&mut self, nid: NodeId) -> Result<(), Error> {
+        for line in self.call::<CommandResult>(Command::Disconnect { nid }, DEFAULT_TIMEOUT)? {
+            line?;
+        }
+        Ok(())
+    }
+
+    fn addresses(&self) -> Result<Vec<Seed>, Error> {
+        let addresses = self
+            .call::<Vec<Seed>>(Command::Addresses, DEFAULT_TIMEOUT)?
+            .next()
+            .ok_or(Error::EmptyResponse {})??;
+
+        Ok(addresses)
+    }
+
+    fn routing(&self, nid: NodeId) -> Result<Vec<Id>, Error> {
+        let rids = self
+            .call::<Vec<Id>>(Command::Routing { nid }, DEFAULT_TIMEOUT)?
+            .next()
+            .ok_or(Error::EmptyResponse {})??;
+
+        Ok(rids)
+    }
+
+    fn config(&self) -> Result<Config, Error> {
+        let config = self
+            .call::<Config>(Command::Config, DEFAULT_TIMEOUT)?
+            .next()
+            .ok_or(Error::EmptyResponse {})??;
+
+        Ok(config)
+    }
+
+    fn update_limits(&mut self, limits: config::Limits) -> Result<(), Error> {
+        for line in self.call::<CommandResult>(Command::UpdateLimits { limits }, DEFAULT_TIMEOUT)? {
+            line?;
+        }
+        Ok(())
+    }
+
     fn shutdown(self) -> Result<(), Error> {
         for line in self.call::<CommandResult>(Command::Shutdown, DEFAULT_TIMEOUT)? {
             line?;
@@ -37,3 +37,29 @@ create table if not exists "addresses" (
   unique ("node", "type", "value")
   --
 ) strict;
+
+-- Node misbehavior, used to prefer well-behaved peers and to temporarily
+-- ban peers that misbehave repeatedly.
+create table if not exists "penalties" (
+  -- Node ID.
+  "node"               text      primary key not null,
+  -- Accumulated penalty score. Decays over time.
+  "score"              integer   not null default 0,
+  -- Local time at which the score was last updated.
+  "updated_at"         integer   not null,
+  -- Local time until which the node is banned, if any.
+  "banned_until"       integer   default null
+  --
+) strict;
+
+-- Node key successions. When a node rotates its key, the old key signs the
+-- new one, and what we know about the old node is carried over to the new one.
+create table if not exists "successions" (
+  -- Old node ID.
+  "old"                text      primary key not null,
+  -- New node ID.
+  "new"                text      not null,
+  -- When the key was rotated, as signed by the old key.
+  "timestamp"          integer   not null
+  --
+) strict;
@@ -7,7 +7,8 @@ use sqlite as sql;
 use thiserror::Error;
 
 use crate::node;
-use crate::node::address::{KnownAddress, Source};
+use crate::node::address::{KnownAddress, Reputation, Source};
+use crate::node::address::{BAN_DURATION, BAN_THRESHOLD};
 use crate::node::{Address, Alias, AliasError, AliasStore, NodeId};
 use crate::prelude::Timestamp;
 use crate::sql::transaction;
@@ -189,6 +190,76 @@ impl Store for Book {
         .map_err(Error::from)
     }
 
+    fn rotate(&mut self, old: &NodeId, new: &NodeId, timestamp: Timestamp) -> Result<bool, Error> {
+        transaction(&self.db, move |db| {
+            // The new key should be fresh. If it was penalized, is part of another succession,
+            // or was announced before the rotation, it isn't, and we leave it untouched.
+            let mut stmt = db.prepare(
+                "SELECT 1 FROM penalties WHERE node = ?1
+                 UNION ALL
+                 SELECT 1 FROM successions WHERE old = ?1 OR new = ?1
+                 UNION ALL
+                 SELECT 1 FROM nodes WHERE id = ?1 AND timestamp < ?2
+                 LIMIT 1",
+            )?;
+            stmt.bind((1, new))?;
+            stmt.bind((2, timestamp as i64))?;
+
+            if let sql::State::Row = stmt.next()? {
+                return Ok(false);
+            }
+            let mut stmt = db.prepare(
+                "INSERT INTO successions (old, new, timestamp)
+                 VALUES (?1, ?2, ?3)
+                 ON CONFLICT DO NOTHING",
+            )?;
+            stmt.bind((1, old))?;
+            stmt.bind((2, new))?;
+            stmt.bind((3, timestamp as i64))?;
+            stmt.next()?;
+
+            // A key can only be succeeded once.
+            if db.change_count() == 0 {
+                return Ok(false);
+            }
+            for query in [
+                "INSERT INTO nodes (id, features, alias, pow, timestamp)
+                 SELECT ?2, features, alias, pow, timestamp FROM nodes WHERE id = ?1
+                 ON CONFLICT DO NOTHING",
+                "INSERT INTO addresses (node, type, value, source, timestamp, last_attempt, last_success)
+                 SELECT ?2, type, value, source, timestamp, last_attempt, last_success
+                 FROM addresses WHERE node = ?1
+                 ON CONFLICT DO NOTHING",
+                // Penalties are carried over too, so that rotating keys can't be used
+                // to get out of a ban.
+                "INSERT INTO penalties (node, score, updated_at, banned_until)
+                 SELECT ?2, score, updated_at, banned_until FROM penalties WHERE node = ?1
+                 ON CONFLICT DO NOTHING",
+            ] {
+                let mut stmt = db.prepare(query)?;
+                stmt.bind((1, old))?;
+                stmt.bind((2, new))?;
+                stmt.next()?;
+            }
+            Ok(true)
+        })
+        .map_err(Error::from)
+    }
+
+    fn successor(&self, nid: &NodeId) -> Result<Option<NodeId>, Error> {
+        let mut stmt = self
+            .db
+            .prepare("SELECT new FROM successions WHERE old = ?")?;
+
+        stmt.bind((1, nid))?;
+
+        if let Some(Ok(row)) = stmt.into_iter().next() {
+            Ok(Some(row.read::<NodeId, _>("new")))
+        } else {
+            Ok(None)
+        }
+    }
+
     fn entries(&self) -> Result<Box<dyn Iterator<Item = (NodeId, KnownAddress)>>, Error> {
         let mut stmt = self
             .db
@@ -254,6 +325,94 @@ impl Store for Book {
 
         Ok(())
     }
+
+    fn reputation(&self, nid: &NodeId, time: Timestamp) -> Result<Reputation, Error> {
+        let mut stmt = self
+            .db
+            .prepare("SELECT score, updated_at, banned_until FROM penalties WHERE node = ?")?;
+
+        stmt.bind((1, nid))?;
+
+        if let Some(Ok(row)) = stmt.into_iter().next() {
+            Ok(reputation(&row, time))
+        } else {
+            Ok(Reputation::default())
+        }
+    }
+
+    fn reputations(
+        &self,
+        time: Timestamp,
+    ) -> Result<Box<dyn Iterator<Item = (NodeId, Reputation)>>, Error> {
+        let mut stmt = self
+            .db
+            .prepare("SELECT node, score, updated_at, banned_until FROM penalties ORDER BY node")?
+            .into_iter();
+        let mut entries = Vec::new();
+
+        while let Some(Ok(row)) = stmt.next() {
+            let node = row.read::<NodeId, _>("node");
+            let reputation = reputation(&row, time);
+
+            if reputation.score > 0 || reputation.banned_until.is_some() {
+                entries.push((node, reputation));
+            }
+        }
+        Ok(Box::new(entries.into_iter()))
+    }
+
+    fn penalize(
+        &mut self,
+        nid: &NodeId,
+        penalty: u32,
+        time: Timestamp,
+    ) -> Result<Reputation, Error> {
+        let current = self.reputation(nid, time)?;
+        let score = current.score.saturating_add(penalty);
+        // Nb. the score is kept across bans, so that a node that keeps misbehaving once its
+        // ban is lifted is banned again sooner.
+        let banned_until = match current.banned_until {
+            Some(t) => Some(t.as_millis() as Timestamp),
+            None if score >= BAN_THRESHOLD => Some(time + BAN_DURATION),
+            None => None,
+        };
+        let mut stmt = self.db.prepare(
+            "INSERT INTO penalties (node, score, updated_at, banned_until)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT DO UPDATE
+             SET score = ?2, updated_at = ?3, banned_until = ?4",
+        )?;
+
+        stmt.bind((1, nid))?;
+        stmt.bind((2, score as i64))?;
+        stmt.bind((3, time as i64))?;
+        stmt.bind((4, banned_until.map(|t| t as i64)))?;
+        stmt.next()?;
+
+        Ok(Reputation {
+            score,
+            banned_until: banned_until.map(|t| LocalTime::from_millis(t as u128)),
+        })
+    }
+}
+
+/// Read a node's reputation from a `penalties` row.
+fn reputation(row: &sql::Row, time: Timestamp) -> Reputation {
+    let score = row.read::<i64, _>("score") as u32;
+    let updated_at = row.read::<i64, _>("updated_at") as Timestamp;
+    let banned_until = row
+        .read::<Option<i64>, _>("banned_until")
+        .map(|t| t as Timestamp);
+    // Scores don't decay while a node is banned.
+    let since = banned_until.map_or(updated_at, |t| t.max(updated_at));
+
+    Reputation {
+        score: Reputation::decayed(score, since, time),
+        // Expired bans are ignored.
+        banned_until: banned_until
+            .filter(|t| *t > time)
+            .map(|t| LocalTime::from_millis(t as u128)),
+    }
 }
 
 impl AliasStore for Book {
@@ -286,6 +445,16 @@ pub trait Store {
     ) -> Result<bool, Error>;
     /// Remove an address from the store.
     fn remove(&mut self, id: &NodeId) -> Result<bool, Error>;
+    /// Record that a node key was succeeded by a new key, and carry over the old node's
+    /// metadata, addresses and penalties to the new node. The old entries are left in place,
+    /// and are eventually pruned.
+    ///
+    /// Returns `true` if the succession was recorded, and `false` if the old key was already
+    /// succeeded, or if the new key isn't fresh, ie. it was penalized, is part of another
+    /// succession, or was announced before the given rotation time.
+    fn rotate(&mut self, old: &NodeId, new: &NodeId, timestamp: Timestamp) -> Result<bool, Error>;
+    /// Get the key that succeeded the given node key, if any.
+    fn successor(&self, nid: &NodeId) -> Result<Option<NodeId>, Error>;
     /// Returns the number of addresses.
     fn len(&self) -> Result<usize, Error>;
     /// Returns true if there are no addresses.
@@ -298,6 +467,24 @@ pub trait Store {
     fn attempted(&self, nid: &NodeId, addr: &Address, time: Timestamp) -> Result<(), Error>;
     /// Mark a node as successfully connected at a certain time.
     fn connected(&self, nid: &NodeId, addr: &Address, time: Timestamp) -> Result<(), Error>;
+    /// Get a node's reputation at a certain time.
+    fn reputation(&self, nid: &NodeId, time: Timestamp) -> Result<Reputation, Error>;
+    /// Get the reputation of all nodes that have misbehaved, at a certain time.
+    fn reputations(
+        &self,
+        time: Timestamp,
+    ) -> Result<Box<dyn Iterator<Item = (NodeId, Reputation)>>, Error>;
+    /// Penalize a node for misbehaving at a certain time, by the given number of penalty
+    /// points, see [`crate::node::address::Misbehavior::penalty`]. Nodes which accumulate
+    /// too much penalty are banned for some time.
+    ///
+    /// Returns the node's updated reputation.
+    fn penalize(
+        &mut self,
+        nid: &NodeId,
+        penalty: u32,
+        time: Timestamp,
+    ) -> Result<Reputation, Error>;
 }
 
 impl TryFrom<&sql::Value> for Source {
@@ -311,6 +498,7 @@ impl TryFrom<&sql::Value> for Source {
         match value {
             sql::Value::String(s) => match s.as_str() {
                 "bootstrap" => Ok(Source::Bootstrap),
+                "dns" => Ok(Source::Dns),
                 "peer" => Ok(Source::Peer),
                 "imported" => Ok(Source::Imported),
                 _ => Err(err),
@@ -324,6 +512,7 @@ impl sql::BindableWithIndex for Source {
     fn bind<I: sql::ParameterIndex>(self, stmt: &mut sql::Statement<'_>, i: I) -> sql::Result<()> {
         match self {
             Self::Bootstrap => "bootstrap".bind(stmt, i),
+            Self::Dns => "dns".bind(stmt, i),
             Self::Peer => "peer".bind(stmt, i),
             Self::Imported => "imported".bind(stmt, i),
         }
@@ -367,6 +556,7 @@ mod test {
     use std::net;
 
     use super::*;
+    use crate::node::address::{Misbehavior, PENALTY_DECAY_PER_HOUR};
     use crate::test::arbitrary;
     use localtime::LocalTime;
 
@@ -577,6 +767,65 @@ mod test {
         assert_eq!(cache.len().unwrap(), 0);
     }
 
+    #[test]
+    fn test_penalize() {
+        let alice = arbitrary::gen::<NodeId>(1);
+        let mut cache = Book::memory().unwrap();
+        let time = LocalTime::now().as_millis();
+
+        assert_eq!(
+            cache.reputation(&alice, time).unwrap(),
+            Reputation::default()
+        );
+        assert_eq!(cache.reputations(time).unwrap().count(), 0);
+
+        let rep = cache
+            .penalize(&alice, Misbehavior::InvalidAnnouncement.penalty(), time)
+            .unwrap();
+        assert_eq!(rep.score, Misbehavior::InvalidAnnouncement.penalty());
+        assert!(!rep.is_banned(LocalTime::from_millis(time as u128)));
+        assert_eq!(cache.reputation(&alice, time).unwrap(), rep);
+        assert_eq!(cache.reputations(time).unwrap().count(), 1);
+
+        // Penalties decay over time.
+        let later = time + 1000 * 60 * 60;
+        assert_eq!(
+            cache.reputation(&alice, later).unwrap().score,
+            rep.score - PENALTY_DECAY_PER_HOUR
+        );
+
+        // Enough misbehavior gets a node banned.
+        cache
+            .penalize(&alice, Misbehavior::InvalidSignature.penalty(), time)
+            .unwrap();
+        let rep = cache
+            .penalize(&alice, Misbehavior::InvalidSignature.penalty(), time)
+            .unwrap();
+        assert!(rep.is_banned(LocalTime::from_millis(time as u128)));
+        assert_eq!(rep.score, 120);
+
+        // Until the ban expires. The score is kept, and doesn't decay while banned.
+        let lifted = time + BAN_DURATION;
+        let rep = cache.reputation(&alice, lifted).unwrap();
+        assert!(!rep.is_banned(LocalTime::from_millis(lifted as u128)));
+        assert_eq!(rep.score, 120);
+        assert_eq!(cache.reputations(lifted).unwrap().count(), 1);
+
+        // Further misbehavior gets the node banned again.
+        let rep = cache
+            .penalize(&alice, Misbehavior::RateLimited.penalty(), lifted)
+            .unwrap();
+        assert!(rep.is_banned(LocalTime::from_millis(lifted as u128)));
+
+        // Once lifted, the score decays again.
+        let lifted = lifted + BAN_DURATION;
+        let later = lifted + 1000 * 60 * 60;
+        assert_eq!(
+            cache.reputation(&alice, later).unwrap().score,
+            rep.score - PENALTY_DECAY_PER_HOUR
+        );
+    }
+
     #[test]
     fn test_entries() {
         let ids = arbitrary::vec::<NodeId>(16);
@@ -611,4 +860,67 @@ mod test {
         assert_eq!(cache.len().unwrap(), actual.len());
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn test_rotate() {
+        let alice = arbitrary::gen::<NodeId>(1);
+        let alice2 = arbitrary::gen::<NodeId>(2);
+        let eve = arbitrary::gen::<NodeId>(3);
+        let bob = arbitrary::gen::<NodeId>(4);
+        let carol = arbitrary::gen::<NodeId>(5);
+        let mut cache = Book::memory().unwrap();
+        let timestamp = LocalTime::now().as_millis();
+        let ka = KnownAddress {
+            addr: net::SocketAddr::from(([4, 4, 4, 4], 8776)).into(),
+            source: Source::Peer,
+            last_success: None,
+            last_attempt: None,
+        };
+        cache
+            .insert(
+                &alice,
+                node::Features::SEED,
+                Alias::new("alice"),
+                0,
+                timestamp,
+                [ka.clone()],
+            )
+            .unwrap();
+        cache.penalize(&alice, BAN_THRESHOLD, timestamp).unwrap();
+
+        assert!(cache.rotate(&alice, &alice2, timestamp).unwrap());
+        assert!(!cache.rotate(&alice, &eve, timestamp).unwrap());
+        assert_eq!(cache.successor(&alice).unwrap(), Some(alice2));
+        assert_eq!(cache.successor(&alice2).unwrap(), None);
+
+        let node = cache.get(&alice2).unwrap().unwrap();
+        assert_eq!(node.alias.as_ref(), "alice");
+        assert_eq!(node.addrs, vec![ka.clone()]);
+        // Penalties are carried over: a banned key's successor is still banned.
+        let reputation = cache.reputation(&alice2, timestamp).unwrap();
+        assert_eq!(reputation, cache.reputation(&alice, timestamp).unwrap());
+        assert!(reputation.is_banned(LocalTime::from_millis(timestamp as u128)));
+        // The old entry is still there.
+        assert!(cache.get(&alice).unwrap().is_some());
+
+        // A key that succeeded another can't succeed a second one.
+        assert!(!cache.rotate(&bob, &alice2, timestamp).unwrap());
+        // Nor can a key that was penalized, or that was announced before the rotation.
+        cache
+            .penalize(&eve, Misbehavior::InvalidSignature.penalty(), timestamp)
+            .unwrap();
+        assert!(!cache.rotate(&bob, &eve, timestamp).unwrap());
+        cache
+            .insert(
+                &carol,
+                node::Features::SEED,
+                Alias::new("carol"),
+                0,
+                timestamp - 1,
+                [ka],
+            )
+            .unwrap();
+        assert!(!cache.rotate(&bob, &carol, timestamp).unwrap());
+        assert_eq!(cache.successor(&bob).unwrap(), None);
+    }
 }
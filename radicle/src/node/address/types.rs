@@ -10,6 +10,16 @@ use crate::node;
 use crate::node::{Address, Alias};
 use crate::prelude::Timestamp;
 
+/// Penalty score at which a node is banned.
+pub const BAN_THRESHOLD: u32 = 100;
+/// How long a node stays banned for, in milliseconds.
+pub const BAN_DURATION: Timestamp = 1000 * 60 * 60 * 24;
+/// Penalty score up to which a node is penalized for being rate-limited. Rate limiting
+/// alone never gets a node banned.
+pub const RATE_LIMITED_MAX_SCORE: u32 = BAN_THRESHOLD / 2;
+/// How many penalty points decay per hour of good behavior.
+pub const PENALTY_DECAY_PER_HOUR: u32 = 5;
+
 /// A map with the ability to randomly select values.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(transparent)]
@@ -138,6 +148,8 @@ pub enum Source {
     Peer,
     /// An bootstrap node address.
     Bootstrap,
+    /// A bootstrap node address, resolved via DNS.
+    Dns,
     /// An address that came from some source external to the system, eg.
     /// specified by the user or added directly to the address manager.
     Imported,
@@ -148,7 +160,70 @@ impl std::fmt::Display for Source {
         match self {
             Self::Peer => write!(f, "Peer"),
             Self::Bootstrap => write!(f, "Bootstrap"),
+            Self::Dns => write!(f, "DNS"),
             Self::Imported => write!(f, "Imported"),
         }
     }
 }
+
+/// Something a node did that violates the protocol or wastes our resources.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Misbehavior {
+    /// The node sent or relayed a message with an invalid signature.
+    InvalidSignature,
+    /// The node sent an invalid announcement, eg. with a timestamp too far in the future.
+    InvalidAnnouncement,
+    /// A fetch from this node failed.
+    FetchFailed,
+    /// The node was rate-limited.
+    RateLimited,
+}
+
+impl Misbehavior {
+    /// Penalty score added to a node's reputation for this misbehavior.
+    pub fn penalty(&self) -> u32 {
+        match self {
+            Self::InvalidSignature => 50,
+            Self::InvalidAnnouncement => 20,
+            Self::FetchFailed => 5,
+            Self::RateLimited => 1,
+        }
+    }
+}
+
+impl std::fmt::Display for Misbehavior {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidSignature => write!(f, "invalid signature"),
+            Self::InvalidAnnouncement => write!(f, "invalid announcement"),
+            Self::FetchFailed => write!(f, "fetch failed"),
+            Self::RateLimited => write!(f, "rate limited"),
+        }
+    }
+}
+
+/// Reputation of a node, based on its past misbehavior.
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reputation {
+    /// Accumulated penalty score. The lower, the better.
+    pub score: u32,
+    /// Until when this node is banned, if at all.
+    pub banned_until: Option<LocalTime>,
+}
+
+impl Reputation {
+    /// Check whether the node is banned at the given time.
+    pub fn is_banned(&self, now: LocalTime) -> bool {
+        self.banned_until.map_or(false, |t| now < t)
+    }
+
+    /// Return the score, decayed according to the time elapsed since it was last updated.
+    pub fn decayed(score: u32, updated_at: Timestamp, now: Timestamp) -> u32 {
+        let hours = now.saturating_sub(updated_at) / (1000 * 60 * 60);
+        let decay = (hours as u32).saturating_mul(PENALTY_DECAY_PER_HOUR);
+
+        score.saturating_sub(decay)
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::HashSet;
+use std::net;
 use std::ops::Deref;
 
 use cyphernet::addr::PeerAddr;
@@ -55,6 +56,12 @@ pub struct Limits {
     /// How long to keep a routing table entry before being pruned.
     #[serde(with = "crate::serde_ext::localtime::duration")]
     pub routing_max_age: LocalDuration,
+    /// How long to keep a gossip announcement before being pruned.
+    #[serde(
+        default = "Limits::default_gossip_max_age",
+        with = "crate::serde_ext::localtime::duration"
+    )]
+    pub gossip_max_age: LocalDuration,
     /// Maximum number of concurrent fetches per per connection.
     pub fetch_concurrency: usize,
 }
@@ -64,11 +71,114 @@ impl Default for Limits {
         Self {
             routing_max_size: 1000,
             routing_max_age: LocalDuration::from_mins(7 * 24 * 60),
+            gossip_max_age: Self::default_gossip_max_age(),
             fetch_concurrency: 1,
         }
     }
 }
 
+impl Limits {
+    fn default_gossip_max_age() -> LocalDuration {
+        LocalDuration::from_mins(14 * 24 * 60)
+    }
+}
+
+/// Tor configuration.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tor {
+    /// Address of the SOCKS5 proxy used to connect to `.onion` addresses.
+    pub proxy: net::SocketAddr,
+    /// Route all outbound peer connections through the proxy, not only
+    /// connections to `.onion` addresses.
+    #[serde(default)]
+    pub force: bool,
+}
+
+impl Default for Tor {
+    fn default() -> Self {
+        Self {
+            proxy: net::SocketAddr::new(net::Ipv4Addr::LOCALHOST.into(), 9050),
+            force: false,
+        }
+    }
+}
+
+/// NAT traversal configuration.
+#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Nat {
+    /// Try to map our listening ports on the gateway using NAT-PMP.
+    /// UPnP gateways are not supported.
+    #[serde(default)]
+    pub pmp: bool,
+    /// Gateway to send NAT-PMP requests to. If not set, the default route's
+    /// gateway is used, when it can be found.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gateway: Option<net::Ipv4Addr>,
+}
+
+/// DNS bootstrap configuration.
+#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Dns {
+    /// Domains to resolve bootstrap nodes from, when the address book is empty.
+    /// Each `TXT` record of these domains should hold a node address, eg.
+    /// `z6Mk..@seed.example.com:8776`. If no nodes can be resolved, the network's
+    /// hardcoded bootstrap nodes are used.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub seeds: Vec<String>,
+    /// Name server to query. If not set, the first name server in `/etc/resolv.conf`
+    /// is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nameserver: Option<net::SocketAddr>,
+}
+
+/// Proxy used for outbound peer connections.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum Proxy {
+    /// SOCKS5 proxy, with optional username/password authentication.
+    Socks5 {
+        addr: net::SocketAddr,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        auth: Option<ProxyAuth>,
+    },
+    /// HTTP proxy, supporting the `CONNECT` method.
+    Http { addr: net::SocketAddr },
+}
+
+impl Proxy {
+    /// Address of the proxy.
+    pub fn addr(&self) -> net::SocketAddr {
+        match self {
+            Self::Socks5 { addr, .. } | Self::Http { addr } => *addr,
+        }
+    }
+}
+
+/// Proxy credentials.
+#[derive(Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyAuth {
+    pub username: String,
+    pub password: String,
+}
+
+impl ProxyAuth {
+    /// Placeholder for the password, when it isn't shown.
+    pub const REDACTED: &str = "********";
+}
+
+// N.b. we don't want the password to end up in logs.
+impl std::fmt::Debug for ProxyAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProxyAuth")
+            .field("username", &self.username)
+            .finish_non_exhaustive()
+    }
+}
+
 /// Full address used to connect to a remote node.
 #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, Hash)]
 #[serde(transparent)]
@@ -137,9 +247,33 @@ pub struct Config {
     /// Connections to these peers will be maintained.
     #[serde(default)]
     pub connect: HashSet<ConnectAddress>,
-    /// Specify the node's public addresses
+    /// Specify the node's public addresses. These may include `.onion` addresses.
+    /// If empty, the node announces the external address observed by its peers, once
+    /// they were able to dial it back.
     #[serde(default)]
     pub external_addresses: Vec<Address>,
+    /// NAT traversal configuration.
+    #[serde(default)]
+    pub nat: Nat,
+    /// Tor configuration.
+    #[serde(default)]
+    pub tor: Tor,
+    /// DNS bootstrap configuration.
+    #[serde(default)]
+    pub dns: Dns,
+    /// Proxy to use for outbound peer connections, except to `.onion` addresses, which
+    /// always go through Tor. Since `rad clone` and `git-remote-rad` fetch from seeds via
+    /// the node, this applies to them as well.
+    #[serde(default)]
+    pub proxy: Option<Proxy>,
+    /// Address to serve Prometheus metrics on, over HTTP. Metrics are always
+    /// available via the control socket.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<net::SocketAddr>,
+    /// Whether to maintain the search index over the projects, issues and patches we
+    /// store. Needed to serve search requests, eg. via `radicle-httpd`.
+    #[serde(default)]
+    pub search: bool,
     /// Peer-to-peer network.
     #[serde(default)]
     pub network: Network,
@@ -149,6 +283,11 @@ pub struct Config {
     /// Configured service limits.
     #[serde(default)]
     pub limits: Limits,
+    /// Number of other seeds the repositories we track should be replicated on.
+    /// Repositories replicated on fewer seeds are offered to the connected seeds that don't
+    /// have them, so that they fetch them. Disabled if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replicas: Option<usize>,
     /// Default tracking policy.
     #[serde(default)]
     pub policy: Policy,
@@ -171,9 +310,16 @@ impl Config {
             peers: PeerConfig::default(),
             connect: HashSet::default(),
             external_addresses: vec![],
+            nat: Nat::default(),
+            tor: Tor::default(),
+            dns: Dns::default(),
+            proxy: None,
+            metrics: None,
+            search: false,
             network: Network::default(),
             relay: true,
             limits: Limits::default(),
+            replicas: None,
             policy: Policy::default(),
             scope: Scope::default(),
         }
@@ -181,6 +327,18 @@ impl Config {
 }
 
 impl Config {
+    /// Get the configuration with its secrets, ie. the proxy password, redacted. This is the
+    /// configuration that should be shown to users.
+    pub fn redacted(mut self) -> Self {
+        if let Some(Proxy::Socks5 {
+            auth: Some(auth), ..
+        }) = &mut self.proxy
+        {
+            auth.password = ProxyAuth::REDACTED.to_owned();
+        }
+        self
+    }
+
     pub fn peer(&self, id: &NodeId) -> Option<&Address> {
         self.connect
             .iter()
@@ -192,7 +350,53 @@ impl Config {
         self.peer(id).is_some()
     }
 
+    /// Features advertized in our node announcement.
     pub fn features(&self) -> node::Features {
-        node::Features::SEED
+        // Let peers know they can say hello to us. Other protocol features are advertized in
+        // the hello itself.
+        let features = node::Features::HELLO;
+
+        // Only nodes that can be dialed are seeds. If no addresses are configured, we become
+        // one once peers confirm they can reach us.
+        if self.external_addresses.is_empty() {
+            features
+        } else {
+            features.with(node::Features::SEED)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_redacted() {
+        let mut config = Config::new(Alias::new("alice"));
+        config.proxy = Some(Proxy::Socks5 {
+            addr: ([127, 0, 0, 1], 1080).into(),
+            auth: Some(ProxyAuth {
+                username: String::from("alice"),
+                password: String::from("hunter2"),
+            }),
+        });
+        let json = serde_json::to_string(&config.redacted()).unwrap();
+
+        assert!(json.contains("alice"));
+        assert!(!json.contains("hunter2"));
+        assert!(json.contains(ProxyAuth::REDACTED));
+    }
+
+    #[test]
+    fn test_features() {
+        let mut config = Config::new(Alias::new("alice"));
+        assert!(config.features().has(node::Features::HELLO));
+        assert!(!config.features().has(node::Features::SEED));
+
+        config.external_addresses = vec![Address::from(net::SocketAddr::from((
+            [192, 0, 2, 1],
+            node::DEFAULT_PORT,
+        )))];
+        assert!(config.features().has(node::Features::SEED));
     }
 }
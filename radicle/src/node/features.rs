@@ -12,9 +12,23 @@ impl Features {
     /// `SEED` is the base feature set all seed nodes must support.
     pub const SEED: Features = Features(0b00000001);
 
+    /// `DIAL_BACK` means the node answers dial-back requests, used for reachability checks.
+    pub const DIAL_BACK: Features = Features(0b00000010);
+
+    /// `SUCCESSION` means the node understands key succession announcements.
+    pub const SUCCESSION: Features = Features(0b00000100);
+
+    /// `INVENTORY_SYNC` means the node reconciles inventories with its peers, instead of
+    /// relying on inventory announcements alone.
+    pub const INVENTORY_SYNC: Features = Features(0b00001000);
+
+    /// `HELLO` means the node understands hello messages. Nodes advertize it in their node
+    /// announcement, since peers that don't support it can't decode a hello.
+    pub const HELLO: Features = Features(0b00010000);
+
     /// Returns [`Features`] with the other features added.
     #[must_use]
-    pub fn with(self, other: Features) -> Features {
+    pub const fn with(self, other: Features) -> Features {
         Self(self.0 | other.0)
     }
 
@@ -28,6 +42,12 @@ impl Features {
     pub fn has(self, flags: Features) -> bool {
         (self.0 | flags.0) == self.0
     }
+
+    /// Returns the features that are in `self`, but not in `other`.
+    #[must_use]
+    pub fn difference(self, other: Features) -> Features {
+        Self(self.0 & !other.0)
+    }
 }
 
 impl Default for Features {
@@ -126,5 +146,16 @@ mod test {
             Features::NONE.with(Features::SEED).without(Features::SEED),
             Features::NONE
         );
+
+        assert_eq!(
+            Features::SEED
+                .with(Features::DIAL_BACK)
+                .difference(Features::SEED),
+            Features::DIAL_BACK
+        );
+        assert_eq!(
+            Features::SEED.difference(Features::SEED.with(Features::DIAL_BACK)),
+            Features::NONE
+        );
     }
 }
@@ -0,0 +1,221 @@
+//! Node metrics, exposed in the Prometheus text exposition format.
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::node::NodeId;
+
+/// Content type of the text exposition format.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Byte counters of a connected peer.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerMetrics {
+    /// Bytes received from the peer.
+    pub received: u64,
+    /// Bytes sent to the peer.
+    pub sent: u64,
+}
+
+/// Node counters and gauges.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Metrics {
+    /// Number of connected inbound sessions.
+    pub inbound_sessions: usize,
+    /// Number of connected outbound sessions.
+    pub outbound_sessions: usize,
+    /// Number of fetches started.
+    pub fetches_started: u64,
+    /// Number of fetches that succeeded.
+    pub fetches_succeeded: u64,
+    /// Number of fetches that failed.
+    pub fetches_failed: u64,
+    /// Total time spent in completed fetches, in milliseconds.
+    pub fetch_duration: u64,
+    /// Number of entries in the routing table.
+    pub routing_entries: usize,
+    /// Number of gossip announcements received.
+    pub gossip_received: u64,
+    /// Number of gossip announcements relayed to other peers.
+    pub gossip_relayed: u64,
+    /// Number of gossip announcements dropped, because they were stale or invalid.
+    pub gossip_dropped: u64,
+    /// Number of connections and messages rejected by the rate limiter.
+    pub rate_limited: u64,
+    /// Byte counters of connected peers.
+    pub peers: BTreeMap<NodeId, PeerMetrics>,
+}
+
+impl Metrics {
+    /// Encode metrics in the Prometheus text exposition format.
+    pub fn encode(&self) -> String {
+        let mut out = String::new();
+
+        self.write(&mut out)
+            .expect("Metrics::encode: writing to a string never fails");
+        out
+    }
+
+    fn write(&self, w: &mut impl fmt::Write) -> fmt::Result {
+        metric(w, "radicle_sessions", "gauge", "Connected sessions.")?;
+        writeln!(
+            w,
+            "radicle_sessions{{link=\"inbound\"}} {}",
+            self.inbound_sessions
+        )?;
+        writeln!(
+            w,
+            "radicle_sessions{{link=\"outbound\"}} {}",
+            self.outbound_sessions
+        )?;
+
+        metric(w, "radicle_fetches_total", "counter", "Fetches, by result.")?;
+        writeln!(
+            w,
+            "radicle_fetches_total{{result=\"started\"}} {}",
+            self.fetches_started
+        )?;
+        writeln!(
+            w,
+            "radicle_fetches_total{{result=\"succeeded\"}} {}",
+            self.fetches_succeeded
+        )?;
+        writeln!(
+            w,
+            "radicle_fetches_total{{result=\"failed\"}} {}",
+            self.fetches_failed
+        )?;
+
+        metric(
+            w,
+            "radicle_fetch_duration_seconds",
+            "summary",
+            "Duration of completed fetches.",
+        )?;
+        writeln!(
+            w,
+            "radicle_fetch_duration_seconds_sum {}",
+            self.fetch_duration as f64 / 1000.
+        )?;
+        writeln!(
+            w,
+            "radicle_fetch_duration_seconds_count {}",
+            self.fetches_succeeded + self.fetches_failed
+        )?;
+
+        metric(
+            w,
+            "radicle_routing_entries",
+            "gauge",
+            "Routing table entries.",
+        )?;
+        writeln!(w, "radicle_routing_entries {}", self.routing_entries)?;
+
+        metric(
+            w,
+            "radicle_gossip_total",
+            "counter",
+            "Gossip announcements, by outcome.",
+        )?;
+        writeln!(
+            w,
+            "radicle_gossip_total{{outcome=\"received\"}} {}",
+            self.gossip_received
+        )?;
+        writeln!(
+            w,
+            "radicle_gossip_total{{outcome=\"relayed\"}} {}",
+            self.gossip_relayed
+        )?;
+        writeln!(
+            w,
+            "radicle_gossip_total{{outcome=\"dropped\"}} {}",
+            self.gossip_dropped
+        )?;
+
+        metric(
+            w,
+            "radicle_rate_limited_total",
+            "counter",
+            "Rate limiter rejections.",
+        )?;
+        writeln!(w, "radicle_rate_limited_total {}", self.rate_limited)?;
+
+        metric(
+            w,
+            "radicle_peer_received_bytes_total",
+            "counter",
+            "Bytes received, by peer.",
+        )?;
+        for (nid, peer) in &self.peers {
+            writeln!(
+                w,
+                "radicle_peer_received_bytes_total{{nid=\"{nid}\"}} {}",
+                peer.received
+            )?;
+        }
+        metric(
+            w,
+            "radicle_peer_sent_bytes_total",
+            "counter",
+            "Bytes sent, by peer.",
+        )?;
+        for (nid, peer) in &self.peers {
+            writeln!(
+                w,
+                "radicle_peer_sent_bytes_total{{nid=\"{nid}\"}} {}",
+                peer.sent
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Write the metadata lines of a metric.
+fn metric(w: &mut impl fmt::Write, name: &str, kind: &str, help: &str) -> fmt::Result {
+    writeln!(w, "# HELP {name} {help}")?;
+    writeln!(w, "# TYPE {name} {kind}")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::arbitrary;
+
+    #[test]
+    fn test_encode() {
+        let nid = arbitrary::gen::<NodeId>(1);
+        let metrics = Metrics {
+            inbound_sessions: 2,
+            fetches_succeeded: 3,
+            fetches_failed: 1,
+            fetch_duration: 1500,
+            peers: BTreeMap::from([(
+                nid,
+                PeerMetrics {
+                    received: 7,
+                    sent: 9,
+                },
+            )]),
+            ..Metrics::default()
+        };
+        let text = metrics.encode();
+
+        assert!(text.contains("radicle_sessions{link=\"inbound\"} 2\n"));
+        assert!(text.contains("radicle_sessions{link=\"outbound\"} 0\n"));
+        assert!(text.contains("radicle_fetch_duration_seconds_sum 1.5\n"));
+        assert!(text.contains("radicle_fetch_duration_seconds_count 4\n"));
+        assert!(text.contains(&format!(
+            "radicle_peer_received_bytes_total{{nid=\"{nid}\"}} 7\n"
+        )));
+        assert!(text.contains(&format!(
+            "radicle_peer_sent_bytes_total{{nid=\"{nid}\"}} 9\n"
+        )));
+        assert!(text
+            .lines()
+            .all(|l| l.starts_with('#') || l.split_whitespace().count() == 2));
+    }
+}
@@ -0,0 +1,161 @@
+//! Node key succession.
+//!
+//! When a node rotates its key, the new key co-signs the old key, and the old key signs the
+//! new one, so that other nodes can carry over what they know about the old node, eg. its
+//! addresses and tracking policies. Both signatures are required, so that a node can't name
+//! another node as its successor.
+use std::path::Path;
+use std::{fs, io};
+
+use serde::{Deserialize, Serialize};
+use serde_json as json;
+use thiserror::Error;
+
+use crate::crypto::{Signature, Signer};
+use crate::node::NodeId;
+use crate::prelude::Timestamp;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("i/o error: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid succession record: {0}")]
+    Json(#[from] json::Error),
+    #[error("invalid succession signature")]
+    InvalidSignature,
+}
+
+/// A signed statement that a node key was succeeded by a new key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Succession {
+    /// The old node key, which signs this record.
+    pub old: NodeId,
+    /// The new node key, which co-signs this record.
+    pub new: NodeId,
+    /// When the key was rotated.
+    pub timestamp: Timestamp,
+    /// Signature of the new key over the old key, new key and timestamp.
+    pub cosignature: Signature,
+    /// Signature of the old key over the new key, timestamp and co-signature.
+    pub signature: Signature,
+}
+
+impl Succession {
+    /// Create a new succession record, co-signed by the new key and signed by the old key.
+    pub fn new<G: Signer, H: Signer>(timestamp: Timestamp, signer: &G, successor: &H) -> Self {
+        let old = *signer.public_key();
+        let new = *successor.public_key();
+        let cosignature = successor.sign(&Self::cosigned(&old, &new, timestamp));
+        let signature = signer.sign(&Self::payload(&new, timestamp, &cosignature));
+
+        Self {
+            old,
+            new,
+            timestamp,
+            cosignature,
+            signature,
+        }
+    }
+
+    /// The payload co-signed by the new key: the old key, the new key, followed by the
+    /// big-endian timestamp.
+    pub fn cosigned(old: &NodeId, new: &NodeId, timestamp: Timestamp) -> Vec<u8> {
+        let mut payload = Vec::with_capacity(old.len() + new.len() + 8);
+        payload.extend_from_slice(old.as_ref());
+        payload.extend_from_slice(new.as_ref());
+        payload.extend_from_slice(&timestamp.to_be_bytes());
+        payload
+    }
+
+    /// The payload signed by the old key: the new key, the big-endian timestamp, followed by
+    /// the co-signature.
+    ///
+    /// Nb. This is also the wire encoding of a succession announcement, which means a record
+    /// can be turned into a gossip message signed by the old key, and vice-versa.
+    pub fn payload(new: &NodeId, timestamp: Timestamp, cosignature: &Signature) -> Vec<u8> {
+        let cosignature: &[u8] = cosignature.as_ref();
+        let mut payload = Vec::with_capacity(new.len() + 8 + cosignature.len());
+        payload.extend_from_slice(new.as_ref());
+        payload.extend_from_slice(&timestamp.to_be_bytes());
+        payload.extend_from_slice(cosignature);
+        payload
+    }
+
+    /// Verify the signatures of the old and new keys.
+    pub fn verify(&self) -> bool {
+        self.old != self.new
+            && self
+                .new
+                .verify(
+                    Self::cosigned(&self.old, &self.new, self.timestamp),
+                    &self.cosignature,
+                )
+                .is_ok()
+            && self
+                .old
+                .verify(
+                    Self::payload(&self.new, self.timestamp, &self.cosignature),
+                    &self.signature,
+                )
+                .is_ok()
+    }
+
+    /// Load and verify a succession record from a file.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let succession: Self = json::from_slice(&fs::read(path)?)?;
+
+        if !succession.verify() {
+            return Err(Error::InvalidSignature);
+        }
+        Ok(succession)
+    }
+
+    /// Write a succession record to a file.
+    pub fn write(&self, path: &Path) -> Result<(), Error> {
+        fs::write(path, json::to_vec_pretty(self)?)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use radicle_crypto::test::signer::MockSigner;
+
+    #[test]
+    fn test_verify() {
+        let old = MockSigner::from_seed([0xff; 32]);
+        let new = MockSigner::from_seed([0xfe; 32]);
+        let eve = MockSigner::from_seed([0xfd; 32]);
+        let succession = Succession::new(42, &old, &new);
+
+        assert!(succession.verify());
+        assert!(!Succession {
+            timestamp: 43,
+            ..succession.clone()
+        }
+        .verify());
+        assert!(!Succession {
+            new: *eve.public_key(),
+            ..succession.clone()
+        }
+        .verify());
+
+        // Naming a successor without its co-signature.
+        let cosignature = eve.sign(&Succession::cosigned(
+            old.public_key(),
+            new.public_key(),
+            42,
+        ));
+        let signature = old.sign(&Succession::payload(new.public_key(), 42, &cosignature));
+
+        assert!(!Succession {
+            cosignature,
+            signature,
+            ..succession
+        }
+        .verify());
+    }
+}
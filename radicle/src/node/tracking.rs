@@ -24,6 +24,15 @@ pub struct Node {
     pub policy: Policy,
 }
 
+/// Tracking policy of a single remote of a repository. Takes precedence over
+/// the repository scope and the node's own policy.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Remote {
+    pub rid: Id,
+    pub nid: NodeId,
+    pub policy: Policy,
+}
+
 /// Tracking policy.
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
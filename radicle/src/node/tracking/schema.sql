@@ -30,3 +30,19 @@ create table if not exists "repo-policies" (
   "policy"             text      default 'track'
   --
 ) strict;
+
+-- Per-repository remote tracking policy.
+--
+-- Overrides the repository scope and the node policy for a single remote
+-- of a single repository, eg. to block a remote of a repository tracked with
+-- scope "all", or to track a remote only for a given repository.
+create table if not exists "remote-policies" (
+  -- Repository ID.
+  "rid"                text      not null,
+  -- Node ID of the remote.
+  "nid"                text      not null,
+  -- Tracking policy for this remote of the repository.
+  "policy"             text      default 'track',
+  --
+  primary key ("rid", "nid")
+) strict;
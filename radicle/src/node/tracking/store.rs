@@ -9,7 +9,7 @@ use thiserror::Error;
 use crate::node::{Alias, AliasStore};
 use crate::prelude::{Id, NodeId};
 
-use super::{Node, Policy, Repo, Scope};
+use super::{Node, Policy, Remote, Repo, Scope};
 
 /// How long to wait for the database lock to be released before failing a read.
 const DB_READ_TIMEOUT: time::Duration = time::Duration::from_secs(3);
@@ -177,6 +177,66 @@ impl Config<Write> {
         Ok(self.db.change_count() > 0)
     }
 
+    /// Set the tracking policy of a remote, for a specific repository.
+    pub fn set_remote_policy(
+        &mut self,
+        rid: &Id,
+        nid: &NodeId,
+        policy: Policy,
+    ) -> Result<bool, Error> {
+        let mut stmt = self.db.prepare(
+            "INSERT INTO `remote-policies` (rid, nid, policy)
+             VALUES (?1, ?2, ?3)
+             ON CONFLICT DO UPDATE
+             SET policy = ?3 WHERE policy != ?3",
+        )?;
+
+        stmt.bind((1, rid))?;
+        stmt.bind((2, nid))?;
+        stmt.bind((3, policy))?;
+        stmt.next()?;
+
+        Ok(self.db.change_count() > 0)
+    }
+
+    /// Remove the tracking policy of a remote, for a specific repository.
+    pub fn unset_remote_policy(&mut self, rid: &Id, nid: &NodeId) -> Result<bool, Error> {
+        let mut stmt = self
+            .db
+            .prepare("DELETE FROM `remote-policies` WHERE rid = ?1 AND nid = ?2")?;
+
+        stmt.bind((1, rid))?;
+        stmt.bind((2, nid))?;
+        stmt.next()?;
+
+        Ok(self.db.change_count() > 0)
+    }
+
+    /// Carry over the policies and alias of a node to the key that succeeded it.
+    /// Policies already set for the new key are left untouched, as are the old key's
+    /// policies, since its existing data may still need to be tracked.
+    pub fn rotate(&mut self, old: &NodeId, new: &NodeId) -> Result<bool, Error> {
+        let mut updated = false;
+
+        for query in [
+            "INSERT INTO `node-policies` (id, alias, policy)
+             SELECT ?2, alias, policy FROM `node-policies` WHERE id = ?1
+             ON CONFLICT DO NOTHING",
+            "INSERT INTO `remote-policies` (rid, nid, policy)
+             SELECT rid, ?2, policy FROM `remote-policies` WHERE nid = ?1
+             ON CONFLICT DO NOTHING",
+        ] {
+            let mut stmt = self.db.prepare(query)?;
+
+            stmt.bind((1, old))?;
+            stmt.bind((2, new))?;
+            stmt.next()?;
+
+            updated |= self.db.change_count() > 0;
+        }
+        Ok(updated)
+    }
+
     /// Untrack a node.
     pub fn untrack_node(&mut self, id: &NodeId) -> Result<bool, Error> {
         let mut stmt = self
@@ -189,7 +249,7 @@ impl Config<Write> {
         Ok(self.db.change_count() > 0)
     }
 
-    /// Untrack a repository.
+    /// Untrack a repository. Also removes any remote policies for that repository.
     pub fn untrack_repo(&mut self, id: &Id) -> Result<bool, Error> {
         let mut stmt = self
             .db
@@ -198,7 +258,15 @@ impl Config<Write> {
         stmt.bind((1, id))?;
         stmt.next()?;
 
-        Ok(self.db.change_count() > 0)
+        let updated = self.db.change_count() > 0;
+        let mut stmt = self
+            .db
+            .prepare("DELETE FROM `remote-policies` WHERE rid = ?")?;
+
+        stmt.bind((1, id))?;
+        stmt.next()?;
+
+        Ok(updated)
     }
 }
 
@@ -216,6 +284,19 @@ impl<T> Config<T> {
         ))
     }
 
+    /// Check if there is any policy for a node, either as a node or as a repository remote.
+    pub fn has_policies(&self, id: &NodeId) -> Result<bool, Error> {
+        let mut stmt = self.db.prepare(
+            "SELECT 1 FROM `node-policies` WHERE id = ?1
+             UNION ALL
+             SELECT 1 FROM `remote-policies` WHERE nid = ?1
+             LIMIT 1",
+        )?;
+        stmt.bind((1, id))?;
+
+        Ok(matches!(stmt.next()?, sql::State::Row))
+    }
+
     /// Check if a repository is tracked.
     pub fn is_repo_tracked(&self, id: &Id) -> Result<bool, Error> {
         Ok(matches!(
@@ -271,6 +352,48 @@ impl<T> Config<T> {
         Ok(None)
     }
 
+    /// Get the tracking policy of a remote, for a specific repository.
+    pub fn remote_policy(&self, rid: &Id, nid: &NodeId) -> Result<Option<Remote>, Error> {
+        let mut stmt = self
+            .db
+            .prepare("SELECT policy FROM `remote-policies` WHERE rid = ?1 AND nid = ?2")?;
+
+        stmt.bind((1, rid))?;
+        stmt.bind((2, nid))?;
+
+        if let Some(Ok(row)) = stmt.into_iter().next() {
+            return Ok(Some(Remote {
+                rid: *rid,
+                nid: *nid,
+                policy: row.read::<Policy, _>("policy"),
+            }));
+        }
+        Ok(None)
+    }
+
+    /// Get the remote tracking policies of a repository.
+    pub fn remote_policies(&self, rid: &Id) -> Result<Box<dyn Iterator<Item = Remote>>, Error> {
+        let mut stmt = self
+            .db
+            .prepare("SELECT nid, policy FROM `remote-policies` WHERE rid = ?")?;
+        stmt.bind((1, rid))?;
+
+        let mut stmt = stmt.into_iter();
+        let mut entries = Vec::new();
+
+        while let Some(Ok(row)) = stmt.next() {
+            let nid = row.read("nid");
+            let policy = row.read::<Policy, _>("policy");
+
+            entries.push(Remote {
+                rid: *rid,
+                nid,
+                policy,
+            });
+        }
+        Ok(Box::new(entries.into_iter()))
+    }
+
     /// Get node tracking policies.
     pub fn node_policies(&self) -> Result<Box<dyn Iterator<Item = Node>>, Error> {
         let mut stmt = self
@@ -343,6 +466,30 @@ mod test {
         assert!(!db.is_node_tracked(&id).unwrap());
     }
 
+    #[test]
+    fn test_rotate() {
+        let old = arbitrary::gen::<NodeId>(1);
+        let new = arbitrary::gen::<NodeId>(2);
+        let rid = arbitrary::gen::<Id>(1);
+        let mut db = Config::open(":memory:").unwrap();
+
+        assert!(!db.rotate(&old, &new).unwrap());
+        assert!(db.track_node(&old, Some("alice")).unwrap());
+        assert!(db.set_remote_policy(&rid, &old, Policy::Block).unwrap());
+        assert!(!db.has_policies(&new).unwrap());
+        assert!(db.rotate(&old, &new).unwrap());
+        assert!(db.has_policies(&new).unwrap());
+        assert!(!db.rotate(&old, &new).unwrap());
+
+        assert!(db.is_node_tracked(&old).unwrap());
+        assert!(db.is_node_tracked(&new).unwrap());
+        assert_eq!(db.alias(&new), Some(Alias::new("alice")));
+        assert_eq!(
+            db.remote_policy(&rid, &new).unwrap().map(|r| r.policy),
+            Some(Policy::Block)
+        );
+    }
+
     #[test]
     fn test_track_and_untrack_repo() {
         let id = arbitrary::gen::<Id>(1);
@@ -425,6 +572,31 @@ mod test {
         assert_eq!(db.repo_policy(&id).unwrap().unwrap().policy, Policy::Block);
     }
 
+    #[test]
+    fn test_remote_policy() {
+        let rid = arbitrary::gen::<Id>(1);
+        let nids = arbitrary::vec::<NodeId>(2);
+        let mut db = Config::open(":memory:").unwrap();
+
+        assert!(db.track_repo(&rid, Scope::All).unwrap());
+        assert_eq!(db.remote_policy(&rid, &nids[0]).unwrap(), None);
+        assert!(db.set_remote_policy(&rid, &nids[0], Policy::Block).unwrap());
+        assert!(!db.set_remote_policy(&rid, &nids[0], Policy::Block).unwrap());
+        assert!(db.set_remote_policy(&rid, &nids[1], Policy::Track).unwrap());
+        assert_eq!(
+            db.remote_policy(&rid, &nids[0]).unwrap().unwrap().policy,
+            Policy::Block
+        );
+        assert_eq!(db.remote_policies(&rid).unwrap().count(), 2);
+        assert!(db.unset_remote_policy(&rid, &nids[1]).unwrap());
+        assert!(!db.unset_remote_policy(&rid, &nids[1]).unwrap());
+        assert_eq!(db.remote_policies(&rid).unwrap().count(), 1);
+
+        // Untracking the repository also removes its remote policies.
+        assert!(db.untrack_repo(&rid).unwrap());
+        assert_eq!(db.remote_policies(&rid).unwrap().count(), 0);
+    }
+
     #[test]
     fn test_node_policy() {
         let id = arbitrary::gen::<NodeId>(1);
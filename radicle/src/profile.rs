@@ -24,8 +24,10 @@ use crate::node;
 use crate::node::{address, routing, tracking, Alias, AliasStore};
 use crate::prelude::Did;
 use crate::prelude::NodeId;
+use crate::search;
 use crate::storage::git::transport;
 use crate::storage::git::Storage;
+use crate::tokens;
 
 /// Environment variables used by radicle.
 pub mod env {
@@ -246,6 +248,22 @@ impl Profile {
         Ok(addresses)
     }
 
+    /// Return a handle to the search index.
+    pub fn search(&self) -> Result<search::Index, search::Error> {
+        let path = self.home.node().join(search::SEARCH_DB_FILE);
+        let index = search::Index::open(path)?;
+
+        Ok(index)
+    }
+
+    /// Return a handle to the API tokens store.
+    pub fn tokens(&self) -> Result<tokens::Store, tokens::Error> {
+        let path = self.home.node().join(tokens::TOKENS_DB_FILE);
+        let tokens = tokens::Store::open(path)?;
+
+        Ok(tokens)
+    }
+
     /// Return a multi-source store for aliases.
     pub fn aliases(&self) -> Aliases {
         let tracking = self.tracking().ok();
@@ -0,0 +1,587 @@
+//! Search index.
+//!
+//! Indexes project names and descriptions, as well as the titles, descriptions and comments of
+//! issues and patches, so that they can be searched by keyword. Indexing is incremental: a
+//! repository is only re-indexed when its signed refs change.
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::str::FromStr;
+use std::{fmt, mem, time};
+
+use serde::{Deserialize, Serialize};
+use sqlite as sql;
+use thiserror::Error;
+
+use crate::cob::{issue, patch, store, ObjectId};
+use crate::git;
+use crate::identity::{Id, IdentityError};
+use crate::sql::transaction;
+use crate::storage;
+use crate::storage::git::{Repository, Storage};
+use crate::storage::ReadStorage as _;
+
+/// Search index file name.
+pub const SEARCH_DB_FILE: &str = "search.db";
+/// Number of results returned by default.
+pub const DEFAULT_LIMIT: usize = 20;
+/// Length of a result snippet, in characters.
+pub const SNIPPET_LENGTH: usize = 96;
+/// Number of characters shown before the first match in a snippet.
+pub const SNIPPET_CONTEXT: usize = 24;
+
+/// Shortest word that is indexed, in characters.
+const MIN_TERM_LENGTH: usize = 2;
+/// Longest word that is indexed, in characters.
+const MAX_TERM_LENGTH: usize = 64;
+/// How long to wait for the database lock to be released before failing.
+const DB_TIMEOUT: time::Duration = time::Duration::from_secs(6);
+
+/// An error occuring while indexing or searching.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// An Internal error.
+    #[error("internal error: {0}")]
+    Internal(#[from] sql::Error),
+    /// Storage error.
+    #[error("storage error: {0}")]
+    Storage(#[from] storage::Error),
+    /// Git error.
+    #[error("git error: {0}")]
+    Git(#[from] git::raw::Error),
+    /// Identity error.
+    #[error("identity error: {0}")]
+    Identity(#[from] IdentityError),
+    /// COB store error.
+    #[error("cob error: {0}")]
+    Cob(#[from] store::Error),
+}
+
+/// Kind of indexed document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Kind {
+    Project,
+    Issue,
+    Patch,
+}
+
+impl Kind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Project => "project",
+            Self::Issue => "issue",
+            Self::Patch => "patch",
+        }
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Kind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "project" => Ok(Self::Project),
+            "issue" => Ok(Self::Issue),
+            "patch" => Ok(Self::Patch),
+            other => Err(format!("unknown document kind '{other}'")),
+        }
+    }
+}
+
+/// A search result.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Match {
+    /// Repository the document is part of.
+    pub rid: Id,
+    /// Kind of document.
+    pub kind: Kind,
+    /// Issue or patch id. Not set for projects.
+    #[serde(serialize_with = "serialize_id")]
+    pub id: Option<ObjectId>,
+    /// Document title.
+    pub title: String,
+    /// Excerpt of the document body, around the first match.
+    pub snippet: String,
+}
+
+/// Serialize an object id as a string, rather than as bytes.
+fn serialize_id<S: serde::Serializer>(
+    id: &Option<ObjectId>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match id {
+        Some(id) => serializer.collect_str(id),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// A document to index.
+#[derive(Debug)]
+struct Document {
+    kind: Kind,
+    id: Option<ObjectId>,
+    title: String,
+    body: String,
+}
+
+/// Persistent search index.
+pub struct Index {
+    db: sql::Connection,
+}
+
+impl fmt::Debug for Index {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Index(..)")
+    }
+}
+
+impl Index {
+    const SCHEMA: &str = include_str!("search/schema.sql");
+
+    /// Open a search index at the given path. Creates a new empty index
+    /// if an existing index isn't found.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let mut db = sql::Connection::open(path)?;
+        db.set_busy_timeout(DB_TIMEOUT.as_millis() as usize)?;
+        db.execute(Self::SCHEMA)?;
+
+        Ok(Self { db })
+    }
+
+    /// Create a new in-memory search index.
+    pub fn memory() -> Result<Self, Error> {
+        let db = sql::Connection::open(":memory:")?;
+        db.execute(Self::SCHEMA)?;
+
+        Ok(Self { db })
+    }
+
+    /// Bring the index up to date with storage. Repositories whose refs changed since they
+    /// were last indexed are re-indexed, and repositories no longer in storage are removed.
+    /// Returns the number of repositories that were (re-)indexed.
+    pub fn sync(&mut self, storage: &Storage) -> Result<usize, Error> {
+        let repos = storage.repositories()?.into_iter().collect::<BTreeSet<_>>();
+        let mut indexed = 0;
+
+        for rid in &repos {
+            match storage
+                .repository(*rid)
+                .map_err(Error::from)
+                .and_then(|repo| self.index(&repo))
+            {
+                Ok(true) => indexed += 1,
+                Ok(false) => {}
+                Err(e) => log::warn!(target: "search", "Failed to index {rid}: {e}"),
+            }
+        }
+        for rid in self.repositories()? {
+            if !repos.contains(&rid) {
+                self.remove(&rid)?;
+            }
+        }
+        Ok(indexed)
+    }
+
+    /// Index a repository, if it changed since it was last indexed.
+    /// Returns whether the repository was (re-)indexed.
+    pub fn index(&mut self, repo: &Repository) -> Result<bool, Error> {
+        let fingerprint = repo.fingerprint()?.to_string();
+        let mut stmt = self
+            .db
+            .prepare("SELECT fingerprint FROM repos WHERE repo = ?")?;
+        stmt.bind((1, &repo.id))?;
+
+        if let Some(row) = stmt.into_iter().next() {
+            if row?.read::<&str, _>("fingerprint") == fingerprint {
+                return Ok(false);
+            }
+        }
+        let documents = documents(repo)?;
+
+        transaction(&self.db, |db| {
+            remove(db, &repo.id)?;
+
+            for doc in &documents {
+                let mut stmt = db.prepare(
+                    "INSERT INTO documents (repo, kind, object, title, body)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                )?;
+                stmt.bind((1, &repo.id))?;
+                stmt.bind((2, doc.kind.as_str()))?;
+                stmt.bind((
+                    3,
+                    doc.id.map(|id| id.to_string()).unwrap_or_default().as_str(),
+                ))?;
+                stmt.bind((4, doc.title.as_str()))?;
+                stmt.bind((5, doc.body.as_str()))?;
+                stmt.next()?;
+
+                let mut stmt = db.prepare("SELECT last_insert_rowid() AS id")?;
+                stmt.next()?;
+                let document = stmt.read::<i64, _>("id")?;
+
+                let mut counts = HashMap::<String, i64>::new();
+                for (_, word) in words(&doc.title).into_iter().chain(words(&doc.body)) {
+                    *counts.entry(word).or_default() += 1;
+                }
+                for (term, count) in counts {
+                    let mut stmt = db
+                        .prepare("INSERT INTO terms (term, document, count) VALUES (?1, ?2, ?3)")?;
+                    stmt.bind((1, term.as_str()))?;
+                    stmt.bind((2, document))?;
+                    stmt.bind((3, count))?;
+                    stmt.next()?;
+                }
+            }
+            let mut stmt = db.prepare(
+                "INSERT INTO repos (repo, fingerprint) VALUES (?1, ?2)
+                 ON CONFLICT DO UPDATE SET fingerprint = ?2",
+            )?;
+            stmt.bind((1, &repo.id))?;
+            stmt.bind((2, fingerprint.as_str()))?;
+            stmt.next()?;
+
+            Ok(())
+        })?;
+
+        Ok(true)
+    }
+
+    /// Remove a repository from the index.
+    pub fn remove(&mut self, rid: &Id) -> Result<bool, Error> {
+        transaction(&self.db, |db| {
+            remove(db, rid)?;
+
+            let mut stmt = db.prepare("DELETE FROM repos WHERE repo = ?")?;
+            stmt.bind((1, rid))?;
+            stmt.next()?;
+
+            Ok(db.change_count() > 0)
+        })
+        .map_err(Error::from)
+    }
+
+    /// Get the indexed repositories.
+    pub fn repositories(&self) -> Result<Vec<Id>, Error> {
+        let stmt = self.db.prepare("SELECT repo FROM repos")?;
+        let mut repos = Vec::new();
+
+        for row in stmt.into_iter() {
+            repos.push(row?.read::<Id, _>("repo"));
+        }
+        Ok(repos)
+    }
+
+    /// Search the index. Documents must contain all the words of the query; the last word may
+    /// also be the prefix of a word, so that results can be shown while typing.
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<Match>, Error> {
+        let mut terms = Vec::<String>::new();
+        for (_, word) in words(query) {
+            if !terms.contains(&word) {
+                terms.push(word);
+            }
+        }
+        let Some(last) = terms.len().checked_sub(1) else {
+            return Ok(vec![]);
+        };
+        let mut stmt = self.db.prepare("SELECT COUNT(*) AS count FROM documents")?;
+        stmt.next()?;
+        let total = stmt.read::<i64, _>("count")? as f64;
+        let mut scores: Option<HashMap<i64, f64>> = None;
+
+        for (i, term) in terms.iter().enumerate() {
+            let mut stmt = if i == last {
+                let mut stmt = self
+                    .db
+                    .prepare("SELECT document, count FROM terms WHERE term >= ?1 AND term < ?2")?;
+                stmt.bind((1, term.as_str()))?;
+                stmt.bind((2, format!("{term}{}", char::MAX).as_str()))?;
+                stmt
+            } else {
+                let mut stmt = self
+                    .db
+                    .prepare("SELECT document, count FROM terms WHERE term = ?1")?;
+                stmt.bind((1, term.as_str()))?;
+                stmt
+            };
+            let mut counts = HashMap::<i64, f64>::new();
+            while let sql::State::Row = stmt.next()? {
+                *counts.entry(stmt.read::<i64, _>("document")?).or_default() +=
+                    stmt.read::<i64, _>("count")? as f64;
+            }
+            // Words that are in fewer documents weigh more.
+            let weight = (1. + total / counts.len().max(1) as f64).ln();
+
+            scores = Some(match scores {
+                None => counts
+                    .into_iter()
+                    .map(|(doc, count)| (doc, count * weight))
+                    .collect(),
+                Some(scores) => scores
+                    .into_iter()
+                    .filter_map(|(doc, score)| {
+                        counts.get(&doc).map(|count| (doc, score + count * weight))
+                    })
+                    .collect(),
+            });
+        }
+        let mut ranked = scores.unwrap_or_default().into_iter().collect::<Vec<_>>();
+        ranked.sort_by(|(a, x), (b, y)| y.total_cmp(x).then(a.cmp(b)));
+
+        let mut matches = Vec::new();
+        for (document, _) in ranked.into_iter().take(limit) {
+            let mut stmt = self
+                .db
+                .prepare("SELECT repo, kind, object, title, body FROM documents WHERE id = ?")?;
+            stmt.bind((1, document))?;
+
+            if let Some(row) = stmt.into_iter().next() {
+                let row = row?;
+                let kind = row
+                    .read::<&str, _>("kind")
+                    .parse()
+                    .map_err(|e: String| sql::Error {
+                        code: None,
+                        message: Some(e),
+                    })?;
+                let id = row.read::<&str, _>("object").parse().ok();
+
+                matches.push(Match {
+                    rid: row.read::<Id, _>("repo"),
+                    kind,
+                    id,
+                    title: row.read::<&str, _>("title").to_owned(),
+                    snippet: snippet(row.read::<&str, _>("body"), &terms),
+                });
+            }
+        }
+        Ok(matches)
+    }
+}
+
+/// Remove the documents of a repository.
+fn remove(db: &sql::Connection, rid: &Id) -> Result<(), sql::Error> {
+    let mut stmt = db
+        .prepare("DELETE FROM terms WHERE document IN (SELECT id FROM documents WHERE repo = ?)")?;
+    stmt.bind((1, rid))?;
+    stmt.next()?;
+
+    let mut stmt = db.prepare("DELETE FROM documents WHERE repo = ?")?;
+    stmt.bind((1, rid))?;
+    stmt.next()?;
+
+    Ok(())
+}
+
+/// Get the documents to index in a repository.
+fn documents(repo: &Repository) -> Result<Vec<Document>, Error> {
+    let mut documents = Vec::new();
+    let project = repo.project()?;
+
+    documents.push(Document {
+        kind: Kind::Project,
+        id: None,
+        title: project.name().to_owned(),
+        body: project.description().to_owned(),
+    });
+
+    for result in issue::Issues::open(repo)?.all()? {
+        let (id, issue) = match result {
+            Ok(issue) => issue,
+            Err(e) => {
+                log::warn!(target: "search", "Failed to load issue in {}: {e}", repo.id);
+                continue;
+            }
+        };
+        let body = issue
+            .comments()
+            .map(|(_, c)| c.body())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        documents.push(Document {
+            kind: Kind::Issue,
+            id: Some(id),
+            title: issue.title().to_owned(),
+            body,
+        });
+    }
+
+    for result in patch::Patches::open(repo)?.all()? {
+        let (id, patch) = match result {
+            Ok(patch) => patch,
+            Err(e) => {
+                log::warn!(target: "search", "Failed to load patch in {}: {e}", repo.id);
+                continue;
+            }
+        };
+        let mut body = Vec::new();
+
+        for (_, revision) in patch.revisions() {
+            body.push(revision.description());
+            body.extend(revision.discussion().comments().map(|(_, c)| c.body()));
+
+            for (_, review) in revision.reviews() {
+                body.extend(review.summary());
+                body.extend(review.comments().map(|(_, c)| c.body()));
+            }
+        }
+        documents.push(Document {
+            kind: Kind::Patch,
+            id: Some(id),
+            title: patch.title().to_owned(),
+            body: body.join("\n\n"),
+        });
+    }
+    Ok(documents)
+}
+
+/// Split text into lowercase words, along with the character offset they start at.
+fn words(text: &str) -> Vec<(usize, String)> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut start = 0;
+
+    for (i, c) in text.chars().chain([' ']).enumerate() {
+        if c.is_alphanumeric() {
+            if word.is_empty() {
+                start = i;
+            }
+            word.extend(c.to_lowercase());
+        } else if !word.is_empty() {
+            words.push((start, mem::take(&mut word)));
+        }
+    }
+    words.retain(|(_, w)| (MIN_TERM_LENGTH..=MAX_TERM_LENGTH).contains(&w.chars().count()));
+    words
+}
+
+/// Get an excerpt of the text, around the first word matching one of the terms.
+fn snippet(text: &str, terms: &[String]) -> String {
+    let start = words(text)
+        .into_iter()
+        .find(|(_, w)| terms.iter().any(|t| w.starts_with(t.as_str())))
+        .map(|(i, _)| i.saturating_sub(SNIPPET_CONTEXT))
+        .unwrap_or_default();
+    let chars = text.chars().collect::<Vec<_>>();
+    let end = (start + SNIPPET_LENGTH).min(chars.len());
+    let mut snippet = String::new();
+
+    if start > 0 {
+        snippet.push('…');
+    }
+    for c in &chars[start..end] {
+        // Collapse whitespace, so that snippets fit on one line.
+        if c.is_whitespace() {
+            if !snippet.ends_with(' ') {
+                snippet.push(' ');
+            }
+        } else {
+            snippet.push(*c);
+        }
+    }
+    if end < chars.len() {
+        snippet.push('…');
+    }
+    snippet.trim().to_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cob::issue::Issues;
+    use crate::crypto::test::signer::MockSigner;
+    use crate::storage::ReadStorage;
+    use crate::test::fixtures;
+
+    use super::*;
+
+    #[test]
+    fn test_words() {
+        assert_eq!(
+            words("Fix the `rad-search` command, a.k.a. Ω-Search!")
+                .into_iter()
+                .map(|(_, w)| w)
+                .collect::<Vec<_>>(),
+            vec!["fix", "the", "rad", "search", "command", "ω", "search"]
+                .into_iter()
+                .filter(|w| w.chars().count() >= MIN_TERM_LENGTH)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_snippet() {
+        let text = "The quick brown fox\njumps over the lazy dog. ".repeat(8);
+        let snippet = snippet(&text, &[String::from("lazy")]);
+
+        assert!(snippet.starts_with('…'));
+        assert!(snippet.ends_with('…'));
+        assert!(snippet.contains("the lazy dog"));
+        assert!(!snippet.contains('\n'));
+    }
+
+    #[test]
+    fn test_search() {
+        let tmp = tempfile::tempdir().unwrap();
+        let signer = MockSigner::default();
+        let storage = fixtures::storage(tmp.path(), &signer).unwrap();
+        let mut index = Index::memory().unwrap();
+
+        assert_eq!(index.sync(&storage).unwrap(), 3);
+        // Nothing changed, so nothing is re-indexed.
+        assert_eq!(index.sync(&storage).unwrap(), 0);
+
+        let results = index.search("pixel editor", 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].kind, Kind::Project);
+        assert_eq!(results[0].title, "rx");
+        assert_eq!(results[0].snippet, "A pixel editor");
+        // Both editors match, but only one is a pixel editor.
+        assert_eq!(index.search("editor", 10).unwrap().len(), 2);
+        // The last word can be a prefix.
+        assert_eq!(index.search("pix", 10).unwrap().len(), 1);
+        assert!(index.search("", 10).unwrap().is_empty());
+
+        let (_, acme) = storage
+            .inventory()
+            .unwrap()
+            .into_iter()
+            .map(|rid| (rid, storage.repository(rid).unwrap()))
+            .find(|(_, repo)| repo.project().unwrap().name() == "acme")
+            .unwrap();
+        let mut issues = Issues::open(&acme).unwrap();
+        let issue = issues
+            .create(
+                "Flux capacitor overheats",
+                "It reaches 1.21 gigawatts before shutting down.",
+                &[],
+                &[],
+                [],
+                &signer,
+            )
+            .unwrap();
+
+        assert_eq!(index.sync(&storage).unwrap(), 1);
+
+        let results = index.search("gigawatts", 10).unwrap();
+        assert_eq!(
+            results,
+            vec![Match {
+                rid: acme.id,
+                kind: Kind::Issue,
+                id: Some(*issue.id()),
+                title: String::from("Flux capacitor overheats"),
+                snippet: String::from("It reaches 1.21 gigawatts before shutting down."),
+            }]
+        );
+        assert!(index.remove(&acme.id).unwrap());
+        assert!(index.search("gigawatts", 10).unwrap().is_empty());
+    }
+}
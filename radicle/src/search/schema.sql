@@ -0,0 +1,37 @@
+--
+-- Search index SQL schema.
+--
+create table if not exists "documents" (
+  -- Document identifier.
+  "id"           integer   primary key,
+  -- Repository the document is part of.
+  "repo"         text      not null,
+  -- Kind of document, eg. 'project' or 'issue'.
+  "kind"         text      not null,
+  -- Collaborative object id, or the empty string for projects.
+  "object"       text      not null,
+  -- Document title.
+  "title"        text      not null,
+  -- Document body.
+  "body"         text      not null
+);
+create index if not exists "documents_repo" on "documents" ("repo");
+
+create table if not exists "terms" (
+  -- Indexed term, in lowercase.
+  "term"         text      not null,
+  -- Document the term appears in.
+  "document"     integer   not null,
+  -- Number of times the term appears in the document.
+  "count"        integer   not null,
+
+  primary key ("term", "document")
+);
+create index if not exists "terms_document" on "terms" ("document");
+
+create table if not exists "repos" (
+  -- Indexed repository.
+  "repo"         text      primary key not null,
+  -- Fingerprint of the repository's signed refs, when it was indexed.
+  "fingerprint"  text      not null
+);
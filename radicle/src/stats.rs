@@ -0,0 +1,376 @@
+//! Repository and node statistics.
+//!
+//! Contributors are identified by [`Did`]: a contributor is anyone who authored an issue, a
+//! patch, or a signed commit on the repository's default branch. Unsigned commits can't be
+//! attributed to a key, and are only counted towards commit activity.
+//!
+//! Computing the statistics of a repository walks its entire history, so they are best
+//! computed through a [`Cache`], which only computes them again when the repository changes,
+//! ideally in the background.
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::cob::{issue, patch, store};
+use crate::git;
+use crate::identity::{Did, Id, IdentityError};
+use crate::storage;
+use crate::storage::git::{Repository, Storage};
+use crate::storage::RemoteId;
+use crate::storage::{ReadRepository as _, ReadStorage as _};
+
+/// Length of a commit activity period, in seconds.
+pub const ACTIVITY_PERIOD: u64 = 7 * 24 * 60 * 60;
+
+/// An error occuring while computing statistics.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// Storage error.
+    #[error("storage error: {0}")]
+    Storage(#[from] storage::Error),
+    /// Signed refs error.
+    #[error("refs error: {0}")]
+    Refs(#[from] storage::refs::Error),
+    /// Git error.
+    #[error("git error: {0}")]
+    Git(#[from] git::raw::Error),
+    /// Identity error.
+    #[error("identity error: {0}")]
+    Identity(#[from] IdentityError),
+    /// COB store error.
+    #[error("cob error: {0}")]
+    Cob(#[from] store::Error),
+}
+
+/// Statistics of a node, across all its repositories.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeStats {
+    /// Number of repositories.
+    pub projects: usize,
+    /// Number of unique remotes, across repositories.
+    pub remotes: usize,
+    /// Number of unique contributors, across repositories.
+    pub contributors: usize,
+}
+
+/// Statistics of a repository.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepoStats {
+    /// Number of remotes.
+    pub remotes: usize,
+    /// Contributors, most active first.
+    pub contributors: Vec<Contributor>,
+    /// Commits on the default branch.
+    pub commits: CommitStats,
+    /// Issue statistics.
+    pub issues: IssueStats,
+    /// Patch statistics.
+    pub patches: PatchStats,
+}
+
+/// A repository contributor.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Contributor {
+    pub id: Did,
+    /// Signed commits on the default branch.
+    pub commits: usize,
+    /// Patches opened.
+    pub patches: usize,
+    /// Issues opened.
+    pub issues: usize,
+}
+
+impl Contributor {
+    fn new(id: Did) -> Self {
+        Self {
+            id,
+            commits: 0,
+            patches: 0,
+            issues: 0,
+        }
+    }
+
+    /// Total number of contributions.
+    pub fn total(&self) -> usize {
+        self.commits + self.patches + self.issues
+    }
+}
+
+/// Commit statistics.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitStats {
+    /// Total number of commits.
+    pub total: usize,
+    /// Commit activity over time, oldest first. Periods without commits are omitted.
+    pub activity: Vec<Activity>,
+}
+
+/// Number of commits in a period of [`ACTIVITY_PERIOD`] seconds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Activity {
+    /// Start of the period, in seconds since the epoch.
+    pub time: u64,
+    /// Number of commits in the period.
+    pub commits: usize,
+}
+
+/// Issue statistics.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IssueStats {
+    pub open: usize,
+    pub closed: usize,
+    /// Fraction of issues that were closed, between `0` and `1`.
+    pub close_rate: f64,
+}
+
+/// Patch statistics.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchStats {
+    pub open: usize,
+    pub draft: usize,
+    pub archived: usize,
+    pub merged: usize,
+    /// Fraction of patches that were merged, between `0` and `1`.
+    pub merge_rate: f64,
+    /// Average time between opening a patch and merging it, in seconds, if any patch was
+    /// merged.
+    pub average_time_to_merge: Option<u64>,
+}
+
+/// Cache of repository statistics, keyed on the signed refs of each repository.
+///
+/// The cache can be shared between threads. Statistics are computed without holding the lock
+/// on the whole cache, so that computing the statistics of one repository doesn't hold up
+/// access to the others.
+#[derive(Debug, Default)]
+pub struct Cache {
+    repos: Mutex<HashMap<Id, Entry>>,
+}
+
+/// A cache entry.
+#[derive(Debug, Default)]
+struct Entry {
+    /// Held while the statistics of the repository are computed, so that they're only
+    /// computed once.
+    lock: Arc<Mutex<()>>,
+    /// The latest statistics computed, if any.
+    cached: Option<Cached>,
+}
+
+/// Statistics computed for a given state of a repository.
+#[derive(Debug)]
+struct Cached {
+    fingerprint: git::Oid,
+    stats: Arc<RepoStats>,
+    remotes: Vec<RemoteId>,
+}
+
+impl Cache {
+    /// Get the statistics of a repository, computing them if the repository changed.
+    pub fn repo(&self, repo: &Repository) -> Result<Arc<RepoStats>, Error> {
+        let fingerprint = repo.fingerprint()?;
+        let lock = {
+            let mut repos = self.repos.lock().expect("stats: lock is not poisoned");
+            let entry = repos.entry(repo.id).or_default();
+
+            if let Some(stats) = entry.fresh(fingerprint) {
+                return Ok(stats);
+            }
+            entry.lock.clone()
+        };
+        let _guard = lock.lock().expect("stats: lock is not poisoned");
+
+        // The statistics may have been computed while we were waiting.
+        if let Some(stats) = self.fresh(&repo.id, fingerprint) {
+            return Ok(stats);
+        }
+        let stats = Arc::new(self::repo(repo)?);
+        let remotes = repo.remote_ids()?.collect::<Result<Vec<_>, _>>()?;
+
+        self.repos
+            .lock()
+            .expect("stats: lock is not poisoned")
+            .entry(repo.id)
+            .or_default()
+            .cached = Some(Cached {
+            fingerprint,
+            stats: stats.clone(),
+            remotes,
+        });
+
+        Ok(stats)
+    }
+
+    /// Compute the statistics of all repositories that changed since they were last
+    /// computed. Repositories that fail to load are skipped.
+    pub fn update(&self, storage: &Storage) -> Result<(), Error> {
+        let rids = storage.repositories()?;
+
+        // Forget about repositories that were removed.
+        self.repos
+            .lock()
+            .expect("stats: lock is not poisoned")
+            .retain(|rid, _| rids.contains(rid));
+
+        for rid in &rids {
+            let Ok(repo) = storage.repository(*rid) else {
+                continue;
+            };
+            if let Err(e) = self.repo(&repo) {
+                log::warn!(target: "stats", "Failed to compute statistics of {rid}: {e}");
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the statistics of a node, from the statistics of its repositories that were
+    /// computed so far, even if out of date. See [`Cache::update`].
+    pub fn node(&self, storage: &Storage) -> Result<NodeStats, Error> {
+        let projects = storage.repositories()?.len();
+        let repos = self.repos.lock().expect("stats: lock is not poisoned");
+        let mut remotes = BTreeSet::new();
+        let mut contributors = BTreeSet::new();
+
+        for cached in repos.values().filter_map(|e| e.cached.as_ref()) {
+            contributors.extend(cached.stats.contributors.iter().map(|c| c.id));
+            remotes.extend(cached.remotes.iter().copied());
+        }
+
+        Ok(NodeStats {
+            projects,
+            remotes: remotes.len(),
+            contributors: contributors.len(),
+        })
+    }
+
+    /// Get the statistics of a repository, if they're up to date.
+    fn fresh(&self, rid: &Id, fingerprint: git::Oid) -> Option<Arc<RepoStats>> {
+        self.repos
+            .lock()
+            .expect("stats: lock is not poisoned")
+            .get(rid)
+            .and_then(|e| e.fresh(fingerprint))
+    }
+}
+
+impl Entry {
+    /// Get the cached statistics, if they were computed for the given state.
+    fn fresh(&self, fingerprint: git::Oid) -> Option<Arc<RepoStats>> {
+        self.cached
+            .as_ref()
+            .filter(|c| c.fingerprint == fingerprint)
+            .map(|c| c.stats.clone())
+    }
+}
+
+/// Compute the statistics of a repository.
+pub fn repo(repo: &Repository) -> Result<RepoStats, Error> {
+    let mut contributors = BTreeMap::<Did, Contributor>::new();
+    let remotes = repo.remote_ids()?.collect::<Result<Vec<_>, _>>()?.len();
+
+    // Commits.
+    let (_, head) = repo.head()?;
+    let mut walk = repo.backend.revwalk()?;
+    let mut activity = BTreeMap::<u64, usize>::new();
+    let mut commits = CommitStats::default();
+
+    walk.push(*head)?;
+    for oid in walk {
+        let oid = oid?;
+        let commit = repo.backend.find_commit(oid)?;
+        let time = commit.time().seconds().max(0) as u64;
+
+        commits.total += 1;
+        *activity.entry(time - time % ACTIVITY_PERIOD).or_default() += 1;
+
+        if let Some(key) = git::signer(&repo.backend, oid.into())? {
+            contributor(&mut contributors, key.into()).commits += 1;
+        }
+    }
+    commits.activity = activity
+        .into_iter()
+        .map(|(time, commits)| Activity { time, commits })
+        .collect();
+
+    // Issues and patches that fail to load are skipped.
+    let mut issues = IssueStats::default();
+    for result in issue::Issues::open(repo)?.all()? {
+        let Ok((_, issue)) = result else {
+            continue;
+        };
+
+        match issue.state() {
+            issue::State::Open => issues.open += 1,
+            issue::State::Closed { .. } => issues.closed += 1,
+        }
+        contributor(&mut contributors, issue.author().id).issues += 1;
+    }
+    issues.close_rate = rate(issues.closed, issues.open + issues.closed);
+
+    // Patches.
+    let mut patches = PatchStats::default();
+    let mut time_to_merge = Vec::new();
+    for result in patch::Patches::open(repo)?.all()? {
+        let Ok((_, patch)) = result else {
+            continue;
+        };
+
+        match patch.state() {
+            patch::State::Draft => patches.draft += 1,
+            patch::State::Open { .. } => patches.open += 1,
+            patch::State::Archived => patches.archived += 1,
+            patch::State::Merged { .. } => patches.merged += 1,
+        }
+        if let Some(merged) = patch.merges().map(|(_, m)| m.timestamp).min() {
+            time_to_merge.push(merged.as_secs().saturating_sub(patch.timestamp().as_secs()));
+        }
+        contributor(&mut contributors, patch.author().id).patches += 1;
+    }
+    patches.merge_rate = rate(
+        patches.merged,
+        patches.open + patches.draft + patches.archived + patches.merged,
+    );
+    patches.average_time_to_merge = (!time_to_merge.is_empty())
+        .then(|| time_to_merge.iter().sum::<u64>() / time_to_merge.len() as u64);
+
+    let mut contributors = contributors.into_values().collect::<Vec<_>>();
+    contributors.sort_by(|a, b| b.total().cmp(&a.total()).then(a.id.cmp(&b.id)));
+
+    Ok(RepoStats {
+        remotes,
+        contributors,
+        commits,
+        issues,
+        patches,
+    })
+}
+
+/// Compute the statistics of a node. Repositories that fail to load are skipped.
+pub fn node(storage: &Storage) -> Result<NodeStats, Error> {
+    let cache = Cache::default();
+    cache.update(storage)?;
+    cache.node(storage)
+}
+
+fn contributor(contributors: &mut BTreeMap<Did, Contributor>, did: Did) -> &mut Contributor {
+    contributors
+        .entry(did)
+        .or_insert_with(|| Contributor::new(did))
+}
+
+fn rate(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.
+    } else {
+        count as f64 / total as f64
+    }
+}
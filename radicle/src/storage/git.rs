@@ -323,6 +323,24 @@ impl Repository {
         Ok(iter)
     }
 
+    /// Compute a fingerprint of the signed refs of all remotes. Since all changes to a
+    /// repository are signed, the fingerprint changes whenever the repository does.
+    pub fn fingerprint(&self) -> Result<Oid, git2::Error> {
+        let mut tips = Vec::new();
+
+        for r in self.backend.references_glob(SIGREFS_GLOB.as_str())? {
+            let r = r?;
+            if let (Some(name), Some(oid)) = (r.name(), r.target()) {
+                tips.push(format!("{name} {oid}\n"));
+            }
+        }
+        tips.sort();
+
+        let oid = git2::Oid::hash_object(git2::ObjectType::Blob, tips.concat().as_bytes())?;
+
+        Ok(oid.into())
+    }
+
     pub fn remotes(
         &self,
     ) -> Result<
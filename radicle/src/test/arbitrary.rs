@@ -218,7 +218,7 @@ impl Arbitrary for Id {
 
 impl Arbitrary for AddressType {
     fn arbitrary(g: &mut qcheck::Gen) -> Self {
-        let t = *g.choose(&[1, 2, 3]).unwrap() as u8;
+        let t = *g.choose(&[1, 2, 3, 4]).unwrap() as u8;
 
         AddressType::try_from(t).unwrap()
     }
@@ -243,7 +243,15 @@ impl Arbitrary for Address {
                 .unwrap()
                 .to_string(),
             ),
-            AddressType::Onion => todo!(),
+            AddressType::Onion => cyphernet::addr::HostName::Tor(
+                g.choose(&[
+                    "2gzyxa5ihm7nsggfxnu52rck2vv4rvmdlkiu3zzui5du4xyclen53wid.onion",
+                    "duckduckgogg42xjoc72x3sjasowoarfbgcmvfimaftt6twagswzczad.onion",
+                ])
+                .unwrap()
+                .parse()
+                .unwrap(),
+            ),
         };
 
         Address::from(cyphernet::addr::NetAddr {
@@ -201,8 +201,13 @@ impl ReadRepository for MockRepository {
     fn reference_oid(
         &self,
         _remote: &RemoteId,
-        _reference: &git::Qualified,
+        reference: &git::Qualified,
     ) -> Result<git_ext::Oid, git_ext::Error> {
+        if reference == &*crate::git::refs::storage::PERSON_BRANCH {
+            return Err(git_ext::Error::NotFound(git_ext::NotFound::NoSuchBranch(
+                reference.to_string(),
+            )));
+        }
         Ok(Oid::from_str("ffffffffffffffffffffffffffffffffffffffff").unwrap())
     }
 
@@ -0,0 +1,312 @@
+//! API tokens.
+//!
+//! Tokens let non-interactive clients of the HTTP API, eg. CI bots, authenticate without
+//! going through the session signing flow. They don't expire, but are limited to a set of
+//! [`Scope`]s, can be restricted to a single repository, and can be revoked at any time.
+//!
+//! Only a hash of each token's secret is stored, so the secret is only known when the token is
+//! created.
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::str::FromStr;
+use std::{fmt, time};
+
+use cyphernet::{Digest as _, Sha256};
+use localtime::LocalTime;
+use serde::{Deserialize, Serialize};
+use sqlite as sql;
+use thiserror::Error;
+
+use crate::crypto::Seed;
+use crate::identity::Id;
+
+/// API tokens database file name.
+pub const TOKENS_DB_FILE: &str = "tokens.db";
+/// Prefix of token secrets, to make them easy to recognize.
+pub const SECRET_PREFIX: &str = "rad_";
+
+/// Length of token identifiers.
+const ID_LENGTH: usize = 8;
+/// How long to wait for the database lock to be released before failing.
+const DB_TIMEOUT: time::Duration = time::Duration::from_secs(6);
+
+/// An error occuring with the token store.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// An Internal error.
+    #[error("internal error: {0}")]
+    Internal(#[from] sql::Error),
+    /// A token scope is invalid.
+    #[error("invalid token scope: {0}")]
+    Scope(#[from] ScopeError),
+}
+
+/// An error parsing a token scope.
+#[derive(Error, Debug)]
+#[error("unknown scope '{0}', expected one of 'read', 'issue-write' or 'patch-write'")]
+pub struct ScopeError(String);
+
+/// What a token is allowed to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Scope {
+    /// Read access, for read endpoints requiring authentication, eg. listing webhooks.
+    Read,
+    /// Open, comment on and update issues.
+    IssueWrite,
+    /// Open, comment on, review and update patches.
+    PatchWrite,
+}
+
+impl Scope {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::IssueWrite => "issue-write",
+            Self::PatchWrite => "patch-write",
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Scope {
+    type Err = ScopeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read" => Ok(Self::Read),
+            "issue-write" => Ok(Self::IssueWrite),
+            "patch-write" => Ok(Self::PatchWrite),
+            other => Err(ScopeError(other.to_owned())),
+        }
+    }
+}
+
+/// An API token.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Token {
+    /// Token identifier, used to manage the token. This is not the token secret.
+    pub id: String,
+    /// Token name.
+    pub name: String,
+    /// Scopes granted to the token.
+    pub scopes: BTreeSet<Scope>,
+    /// Repository the token is restricted to. If `None`, the token is valid for all
+    /// repositories.
+    pub rid: Option<Id>,
+    /// When the token was created.
+    #[serde(with = "crate::serde_ext::localtime::time")]
+    pub created_at: LocalTime,
+}
+
+impl Token {
+    /// Whether the token grants the given scope on the given repository.
+    /// Write scopes also grant read access.
+    pub fn allows(&self, scope: Scope, rid: &Id) -> bool {
+        if self.rid.map_or(false, |r| r != *rid) {
+            return false;
+        }
+        match scope {
+            Scope::Read => !self.scopes.is_empty(),
+            Scope::IssueWrite | Scope::PatchWrite => self.scopes.contains(&scope),
+        }
+    }
+}
+
+/// Persistent storage for API tokens.
+pub struct Store {
+    db: sql::Connection,
+}
+
+impl fmt::Debug for Store {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Store(..)")
+    }
+}
+
+impl Store {
+    const SCHEMA: &str = include_str!("tokens/schema.sql");
+
+    /// Open a token store at the given path. Creates a new empty store
+    /// if an existing store isn't found.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let mut db = sql::Connection::open(path)?;
+        db.set_busy_timeout(DB_TIMEOUT.as_millis() as usize)?;
+        db.execute(Self::SCHEMA)?;
+
+        Ok(Self { db })
+    }
+
+    /// Create a new in-memory token store.
+    pub fn memory() -> Result<Self, Error> {
+        let db = sql::Connection::open(":memory:")?;
+        db.execute(Self::SCHEMA)?;
+
+        Ok(Self { db })
+    }
+
+    /// Create a new token. Returns the token along with its secret, which can't be
+    /// retrieved later.
+    pub fn create(
+        &mut self,
+        name: &str,
+        scopes: BTreeSet<Scope>,
+        rid: Option<Id>,
+    ) -> Result<(Token, String), Error> {
+        let mut rng = fastrand::Rng::new();
+        let id = std::iter::repeat_with(|| rng.alphanumeric())
+            .take(ID_LENGTH)
+            .collect::<String>()
+            .to_lowercase();
+        let secret = format!(
+            "{SECRET_PREFIX}{}",
+            multibase::Base::Base58Btc.encode(*Seed::generate())
+        );
+        let token = Token {
+            id,
+            name: name.to_owned(),
+            scopes,
+            rid,
+            created_at: LocalTime::now(),
+        };
+        let mut stmt = self.db.prepare(
+            "INSERT INTO tokens (id, hash, name, scopes, repo, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        stmt.bind((1, token.id.as_str()))?;
+        stmt.bind((2, hash(&secret).as_str()))?;
+        stmt.bind((3, token.name.as_str()))?;
+        stmt.bind((4, scopes_to_string(&token.scopes).as_str()))?;
+        match &token.rid {
+            Some(rid) => stmt.bind((5, rid))?,
+            None => stmt.bind((5, sql::Value::Null))?,
+        }
+        stmt.bind((6, token.created_at.as_secs() as i64))?;
+        stmt.next()?;
+
+        Ok((token, secret))
+    }
+
+    /// Revoke a token. Returns whether the token existed.
+    pub fn revoke(&mut self, id: &str) -> Result<bool, Error> {
+        let mut stmt = self.db.prepare("DELETE FROM tokens WHERE id = ?")?;
+        stmt.bind((1, id))?;
+        stmt.next()?;
+
+        Ok(self.db.change_count() > 0)
+    }
+
+    /// Get the token with the given secret, if any.
+    pub fn authenticate(&self, secret: &str) -> Result<Option<Token>, Error> {
+        let mut stmt = self
+            .db
+            .prepare("SELECT id, name, scopes, repo, created_at FROM tokens WHERE hash = ?")?;
+        stmt.bind((1, hash(secret).as_str()))?;
+
+        match stmt.into_iter().next() {
+            Some(row) => Ok(Some(token(&row?)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get all tokens.
+    pub fn tokens(&self) -> Result<Vec<Token>, Error> {
+        let stmt = self
+            .db
+            .prepare("SELECT id, name, scopes, repo, created_at FROM tokens ORDER BY created_at")?;
+        let mut tokens = Vec::new();
+
+        for row in stmt.into_iter() {
+            tokens.push(token(&row?)?);
+        }
+        Ok(tokens)
+    }
+}
+
+/// Hash a token secret.
+fn hash(secret: &str) -> String {
+    Sha256::digest(secret.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+fn scopes_to_string(scopes: &BTreeSet<Scope>) -> String {
+    scopes
+        .iter()
+        .map(Scope::as_str)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn token(row: &sql::Row) -> Result<Token, Error> {
+    let scopes = row
+        .read::<&str, _>("scopes")
+        .split(',')
+        .filter(|s| !s.is_empty())
+        .map(Scope::from_str)
+        .collect::<Result<_, _>>()?;
+    let rid = match row.read::<Option<&str>, _>("repo") {
+        Some(_) => Some(row.try_read::<Id, _>("repo")?),
+        None => None,
+    };
+
+    Ok(Token {
+        id: row.read::<&str, _>("id").to_owned(),
+        name: row.read::<&str, _>("name").to_owned(),
+        scopes,
+        rid,
+        created_at: LocalTime::from_secs(row.read::<i64, _>("created_at") as u64),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::arbitrary;
+
+    #[test]
+    fn test_create_authenticate_revoke() {
+        let mut store = Store::memory().unwrap();
+        let rid = arbitrary::gen::<Id>(1);
+        let (token, secret) = store
+            .create("ci", BTreeSet::from([Scope::PatchWrite]), Some(rid))
+            .unwrap();
+
+        assert!(secret.starts_with(SECRET_PREFIX));
+        assert_eq!(store.authenticate(&secret).unwrap(), Some(token.clone()));
+        assert_eq!(store.authenticate("rad_unknown").unwrap(), None);
+        assert_eq!(store.tokens().unwrap(), vec![token.clone()]);
+
+        assert!(store.revoke(&token.id).unwrap());
+        assert!(!store.revoke(&token.id).unwrap());
+        assert_eq!(store.authenticate(&secret).unwrap(), None);
+    }
+
+    #[test]
+    fn test_allows() {
+        let rid = arbitrary::gen::<Id>(1);
+        let other = arbitrary::gen::<Id>(2);
+        let mut token = Token {
+            id: String::from("abcdefgh"),
+            name: String::from("ci"),
+            scopes: BTreeSet::from([Scope::IssueWrite]),
+            rid: Some(rid),
+            created_at: LocalTime::now(),
+        };
+
+        assert!(token.allows(Scope::Read, &rid));
+        assert!(token.allows(Scope::IssueWrite, &rid));
+        assert!(!token.allows(Scope::PatchWrite, &rid));
+        assert!(!token.allows(Scope::IssueWrite, &other));
+
+        token.rid = None;
+        assert!(token.allows(Scope::IssueWrite, &other));
+    }
+}
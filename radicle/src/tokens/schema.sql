@@ -0,0 +1,17 @@
+--
+-- API tokens SQL schema.
+--
+create table if not exists "tokens" (
+  -- Token identifier. Not secret.
+  "id"           text      primary key not null,
+  -- SHA-256 hash of the token secret, hex-encoded.
+  "hash"         text      unique not null,
+  -- Name given to the token, eg. the client using it.
+  "name"         text      not null,
+  -- Comma-separated list of scopes granted to the token.
+  "scopes"       text      not null,
+  -- Repository the token is restricted to, if any.
+  "repo"         text,
+  -- Local time at which the token was created, in seconds.
+  "created_at"   integer   not null
+) strict;